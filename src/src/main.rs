@@ -1,4 +1,6 @@
 extern crate num;
+extern crate encoding_rs;
+extern crate flate2;
 #[macro_use]
 extern crate num_derive;
 
@@ -9,25 +11,289 @@ use n64::memory::MemoryMapping;
 use n64::cpu::CPU;
 use n64::rom::RomHeader;
 use n64::rom::Rom;
+use n64::rom::RomError;
+use n64::rom::read_rom_from_filename;
 use n64::connector::Connector;
 use n64::n64::N64;
+use n64::controller::{ControllerState, InputSource, ScriptedInput};
+use n64::controller::ControllerAccessory;
+use n64::mempak::MemPak;
+use n64::rumble_pak::RumblePak;
+use n64::transfer_pak::{GameBoyCartridge, TransferPak};
+use n64::save_file::SaveError;
+use n64::rtc::DeterministicClock;
+use n64::game_db::{GameDatabase, GameEntry, Accessory};
+use n64::frame_dump::{FrameDump, ImageFormat};
+use std::path::Path;
+use std::fs;
 use std::env;
+use std::process;
+
+//Controller Pak maintenance that runs on the --mempak file instead of a game
+#[allow(non_camel_case_types)]
+enum NoteCommand
+{
+    LIST,
+    EXPORT(usize, String),
+    IMPORT(String),
+    DELETE(usize),
+}
+
+struct Arguments
+{
+    rom_filename: Option<String>,
+    patch_filenames: Vec<String>,
+    pif_rom_filename: Option<String>,
+    mempak_filename: Option<String>,
+    rumble_pak: bool,
+    transfer_pak_filename: Option<String>,
+    rtc_time: Option<i64>,
+    game_db_filename: Option<String>,
+    hle: bool,
+    frame_directory: Option<String>,
+    frame_format: ImageFormat,
+    frame_indices: Option<Vec<u64>>,
+    frame_limit: Option<u64>,
+    input_script_filename: Option<String>,
+    note_command: Option<NoteCommand>,
+}
 
 fn main() 
 {
-    let filename = get_filename();
-    let mut n64: N64 = N64::new(&filename);
-    n64.run_pif_rom();
+    let arguments = get_arguments();
+    if let Some(ref note_command) = arguments.note_command
+    {
+        let mempak_filename = arguments.mempak_filename.as_ref().expect("Controller Pak note commands need a --mempak file");
+        if let Err(e) = run_note_command(mempak_filename, note_command)
+        {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+    let mut game_database = GameDatabase::builtin();
+    if let Some(ref game_db_filename) = arguments.game_db_filename
+    {
+        if let Err(e) = game_database.load_overrides(game_db_filename)
+        {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+    let mut n64: N64 = match load_rom(&arguments)
+    {
+        Ok(rom) => N64::from_rom_with_database(rom, &game_database),
+        Err(e) =>
+        {
+            eprintln!("{}", e);
+            process::exit(1);
+        },
+    };
+    match arguments.pif_rom_filename
+    {
+        Some(ref pif_rom_filename) =>
+        {
+            let boot_rom = read_rom_from_filename(pif_rom_filename).and_then(|boot_rom| n64.run_pif_boot_rom(boot_rom));
+            if let Err(e) = boot_rom
+            {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        },
+        None => n64.run_pif_rom(),
+    };
+    if let Err(e) = n64.open_save_files()
+    {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+
+    //Player one always has a controller plugged in, played from a script when one is given
+    let input: Box<dyn InputSource> = match arguments.input_script_filename
+    {
+        Some(ref input_script_filename) => match fs::read_to_string(input_script_filename).map_err(|e| e.to_string()).and_then(|text| ScriptedInput::from_script(&text))
+        {
+            Ok(script) => Box::new(script),
+            Err(e) =>
+            {
+                eprintln!("{}", e);
+                process::exit(1);
+            },
+        },
+        None => Box::new(ControllerState::neutral()),
+    };
+    match load_accessory(&arguments, &n64.game_entry)
+    {
+        Ok(Some(accessory)) => n64.connect_controller_with_accessory(0, input, accessory),
+        Ok(None) => n64.connect_controller(0, input),
+        Err(e) =>
+        {
+            eprintln!("{}", e);
+            process::exit(1);
+        },
+    };
+    //A fixed RTC start time keeps runs reproducible
+    if let Some(rtc_time) = arguments.rtc_time
+    {
+        n64.set_rtc_clock(Box::new(DeterministicClock::new(rtc_time)));
+    }
+    n64.hle = arguments.hle;
+    //Headless runs write frames to disk instead of showing them
+    if let Some(ref frame_directory) = arguments.frame_directory
+    {
+        let mut frame_dump = FrameDump::new(frame_directory, arguments.frame_format);
+        frame_dump.indices = arguments.frame_indices.clone();
+        n64.frame_dump = Some(frame_dump);
+    }
+    n64.frame_limit = arguments.frame_limit;
     n64.register_debug();
     n64.run();
+    if let Some((program_counter, ref e)) = n64.rsp_core.fault
+    {
+        eprintln!("RSP halted at 0x{:03X}: {}", program_counter, e);
+    }
+    if let Some(ref e) = n64.hle_error
+    {
+        eprintln!("{}", e);
+    }
 }
 
-fn get_filename() -> String
+fn load_rom(arguments: &Arguments) -> Result<Rom, RomError>
 {
-    let mut args: Vec<String> = env::args().collect();
-    if args.len() == 1
+    let mut rom = Rom::new(arguments.rom_filename.as_ref().expect("Emulator needs a rom to function!"))?;
+    for patch_filename in arguments.patch_filenames.iter()
+    {
+        rom.apply_patch_file(patch_filename)?;
+    }
+    Ok(rom)
+}
+
+fn run_note_command(mempak_filename: &str, note_command: &NoteCommand) -> Result<(), SaveError>
+{
+    let mut mempak = MemPak::open(mempak_filename)?;
+    match *note_command
+    {
+        NoteCommand::LIST =>
+        {
+            for note in mempak.notes()
+            {
+                let name = if note.extension.is_empty() {note.name} else {format!("{}.{}", note.name, note.extension)};
+                println!("{:2}: {:08X} {:04X} {} ({} pages)", note.index, note.game_code, note.publisher_code, name, note.pages.len());
+            }
+            println!("{} free pages", mempak.free_pages());
+        },
+        NoteCommand::EXPORT(index, ref note_filename) => fs::write(note_filename, mempak.export_note(index)?)?,
+        NoteCommand::IMPORT(ref note_filename) =>
+        {
+            let index = mempak.import_note(&fs::read(note_filename)?)?;
+            println!("Imported {} as note {}", note_filename, index);
+        },
+        NoteCommand::DELETE(index) => mempak.delete_note(index)?,
+    }
+    mempak.flush()
+}
+
+fn load_accessory(arguments: &Arguments, game_entry: &Option<GameEntry>) -> Result<Option<Box<dyn ControllerAccessory>>, SaveError>
+{
+    if let Some(ref mempak_filename) = arguments.mempak_filename
+    {
+        return Ok(Some(Box::new(MemPak::open(mempak_filename)?)));
+    }
+    if let Some(ref transfer_pak_filename) = arguments.transfer_pak_filename
+    {
+        //Cartridge RAM lives next to the Game Boy ROM
+        let save_filename = Path::new(transfer_pak_filename).with_extension("sav");
+        let cartridge = GameBoyCartridge::open(transfer_pak_filename, save_filename)?;
+        return Ok(Some(Box::new(TransferPak::new(Some(cartridge)))));
+    }
+    if arguments.rumble_pak
+    {
+        return Ok(Some(Box::new(rumble_pak())));
+    }
+
+    //Without a choice on the command line use what the game database suggests
+    if let Some(GameEntry {accessories: Some(ref accessories), ..}) = *game_entry
+    {
+        match accessories.first()
+        {
+            Some(Accessory::MEMPAK) => return Ok(Some(Box::new(MemPak::new()))),
+            Some(Accessory::RUMBLE_PAK) => return Ok(Some(Box::new(RumblePak::new()))),
+            //A Transfer Pak is useless without a Game Boy cartridge
+            _ => (),
+        }
+    }
+    Ok(None)
+}
+
+//Headless runs have no motor to drive, so a pak asked for on the command line logs the game turning it on and off
+fn rumble_pak() -> RumblePak
+{
+    RumblePak::with_callback(Box::new(|rumbling| eprintln!("Rumble Pak motor {}", if rumbling {"on"} else {"off"})))
+}
+
+fn get_arguments() -> Arguments
+{
+    let mut args = env::args().skip(1);
+    let mut rom_filename: Option<String> = None;
+    let mut patch_filenames: Vec<String> = Vec::new();
+    let mut pif_rom_filename: Option<String> = None;
+    let mut mempak_filename: Option<String> = None;
+    let mut rumble_pak = false;
+    let mut transfer_pak_filename: Option<String> = None;
+    let mut rtc_time: Option<i64> = None;
+    let mut game_db_filename: Option<String> = None;
+    let mut hle = false;
+    let mut frame_directory: Option<String> = None;
+    let mut frame_format = ImageFormat::PNG;
+    let mut frame_indices: Option<Vec<u64>> = None;
+    let mut frame_limit: Option<u64> = None;
+    let mut input_script_filename: Option<String> = None;
+    let mut note_command: Option<NoteCommand> = None;
+    while let Some(arg) = args.next()
+    {
+        match arg.as_str()
+        {
+            "--patch" => patch_filenames.push(args.next().expect("--patch needs a patch file")),
+            "--pif-rom" => pif_rom_filename = Some(args.next().expect("--pif-rom needs a PIF boot rom image")),
+            "--mempak" => mempak_filename = Some(args.next().expect("--mempak needs a .mpk file")),
+            "--rumble-pak" => rumble_pak = true,
+            "--transfer-pak" => transfer_pak_filename = Some(args.next().expect("--transfer-pak needs a Game Boy rom")),
+            "--rtc-time" => rtc_time = Some(args.next().and_then(|time| time.parse().ok()).expect("--rtc-time needs a Unix timestamp")),
+            "--game-db" => game_db_filename = Some(args.next().expect("--game-db needs a game database file")),
+            "--hle" => hle = true,
+            "--dump-frames" => frame_directory = Some(args.next().expect("--dump-frames needs an output directory")),
+            "--frame-format" => frame_format = args.next().and_then(|format| ImageFormat::from_name(&format)).expect("--frame-format needs png or ppm"),
+            "--frames" => frame_indices = Some(args.next().and_then(|frames| frames.split(',').map(|index| index.trim().parse().ok()).collect()).expect("--frames needs a comma separated list of frame indices")),
+            "--frame-limit" => frame_limit = Some(args.next().and_then(|limit| limit.parse().ok()).expect("--frame-limit needs a frame count")),
+            "--input-script" => input_script_filename = Some(args.next().expect("--input-script needs a controller script file")),
+            "--list-notes" => note_command = Some(NoteCommand::LIST),
+            "--export-note" =>
+            {
+                let index = args.next().and_then(|index| index.parse().ok()).expect("--export-note needs a note index");
+                note_command = Some(NoteCommand::EXPORT(index, args.next().expect("--export-note needs an output file")));
+            },
+            "--import-note" => note_command = Some(NoteCommand::IMPORT(args.next().expect("--import-note needs a note file"))),
+            "--delete-note" => note_command = Some(NoteCommand::DELETE(args.next().and_then(|index| index.parse().ok()).expect("--delete-note needs a note index"))),
+            _ => rom_filename = Some(arg),
+        }
+    }
+
+    return Arguments
     {
-        panic!("Emulator needs a rom to function!");
+        rom_filename: rom_filename,
+        patch_filenames: patch_filenames,
+        pif_rom_filename: pif_rom_filename,
+        mempak_filename: mempak_filename,
+        rumble_pak: rumble_pak,
+        transfer_pak_filename: transfer_pak_filename,
+        rtc_time: rtc_time,
+        game_db_filename: game_db_filename,
+        hle: hle,
+        frame_directory: frame_directory,
+        frame_format: frame_format,
+        frame_indices: frame_indices,
+        frame_limit: frame_limit,
+        input_script_filename: input_script_filename,
+        note_command: note_command,
     }
-    return String::from(args.remove(1));
 }
\ No newline at end of file
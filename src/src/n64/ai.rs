@@ -0,0 +1,221 @@
+const AI_DRAM_ADDR_REG_START: usize = 0x00000000;
+const AI_DRAM_ADDR_REG_END: usize = 0x00000003;
+const AI_LEN_REG_START: usize = 0x00000004;
+const AI_LEN_REG_END: usize = 0x00000007;
+const AI_CONTROL_REG_START: usize = 0x00000008;
+const AI_CONTROL_REG_END: usize = 0x0000000B;
+const AI_STATUS_REG_START: usize = 0x0000000C;
+const AI_STATUS_REG_END: usize = 0x0000000F;
+const AI_DACRATE_REG_START: usize = 0x00000010;
+const AI_DACRATE_REG_END: usize = 0x00000013;
+const AI_BITRATE_REG_START: usize = 0x00000014;
+const AI_BITRATE_REG_END: usize = 0x00000017;
+
+//AI_STATUS bits
+pub const AI_STATUS_FULL: u32 = 0x80000001;
+pub const AI_STATUS_BUSY: u32 = 0x40000000;
+pub const AI_STATUS_ENABLED: u32 = 0x02000000;
+
+pub const AI_CONTROL_DMA_ENABLE: u32 = 0x00000001;
+
+const AI_DRAM_ADDR_MASK: u32 = 0x00FFFFF8;
+const AI_LEN_MASK: u32 = 0x0003FFF8;
+const AI_DACRATE_MASK: u32 = 0x00003FFF;
+const AI_BITRATE_MASK: u32 = 0x0000000F;
+//Two buffers can be queued, the one playing and the one after it
+pub const AI_FIFO_SIZE: usize = 2;
+//Each sample is a big endian 16 bit left and right pair
+const AI_SAMPLE_SIZE: u32 = 4;
+
+use n64::arch::Reg;
+use n64::exceptions::Exception;
+use n64::rdram::RDRAM;
+use n64::rom::TvType;
+use n64::vi::{video_clock, COUNT_CLOCK};
+use std::collections::VecDeque;
+
+//Receives the stereo samples the AI plays, in [left, right] pairs
+pub trait AudioSink
+{
+    fn push_samples(&mut self, samples: &[[i16; 2]], sample_rate: u32);
+}
+
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq)]
+pub struct AIBuffer
+{
+    pub address: u32,
+    pub length: u32,
+}
+
+// Referenced: https://n64brew.dev/wiki/Audio_Interface
+pub struct AudioInterface
+{
+    pub dram_address: Reg,
+    pub control: Reg,
+    pub dac_rate: Reg,
+    //Bits per sample minus one, only affects the serial clock of the real DAC
+    pub bit_rate: Reg,
+    pub fifo: VecDeque<AIBuffer>,
+    //Bytes of the front buffer already played
+    pub position: u32,
+    pub video_clock: u32,
+    pub pending_interrupt: Option<bool>,
+    pub sink: Option<Box<dyn AudioSink>>,
+    //Elapsed time in units of COUNT_CLOCK * video_clock, so no precision is lost to the divider
+    sample_cycles: u64,
+}
+
+impl AudioInterface
+{
+    pub fn new(tv_type: TvType) -> AudioInterface
+    {
+        return AudioInterface
+        {
+            dram_address: Reg::default(),
+            control: Reg::default(),
+            dac_rate: Reg::default(),
+            bit_rate: Reg::default(),
+            fifo: VecDeque::new(),
+            position: 0,
+            video_clock: video_clock(tv_type),
+            pending_interrupt: None,
+            sink: None,
+            sample_cycles: 0,
+        }
+    }
+
+    pub fn read_u32_from_address(&self, address: usize) -> Result<u32, Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match address
+        {
+            AI_STATUS_REG_START...AI_STATUS_REG_END => Ok(self.status()),
+            //Every other register is write only and reads back the remaining length
+            AI_DRAM_ADDR_REG_START...AI_BITRATE_REG_END => Ok(self.remaining_length()),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    pub fn load_u32_to_address(&mut self, address: usize, value: u32) -> Result<(), Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match address
+        {
+            AI_DRAM_ADDR_REG_START...AI_DRAM_ADDR_REG_END => Ok(self.dram_address.set_value(value & AI_DRAM_ADDR_MASK)),
+            AI_LEN_REG_START...AI_LEN_REG_END => Ok(self.queue_buffer(value & AI_LEN_MASK)),
+            AI_CONTROL_REG_START...AI_CONTROL_REG_END => Ok(self.control.set_value(value & AI_CONTROL_DMA_ENABLE)),
+            //Any write to the status acknowledges the interrupt
+            AI_STATUS_REG_START...AI_STATUS_REG_END => Ok(self.pending_interrupt = Some(false)),
+            AI_DACRATE_REG_START...AI_DACRATE_REG_END => Ok(self.dac_rate.set_value(value & AI_DACRATE_MASK)),
+            AI_BITRATE_REG_START...AI_BITRATE_REG_END => Ok(self.bit_rate.set_value(value & AI_BITRATE_MASK)),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    //Writing the length pairs it with the last DRAM address, a full FIFO drops the buffer
+    fn queue_buffer(&mut self, length: u32)
+    {
+        if length == 0 || self.fifo.len() >= AI_FIFO_SIZE
+        {
+            return;
+        }
+        self.fifo.push_back(AIBuffer {address: self.dram_address.get_value() as u32, length: length});
+    }
+
+    pub fn status(&self) -> u32
+    {
+        let mut status = 0;
+        if self.fifo.len() >= AI_FIFO_SIZE
+        {
+            status |= AI_STATUS_FULL;
+        }
+        if !self.fifo.is_empty()
+        {
+            status |= AI_STATUS_BUSY;
+        }
+        if self.is_enabled()
+        {
+            status |= AI_STATUS_ENABLED;
+        }
+        status
+    }
+
+    pub fn remaining_length(&self) -> u32
+    {
+        match self.fifo.front()
+        {
+            Some(buffer) => buffer.length - self.position,
+            None => 0,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool
+    {
+        self.control.get_value() as u32 & AI_CONTROL_DMA_ENABLE != 0
+    }
+
+    //The DAC divides the video clock by DACRATE + 1
+    pub fn sample_rate(&self) -> u32
+    {
+        self.video_clock / (self.dac_rate.get_value() as u32 + 1)
+    }
+
+    //Returns true when a buffer finishes playing and the AI interrupt should be raised
+    pub fn step(&mut self, cycles: u32, rdram: &RDRAM) -> bool
+    {
+        if !self.is_enabled() || self.fifo.is_empty()
+        {
+            self.sample_cycles = 0;
+            return false;
+        }
+        self.sample_cycles += cycles as u64 * self.video_clock as u64;
+        let sample_period = COUNT_CLOCK * (self.dac_rate.get_value() + 1);
+        let mut samples = Vec::new();
+        let mut interrupt = false;
+        while self.sample_cycles >= sample_period
+        {
+            let buffer = match self.fifo.front()
+            {
+                Some(buffer) => *buffer,
+                None =>
+                {
+                    self.sample_cycles = 0;
+                    break;
+                },
+            };
+            self.sample_cycles -= sample_period;
+            //Unmapped RDRAM plays as silence
+            let address = (buffer.address + self.position) as usize;
+            let read_i16 = |offset: usize| ((rdram.read_u8(address + offset).unwrap_or(0) as u16) << 8 | rdram.read_u8(address + offset + 1).unwrap_or(0) as u16) as i16;
+            samples.push([read_i16(0), read_i16(2)]);
+            self.position += AI_SAMPLE_SIZE;
+            if self.position >= buffer.length
+            {
+                self.fifo.pop_front();
+                self.position = 0;
+                interrupt = true;
+            }
+        }
+        if !samples.is_empty()
+        {
+            let sample_rate = self.sample_rate();
+            if let Some(ref mut sink) = self.sink
+            {
+                sink.push_samples(&samples, sample_rate);
+            }
+        }
+        interrupt
+    }
+}
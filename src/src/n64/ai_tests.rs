@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod ai_tests
+{
+    use n64::ai::*;
+    use n64::connector::Connector;
+    use n64::hle::store_rdram_u32;
+    use n64::mips_iface::MI_INTR_AI;
+    use n64::vi::{NTSC_VIDEO_CLOCK, COUNT_CLOCK};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    const AI_DRAM_ADDR: u32 = 0x04500000;
+    const AI_LEN: u32 = 0x04500004;
+    const AI_CONTROL: u32 = 0x04500008;
+    const AI_STATUS: u32 = 0x0450000C;
+    const AI_DACRATE: u32 = 0x04500010;
+    const AI_BITRATE: u32 = 0x04500014;
+
+    struct SharedSink
+    {
+        samples: Rc<RefCell<Vec<([i16; 2], u32)>>>,
+    }
+
+    impl AudioSink for SharedSink
+    {
+        fn push_samples(&mut self, samples: &[[i16; 2]], sample_rate: u32)
+        {
+            self.samples.borrow_mut().extend(samples.iter().map(|&sample| (sample, sample_rate)));
+        }
+    }
+
+    fn ai_interrupt(connector: &Connector) -> bool
+    {
+        connector.mips_interface.interrupt.get_value() as u32 & MI_INTR_AI != 0
+    }
+
+    //COUNT cycles until the given number of samples have played
+    fn sample_cycles(samples: u64, dac_rate: u64) -> u32
+    {
+        ((samples * COUNT_CLOCK * (dac_rate + 1) + NTSC_VIDEO_CLOCK as u64 - 1) / NTSC_VIDEO_CLOCK as u64) as u32
+    }
+
+    fn queue(connector: &mut Connector, address: u32, length: u32)
+    {
+        connector.store_u32(AI_DRAM_ADDR, address).unwrap();
+        connector.store_u32(AI_LEN, length).unwrap();
+    }
+
+    #[test]
+    fn buffers_fill_a_two_entry_fifo()
+    {
+        let mut connector = Connector::test();
+        assert_eq!(connector.read_u32(AI_STATUS).unwrap(), 0);
+        queue(&mut connector, 0x1004, 0x20);
+        assert_eq!(connector.read_u32(AI_STATUS).unwrap(), AI_STATUS_BUSY);
+        queue(&mut connector, 0x2000, 0x3FFFF);
+        assert_eq!(connector.read_u32(AI_STATUS).unwrap(), AI_STATUS_FULL | AI_STATUS_BUSY);
+        //A third buffer has nowhere to go
+        queue(&mut connector, 0x3000, 0x100);
+        assert_eq!(connector.ai.fifo.iter().cloned().collect::<Vec<AIBuffer>>(), vec![AIBuffer {address: 0x1000, length: 0x20}, AIBuffer {address: 0x2000, length: 0x3FFF8}]);
+
+        //Write only registers read back the length left in the playing buffer
+        assert_eq!(connector.read_u32(AI_LEN).unwrap(), 0x20);
+        assert_eq!(connector.read_u32(AI_DACRATE).unwrap(), 0x20);
+        connector.store_u32(AI_CONTROL, 0xFFFFFFFF).unwrap();
+        connector.store_u32(AI_DACRATE, 0xFFFFFFFF).unwrap();
+        connector.store_u32(AI_BITRATE, 0xFFFFFFFF).unwrap();
+        assert_eq!(connector.ai.dac_rate.get_value(), 0x3FFF);
+        assert_eq!(connector.ai.bit_rate.get_value(), 0xF);
+        assert_eq!(connector.read_u32(AI_STATUS).unwrap() & AI_STATUS_ENABLED, AI_STATUS_ENABLED);
+        assert!(connector.read_u32(0x04500018).is_err());
+    }
+
+    #[test]
+    fn samples_play_at_the_dac_rate_and_interrupt_per_buffer()
+    {
+        let mut connector = Connector::test();
+        let samples = Rc::new(RefCell::new(Vec::new()));
+        connector.ai.sink = Some(Box::new(SharedSink {samples: samples.clone()}));
+        store_rdram_u32(&mut connector, 0x1000, 0x0001FFFF);
+        store_rdram_u32(&mut connector, 0x1004, 0x7FFF8000);
+        store_rdram_u32(&mut connector, 0x2000, 0x12345678);
+        store_rdram_u32(&mut connector, 0x2004, 0x00000000);
+
+        //Roughly 32kHz
+        let dac_rate = (NTSC_VIDEO_CLOCK / 32000 - 1) as u64;
+        connector.store_u32(AI_DACRATE, dac_rate as u32).unwrap();
+        assert_eq!(connector.ai.sample_rate(), NTSC_VIDEO_CLOCK / (dac_rate as u32 + 1));
+        queue(&mut connector, 0x1000, 8);
+        queue(&mut connector, 0x2000, 8);
+
+        //Nothing plays until DMA is enabled
+        connector.step(sample_cycles(4, dac_rate));
+        assert!(samples.borrow().is_empty());
+        connector.store_u32(AI_CONTROL, AI_CONTROL_DMA_ENABLE).unwrap();
+
+        connector.step(sample_cycles(1, dac_rate) - 1);
+        assert!(samples.borrow().is_empty());
+        connector.step(1);
+        assert_eq!(connector.read_u32(AI_LEN).unwrap(), 4);
+        assert!(!ai_interrupt(&connector));
+
+        connector.step(sample_cycles(2, dac_rate) - sample_cycles(1, dac_rate));
+        assert!(ai_interrupt(&connector));
+        assert_eq!(connector.read_u32(AI_STATUS).unwrap(), AI_STATUS_BUSY | AI_STATUS_ENABLED);
+        assert_eq!(connector.read_u32(AI_LEN).unwrap(), 8);
+        connector.store_u32(AI_STATUS, 0).unwrap();
+        assert!(!ai_interrupt(&connector));
+
+        connector.step(sample_cycles(4, dac_rate) - sample_cycles(2, dac_rate));
+        assert!(ai_interrupt(&connector));
+        assert_eq!(connector.read_u32(AI_STATUS).unwrap(), AI_STATUS_ENABLED);
+        let rate = NTSC_VIDEO_CLOCK / (dac_rate as u32 + 1);
+        assert_eq!(*samples.borrow(), vec![([1, -1], rate), ([0x7FFF, -0x8000], rate), ([0x1234, 0x5678], rate), ([0, 0], rate)]);
+
+        //An empty FIFO leaves the sink alone
+        connector.step(sample_cycles(4, dac_rate));
+        assert_eq!(samples.borrow().len(), 4);
+    }
+}
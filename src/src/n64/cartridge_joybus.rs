@@ -0,0 +1,52 @@
+use n64::joybus::{JoybusDevice, JoybusError};
+use n64::eeprom::Eeprom;
+use n64::rtc::RTC;
+use n64::save_file::SaveError;
+
+//The cartridge port can carry an EEPROM and an RTC at once, each answering its own commands
+pub struct CartridgeJoybus
+{
+    pub eeprom: Option<Eeprom>,
+    pub rtc: Option<RTC>,
+}
+
+impl CartridgeJoybus
+{
+    pub fn new() -> CartridgeJoybus
+    {
+        return CartridgeJoybus
+        {
+            eeprom: None,
+            rtc: None,
+        }
+    }
+}
+
+impl JoybusDevice for CartridgeJoybus
+{
+    fn process_command(&mut self, send: &[u8], receive: &mut [u8]) -> Result<(), JoybusError>
+    {
+        let mut result = Err(JoybusError::NO_RESPONSE);
+        if let Some(ref mut eeprom) = self.eeprom
+        {
+            result = eeprom.process_command(send, receive);
+        }
+        if result == Err(JoybusError::NO_RESPONSE)
+        {
+            if let Some(ref mut rtc) = self.rtc
+            {
+                result = rtc.process_command(send, receive);
+            }
+        }
+        result
+    }
+
+    fn flush(&mut self) -> Result<(), SaveError>
+    {
+        match self.eeprom
+        {
+            Some(ref mut eeprom) => eeprom.flush(),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use n64::cpu::{CPURegisters, CPURegisterName};
+use n64::rsp::RealitySignalProcessor;
+use n64::rom::TvType;
+use std::fmt;
+
+// Referenced: Sum of the IPL3 words (0x40 - 0x1000) as used by mupen64 for chip identification
+const CIC_X101_CHECKSUM: u64 = 0x000000D0027FDF31;
+const CIC_7102_CHECKSUM: u64 = 0x000000CFFB631223;
+const CIC_X102_CHECKSUM: u64 = 0x000000D057C85244;
+const CIC_X103_CHECKSUM: u64 = 0x000000D6497E414B;
+const CIC_X105_CHECKSUM: u64 = 0x0000011A49F60E96;
+const CIC_X106_CHECKSUM: u64 = 0x000000D6D5BE5580;
+const CIC_5167_CHECKSUM: u64 = 0x000001053BC19870;
+const CIC_8303_CHECKSUM: u64 = 0x000000D2E53EF008;
+const CIC_8401_CHECKSUM: u64 = 0x000000D2E53EF39F;
+
+const RESET_TYPE_COLD: u32 = 0;
+const ROM_TYPE_CARTRIDGE: u32 = 0;
+const ROM_TYPE_DISK_DRIVE: u32 = 1;
+
+// IPL3 of the 6105 polls the PIF from IMEM before jumping to the game
+const CIC_X105_IMEM_ADDRESS: usize = 0x1004;
+const CIC_X105_IMEM_VALUES: [u32; 8] = [0x3C0DBFC0, 0x8DA807FC, 0x25AD07C0, 0x31080080, 0x5500FFFC, 0x3C0DBFC0, 0x8DA80024, 0x3C0BB000];
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum CIC
+{
+    CIC_6101,
+    CIC_6102,
+    CIC_6103,
+    CIC_6105,
+    CIC_6106,
+    CIC_7101,
+    CIC_7102,
+    CIC_7103,
+    CIC_7105,
+    CIC_7106,
+    CIC_8303,
+    CIC_8401,
+    CIC_5167,
+    UNKNOWN,
+}
+
+impl CIC
+{
+    pub fn from_boot_code(boot_code: &Vec<u32>, tv_type: TvType) -> CIC
+    {
+        let pal = tv_type == TvType::PAL;
+        match compute_boot_code_checksum(boot_code)
+        {
+            CIC_X101_CHECKSUM => if pal {CIC::CIC_7102} else {CIC::CIC_6101},
+            CIC_7102_CHECKSUM => CIC::CIC_7102,
+            CIC_X102_CHECKSUM => if pal {CIC::CIC_7101} else {CIC::CIC_6102},
+            CIC_X103_CHECKSUM => if pal {CIC::CIC_7103} else {CIC::CIC_6103},
+            CIC_X105_CHECKSUM => if pal {CIC::CIC_7105} else {CIC::CIC_6105},
+            CIC_X106_CHECKSUM => if pal {CIC::CIC_7106} else {CIC::CIC_6106},
+            CIC_5167_CHECKSUM => CIC::CIC_5167,
+            CIC_8303_CHECKSUM => CIC::CIC_8303,
+            CIC_8401_CHECKSUM => CIC::CIC_8401,
+            _ => CIC::UNKNOWN,
+        }
+    }
+
+    pub fn seed(self) -> u8
+    {
+        match self
+        {
+            CIC::CIC_6101 | CIC::CIC_6102 | CIC::CIC_7101 | CIC::CIC_7102 => 0x3F,
+            CIC::CIC_6103 | CIC::CIC_7103 => 0x78,
+            CIC::CIC_6105 | CIC::CIC_7105 => 0x91,
+            CIC::CIC_6106 | CIC::CIC_7106 => 0x85,
+            CIC::CIC_8303 | CIC::CIC_8401 | CIC::CIC_5167 => 0xDD,
+            //Unknown chips boot like the 6102, the most common variant
+            CIC::UNKNOWN => 0x3F,
+        }
+    }
+
+    pub fn crc_seed(self) -> u32
+    {
+        match self
+        {
+            CIC::CIC_6103 | CIC::CIC_7103 => 0xA3886759,
+            CIC::CIC_6105 | CIC::CIC_7105 => 0xDF26F436,
+            CIC::CIC_6106 | CIC::CIC_7106 => 0x1FEA617A,
+            _ => 0xF8CA4DDC,
+        }
+    }
+
+    pub fn is_x105(self) -> bool
+    {
+        self == CIC::CIC_6105 || self == CIC::CIC_7105
+    }
+
+    pub fn rom_type(self) -> u32
+    {
+        match self
+        {
+            CIC::CIC_8303 | CIC::CIC_8401 | CIC::CIC_5167 => ROM_TYPE_DISK_DRIVE,
+            _ => ROM_TYPE_CARTRIDGE,
+        }
+    }
+
+    pub fn set_pif_rom_values(self, cpu_registers: &mut CPURegisters, tv_type: TvType)
+    {
+        // Referenced: http://www.emulation64.com/ultra64/bootn64.html
+        cpu_registers.register[CPURegisterName::s3 as usize].set_value(self.rom_type());
+        cpu_registers.register[CPURegisterName::s4 as usize].set_value(tv_type as u32);
+        cpu_registers.register[CPURegisterName::s5 as usize].set_value(RESET_TYPE_COLD);
+        cpu_registers.register[CPURegisterName::s6 as usize].set_value(self.seed() as u32);
+        cpu_registers.register[CPURegisterName::s7 as usize].set_value(0_u32);
+    }
+
+    pub fn set_rsp_pif_rom_values(self, rsp: &mut RealitySignalProcessor) -> Result<(), usize>
+    {
+        if self.is_x105()
+        {
+            for (index, value) in CIC_X105_IMEM_VALUES.iter().enumerate()
+            {
+                rsp.load_u32_to_address(CIC_X105_IMEM_ADDRESS + (index * 4), *value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for CIC
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self
+        {
+            CIC::UNKNOWN => write!(f, "Unknown CIC"),
+            _ => write!(f, "{}", format!("{:?}", self).replace("_", "-")),
+        }
+    }
+}
+
+pub fn compute_boot_code_checksum(boot_code: &Vec<u32>) -> u64
+{
+    boot_code.iter().fold(0_u64, |sum, word| sum + (*word as u64))
+}
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod cic_tests
+{
+    use n64::cic::*;
+    use n64::cpu::{CPURegisters, CPURegisterName};
+    use n64::rsp::RealitySignalProcessor;
+    use n64::rom::{RomHeader, TvType};
+
+    fn boot_code_with_checksum(checksum: u64) -> Vec<u32>
+    {
+        let mut boot_code: Vec<u32> = vec![0; 0x3F0];
+        let full_words = (checksum / 0xFFFFFFFF) as usize;
+        for index in 0..full_words
+        {
+            boot_code[index] = 0xFFFFFFFF;
+        }
+        boot_code[full_words] = (checksum % 0xFFFFFFFF) as u32;
+        boot_code
+    }
+
+    #[test]
+    fn detects_cic_from_boot_code_checksum()
+    {
+        assert_eq!(CIC::from_boot_code(&boot_code_with_checksum(0x000000D057C85244), TvType::NTSC), CIC::CIC_6102);
+        assert_eq!(CIC::from_boot_code(&boot_code_with_checksum(0x000000D057C85244), TvType::PAL), CIC::CIC_7101);
+        assert_eq!(CIC::from_boot_code(&boot_code_with_checksum(0x000000D6497E414B), TvType::NTSC), CIC::CIC_6103);
+        assert_eq!(CIC::from_boot_code(&boot_code_with_checksum(0x0000011A49F60E96), TvType::NTSC), CIC::CIC_6105);
+        assert_eq!(CIC::from_boot_code(&boot_code_with_checksum(0x000000D6D5BE5580), TvType::PAL), CIC::CIC_7106);
+        assert_eq!(CIC::from_boot_code(&boot_code_with_checksum(0x000000D2E53EF008), TvType::NTSC), CIC::CIC_8303);
+        assert_eq!(CIC::from_boot_code(&vec![0; 0x3F0], TvType::NTSC), CIC::UNKNOWN);
+    }
+
+    #[test]
+    fn header_country_code_selects_tv_type()
+    {
+        let mut header_data: Vec<u8> = vec![0; 0x1000];
+        header_data[0x3E] = b'E';
+        assert_eq!(RomHeader::new(header_data.to_vec()).unwrap().tv_type(), TvType::NTSC);
+        header_data[0x3E] = b'P';
+        assert_eq!(RomHeader::new(header_data.to_vec()).unwrap().tv_type(), TvType::PAL);
+        header_data[0x3E] = b'B';
+        assert_eq!(RomHeader::new(header_data.to_vec()).unwrap().tv_type(), TvType::MPAL);
+    }
+
+    #[test]
+    fn sets_seed_and_boot_registers()
+    {
+        let mut cpu_registers = CPURegisters::new();
+        CIC::CIC_6105.set_pif_rom_values(&mut cpu_registers, TvType::PAL);
+        assert_eq!(cpu_registers.register[CPURegisterName::s3 as usize].get_value(), 0x00);
+        assert_eq!(cpu_registers.register[CPURegisterName::s4 as usize].get_value(), 0x00);
+        assert_eq!(cpu_registers.register[CPURegisterName::s5 as usize].get_value(), 0x00);
+        assert_eq!(cpu_registers.register[CPURegisterName::s6 as usize].get_value(), 0x91);
+
+        CIC::CIC_8303.set_pif_rom_values(&mut cpu_registers, TvType::NTSC);
+        assert_eq!(cpu_registers.register[CPURegisterName::s3 as usize].get_value(), 0x01);
+        assert_eq!(cpu_registers.register[CPURegisterName::s4 as usize].get_value(), 0x01);
+        assert_eq!(cpu_registers.register[CPURegisterName::s6 as usize].get_value(), 0xDD);
+
+        CIC::UNKNOWN.set_pif_rom_values(&mut cpu_registers, TvType::NTSC);
+        assert_eq!(cpu_registers.register[CPURegisterName::s6 as usize].get_value(), 0x3F);
+    }
+
+    #[test]
+    fn x105_writes_imem_setup_code()
+    {
+        let mut rsp = RealitySignalProcessor::new();
+        CIC::CIC_6102.set_rsp_pif_rom_values(&mut rsp).unwrap();
+        assert_eq!(rsp.read_u32_from_address(0x1004).unwrap(), 0x00000000);
+
+        CIC::CIC_6105.set_rsp_pif_rom_values(&mut rsp).unwrap();
+        assert_eq!(rsp.read_u32_from_address(0x1004).unwrap(), 0x3C0DBFC0);
+        assert_eq!(rsp.read_u32_from_address(0x1020).unwrap(), 0x3C0BB000);
+    }
+}
@@ -1,30 +1,58 @@
-use n64::{cpu, rom, mips_iface, memory,rsp, rdram_iface, rdram_registers, rdram, icache};
+use n64::{cpu, rom, mips_iface, memory,rsp, rdram_iface, rdram_registers, rdram, icache, pif, si, save_type, sram, flash_ram, dp, rdp, vi, ai, pi};
 use n64::exceptions::Exception;
+use n64::rdp_commands::RDPCommand;
+
+const CART_ADDRESS_MASK: u32 = 0x1FFFFFFF;
 
 pub struct Connector
 {
     pub rom: rom::Rom,
     pub mips_interface: mips_iface::MipsInterface,
     pub rsp: rsp::RealitySignalProcessor,
+    pub dp: dp::DisplayProcessor,
+    pub rdp: rdp::Renderer,
+    pub vi: vi::VideoInterface,
+    pub ai: ai::AudioInterface,
+    pub pi: pi::PeripheralInterface,
     pub rdram_iface: rdram_iface::RDRAMInterface,
     pub rdram_registers: rdram_registers::RDRAMRegisters, 
     pub rdram: rdram::RDRAM,
     pub icache: icache::ICache,
+    pub pif: pif::PIF,
+    pub si: si::SerialInterface,
+    pub save_type: save_type::SaveType,
+    pub sram: Option<sram::SRAM>,
+    pub flash_ram: Option<flash_ram::FlashRAM>,
 }
 
 impl Connector
 {
-    pub fn new(filename: &str) -> Connector
+    pub fn new(filename: &str) -> Result<Connector, rom::RomError>
+    {
+        Ok(Connector::from_rom(rom::Rom::new(filename)?))
+    }
+
+    pub fn from_rom(rom: rom::Rom) -> Connector
     {
         return Connector
         {
-            rom: rom::Rom::new(filename),
+            save_type: save_type::detect_save_type(&rom.rom_header),
+            vi: vi::VideoInterface::new(rom.rom_header.tv_type()),
+            ai: ai::AudioInterface::new(rom.rom_header.tv_type()),
+            pi: pi::PeripheralInterface::new(),
+            rom: rom,
             mips_interface: mips_iface::MipsInterface::new(),
             rsp: rsp::RealitySignalProcessor::new(),
+            dp: dp::DisplayProcessor::new(),
+            rdp: rdp::Renderer::new(),
             rdram_iface: rdram_iface::RDRAMInterface::new(),
             rdram_registers: rdram_registers::RDRAMRegisters::new(),
             rdram: rdram::RDRAM::new(),
-            icache: icache::ICache::new()
+            icache: icache::ICache::new(),
+            pif: pif::PIF::new(),
+            si: si::SerialInterface::new(),
+            sram: None,
+            flash_ram: None,
         }
     }
 
@@ -32,13 +60,23 @@ impl Connector
     {
         return Connector
         {
+            save_type: save_type::SaveType::NONE,
             rom: rom::Rom::test(),
+            vi: vi::VideoInterface::new(rom::TvType::NTSC),
+            ai: ai::AudioInterface::new(rom::TvType::NTSC),
+            pi: pi::PeripheralInterface::new(),
             mips_interface: mips_iface::MipsInterface::new(),
             rsp: rsp::RealitySignalProcessor::new(),
+            dp: dp::DisplayProcessor::new(),
+            rdp: rdp::Renderer::new(),
             rdram_iface: rdram_iface::RDRAMInterface::new(),
             rdram_registers: rdram_registers::RDRAMRegisters::new(),
             rdram: rdram::RDRAM::new(),
-            icache: icache::ICache::new()
+            icache: icache::ICache::new(),
+            pif: pif::PIF::new(),
+            si: si::SerialInterface::new(),
+            sram: None,
+            flash_ram: None,
         }
     }
 
@@ -48,10 +86,20 @@ impl Connector
         match mapping.sector
         {
             memory::Sector::SP_REG => Ok(self.rsp.read_u32_from_address(mapping.mapped_address as usize).unwrap()),
+            memory::Sector::DP_COMMAND_REG => Ok(self.dp.read_u32_from_address(mapping.mapped_address as usize)?),
+            memory::Sector::DP_SPAN_REG => Ok(self.dp.read_span_u32_from_address(mapping.mapped_address as usize)?),
+            memory::Sector::VI_REG => Ok(self.vi.read_u32_from_address(mapping.mapped_address as usize)?),
+            memory::Sector::AI_REG => Ok(self.ai.read_u32_from_address(mapping.mapped_address as usize)?),
+            memory::Sector::PI_REG => Ok(self.pi.read_u32_from_address(mapping.mapped_address as usize)?),
             memory::Sector::RI_REG => Ok(self.rdram_iface.read_u32_from_address(mapping.mapped_address as usize).unwrap()),
             memory::Sector::MI_REG => Ok(self.mips_interface.read_u32_from_address(mapping.mapped_address as usize)?),
             memory::Sector::RDRAM_REG => Ok(self.rdram_registers.read_u32_from_address(mapping.mapped_address as usize)?),
             memory::Sector::RDRAM_MEM => Ok(self.rdram.read_u32_from_address(mapping.mapped_address as usize)?),
+            memory::Sector::PIF_BOOT_ROM => Ok(self.pif.read_boot_rom_u32(mapping.mapped_address as usize)?),
+            memory::Sector::PIF_RAM => Ok(self.pif.read_u32_from_address(mapping.mapped_address as usize)?),
+            memory::Sector::SI_REG => Ok(self.si.read_u32_from_address(mapping.mapped_address as usize)?),
+            memory::Sector::CD_2_ADDR_2 => self.read_cartridge_save_u32(mapping.mapped_address as usize),
+            memory::Sector::CD_1_ADDR_2 => Ok(self.rom.read_u32_from_address(mapping.mapped_address as usize)?),
             _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
         }
     }
@@ -70,15 +118,335 @@ impl Connector
         match mapping.sector
         {
             memory::Sector::RI_REG => self.rdram_iface.load_u32_to_address(mapping.mapped_address as usize, value).unwrap(),
-            memory::Sector::SP_REG => self.rsp.load_u32_to_address(mapping.mapped_address as usize, value).unwrap(),
+            memory::Sector::SP_REG => self.store_sp_u32(mapping.mapped_address as usize, value)?,
+            memory::Sector::DP_COMMAND_REG => self.dp.load_u32_to_address(mapping.mapped_address as usize, value)?,
+            memory::Sector::DP_SPAN_REG => self.dp.load_span_u32_to_address(mapping.mapped_address as usize, value)?,
+            memory::Sector::VI_REG => self.store_vi_u32(mapping.mapped_address as usize, value)?,
+            memory::Sector::AI_REG => self.store_ai_u32(mapping.mapped_address as usize, value)?,
+            memory::Sector::PI_REG => self.store_pi_u32(mapping.mapped_address as usize, value)?,
             memory::Sector::MI_REG => self.mips_interface.load_u32_to_address(mapping.mapped_address as usize, value)?,
             memory::Sector::RDRAM_REG => self.rdram_registers.load_u32_to_address(mapping.mapped_address as usize, value)?,
             memory::Sector::RDRAM_MEM => self.rdram.load_u32_to_address(mapping.mapped_address as usize, value)?,
+            //Boot rom is read only
+            memory::Sector::PIF_BOOT_ROM => (),
+            memory::Sector::PIF_RAM => self.pif.load_u32_to_address(mapping.mapped_address as usize, value)?,
+            memory::Sector::SI_REG => self.store_si_u32(mapping.mapped_address as usize, value)?,
+            memory::Sector::CD_2_ADDR_2 => self.store_cartridge_save_u32(mapping.mapped_address as usize, value)?,
+            //Cart ROM is read only
+            memory::Sector::CD_1_ADDR_2 => (),
             _ => return Err(Exception::UNIMPLEMENTED_ADDRESS),
         };
         Ok(())
     }
 
+    //RSP COP0 registers 0-7 are the SP registers and 8-15 are the DP command registers
+    pub fn read_rsp_cop0(&self, register: usize) -> Result<u32, Exception>
+    {
+        match register
+        {
+            0...7 => self.rsp.read_u32_from_address(rsp::SP_REGISTERS_START + (register * 4)).ok_or(Exception::UNIMPLEMENTED_ADDRESS),
+            8...15 => self.dp.read_u32_from_address((register - 8) * 4),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    pub fn store_rsp_cop0(&mut self, register: usize, value: u32) -> Result<(), Exception>
+    {
+        match register
+        {
+            0...7 => self.store_sp_u32(rsp::SP_REGISTERS_START + (register * 4), value),
+            8...15 => self.dp.load_u32_to_address((register - 8) * 4, value),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    fn store_sp_u32(&mut self, address: usize, value: u32) -> Result<(), Exception>
+    {
+        self.rsp.load_u32_to_address(address, value).map_err(|_| Exception::UNIMPLEMENTED_ADDRESS)?;
+        match self.rsp.pending_interrupt.take()
+        {
+            Some(true) => self.mips_interface.raise_interrupt(mips_iface::MI_INTR_SP),
+            Some(false) => self.mips_interface.clear_interrupt(mips_iface::MI_INTR_SP),
+            None => (),
+        }
+        match self.rsp.pending_dma.take()
+        {
+            Some(request) if self.rsp.is_dma_busy() => self.rsp.queue_dma(request),
+            Some(request) => self.rsp.start_dma(request),
+            None => (),
+        }
+        Ok(())
+    }
+
+    // Referenced: https://n64brew.dev/wiki/Reality_Signal_Processor/Interface#DMA
+    fn run_sp_dma(&mut self, request: rsp::SPDMARequest)
+    {
+        //The SP side wraps within the selected 4KB memory, the RDRAM side skips between rows
+        let bank = if request.is_imem() {0x1000} else {0};
+        let mut memory_address = request.memory_address as usize;
+        let mut dram_address = request.dram_address as usize;
+        for _ in 0..request.row_count()
+        {
+            for offset in 0..request.row_length()
+            {
+                let sp_address = bank + ((memory_address + offset) & rsp::SP_MEMORY_MASK);
+                //Unmapped RDRAM reads as zero and drops writes
+                match request.direction
+                {
+                    rsp::SPDMADirection::RDRAM_TO_SP =>
+                    {
+                        let value = self.rdram.read_u8(dram_address + offset).unwrap_or(0);
+                        self.rsp.load_u8_to_address(sp_address, value).unwrap();
+                    },
+                    rsp::SPDMADirection::SP_TO_RDRAM =>
+                    {
+                        let value = self.rsp.read_u8_from_address(sp_address).unwrap();
+                        self.rdram.store_u8(dram_address + offset, value).ok();
+                    },
+                }
+            }
+            memory_address = (memory_address + request.row_length()) & rsp::SP_MEMORY_MASK;
+            dram_address += request.row_length() + request.skip();
+        }
+        self.rsp.finish_dma(&request, (bank + memory_address) as u32, dram_address as u32);
+    }
+
+    // Referenced: https://n64brew.dev/wiki/Reality_Display_Processor/Interface
+    fn run_dp_transfer(&mut self, transfer: dp::DPTransfer)
+    {
+        //XBUS fetches wrap within DMEM, unmapped RDRAM reads as zero
+        let read_byte = |connector: &Connector, address: usize| if transfer.xbus
+        {
+            connector.rsp.read_dmem_u8(address)
+        }
+        else
+        {
+            connector.rdram.read_u8(address).unwrap_or(0)
+        };
+        let words: Vec<u64> = (0..transfer.word_count()).map(|index|
+        {
+            let address = transfer.start as usize + (index * 8);
+            (0..8).fold(0_u64, |word, offset| (word << 8) | read_byte(self, address + offset) as u64)
+        }).collect();
+        self.dp.receive_transfer(&transfer, &words);
+    }
+
+    fn store_vi_u32(&mut self, address: usize, value: u32) -> Result<(), Exception>
+    {
+        self.vi.load_u32_to_address(address, value)?;
+        if let Some(false) = self.vi.pending_interrupt.take()
+        {
+            self.mips_interface.clear_interrupt(mips_iface::MI_INTR_VI);
+        }
+        Ok(())
+    }
+
+    fn store_ai_u32(&mut self, address: usize, value: u32) -> Result<(), Exception>
+    {
+        self.ai.load_u32_to_address(address, value)?;
+        if let Some(false) = self.ai.pending_interrupt.take()
+        {
+            self.mips_interface.clear_interrupt(mips_iface::MI_INTR_AI);
+        }
+        Ok(())
+    }
+
+    fn store_pi_u32(&mut self, address: usize, value: u32) -> Result<(), Exception>
+    {
+        self.pi.load_u32_to_address(address, value)?;
+        if let Some(false) = self.pi.pending_interrupt.take()
+        {
+            self.mips_interface.clear_interrupt(mips_iface::MI_INTR_PI);
+        }
+        Ok(())
+    }
+
+    // Referenced: https://n64brew.dev/wiki/Peripheral_Interface#DMA
+    fn run_pi_dma(&mut self, request: pi::PIDMARequest)
+    {
+        //Only the physical bus address reaches the cart
+        let mapping = memory::MemoryMapping::new(request.cart_address & CART_ADDRESS_MASK);
+        let cart_address = mapping.mapped_address as usize;
+        let dram_address = request.dram_address as usize;
+        let length = request.length as usize;
+        match request.direction
+        {
+            pi::PIDMADirection::CART_TO_RDRAM =>
+            {
+                let data: Vec<u8> = match mapping.sector
+                {
+                    memory::Sector::CD_1_ADDR_2 => (0..length).map(|offset| self.rom.read_u8(cart_address + offset)).collect(),
+                    memory::Sector::CD_2_ADDR_2 => self.read_cartridge_save_bytes(cart_address, length),
+                    _ => vec![0; length],
+                };
+                //Unmapped RDRAM drops writes
+                for (offset, value) in data.iter().enumerate()
+                {
+                    self.rdram.store_u8(dram_address + offset, *value).ok();
+                }
+            },
+            pi::PIDMADirection::RDRAM_TO_CART =>
+            {
+                //Cart ROM ignores writes, unmapped RDRAM reads as zero
+                if mapping.sector == memory::Sector::CD_2_ADDR_2
+                {
+                    let data: Vec<u8> = (0..length).map(|offset| self.rdram.read_u8(dram_address + offset).unwrap_or(0)).collect();
+                    self.store_cartridge_save_bytes(cart_address, &data);
+                }
+            },
+        }
+    }
+
+    //Converts the framebuffer the VI currently points at
+    pub fn scanout(&self) -> vi::Frame
+    {
+        self.vi.scanout(&self.rdram)
+    }
+
+    //Commands produced by HLE microcode go straight to the RDP
+    pub fn submit_rdp_commands(&mut self, words: &[u64])
+    {
+        self.dp.submit_commands(words);
+    }
+
+    //Draws everything the DP has parsed so far into RDRAM, the DP interrupt follows each SYNC_FULL once it is drawn
+    pub fn run_rdp(&mut self)
+    {
+        let commands: Vec<RDPCommand> = self.dp.commands.drain(..).collect();
+        for command in commands.iter()
+        {
+            self.rdp.execute(command, &mut self.rdram);
+            if *command == RDPCommand::SYNC_FULL
+            {
+                self.dp.finish_full_sync();
+                self.mips_interface.raise_interrupt(mips_iface::MI_INTR_DP);
+            }
+        }
+    }
+
+    //Domain 2 holds whichever of SRAM or FlashRAM the cartridge has
+    fn read_cartridge_save_u32(&self, address: usize) -> Result<u32, Exception>
+    {
+        match (&self.sram, &self.flash_ram)
+        {
+            (&Some(ref sram), _) => sram.read_u32_from_address(address),
+            (_, &Some(ref flash_ram)) => flash_ram.read_u32_from_address(address),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    fn read_cartridge_save_bytes(&self, address: usize, length: usize) -> Vec<u8>
+    {
+        match (&self.sram, &self.flash_ram)
+        {
+            (&Some(ref sram), _) => sram.dma_read(address, length),
+            (_, &Some(ref flash_ram)) => flash_ram.dma_read(address, length),
+            _ => vec![0; length],
+        }
+    }
+
+    fn store_cartridge_save_bytes(&mut self, address: usize, data: &[u8])
+    {
+        match (&mut self.sram, &mut self.flash_ram)
+        {
+            (&mut Some(ref mut sram), _) => sram.dma_write(address, data),
+            (_, &mut Some(ref mut flash_ram)) => flash_ram.dma_write(address, data),
+            _ => (),
+        }
+    }
+
+    fn store_cartridge_save_u32(&mut self, address: usize, value: u32) -> Result<(), Exception>
+    {
+        match (&mut self.sram, &mut self.flash_ram)
+        {
+            (&mut Some(ref mut sram), _) => sram.load_u32_to_address(address, value),
+            (_, &mut Some(ref mut flash_ram)) => flash_ram.load_u32_to_address(address, value),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    fn store_si_u32(&mut self, address: usize, value: u32) -> Result<(), Exception>
+    {
+        let interrupt_set = self.si.status.get_value() as u32 & si::SI_STATUS_INTERRUPT != 0;
+        self.si.load_u32_to_address(address, value)?;
+        if interrupt_set && self.si.status.get_value() as u32 & si::SI_STATUS_INTERRUPT == 0
+        {
+            self.mips_interface.clear_interrupt(mips_iface::MI_INTR_SI);
+        }
+        Ok(())
+    }
+
+    // Referenced: https://n64brew.dev/wiki/Serial_Interface
+    fn run_si_dma(&mut self, request: si::SIDMARequest)
+    {
+        let dram_address = request.dram_address as usize;
+        match request.direction
+        {
+            si::SIDMADirection::PIF_TO_RDRAM =>
+            {
+                self.pif.set_frame(self.vi.field_count);
+                self.pif.process_joybus_commands();
+                let data = self.pif.ram.to_vec();
+                //Unmapped RDRAM drops writes
+                self.rdram.store_bytes(dram_address, &data).ok();
+            },
+            si::SIDMADirection::RDRAM_TO_PIF =>
+            {
+                //Unmapped RDRAM reads as zero
+                self.pif.ram = self.rdram.read_bytes(dram_address, pif::PIF_RAM_SIZE).unwrap_or_else(|_| vec![0; pif::PIF_RAM_SIZE]);
+                self.pif.process_control_byte();
+            },
+        }
+    }
+
+    //Advances timed device activity such as DMA completion
+    pub fn step(&mut self, cycles: u32)
+    {
+        self.dp.step(cycles);
+        self.run_rdp();
+        //Commands handed over by a write to END are fetched here, so END_VALID is seen until the next step
+        match self.dp.take_transfer()
+        {
+            Some(transfer) => self.run_dp_transfer(transfer),
+            None => (),
+        }
+        if self.vi.step(cycles)
+        {
+            self.mips_interface.raise_interrupt(mips_iface::MI_INTR_VI);
+        }
+        match self.pi.step(cycles)
+        {
+            Some(request) =>
+            {
+                self.run_pi_dma(request);
+                self.mips_interface.raise_interrupt(mips_iface::MI_INTR_PI);
+            },
+            None => (),
+        }
+        if self.ai.step(cycles, &self.rdram)
+        {
+            self.mips_interface.raise_interrupt(mips_iface::MI_INTR_AI);
+        }
+        match self.si.step(cycles)
+        {
+            Some(request) =>
+            {
+                self.run_si_dma(request);
+                self.mips_interface.raise_interrupt(mips_iface::MI_INTR_SI);
+            },
+            None => (),
+        }
+        match self.rsp.step(cycles)
+        {
+            Some(request) =>
+            {
+                self.run_sp_dma(request);
+                self.rsp.start_queued_dma();
+            },
+            None => (),
+        }
+    }
+
     pub fn store_u8(&mut self, mut address: u32, value: u8) -> Result<(), Exception>
     {
         let offset = address % 4;
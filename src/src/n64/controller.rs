@@ -0,0 +1,359 @@
+use n64::joybus::{JoybusDevice, JoybusError, check_command_size, JOYBUS_COMMAND_STATUS, JOYBUS_COMMAND_RESET};
+use n64::save_file::SaveError;
+use std::collections::VecDeque;
+
+pub const CONTROLLER_PORTS: usize = 4;
+
+const CONTROLLER_COMMAND_READ_BUTTONS: u8 = 0x01;
+const CONTROLLER_COMMAND_READ_ACCESSORY: u8 = 0x02;
+const CONTROLLER_COMMAND_WRITE_ACCESSORY: u8 = 0x03;
+pub const ACCESSORY_BLOCK_SIZE: usize = 32;
+const ACCESSORY_ADDRESS_MASK: u16 = 0xFFE0;
+const ACCESSORY_ADDRESS_CRC_MASK: u16 = 0x001F;
+// Referenced: libdragon's controller pak address checksum
+const ACCESSORY_ADDRESS_CRC_TABLE: [u8; 11] = [0x15, 0x1F, 0x0B, 0x16, 0x19, 0x07, 0x0E, 0x1C, 0x0D, 0x1A, 0x01];
+
+// Referenced: https://n64brew.dev/wiki/Joybus_Protocol
+const CONTROLLER_IDENTIFIER: u16 = 0x0500;
+const CONTROLLER_PAK_PRESENT: u8 = 0x01;
+const CONTROLLER_PAK_NOT_PRESENT: u8 = 0x02;
+
+//Button bits as returned by the read buttons command
+pub const BUTTON_A: u16 = 0x8000;
+pub const BUTTON_B: u16 = 0x4000;
+pub const BUTTON_Z: u16 = 0x2000;
+pub const BUTTON_START: u16 = 0x1000;
+pub const BUTTON_D_UP: u16 = 0x0800;
+pub const BUTTON_D_DOWN: u16 = 0x0400;
+pub const BUTTON_D_LEFT: u16 = 0x0200;
+pub const BUTTON_D_RIGHT: u16 = 0x0100;
+pub const BUTTON_L: u16 = 0x0020;
+pub const BUTTON_R: u16 = 0x0010;
+pub const BUTTON_C_UP: u16 = 0x0008;
+pub const BUTTON_C_DOWN: u16 = 0x0004;
+pub const BUTTON_C_LEFT: u16 = 0x0002;
+pub const BUTTON_C_RIGHT: u16 = 0x0001;
+//Names used by input scripts
+const BUTTON_NAMES: [(&str, u16); 14] = [
+    ("A", BUTTON_A), ("B", BUTTON_B), ("Z", BUTTON_Z), ("START", BUTTON_START),
+    ("D_UP", BUTTON_D_UP), ("D_DOWN", BUTTON_D_DOWN), ("D_LEFT", BUTTON_D_LEFT), ("D_RIGHT", BUTTON_D_RIGHT),
+    ("L", BUTTON_L), ("R", BUTTON_R),
+    ("C_UP", BUTTON_C_UP), ("C_DOWN", BUTTON_C_DOWN), ("C_LEFT", BUTTON_C_LEFT), ("C_RIGHT", BUTTON_C_RIGHT),
+];
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+pub struct ControllerState
+{
+    pub buttons: u16,
+    pub stick_x: i8,
+    pub stick_y: i8,
+}
+
+impl ControllerState
+{
+    pub fn new(buttons: u16, stick_x: i8, stick_y: i8) -> ControllerState
+    {
+        return ControllerState
+        {
+            buttons: buttons,
+            stick_x: stick_x,
+            stick_y: stick_y,
+        }
+    }
+
+    pub fn neutral() -> ControllerState
+    {
+        ControllerState::new(0, 0, 0)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 4]
+    {
+        [(self.buttons >> 8) as u8, self.buttons as u8, self.stick_x as u8, self.stick_y as u8]
+    }
+}
+
+//Supplies controller state each time the game polls the controller, frame counts the VI frames finished so far
+pub trait InputSource
+{
+    fn poll(&mut self, frame: u64) -> ControllerState;
+}
+
+//A fixed state, for ports that should always read the same
+impl InputSource for ControllerState
+{
+    fn poll(&mut self, _frame: u64) -> ControllerState
+    {
+        *self
+    }
+}
+
+//Plays back one state per VI frame however often the game polls, holding the last state once the script runs out
+pub struct ScriptedInput
+{
+    pub states: VecDeque<ControllerState>,
+    pub last_state: ControllerState,
+    //Frame the front of the script belongs to
+    pub next_frame: u64,
+    pub poll_count: u64,
+}
+
+impl ScriptedInput
+{
+    pub fn new(states: Vec<ControllerState>) -> ScriptedInput
+    {
+        return ScriptedInput
+        {
+            states: states.into_iter().collect(),
+            last_state: ControllerState::neutral(),
+            next_frame: 0,
+            poll_count: 0,
+        }
+    }
+
+    //Holds a state for a number of frames
+    pub fn hold(&mut self, state: ControllerState, frames: usize)
+    {
+        for _ in 0..frames
+        {
+            self.states.push_back(state);
+        }
+    }
+
+    pub fn press(&mut self, buttons: u16, frames: usize)
+    {
+        self.hold(ControllerState::new(buttons, 0, 0), frames);
+    }
+
+    //One step per line: a frame count, buttons joined by '+' or "none", then an optional stick x and y
+    //e.g. "30 none", "2 A+START" or "10 none 0 127"
+    pub fn from_script(text: &str) -> Result<ScriptedInput, String>
+    {
+        let mut input = ScriptedInput::new(Vec::new());
+        for (index, line) in text.lines().enumerate()
+        {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#')
+            {
+                continue;
+            }
+            let error = |message: String| format!("Input script line {}: {}", index + 1, message);
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 2 && fields.len() != 4
+            {
+                return Err(error(format!("expected frames, buttons and an optional stick position, found \"{}\"", line)));
+            }
+            let frames = fields[0].parse::<usize>().map_err(|_| error(format!("invalid frame count \"{}\"", fields[0])))?;
+            let mut buttons = 0;
+            if !fields[1].eq_ignore_ascii_case("none")
+            {
+                for name in fields[1].split('+')
+                {
+                    buttons |= button_from_name(name).ok_or_else(|| error(format!("unknown button \"{}\"", name)))?;
+                }
+            }
+            if fields.len() == 2
+            {
+                input.press(buttons, frames);
+                continue;
+            }
+            let stick_x = fields[2].parse::<i8>().map_err(|_| error(format!("invalid stick position \"{}\"", fields[2])))?;
+            let stick_y = fields[3].parse::<i8>().map_err(|_| error(format!("invalid stick position \"{}\"", fields[3])))?;
+            input.hold(ControllerState::new(buttons, stick_x, stick_y), frames);
+        }
+        Ok(input)
+    }
+}
+
+pub fn button_from_name(name: &str) -> Option<u16>
+{
+    BUTTON_NAMES.iter().find(|&&(button_name, _)| button_name.eq_ignore_ascii_case(name)).map(|&(_, button)| button)
+}
+
+impl InputSource for ScriptedInput
+{
+    fn poll(&mut self, frame: u64) -> ControllerState
+    {
+        self.poll_count += 1;
+        //Frames the game did not poll in still use up their states
+        while self.next_frame <= frame
+        {
+            match self.states.pop_front()
+            {
+                Some(state) => self.last_state = state,
+                None => break,
+            }
+            self.next_frame += 1;
+        }
+        self.last_state
+    }
+}
+
+//Anything plugged into the controller's accessory port, addressed in 32 byte blocks
+pub trait ControllerAccessory
+{
+    fn read_block(&mut self, address: u16, data: &mut [u8]);
+    fn write_block(&mut self, address: u16, data: &[u8]);
+
+    fn flush(&mut self) -> Result<(), SaveError>
+    {
+        Ok(())
+    }
+}
+
+pub struct Controller
+{
+    pub input: Box<dyn InputSource>,
+    pub accessory: Option<Box<dyn ControllerAccessory>>,
+    pub frame: u64,
+}
+
+impl Controller
+{
+    pub fn new(input: Box<dyn InputSource>) -> Controller
+    {
+        return Controller
+        {
+            input: input,
+            accessory: None,
+            frame: 0,
+        }
+    }
+
+    pub fn with_accessory(input: Box<dyn InputSource>, accessory: Box<dyn ControllerAccessory>) -> Controller
+    {
+        return Controller
+        {
+            input: input,
+            accessory: Some(accessory),
+            frame: 0,
+        }
+    }
+
+    fn pak_status(&self) -> u8
+    {
+        match self.accessory
+        {
+            Some(_) => CONTROLLER_PAK_PRESENT,
+            None => CONTROLLER_PAK_NOT_PRESENT,
+        }
+    }
+
+    fn read_accessory(&mut self, address: u16, receive: &mut [u8])
+    {
+        let (data, crc) = receive.split_at_mut(ACCESSORY_BLOCK_SIZE);
+        for value in data.iter_mut()
+        {
+            *value = 0;
+        }
+        let valid_address = accessory_address_crc(address) == (address & ACCESSORY_ADDRESS_CRC_MASK) as u8;
+        match self.accessory
+        {
+            Some(ref mut accessory) if valid_address =>
+            {
+                accessory.read_block(address & ACCESSORY_ADDRESS_MASK, data);
+                crc[0] = accessory_data_crc(data);
+            },
+            //An inverted CRC tells the game the transfer failed
+            _ => crc[0] = !accessory_data_crc(data),
+        }
+    }
+
+    fn write_accessory(&mut self, address: u16, data: &[u8], receive: &mut [u8])
+    {
+        let valid_address = accessory_address_crc(address) == (address & ACCESSORY_ADDRESS_CRC_MASK) as u8;
+        match self.accessory
+        {
+            Some(ref mut accessory) if valid_address =>
+            {
+                accessory.write_block(address & ACCESSORY_ADDRESS_MASK, data);
+                receive[0] = accessory_data_crc(data);
+            },
+            _ => receive[0] = !accessory_data_crc(data),
+        }
+    }
+}
+
+impl JoybusDevice for Controller
+{
+    fn process_command(&mut self, send: &[u8], receive: &mut [u8]) -> Result<(), JoybusError>
+    {
+        match send[0]
+        {
+            JOYBUS_COMMAND_STATUS | JOYBUS_COMMAND_RESET =>
+            {
+                check_command_size(send, receive, 1, 3)?;
+                receive.copy_from_slice(&[(CONTROLLER_IDENTIFIER >> 8) as u8, CONTROLLER_IDENTIFIER as u8, self.pak_status()]);
+                Ok(())
+            },
+            CONTROLLER_COMMAND_READ_BUTTONS =>
+            {
+                check_command_size(send, receive, 1, 4)?;
+                receive.copy_from_slice(&self.input.poll(self.frame).to_bytes());
+                Ok(())
+            },
+            CONTROLLER_COMMAND_READ_ACCESSORY =>
+            {
+                check_command_size(send, receive, 3, ACCESSORY_BLOCK_SIZE + 1)?;
+                self.read_accessory((send[1] as u16) << 8 | (send[2] as u16), receive);
+                Ok(())
+            },
+            CONTROLLER_COMMAND_WRITE_ACCESSORY =>
+            {
+                check_command_size(send, receive, ACCESSORY_BLOCK_SIZE + 3, 1)?;
+                self.write_accessory((send[1] as u16) << 8 | (send[2] as u16), &send[3..], receive);
+                Ok(())
+            },
+            _ => Err(JoybusError::NO_RESPONSE),
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), SaveError>
+    {
+        match self.accessory
+        {
+            Some(ref mut accessory) => accessory.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn set_frame(&mut self, frame: u64)
+    {
+        self.frame = frame;
+    }
+}
+
+//Five bit checksum carried in the low bits of accessory addresses
+pub fn accessory_address_crc(address: u16) -> u8
+{
+    let mut crc: u8 = 0;
+    for bit in 0..ACCESSORY_ADDRESS_CRC_TABLE.len()
+    {
+        if address & (0x0020 << bit) != 0
+        {
+            crc ^= ACCESSORY_ADDRESS_CRC_TABLE[bit];
+        }
+    }
+    crc & 0x1F
+}
+
+// Referenced: libdragon's controller pak data checksum (polynomial 0x85)
+pub fn accessory_data_crc(data: &[u8]) -> u8
+{
+    let mut crc: u8 = 0;
+    for index in 0..(data.len() + 1)
+    {
+        for bit in (0..8).rev()
+        {
+            let feedback = if crc & 0x80 != 0 {0x85} else {0x00};
+            crc <<= 1;
+            if index < data.len() && data[index] & (1 << bit) != 0
+            {
+                crc |= 0x01;
+            }
+            crc ^= feedback;
+        }
+    }
+    crc
+}
@@ -0,0 +1,149 @@
+#[cfg(test)]
+mod controller_tests
+{
+    use n64::controller::*;
+    use n64::joybus::*;
+    use n64::n64::N64;
+    use n64::rom::Rom;
+    use n64::pif::PIF_CONTROL_JOYBUS;
+    use n64::si::SI_DMA_DURATION;
+
+    #[test]
+    fn status_identifies_standard_controller()
+    {
+        let mut controller = Controller::new(Box::new(ControllerState::neutral()));
+        let mut receive = [0_u8; 3];
+        controller.process_command(&[JOYBUS_COMMAND_STATUS], &mut receive).unwrap();
+        assert_eq!(receive, [0x05, 0x00, 0x02]);
+        controller.process_command(&[JOYBUS_COMMAND_RESET], &mut receive).unwrap();
+        assert_eq!(receive, [0x05, 0x00, 0x02]);
+        assert_eq!(controller.process_command(&[JOYBUS_COMMAND_STATUS], &mut [0_u8; 2]).err(), Some(JoybusError::SIZE_MISMATCH));
+    }
+
+    #[test]
+    fn read_buttons_returns_input_state()
+    {
+        let mut controller = Controller::new(Box::new(ControllerState::new(BUTTON_A | BUTTON_C_RIGHT, -128, 80)));
+        let mut receive = [0_u8; 4];
+        controller.process_command(&[0x01], &mut receive).unwrap();
+        assert_eq!(receive, [0x80, 0x01, 0x80, 0x50]);
+        assert_eq!(controller.process_command(&[0x55], &mut receive).err(), Some(JoybusError::NO_RESPONSE));
+    }
+
+    #[test]
+    fn scripted_input_plays_back_then_holds()
+    {
+        let mut input = ScriptedInput::new(vec![ControllerState::neutral()]);
+        input.press(BUTTON_START, 2);
+        input.hold(ControllerState::new(BUTTON_Z, 10, -10), 1);
+
+        assert_eq!(input.poll(0), ControllerState::neutral());
+        assert_eq!(input.poll(1).buttons, BUTTON_START);
+        assert_eq!(input.poll(2).buttons, BUTTON_START);
+        assert_eq!(input.poll(3), ControllerState::new(BUTTON_Z, 10, -10));
+        assert_eq!(input.poll(4), ControllerState::new(BUTTON_Z, 10, -10));
+        assert_eq!(input.poll_count, 5);
+    }
+
+    #[test]
+    fn scripted_input_follows_frames_not_polls()
+    {
+        let mut input = ScriptedInput::new(Vec::new());
+        input.press(BUTTON_A, 1);
+        input.press(BUTTON_B, 1);
+        input.press(BUTTON_L, 1);
+        input.press(BUTTON_R, 1);
+
+        //Several polls in one frame see the same state
+        assert_eq!(input.poll(0).buttons, BUTTON_A);
+        assert_eq!(input.poll(0).buttons, BUTTON_A);
+        assert_eq!(input.poll(1).buttons, BUTTON_B);
+        //A frame without a poll is skipped over
+        assert_eq!(input.poll(3).buttons, BUTTON_R);
+        assert_eq!(input.poll(9).buttons, BUTTON_R);
+    }
+
+    #[test]
+    fn input_scripts_are_parsed_into_frames()
+    {
+        let mut input = ScriptedInput::from_script("
+            ; Wait for the title screen, then press start
+            2 none
+            1 a+START
+            # Walk forward
+            2 Z -5 127
+        ").unwrap();
+        assert_eq!(input.states.len(), 5);
+        assert_eq!(input.poll(1), ControllerState::neutral());
+        assert_eq!(input.poll(2).buttons, BUTTON_A | BUTTON_START);
+        assert_eq!(input.poll(4), ControllerState::new(BUTTON_Z, -5, 127));
+        assert_eq!(button_from_name("c_right"), Some(BUTTON_C_RIGHT));
+
+        match ScriptedInput::from_script("1 A\n2 X")
+        {
+            Err(e) => assert_eq!(e, "Input script line 2: unknown button \"X\""),
+            Ok(_) => panic!("expected a script error"),
+        }
+        assert!(ScriptedInput::from_script("many A").is_err());
+        assert!(ScriptedInput::from_script("1 A 0").is_err());
+        assert!(ScriptedInput::from_script("1 A 0 128").is_err());
+    }
+
+    //Read buttons on every port through SI DMA, as libultra's osContStartReadData does
+    fn read_buttons_through_si(n64: &mut N64) -> Vec<u8>
+    {
+        let mut block: Vec<u8> = Vec::new();
+        for _ in 0..CONTROLLER_PORTS
+        {
+            block.extend_from_slice(&[0xFF, 0x01, 0x04, 0x01, 0xFF, 0xFF, 0xFF, 0xFF]);
+        }
+        block.push(0xFE);
+        block.resize(0x40, 0);
+        block[0x3F] = PIF_CONTROL_JOYBUS;
+        n64.connector.rdram.store_bytes(0x3000, &block).unwrap();
+
+        n64.connector.store_u32(0xA4800000, 0x3000).unwrap();
+        n64.connector.store_u32(0xA4800010, 0x1FC007C0).unwrap();
+        n64.connector.step(SI_DMA_DURATION);
+        n64.connector.store_u32(0xA4800000, 0x3000).unwrap();
+        n64.connector.store_u32(0xA4800004, 0x1FC007C0).unwrap();
+        n64.connector.step(SI_DMA_DURATION);
+        n64.connector.rdram.read_bytes(0x3000, 0x20).unwrap()
+    }
+
+    #[test]
+    fn controllers_are_polled_through_si()
+    {
+        let mut n64 = N64::from_rom(Rom::test());
+        let mut input = ScriptedInput::new(Vec::new());
+        input.press(BUTTON_B, 1);
+        n64.connect_controller(1, Box::new(input));
+
+        let result = read_buttons_through_si(&mut n64);
+        assert_eq!(result[2], 0x04 | JOYBUS_NO_RESPONSE);
+        assert_eq!(result[8..16].to_vec(), vec![0xFF, 0x01, 0x04, 0x01, 0x40, 0x00, 0x00, 0x00]);
+        assert_eq!(result[18], 0x04 | JOYBUS_NO_RESPONSE);
+    }
+
+    #[test]
+    fn scripts_advance_with_vi_frames()
+    {
+        let mut n64 = N64::from_rom(Rom::test());
+        let mut input = ScriptedInput::new(Vec::new());
+        input.press(BUTTON_B, 1);
+        input.press(BUTTON_A, 1);
+        n64.connect_controller(0, Box::new(input));
+
+        //Two polls in the first frame
+        assert_eq!(read_buttons_through_si(&mut n64)[4..6].to_vec(), vec![0x40, 0x00]);
+        assert_eq!(read_buttons_through_si(&mut n64)[4..6].to_vec(), vec![0x40, 0x00]);
+
+        //Run out the rest of the field, the polls themselves take a few lines
+        let line = n64.connector.vi.cycles_per_line() as u32;
+        while n64.connector.vi.field_count == 0
+        {
+            n64.step_devices(line);
+        }
+        assert_eq!(read_buttons_through_si(&mut n64)[4..6].to_vec(), vec![0x80, 0x00]);
+    }
+}
@@ -43,6 +43,12 @@ impl CPU
         self.program_counter.set_value(0xA4000040_u32);
     }
 
+    pub fn set_cold_reset_values(&mut self)
+    {
+        //Reset exception vector in the PIF boot rom
+        self.program_counter.set_value(0xBFC00000_u32);
+    }
+
     pub fn retrieve_opcode(&mut self, connector: &Connector) -> Opcode
     {
         let pc: u32 = self.program_counter.get_value() as u32;
@@ -118,9 +124,8 @@ impl CPURegisters
     pub fn set_pif_rom_values(&mut self)
     {
         // Referenced: http://www.emulation64.com/ultra64/bootn64.html
-        self.register[CPURegisterName::s4 as usize].set_value(0x00000001_u32);
-        self.register[CPURegisterName::s6 as usize].set_value(0x0000003F_u32);
         self.register[CPURegisterName::sp as usize].set_value(0xA4001FF0_u32);
+        self.register[CPURegisterName::ra as usize].set_value(0xA4001550_u32);
     }
 
 
@@ -272,6 +277,16 @@ impl COP0Registers
         self.register[COP0RegisterName::Config as usize].set_value(0x0006E463_u32);
     }
 
+    pub fn set_cold_reset_values(&mut self)
+    {
+        //Cold reset leaves the CPU in kernel mode with BEV and ERL set
+        self.register[COP0RegisterName::Random as usize].set_value(0x0000001F_u32);
+        self.register[COP0RegisterName::Wired as usize].set_value(0x00000000_u32);
+        self.register[COP0RegisterName::Status as usize].set_value(0x00400004_u32);
+        self.register[COP0RegisterName::PRevID as usize].set_value(0x00000B00_u32);
+        self.register[COP0RegisterName::Config as usize].set_value(0x0006E463_u32);
+    }
+
 
 
     pub fn Debug(&self)
@@ -0,0 +1,349 @@
+const DPC_START_REG_START: usize = 0x00000000;
+const DPC_START_REG_END: usize = 0x00000003;
+const DPC_END_REG_START: usize = 0x00000004;
+const DPC_END_REG_END: usize = 0x00000007;
+const DPC_CURRENT_REG_START: usize = 0x00000008;
+const DPC_CURRENT_REG_END: usize = 0x0000000B;
+const DPC_STATUS_REG_START: usize = 0x0000000C;
+const DPC_STATUS_REG_END: usize = 0x0000000F;
+const DPC_CLOCK_REG_START: usize = 0x00000010;
+const DPC_CLOCK_REG_END: usize = 0x00000013;
+const DPC_BUFBUSY_REG_START: usize = 0x00000014;
+const DPC_BUFBUSY_REG_END: usize = 0x00000017;
+const DPC_PIPEBUSY_REG_START: usize = 0x00000018;
+const DPC_PIPEBUSY_REG_END: usize = 0x0000001B;
+const DPC_TMEM_REG_START: usize = 0x0000001C;
+const DPC_TMEM_REG_END: usize = 0x0000001F;
+
+const DPS_TBIST_REG_START: usize = 0x00000000;
+const DPS_TBIST_REG_END: usize = 0x00000003;
+const DPS_TEST_MODE_REG_START: usize = 0x00000004;
+const DPS_TEST_MODE_REG_END: usize = 0x00000007;
+const DPS_BUFTEST_ADDR_REG_START: usize = 0x00000008;
+const DPS_BUFTEST_ADDR_REG_END: usize = 0x0000000B;
+const DPS_BUFTEST_DATA_REG_START: usize = 0x0000000C;
+const DPS_BUFTEST_DATA_REG_END: usize = 0x0000000F;
+
+//DPC_STATUS read bits
+pub const DPC_STATUS_XBUS_DMEM_DMA: u32 = 0x0001;
+pub const DPC_STATUS_FREEZE: u32 = 0x0002;
+pub const DPC_STATUS_FLUSH: u32 = 0x0004;
+pub const DPC_STATUS_START_GCLK: u32 = 0x0008;
+pub const DPC_STATUS_TMEM_BUSY: u32 = 0x0010;
+pub const DPC_STATUS_PIPE_BUSY: u32 = 0x0020;
+pub const DPC_STATUS_CMD_BUSY: u32 = 0x0040;
+pub const DPC_STATUS_CBUF_READY: u32 = 0x0080;
+pub const DPC_STATUS_DMA_BUSY: u32 = 0x0100;
+pub const DPC_STATUS_END_VALID: u32 = 0x0200;
+pub const DPC_STATUS_START_VALID: u32 = 0x0400;
+
+//DPC_STATUS write bits, pairs clear or set one status bit and the rest clear counters
+const DPC_CLR_XBUS_DMEM_DMA: u32 = 0x0001;
+const DPC_SET_XBUS_DMEM_DMA: u32 = 0x0002;
+const DPC_CLR_FREEZE: u32 = 0x0004;
+const DPC_SET_FREEZE: u32 = 0x0008;
+const DPC_CLR_FLUSH: u32 = 0x0010;
+const DPC_SET_FLUSH: u32 = 0x0020;
+const DPC_CLR_TMEM_CTR: u32 = 0x0040;
+const DPC_CLR_PIPE_CTR: u32 = 0x0080;
+const DPC_CLR_CMD_CTR: u32 = 0x0100;
+const DPC_CLR_CLOCK_CTR: u32 = 0x0200;
+
+const DPC_ADDRESS_MASK: u32 = 0x00FFFFF8;
+//Counters are 24 bits wide
+const DPC_COUNTER_MASK: u32 = 0x00FFFFFF;
+
+use n64::arch::Reg;
+use n64::exceptions::Exception;
+use n64::rdp_commands::{RDPCommand, parse_command};
+
+//Command words waiting to be fetched, from DMEM over the XBUS or from RDRAM
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+pub struct DPTransfer
+{
+    pub start: u32,
+    pub end: u32,
+    pub xbus: bool,
+}
+
+impl DPTransfer
+{
+    pub fn word_count(&self) -> usize
+    {
+        (self.end.saturating_sub(self.start) / 8) as usize
+    }
+}
+
+pub struct DisplayProcessor
+{
+    pub start: Reg,
+    pub end: Reg,
+    pub current: Reg,
+    pub status: Reg,
+    pub clock: Reg,
+    pub buffer_busy: Reg,
+    pub pipe_busy: Reg,
+    pub tmem_busy: Reg,
+    pub tbist: Reg,
+    pub test_mode: Reg,
+    pub buffer_test_address: Reg,
+    pub buffer_test_data: Reg,
+    //Parsed commands waiting for the renderer
+    pub commands: Vec<RDPCommand>,
+    //Words of a command that has not been fully fetched yet
+    buffer: Vec<u64>,
+    pending_transfer: bool,
+}
+
+impl DisplayProcessor
+{
+    pub fn new() -> DisplayProcessor
+    {
+        let mut status = Reg::default();
+        status.set_value(DPC_STATUS_CBUF_READY);
+        return DisplayProcessor
+        {
+            start: Reg::default(),
+            end: Reg::default(),
+            current: Reg::default(),
+            status: status,
+            clock: Reg::default(),
+            buffer_busy: Reg::default(),
+            pipe_busy: Reg::default(),
+            tmem_busy: Reg::default(),
+            tbist: Reg::default(),
+            test_mode: Reg::default(),
+            buffer_test_address: Reg::default(),
+            buffer_test_data: Reg::default(),
+            commands: Vec::new(),
+            buffer: Vec::new(),
+            pending_transfer: false,
+        }
+    }
+
+    pub fn read_u32_from_address(&self, address: usize) -> Result<u32, Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match address
+        {
+            DPC_START_REG_START...DPC_START_REG_END => Ok(self.start.get_value() as u32),
+            DPC_END_REG_START...DPC_END_REG_END => Ok(self.end.get_value() as u32),
+            DPC_CURRENT_REG_START...DPC_CURRENT_REG_END => Ok(self.current.get_value() as u32),
+            DPC_STATUS_REG_START...DPC_STATUS_REG_END => Ok(self.status.get_value() as u32),
+            DPC_CLOCK_REG_START...DPC_CLOCK_REG_END => Ok(self.clock.get_value() as u32),
+            DPC_BUFBUSY_REG_START...DPC_BUFBUSY_REG_END => Ok(self.buffer_busy.get_value() as u32),
+            DPC_PIPEBUSY_REG_START...DPC_PIPEBUSY_REG_END => Ok(self.pipe_busy.get_value() as u32),
+            DPC_TMEM_REG_START...DPC_TMEM_REG_END => Ok(self.tmem_busy.get_value() as u32),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    pub fn load_u32_to_address(&mut self, address: usize, value: u32) -> Result<(), Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match address
+        {
+            DPC_START_REG_START...DPC_START_REG_END => Ok(self.write_start(value)),
+            DPC_END_REG_START...DPC_END_REG_END => Ok(self.write_end(value)),
+            //Current only moves as commands are fetched
+            DPC_CURRENT_REG_START...DPC_CURRENT_REG_END => Ok(()),
+            DPC_STATUS_REG_START...DPC_STATUS_REG_END => Ok(self.write_status(value)),
+            //Counters are read only
+            DPC_CLOCK_REG_START...DPC_TMEM_REG_END => Ok(()),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    pub fn read_span_u32_from_address(&self, address: usize) -> Result<u32, Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match address
+        {
+            DPS_TBIST_REG_START...DPS_TBIST_REG_END => Ok(self.tbist.get_value() as u32),
+            DPS_TEST_MODE_REG_START...DPS_TEST_MODE_REG_END => Ok(self.test_mode.get_value() as u32),
+            DPS_BUFTEST_ADDR_REG_START...DPS_BUFTEST_ADDR_REG_END => Ok(self.buffer_test_address.get_value() as u32),
+            DPS_BUFTEST_DATA_REG_START...DPS_BUFTEST_DATA_REG_END => Ok(self.buffer_test_data.get_value() as u32),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    pub fn load_span_u32_to_address(&mut self, address: usize, value: u32) -> Result<(), Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match address
+        {
+            DPS_TBIST_REG_START...DPS_TBIST_REG_END => Ok(self.tbist.set_value(value & 0x7)),
+            DPS_TEST_MODE_REG_START...DPS_TEST_MODE_REG_END => Ok(self.test_mode.set_value(value & 0x1)),
+            DPS_BUFTEST_ADDR_REG_START...DPS_BUFTEST_ADDR_REG_END => Ok(self.buffer_test_address.set_value(value & 0x7F)),
+            DPS_BUFTEST_DATA_REG_START...DPS_BUFTEST_DATA_REG_END => Ok(self.buffer_test_data.set_value(value)),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    pub fn set_status_bits(&mut self, bits: u32)
+    {
+        let value = self.status.get_value() as u32 | bits;
+        self.status.set_value(value);
+    }
+
+    pub fn clear_status_bits(&mut self, bits: u32)
+    {
+        let value = self.status.get_value() as u32 & !bits;
+        self.status.set_value(value);
+    }
+
+    //A new start is latched until END is written, further writes are ignored until then
+    fn write_start(&mut self, value: u32)
+    {
+        if self.status.get_value() as u32 & DPC_STATUS_START_VALID == 0
+        {
+            self.start.set_value(value & DPC_ADDRESS_MASK);
+            self.set_status_bits(DPC_STATUS_START_VALID);
+        }
+    }
+
+    //Writing END hands the commands between CURRENT and END to the RDP, END_VALID stays set until the connector fetches them on its next step
+    fn write_end(&mut self, value: u32)
+    {
+        self.end.set_value(value & DPC_ADDRESS_MASK);
+        if self.status.get_value() as u32 & DPC_STATUS_START_VALID != 0
+        {
+            let start = self.start.get_value();
+            self.current.set_value(start);
+            self.clear_status_bits(DPC_STATUS_START_VALID);
+            self.buffer.clear();
+        }
+        self.pending_transfer = true;
+        self.set_status_bits(DPC_STATUS_END_VALID);
+    }
+
+    // Referenced: https://n64brew.dev/wiki/Reality_Display_Processor/Interface#0x0410_000C_-_DPC_STATUS
+    fn write_status(&mut self, value: u32)
+    {
+        let mut set: u32 = 0;
+        let mut clear: u32 = 0;
+        let pairs = [(DPC_CLR_XBUS_DMEM_DMA, DPC_SET_XBUS_DMEM_DMA, DPC_STATUS_XBUS_DMEM_DMA), (DPC_CLR_FREEZE, DPC_SET_FREEZE, DPC_STATUS_FREEZE), (DPC_CLR_FLUSH, DPC_SET_FLUSH, DPC_STATUS_FLUSH)];
+        for &(clear_bit, set_bit, status_bit) in pairs.iter()
+        {
+            //Writing both bits of a pair leaves the status unchanged
+            match (value & clear_bit != 0, value & set_bit != 0)
+            {
+                (true, false) => clear |= status_bit,
+                (false, true) => set |= status_bit,
+                _ => (),
+            }
+        }
+        self.clear_status_bits(clear);
+        self.set_status_bits(set);
+
+        if value & DPC_CLR_TMEM_CTR != 0
+        {
+            self.tmem_busy.set_value(0_u32);
+        }
+        if value & DPC_CLR_PIPE_CTR != 0
+        {
+            self.pipe_busy.set_value(0_u32);
+        }
+        if value & DPC_CLR_CMD_CTR != 0
+        {
+            self.buffer_busy.set_value(0_u32);
+        }
+        if value & DPC_CLR_CLOCK_CTR != 0
+        {
+            self.clock.set_value(0_u32);
+        }
+    }
+
+    pub fn is_frozen(&self) -> bool
+    {
+        self.status.get_value() as u32 & DPC_STATUS_FREEZE != 0
+    }
+
+    //Commands stay pending while the RDP is frozen
+    pub fn take_transfer(&mut self) -> Option<DPTransfer>
+    {
+        if !self.pending_transfer || self.is_frozen()
+        {
+            return None;
+        }
+        self.pending_transfer = false;
+        self.clear_status_bits(DPC_STATUS_END_VALID);
+        let start = self.current.get_value() as u32;
+        let end = self.end.get_value() as u32;
+        if end <= start
+        {
+            return None;
+        }
+        self.set_status_bits(DPC_STATUS_DMA_BUSY);
+        Some(DPTransfer
+        {
+            start: start,
+            end: end,
+            xbus: self.status.get_value() as u32 & DPC_STATUS_XBUS_DMEM_DMA != 0,
+        })
+    }
+
+    //Fetched words from a transfer
+    pub fn receive_transfer(&mut self, transfer: &DPTransfer, words: &[u64])
+    {
+        self.current.set_value(transfer.end);
+        self.clear_status_bits(DPC_STATUS_DMA_BUSY);
+        self.submit_commands(words);
+    }
+
+    //Queues command words, also used by HLE microcode which bypasses the command registers
+    pub fn submit_commands(&mut self, words: &[u64])
+    {
+        self.buffer.extend_from_slice(words);
+        self.set_status_bits(DPC_STATUS_START_GCLK | DPC_STATUS_PIPE_BUSY | DPC_STATUS_CMD_BUSY);
+        let mut used = 0;
+        while let Some((command, length)) = parse_command(&self.buffer[used..])
+        {
+            used += length;
+            self.commands.push(command);
+        }
+        self.buffer.drain(0..used);
+        let buffer_busy = (self.buffer_busy.get_value() as u32).wrapping_add(words.len() as u32) & DPC_COUNTER_MASK;
+        self.buffer_busy.set_value(buffer_busy);
+    }
+
+    //A full sync completes once everything before it has been drawn
+    pub fn finish_full_sync(&mut self)
+    {
+        self.clear_status_bits(DPC_STATUS_START_GCLK | DPC_STATUS_PIPE_BUSY | DPC_STATUS_CMD_BUSY);
+    }
+
+    //The clock counter runs while the RDP has work
+    pub fn step(&mut self, cycles: u32)
+    {
+        if self.status.get_value() as u32 & DPC_STATUS_START_GCLK != 0
+        {
+            let clock = (self.clock.get_value() as u32).wrapping_add(cycles) & DPC_COUNTER_MASK;
+            self.clock.set_value(clock);
+            let pipe_busy = (self.pipe_busy.get_value() as u32).wrapping_add(cycles) & DPC_COUNTER_MASK;
+            self.pipe_busy.set_value(pipe_busy);
+        }
+    }
+}
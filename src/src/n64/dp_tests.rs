@@ -0,0 +1,153 @@
+#[cfg(test)]
+mod dp_tests
+{
+    use n64::dp::*;
+    use n64::rdp_commands::*;
+    use n64::connector::Connector;
+    use n64::mips_iface::MI_INTR_DP;
+
+    const DPC_START: u32 = 0x04100000;
+    const DPC_END: u32 = 0x04100004;
+    const DPC_CURRENT: u32 = 0x04100008;
+    const DPC_STATUS: u32 = 0x0410000C;
+    const DPC_CLOCK: u32 = 0x04100010;
+    const DPS_TEST_MODE: u32 = 0x04200004;
+
+    fn store_words(connector: &mut Connector, address: u32, words: &[u64])
+    {
+        for (index, word) in words.iter().enumerate()
+        {
+            let address = address + (index as u32 * 8);
+            connector.store_u32(address, (word >> 32) as u32).unwrap();
+            connector.store_u32(address + 4, *word as u32).unwrap();
+        }
+    }
+
+    #[test]
+    fn commands_are_fetched_from_rdram()
+    {
+        let mut connector = Connector::test();
+        store_words(&mut connector, 0x1000, &[0xF700000000010001, 0xF64FC3BC00000000, 0xE900000000000000]);
+        connector.store_u32(DPC_START, 0x1000).unwrap();
+        assert_eq!(connector.read_u32(DPC_STATUS).unwrap() & DPC_STATUS_START_VALID, DPC_STATUS_START_VALID);
+        connector.store_u32(DPC_END, 0x1018).unwrap();
+        assert!(connector.dp.commands.is_empty());
+        assert_eq!(connector.read_u32(DPC_STATUS).unwrap() & (DPC_STATUS_START_VALID | DPC_STATUS_END_VALID), DPC_STATUS_END_VALID);
+
+        connector.step(1);
+        assert_eq!(connector.dp.commands, vec![
+            RDPCommand::SET_FILL_COLOR(0x00010001),
+            RDPCommand::FILL_RECTANGLE(Rectangle {xl: 0x4FC, yl: 0x3BC, xh: 0, yh: 0}),
+            RDPCommand::SYNC_FULL,
+        ]);
+        assert_eq!(connector.read_u32(DPC_CURRENT).unwrap(), 0x1018);
+        assert_eq!(connector.read_u32(DPC_STATUS).unwrap() & (DPC_STATUS_START_VALID | DPC_STATUS_END_VALID), 0);
+
+        //The full sync only completes once the rectangle has been drawn
+        assert_eq!(connector.read_u32(DPC_STATUS).unwrap() & DPC_STATUS_PIPE_BUSY, DPC_STATUS_PIPE_BUSY);
+        assert_eq!(connector.mips_interface.interrupt.get_value() as u32 & MI_INTR_DP, 0);
+        connector.step(1);
+        assert!(connector.dp.commands.is_empty());
+        assert_eq!(connector.read_u32(DPC_STATUS).unwrap() & DPC_STATUS_PIPE_BUSY, 0);
+        assert_eq!(connector.mips_interface.interrupt.get_value() as u32 & MI_INTR_DP, MI_INTR_DP);
+    }
+
+    #[test]
+    fn xbus_commands_come_from_dmem_and_may_arrive_in_parts()
+    {
+        let mut connector = Connector::test();
+        //Fill triangle, four words of edges
+        let triangle = [0x0880_0028_0014_0000, 0x0005_0000_0000_0000, 0x000A_0000_FFFF_0000, 0x000A_0000_0001_0000];
+        store_words(&mut connector, 0x04000100, &triangle);
+        connector.store_u32(DPC_STATUS, 0x0002).unwrap();
+        connector.store_u32(DPC_START, 0x100).unwrap();
+        connector.store_u32(DPC_END, 0x110).unwrap();
+        connector.step(1);
+        assert!(connector.dp.commands.is_empty());
+        assert_eq!(connector.read_u32(DPC_STATUS).unwrap() & DPC_STATUS_PIPE_BUSY, DPC_STATUS_PIPE_BUSY);
+
+        //Continuing from CURRENT completes the command
+        connector.store_u32(DPC_END, 0x120).unwrap();
+        connector.step(1);
+        match connector.dp.commands[0]
+        {
+            RDPCommand::TRIANGLE(triangle) =>
+            {
+                assert!(triangle.edges.left_major);
+                assert_eq!((triangle.edges.yl, triangle.edges.ym, triangle.edges.yh), (0x28, 0x14, 0));
+                assert_eq!(triangle.edges.xl, 5 << 16);
+                assert_eq!((triangle.edges.xh, triangle.edges.dxhdy), (10 << 16, -0x10000));
+                assert!(triangle.shade.is_none() && triangle.texture.is_none() && triangle.z.is_none());
+            },
+            ref command => panic!("Expected a triangle, got {:?}", command),
+        }
+        assert_eq!(connector.mips_interface.interrupt.get_value() as u32 & MI_INTR_DP, 0);
+    }
+
+    #[test]
+    fn status_writes_set_and_clear_bits()
+    {
+        let mut connector = Connector::test();
+        store_words(&mut connector, 0x2000, &[0xE900000000000000]);
+        //Freezing holds the commands until the RDP is released
+        connector.store_u32(DPC_STATUS, 0x0008).unwrap();
+        connector.store_u32(DPC_START, 0x2000).unwrap();
+        connector.store_u32(DPC_END, 0x2008).unwrap();
+        connector.step(1);
+        assert!(connector.dp.commands.is_empty());
+        assert_eq!(connector.read_u32(DPC_STATUS).unwrap() & (DPC_STATUS_FREEZE | DPC_STATUS_END_VALID), DPC_STATUS_FREEZE | DPC_STATUS_END_VALID);
+        //Both bits of a pair leave the status unchanged
+        connector.store_u32(DPC_STATUS, 0x000C).unwrap();
+        connector.step(1);
+        assert!(connector.dp.commands.is_empty());
+        connector.store_u32(DPC_STATUS, 0x0004).unwrap();
+        connector.step(1);
+        assert_eq!(connector.dp.commands, vec![RDPCommand::SYNC_FULL]);
+        assert_eq!(connector.read_u32(DPC_STATUS).unwrap() & DPC_STATUS_END_VALID, 0);
+
+        connector.dp.set_status_bits(DPC_STATUS_START_GCLK);
+        connector.step(10);
+        assert_eq!(connector.read_u32(DPC_CLOCK).unwrap(), 10);
+        connector.store_u32(DPC_STATUS, 0x0200).unwrap();
+        assert_eq!(connector.read_u32(DPC_CLOCK).unwrap(), 0);
+    }
+
+    #[test]
+    fn texture_rectangles_and_tiles_are_decoded()
+    {
+        let words = [0xE427C1DC00000000, 0x0020_0040_0400_FC00, 0xF510100000014151];
+        let (command, length) = parse_command(&words).unwrap();
+        assert_eq!(length, 2);
+        assert_eq!(command, RDPCommand::TEXTURE_RECTANGLE(TextureRectangle
+        {
+            rectangle: Rectangle {xl: 0x27C, yl: 0x1DC, xh: 0, yh: 0},
+            tile: 0,
+            flip: false,
+            s: 0x20,
+            t: 0x40,
+            dsdx: 0x400,
+            dtdy: -0x400,
+        }));
+        assert!(parse_command(&words[0..1]).is_none());
+
+        let (command, _) = parse_command(&words[2..]).unwrap();
+        match command
+        {
+            RDPCommand::SET_TILE(tile) =>
+            {
+                assert_eq!((tile.format, tile.size, tile.line, tile.tmem_address), (0, 2, 8, 0));
+                assert_eq!((tile.mask_t, tile.shift_t, tile.mask_s, tile.shift_s), (5, 0, 5, 1));
+                assert!(tile.mirror_s && !tile.clamp_s);
+            },
+            ref command => panic!("Expected a tile, got {:?}", command),
+        }
+    }
+
+    #[test]
+    fn span_registers_are_mapped()
+    {
+        let mut connector = Connector::test();
+        connector.store_u32(DPS_TEST_MODE, 0xFFFFFFFF).unwrap();
+        assert_eq!(connector.read_u32(DPS_TEST_MODE).unwrap(), 1);
+    }
+}
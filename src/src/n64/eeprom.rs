@@ -0,0 +1,89 @@
+use n64::joybus::{JoybusDevice, JoybusError, check_command_size, JOYBUS_COMMAND_STATUS, JOYBUS_COMMAND_RESET};
+use n64::save_file::{SaveFile, SaveError};
+use n64::save_type::SaveType;
+
+// Referenced: https://n64brew.dev/wiki/Joybus_Protocol
+const EEPROM_COMMAND_READ: u8 = 0x04;
+const EEPROM_COMMAND_WRITE: u8 = 0x05;
+pub const EEPROM_BLOCK_SIZE: usize = 8;
+const EEPROM_4K_IDENTIFIER: u16 = 0x0080;
+const EEPROM_16K_IDENTIFIER: u16 = 0x00C0;
+const EEPROM_NOT_BUSY: u8 = 0x00;
+//Unwritten EEPROM reads as all ones
+const EEPROM_ERASED: u8 = 0xFF;
+
+pub struct Eeprom
+{
+    pub save_type: SaveType,
+    pub save: SaveFile,
+}
+
+impl Eeprom
+{
+    pub fn new(save_type: SaveType, save: SaveFile) -> Eeprom
+    {
+        return Eeprom
+        {
+            save_type: save_type,
+            save: save,
+        }
+    }
+
+    fn identifier(&self) -> u16
+    {
+        match self.save_type
+        {
+            SaveType::EEPROM_16K => EEPROM_16K_IDENTIFIER,
+            _ => EEPROM_4K_IDENTIFIER,
+        }
+    }
+
+    fn block_location(&self, block: u8) -> Option<usize>
+    {
+        let location = block as usize * EEPROM_BLOCK_SIZE;
+        if location + EEPROM_BLOCK_SIZE <= self.save.data.len() {Some(location)} else {None}
+    }
+}
+
+impl JoybusDevice for Eeprom
+{
+    fn process_command(&mut self, send: &[u8], receive: &mut [u8]) -> Result<(), JoybusError>
+    {
+        match send[0]
+        {
+            JOYBUS_COMMAND_STATUS | JOYBUS_COMMAND_RESET =>
+            {
+                check_command_size(send, receive, 1, 3)?;
+                let identifier = self.identifier();
+                receive.copy_from_slice(&[(identifier >> 8) as u8, identifier as u8, EEPROM_NOT_BUSY]);
+                Ok(())
+            },
+            EEPROM_COMMAND_READ =>
+            {
+                check_command_size(send, receive, 2, EEPROM_BLOCK_SIZE)?;
+                match self.block_location(send[1])
+                {
+                    Some(location) => self.save.read(location, receive),
+                    None => for value in receive.iter_mut() { *value = EEPROM_ERASED; },
+                }
+                Ok(())
+            },
+            EEPROM_COMMAND_WRITE =>
+            {
+                check_command_size(send, receive, EEPROM_BLOCK_SIZE + 2, 1)?;
+                if let Some(location) = self.block_location(send[1])
+                {
+                    self.save.write(location, &send[2..]);
+                }
+                receive[0] = EEPROM_NOT_BUSY;
+                Ok(())
+            },
+            _ => Err(JoybusError::NO_RESPONSE),
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), SaveError>
+    {
+        self.save.flush()
+    }
+}
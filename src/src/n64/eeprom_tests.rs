@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod eeprom_tests
+{
+    use n64::eeprom::*;
+    use n64::save_type::*;
+    use n64::joybus::*;
+    use n64::rom::{Rom, RomHeader};
+    use n64::n64::N64;
+    use n64::pif::{PIF_CONTROL_BYTE, PIF_CONTROL_JOYBUS};
+    use n64::save_file::{SaveFile, SaveError};
+    use std::env;
+    use std::fs;
+
+    fn test_header(game_code: &[u8], revision: u8) -> RomHeader
+    {
+        let mut header_data: Vec<u8> = vec![0; 0x1000];
+        header_data[0x3B..0x3F].copy_from_slice(game_code);
+        header_data[0x3F] = revision;
+        RomHeader::new(header_data).unwrap()
+    }
+
+    //EEPROM that is never written to disk
+    fn in_memory(save_type: SaveType) -> Eeprom
+    {
+        Eeprom::new(save_type, SaveFile::new(save_type.size(), save_type.fill_value()))
+    }
+
+    #[test]
+    fn save_type_is_detected_from_header()
+    {
+        assert_eq!(detect_save_type(&test_header(b"NXXE", 0x00)), SaveType::NONE);
+        assert_eq!(detect_save_type(&test_header(b"NYSE", 0x00)), SaveType::EEPROM_16K);
+        assert_eq!(detect_save_type(&test_header(b"NEDA", 0x20)), SaveType::EEPROM_16K);
+        assert_eq!(detect_save_type(&test_header(b"NEDA", 0x50)), SaveType::FLASH_RAM);
+        assert_eq!(detect_save_type(&test_header(b"NEDA", 0x00)), SaveType::NONE);
+    }
+
+    #[test]
+    fn info_reports_eeprom_size()
+    {
+        let mut receive = [0_u8; 3];
+        in_memory(SaveType::EEPROM_4K).process_command(&[JOYBUS_COMMAND_STATUS], &mut receive).unwrap();
+        assert_eq!(receive, [0x00, 0x80, 0x00]);
+        in_memory(SaveType::EEPROM_16K).process_command(&[JOYBUS_COMMAND_RESET], &mut receive).unwrap();
+        assert_eq!(receive, [0x00, 0xC0, 0x00]);
+    }
+
+    #[test]
+    fn blocks_are_read_and_written()
+    {
+        let mut eeprom = in_memory(SaveType::EEPROM_4K);
+        let mut receive = [0_u8; 8];
+        eeprom.process_command(&[0x04, 0x03], &mut receive).unwrap();
+        assert_eq!(receive, [0xFF; 8]);
+
+        let mut status = [0xAA_u8; 1];
+        eeprom.process_command(&[0x05, 0x03, 1, 2, 3, 4, 5, 6, 7, 8], &mut status).unwrap();
+        assert_eq!(status, [0x00]);
+        eeprom.process_command(&[0x04, 0x03], &mut receive).unwrap();
+        assert_eq!(receive, [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(eeprom.save.data[0x18..0x20].to_vec(), vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+        //4Kbit parts only have 64 blocks
+        eeprom.process_command(&[0x05, 0x40, 1, 2, 3, 4, 5, 6, 7, 8], &mut status).unwrap();
+        eeprom.process_command(&[0x04, 0x40], &mut receive).unwrap();
+        assert_eq!(receive, [0xFF; 8]);
+        assert_eq!(eeprom.process_command(&[0x04, 0x00], &mut [0_u8; 4]).err(), Some(JoybusError::SIZE_MISMATCH));
+    }
+
+    #[test]
+    fn eep_file_is_written_next_to_rom()
+    {
+        let directory = env::temp_dir();
+        let rom_path = directory.join(format!("n8x8_eeprom_test_{}.z64", ::std::process::id()));
+        let eep_path = rom_path.with_extension("eep");
+        let mut rom_data: Vec<u8> = vec![0; 0x1000];
+        rom_data[0..4].copy_from_slice(&[0x80, 0x37, 0x12, 0x40]);
+        rom_data[0x3B..0x3F].copy_from_slice(b"NSME");
+        fs::write(&rom_path, &rom_data).unwrap();
+        let _ = fs::remove_file(&eep_path);
+
+        let mut n64 = N64::from_rom(Rom::new(rom_path.to_str().unwrap()).unwrap());
+        n64.open_save_files().unwrap();
+        //Channels 0-3 are skipped to reach the cartridge
+        let mut commands: Vec<u8> = vec![0x00, 0x00, 0x00, 0x00, 0x0A, 0x01, 0x05, 0x00, 0xDE, 0xAD, 0xBE, 0xEF, 0x00, 0x00, 0x00, 0x00, 0xFE];
+        commands.resize(0x40, 0);
+        commands[PIF_CONTROL_BYTE] = PIF_CONTROL_JOYBUS;
+        n64.connector.pif.ram = commands;
+        n64.connector.pif.process_joybus_commands();
+        assert_eq!(n64.connector.pif.ram[5], 0x01);
+        n64.flush_saves().unwrap();
+
+        let saved = fs::read(&eep_path).unwrap();
+        assert_eq!(saved.len(), 0x200);
+        assert_eq!(saved[0..4].to_vec(), vec![0xDE, 0xAD, 0xBE, 0xEF]);
+        fs::remove_file(&rom_path).unwrap();
+        fs::remove_file(&eep_path).unwrap();
+    }
+
+    #[test]
+    fn mismatched_eep_file_is_not_overwritten()
+    {
+        let rom_path = env::temp_dir().join(format!("n8x8_eeprom_size_test_{}.z64", ::std::process::id()));
+        let eep_path = rom_path.with_extension("eep");
+        let mut rom_data: Vec<u8> = vec![0; 0x1000];
+        rom_data[0..4].copy_from_slice(&[0x80, 0x37, 0x12, 0x40]);
+        rom_data[0x3B..0x3F].copy_from_slice(b"NSME");
+        fs::write(&rom_path, &rom_data).unwrap();
+        //A 16K save next to a 4K game
+        fs::write(&eep_path, &vec![0x5A; 0x800]).unwrap();
+
+        let mut n64 = N64::from_rom(Rom::new(rom_path.to_str().unwrap()).unwrap());
+        assert_eq!(n64.open_save_files().err(), Some(SaveError::INVALID_SIZE(0x800)));
+        n64.flush_saves().unwrap();
+        assert_eq!(fs::read(&eep_path).unwrap(), vec![0x5A; 0x800]);
+        fs::remove_file(&rom_path).unwrap();
+        fs::remove_file(&eep_path).unwrap();
+    }
+}
@@ -0,0 +1,221 @@
+use n64::exceptions::Exception;
+use n64::save_file::{SaveFile, SaveError};
+
+// Referenced: https://n64brew.dev/wiki/Flash_RAM and mupen64plus flashram.c
+const FLASH_RAM_DATA_START: usize = 0x00000000;
+const FLASH_RAM_DATA_END: usize = 0x0000FFFF;
+const FLASH_RAM_COMMAND_START: usize = 0x00010000;
+const FLASH_RAM_COMMAND_END: usize = 0x0001FFFF;
+
+pub const FLASH_RAM_SIZE: usize = 0x20000;
+pub const FLASH_RAM_PAGE_SIZE: usize = 0x80;
+const FLASH_RAM_SECTOR_SIZE: usize = 0x4000;
+const FLASH_RAM_ERASED: u8 = 0xFF;
+
+const FLASH_RAM_COMMAND_SECTOR_ERASE: u8 = 0x4B;
+const FLASH_RAM_COMMAND_CHIP_ERASE: u8 = 0x3C;
+const FLASH_RAM_COMMAND_ERASE_MODE: u8 = 0x78;
+const FLASH_RAM_COMMAND_PAGE_OFFSET: u8 = 0xA5;
+const FLASH_RAM_COMMAND_WRITE_MODE: u8 = 0xB4;
+const FLASH_RAM_COMMAND_EXECUTE: u8 = 0xD2;
+const FLASH_RAM_COMMAND_STATUS_MODE: u8 = 0xE1;
+const FLASH_RAM_COMMAND_READ_MODE: u8 = 0xF0;
+
+//Silicon ID of the Macronix MX29L1100 used in most carts
+const FLASH_RAM_IDENTIFIER: u64 = 0x1111800100C2001E;
+const FLASH_RAM_STATUS_ERASE_BUSY: u64 = 0x1111800800C2001E;
+const FLASH_RAM_STATUS_WRITE_BUSY: u64 = 0x1111800400C2001E;
+const FLASH_RAM_STATUS_READ: u64 = 0x11118004F0000000;
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum FlashRAMMode
+{
+    IDLE,
+    READ,
+    STATUS,
+    SECTOR_ERASE,
+    CHIP_ERASE,
+    WRITE,
+}
+
+pub struct FlashRAM
+{
+    pub save: SaveFile,
+    pub mode: FlashRAMMode,
+    pub status: u64,
+    pub offset: usize,
+    pub page_buffer: Vec<u8>,
+}
+
+impl FlashRAM
+{
+    pub fn new(save: SaveFile) -> FlashRAM
+    {
+        return FlashRAM
+        {
+            save: save,
+            mode: FlashRAMMode::IDLE,
+            status: 0,
+            offset: 0,
+            page_buffer: vec![FLASH_RAM_ERASED; FLASH_RAM_PAGE_SIZE],
+        }
+    }
+
+    pub fn read_u32_from_address(&self, address: usize) -> Result<u32, Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match address
+        {
+            FLASH_RAM_DATA_START...FLASH_RAM_DATA_END => match self.mode
+            {
+                FlashRAMMode::READ =>
+                {
+                    let data = self.dma_read(address, 4);
+                    Ok((data[0] as u32) << 24 | (data[1] as u32) << 16 | (data[2] as u32) << 8 | (data[3] as u32))
+                },
+                //Status and the silicon ID are read back as two words
+                _ => Ok(if address & 0x4 == 0 {(self.status >> 32) as u32} else {self.status as u32}),
+            },
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    pub fn load_u32_to_address(&mut self, address: usize, value: u32) -> Result<(), Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match address
+        {
+            FLASH_RAM_DATA_START...FLASH_RAM_DATA_END =>
+            {
+                self.dma_write(address, &[(value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8]);
+                Ok(())
+            },
+            FLASH_RAM_COMMAND_START...FLASH_RAM_COMMAND_END =>
+            {
+                self.process_command(value);
+                Ok(())
+            },
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    pub fn process_command(&mut self, command: u32)
+    {
+        let offset = (command & 0xFFFF) as usize * FLASH_RAM_PAGE_SIZE;
+        match (command >> 24) as u8
+        {
+            FLASH_RAM_COMMAND_SECTOR_ERASE =>
+            {
+                self.mode = FlashRAMMode::SECTOR_ERASE;
+                self.offset = offset;
+                self.status = FLASH_RAM_STATUS_ERASE_BUSY;
+            },
+            FLASH_RAM_COMMAND_CHIP_ERASE =>
+            {
+                self.mode = FlashRAMMode::CHIP_ERASE;
+                self.status = FLASH_RAM_STATUS_ERASE_BUSY;
+            },
+            FLASH_RAM_COMMAND_ERASE_MODE => self.status = FLASH_RAM_STATUS_ERASE_BUSY,
+            FLASH_RAM_COMMAND_PAGE_OFFSET =>
+            {
+                self.offset = offset;
+                self.status = FLASH_RAM_STATUS_WRITE_BUSY;
+            },
+            FLASH_RAM_COMMAND_WRITE_MODE =>
+            {
+                self.mode = FlashRAMMode::WRITE;
+                for value in self.page_buffer.iter_mut()
+                {
+                    *value = FLASH_RAM_ERASED;
+                }
+            },
+            FLASH_RAM_COMMAND_EXECUTE => self.execute(),
+            FLASH_RAM_COMMAND_STATUS_MODE =>
+            {
+                self.mode = FlashRAMMode::STATUS;
+                self.status = FLASH_RAM_IDENTIFIER;
+            },
+            FLASH_RAM_COMMAND_READ_MODE =>
+            {
+                self.mode = FlashRAMMode::READ;
+                self.status = FLASH_RAM_STATUS_READ;
+            },
+            _ => (),
+        }
+    }
+
+    fn execute(&mut self)
+    {
+        match self.mode
+        {
+            FlashRAMMode::SECTOR_ERASE =>
+            {
+                let start = self.offset & !(FLASH_RAM_SECTOR_SIZE - 1);
+                if start + FLASH_RAM_SECTOR_SIZE <= self.save.data.len()
+                {
+                    self.save.write(start, &[FLASH_RAM_ERASED; FLASH_RAM_SECTOR_SIZE]);
+                }
+            },
+            FlashRAMMode::CHIP_ERASE =>
+            {
+                let size = self.save.data.len();
+                self.save.write(0, &vec![FLASH_RAM_ERASED; size]);
+            },
+            FlashRAMMode::WRITE =>
+            {
+                if self.offset + FLASH_RAM_PAGE_SIZE <= self.save.data.len()
+                {
+                    let page = self.page_buffer.to_vec();
+                    self.save.write(self.offset, &page);
+                }
+            },
+            _ => (),
+        }
+        self.mode = FlashRAMMode::IDLE;
+    }
+
+    //Used by PI DMA, data is read out in read mode and loaded into the page buffer in write mode
+    pub fn dma_read(&self, address: usize, length: usize) -> Vec<u8>
+    {
+        match self.mode
+        {
+            //The flash sits on a 16 bit port, so PI addresses count half words
+            FlashRAMMode::READ => ((address * 2)..((address * 2) + length)).map(|location| match self.save.data.get(location)
+            {
+                Some(value) => *value,
+                None => FLASH_RAM_ERASED,
+            }).collect(),
+            _ => (0..length).map(|index| (self.status >> (56 - ((index % 8) * 8))) as u8).collect(),
+        }
+    }
+
+    pub fn dma_write(&mut self, address: usize, data: &[u8])
+    {
+        if self.mode != FlashRAMMode::WRITE
+        {
+            return;
+        }
+        for (index, value) in data.iter().enumerate()
+        {
+            self.page_buffer[(address + index) % FLASH_RAM_PAGE_SIZE] = *value;
+        }
+    }
+
+    pub fn flush(&mut self) -> Result<(), SaveError>
+    {
+        self.save.flush()
+    }
+}
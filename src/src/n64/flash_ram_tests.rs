@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod flash_ram_tests
+{
+    use n64::flash_ram::*;
+    use n64::save_file::SaveFile;
+    use n64::n64::N64;
+    use n64::rom::Rom;
+    use n64::pi::PI_DMA_CYCLES_PER_BYTE;
+    use std::env;
+    use std::fs;
+
+    fn test_flash_ram() -> FlashRAM
+    {
+        FlashRAM::new(SaveFile::new(FLASH_RAM_SIZE, 0xFF))
+    }
+
+    #[test]
+    fn status_mode_reports_silicon_id()
+    {
+        let mut flash_ram = test_flash_ram();
+        flash_ram.load_u32_to_address(0x10000, 0xE1000000).unwrap();
+        assert_eq!(flash_ram.read_u32_from_address(0x0).unwrap(), 0x11118001);
+        assert_eq!(flash_ram.read_u32_from_address(0x4).unwrap(), 0x00C2001E);
+        assert_eq!(flash_ram.dma_read(0, 8), vec![0x11, 0x11, 0x80, 0x01, 0x00, 0xC2, 0x00, 0x1E]);
+    }
+
+    #[test]
+    fn pages_are_programmed_and_read_back()
+    {
+        let mut flash_ram = test_flash_ram();
+        flash_ram.process_command(0xB4000000);
+        flash_ram.dma_write(0, &(0..0x80).map(|value| value as u8).collect::<Vec<u8>>());
+        flash_ram.process_command(0xA5000002);
+        flash_ram.process_command(0xD2000000);
+        assert_eq!(flash_ram.mode, FlashRAMMode::IDLE);
+        assert_eq!(flash_ram.save.data[0x100], 0x00);
+        assert_eq!(flash_ram.save.data[0x17F], 0x7F);
+
+        flash_ram.process_command(0xF0000000);
+        assert_eq!(flash_ram.dma_read(0x80, 4), vec![0x00, 0x01, 0x02, 0x03]);
+        assert_eq!(flash_ram.read_u32_from_address(0x80).unwrap(), 0x00010203);
+
+        //Page buffer only fills in write mode
+        flash_ram.dma_write(0, &[0x55; 4]);
+        assert_eq!(flash_ram.page_buffer[0], 0x00);
+    }
+
+    #[test]
+    fn sectors_and_chip_are_erased()
+    {
+        let mut flash_ram = test_flash_ram();
+        flash_ram.save.data = vec![0x00; FLASH_RAM_SIZE];
+        flash_ram.process_command(0x4B000080);
+        flash_ram.process_command(0x78000000);
+        flash_ram.process_command(0xD2000000);
+        assert_eq!(flash_ram.save.data[0x3FFF], 0x00);
+        assert_eq!(flash_ram.save.data[0x4000], 0xFF);
+        assert_eq!(flash_ram.save.data[0x7FFF], 0xFF);
+        assert_eq!(flash_ram.save.data[0x8000], 0x00);
+
+        flash_ram.process_command(0x3C000000);
+        flash_ram.process_command(0x78000000);
+        flash_ram.process_command(0xD2000000);
+        assert!(flash_ram.save.data.iter().all(|value| *value == 0xFF));
+    }
+
+    #[test]
+    fn fla_file_is_written_next_to_rom()
+    {
+        let rom_path = env::temp_dir().join(format!("n8x8_flash_test_{}.z64", ::std::process::id()));
+        let fla_path = rom_path.with_extension("fla");
+        let mut rom_data: Vec<u8> = vec![0; 0x1000];
+        rom_data[0..4].copy_from_slice(&[0x80, 0x37, 0x12, 0x40]);
+        rom_data[0x3B..0x40].copy_from_slice(&[b'N', b'E', b'D', b'A', 0x50]);
+        fs::write(&rom_path, &rom_data).unwrap();
+        let _ = fs::remove_file(&fla_path);
+
+        let mut n64 = N64::from_rom(Rom::new(rom_path.to_str().unwrap()).unwrap());
+        n64.open_save_files().unwrap();
+        n64.connector.store_u32(0xA8010000, 0xB4000000).unwrap();
+        n64.connector.store_u32(0xA8000000, 0x12345678).unwrap();
+        n64.connector.store_u32(0xA8010000, 0xA5000000).unwrap();
+        n64.connector.store_u32(0xA8010000, 0xD2000000).unwrap();
+        n64.flush_saves().unwrap();
+
+        let saved = fs::read(&fla_path).unwrap();
+        assert_eq!(saved.len(), FLASH_RAM_SIZE);
+        assert_eq!(saved[0..5].to_vec(), vec![0x12, 0x34, 0x56, 0x78, 0xFF]);
+        fs::remove_file(&rom_path).unwrap();
+        fs::remove_file(&fla_path).unwrap();
+    }
+
+    fn pi_dma(n64: &mut N64, length_register: u32, dram_address: u32, cart_address: u32, length: u32)
+    {
+        n64.connector.store_u32(0xA4600000, dram_address).unwrap();
+        n64.connector.store_u32(0xA4600004, cart_address).unwrap();
+        n64.connector.store_u32(length_register, length - 1).unwrap();
+        n64.connector.step(length * PI_DMA_CYCLES_PER_BYTE);
+    }
+
+    #[test]
+    fn pages_survive_a_reload_through_pi_dma()
+    {
+        let rom_path = env::temp_dir().join(format!("n8x8_flash_dma_test_{}.z64", ::std::process::id()));
+        let fla_path = rom_path.with_extension("fla");
+        let mut rom_data: Vec<u8> = vec![0; 0x1000];
+        rom_data[0..4].copy_from_slice(&[0x80, 0x37, 0x12, 0x40]);
+        rom_data[0x3B..0x40].copy_from_slice(&[b'N', b'E', b'D', b'A', 0x50]);
+        fs::write(&rom_path, &rom_data).unwrap();
+        let _ = fs::remove_file(&fla_path);
+
+        //Commands go through the CPU, the page itself is moved by the PI
+        let page: Vec<u8> = (0..FLASH_RAM_PAGE_SIZE).map(|value| (value * 3) as u8).collect();
+        let mut n64 = N64::from_rom(Rom::new(rom_path.to_str().unwrap()).unwrap());
+        n64.open_save_files().unwrap();
+        n64.connector.rdram.store_bytes(0x1000, &page).unwrap();
+        n64.connector.store_u32(0xA8010000, 0xB4000000).unwrap();
+        pi_dma(&mut n64, 0xA4600008, 0x1000, 0x08000000, FLASH_RAM_PAGE_SIZE as u32);
+        n64.connector.store_u32(0xA8010000, 0xA5000003).unwrap();
+        n64.connector.store_u32(0xA8010000, 0xD2000000).unwrap();
+        n64.flush_saves().unwrap();
+
+        let mut n64 = N64::from_rom(Rom::new(rom_path.to_str().unwrap()).unwrap());
+        n64.open_save_files().unwrap();
+        n64.connector.store_u32(0xA8010000, 0xF0000000).unwrap();
+        //The flash counts half words on the PI side
+        pi_dma(&mut n64, 0xA460000C, 0x2000, 0x08000000 + (3 * FLASH_RAM_PAGE_SIZE as u32 / 2), FLASH_RAM_PAGE_SIZE as u32);
+        assert_eq!(n64.connector.rdram.read_bytes(0x2000, FLASH_RAM_PAGE_SIZE).unwrap(), page);
+        fs::remove_file(&rom_path).unwrap();
+        fs::remove_file(&fla_path).unwrap();
+    }
+}
@@ -0,0 +1,170 @@
+use n64::vi::Frame;
+use flate2::Crc;
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+const PNG_BIT_DEPTH: u8 = 8;
+const PNG_COLOR_TYPE_RGB: u8 = 2;
+const PNG_FILTER_NONE: u8 = 0;
+
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum ImageFormat
+{
+    PNG,
+    PPM,
+}
+
+impl ImageFormat
+{
+    pub fn from_name(name: &str) -> Option<ImageFormat>
+    {
+        match name.to_ascii_lowercase().as_str()
+        {
+            "png" => Some(ImageFormat::PNG),
+            "ppm" => Some(ImageFormat::PPM),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str
+    {
+        match self
+        {
+            ImageFormat::PNG => "png",
+            ImageFormat::PPM => "ppm",
+        }
+    }
+
+    pub fn encode(&self, frame: &Frame) -> Vec<u8>
+    {
+        match self
+        {
+            ImageFormat::PNG => encode_png(frame),
+            ImageFormat::PPM => encode_ppm(frame),
+        }
+    }
+}
+
+//Both formats drop the alpha channel, the VI always outputs opaque pixels
+fn rgb_rows(frame: &Frame) -> Vec<&[u8]>
+{
+    frame.pixels.chunks(frame.width * 4).take(frame.height).collect()
+}
+
+pub fn encode_ppm(frame: &Frame) -> Vec<u8>
+{
+    let mut data = format!("P6\n{} {}\n255\n", frame.width, frame.height).into_bytes();
+    for row in rgb_rows(frame)
+    {
+        for pixel in row.chunks(4)
+        {
+            data.extend_from_slice(&pixel[0..3]);
+        }
+    }
+    data
+}
+
+// Referenced: https://www.w3.org/TR/png/
+pub fn encode_png(frame: &Frame) -> Vec<u8>
+{
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(frame.width as u32).to_be_bytes());
+    header.extend_from_slice(&(frame.height as u32).to_be_bytes());
+    header.extend_from_slice(&[PNG_BIT_DEPTH, PNG_COLOR_TYPE_RGB, 0, 0, 0]);
+
+    //Every scanline is prefixed with its filter type
+    let mut scanlines = Vec::with_capacity(frame.height * ((frame.width * 3) + 1));
+    for row in rgb_rows(frame)
+    {
+        scanlines.push(PNG_FILTER_NONE);
+        for pixel in row.chunks(4)
+        {
+            scanlines.extend_from_slice(&pixel[0..3]);
+        }
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    //Writing into a Vec cannot fail
+    encoder.write_all(&scanlines).unwrap();
+    let image_data = encoder.finish().unwrap();
+
+    let mut data = PNG_SIGNATURE.to_vec();
+    write_png_chunk(&mut data, b"IHDR", &header);
+    write_png_chunk(&mut data, b"IDAT", &image_data);
+    write_png_chunk(&mut data, b"IEND", &[]);
+    data
+}
+
+fn write_png_chunk(data: &mut Vec<u8>, chunk_type: &[u8; 4], contents: &[u8])
+{
+    data.extend_from_slice(&(contents.len() as u32).to_be_bytes());
+    data.extend_from_slice(chunk_type);
+    data.extend_from_slice(contents);
+    let mut crc = Crc::new();
+    crc.update(chunk_type);
+    crc.update(contents);
+    data.extend_from_slice(&crc.sum().to_be_bytes());
+}
+
+//Writes VI frames to numbered image files, either all of them or only the listed indices
+pub struct FrameDump
+{
+    pub directory: PathBuf,
+    pub format: ImageFormat,
+    pub indices: Option<Vec<u64>>,
+}
+
+impl FrameDump
+{
+    pub fn new<P: AsRef<Path>>(directory: P, format: ImageFormat) -> FrameDump
+    {
+        return FrameDump
+        {
+            directory: directory.as_ref().to_path_buf(),
+            format: format,
+            indices: None,
+        }
+    }
+
+    pub fn wants(&self, index: u64) -> bool
+    {
+        match self.indices
+        {
+            Some(ref indices) => indices.contains(&index),
+            None => true,
+        }
+    }
+
+    //A dump of selected frames is done once the last of them has been written
+    pub fn is_complete(&self, frames: u64) -> bool
+    {
+        match self.indices
+        {
+            Some(ref indices) => indices.iter().all(|&index| index < frames),
+            None => false,
+        }
+    }
+
+    pub fn path(&self, index: u64) -> PathBuf
+    {
+        self.directory.join(format!("frame_{:06}.{}", index, self.format.extension()))
+    }
+
+    pub fn write(&self, index: u64, frame: &Frame) -> io::Result<()>
+    {
+        //Nothing is displayed before the VI is programmed and neither format allows an empty image
+        if frame.width == 0 || frame.height == 0
+        {
+            return Ok(());
+        }
+        fs::create_dir_all(&self.directory)?;
+        fs::write(self.path(index), self.format.encode(frame))
+    }
+}
@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod frame_dump_tests
+{
+    use n64::frame_dump::*;
+    use n64::vi::{Frame, VI_TYPE_32};
+    use n64::n64::N64;
+    use n64::rom::Rom;
+    use n64::hle::store_rdram_u32;
+    use flate2::Crc;
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+    use std::env;
+    use std::fs;
+
+    fn test_frame() -> Frame
+    {
+        return Frame
+        {
+            width: 2,
+            height: 2,
+            pixels: vec![0xFF, 0x00, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0x00, 0xFF, 0xFF, 0x12, 0x34, 0x56, 0xFF],
+        }
+    }
+
+    fn read_u32_be(data: &[u8], offset: usize) -> u32
+    {
+        ((data[offset] as u32) << 24) | ((data[offset + 1] as u32) << 16) | ((data[offset + 2] as u32) << 8) | data[offset + 3] as u32
+    }
+
+    #[test]
+    fn ppm_drops_alpha()
+    {
+        let mut expected = b"P6\n2 2\n255\n".to_vec();
+        expected.extend_from_slice(&[0xFF, 0x00, 0x00, 0x00, 0xFF, 0x00, 0x00, 0x00, 0xFF, 0x12, 0x34, 0x56]);
+        assert_eq!(encode_ppm(&test_frame()), expected);
+    }
+
+    #[test]
+    fn png_chunks_hold_the_frame()
+    {
+        let png = encode_png(&test_frame());
+        assert_eq!(png[0..8], [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]);
+
+        let mut offset = 8;
+        let mut chunks = Vec::new();
+        while offset < png.len()
+        {
+            let length = read_u32_be(&png, offset) as usize;
+            let chunk_type = png[offset + 4..offset + 8].to_vec();
+            let contents = png[offset + 8..offset + 8 + length].to_vec();
+            let mut crc = Crc::new();
+            crc.update(&png[offset + 4..offset + 8 + length]);
+            assert_eq!(read_u32_be(&png, offset + 8 + length), crc.sum());
+            chunks.push((chunk_type, contents));
+            offset += length + 12;
+        }
+        assert_eq!(chunks.iter().map(|chunk| chunk.0.clone()).collect::<Vec<Vec<u8>>>(), vec![b"IHDR".to_vec(), b"IDAT".to_vec(), b"IEND".to_vec()]);
+        assert_eq!(chunks[0].1, vec![0, 0, 0, 2, 0, 0, 0, 2, 8, 2, 0, 0, 0]);
+
+        let mut scanlines = Vec::new();
+        ZlibDecoder::new(&chunks[1].1[..]).read_to_end(&mut scanlines).unwrap();
+        assert_eq!(scanlines, vec![0, 0xFF, 0x00, 0x00, 0x00, 0xFF, 0x00, 0, 0x00, 0x00, 0xFF, 0x12, 0x34, 0x56]);
+    }
+
+    #[test]
+    fn selected_frames_finish_the_dump()
+    {
+        let mut frame_dump = FrameDump::new("frames", ImageFormat::PPM);
+        assert!(frame_dump.wants(7));
+        assert!(!frame_dump.is_complete(1000));
+        frame_dump.indices = Some(vec![3, 10]);
+        assert!(frame_dump.wants(3) && !frame_dump.wants(4));
+        assert!(!frame_dump.is_complete(10));
+        assert!(frame_dump.is_complete(11));
+        assert_eq!(frame_dump.path(10), ::std::path::Path::new("frames").join("frame_000010.ppm"));
+        assert_eq!(ImageFormat::from_name("PNG"), Some(ImageFormat::PNG));
+        assert_eq!(ImageFormat::from_name("bmp"), None);
+    }
+
+    #[test]
+    fn finished_fields_are_decoded_and_written()
+    {
+        let directory = env::temp_dir().join(format!("n8x8_frame_dump_test_{}", ::std::process::id()));
+        let mut n64 = N64::from_rom(Rom::test());
+        let mut frame_dump = FrameDump::new(&directory, ImageFormat::PPM);
+        frame_dump.indices = Some(vec![1]);
+        n64.frame_dump = Some(frame_dump);
+
+        store_rdram_u32(&mut n64.connector, 0x1000, 0xAABBCCFF);
+        let registers = [(0x04400000, VI_TYPE_32), (0x04400004, 0x1000), (0x04400008, 1), (0x04400018, 4), (0x0440001C, 99), (0x04400024, 1), (0x04400028, 2), (0x04400030, 0x400), (0x04400034, 0x400)];
+        for &(address, value) in registers.iter()
+        {
+            n64.connector.store_u32(address, value).unwrap();
+        }
+        assert!(n64.last_frame().is_none());
+
+        //Two lines make up a field at a V_SYNC of 4
+        let line = n64.connector.vi.cycles_per_line() as u32;
+        n64.step_devices(line * 2);
+        assert_eq!(n64.frame_count, 1);
+        assert_eq!(n64.last_frame().unwrap().pixels, vec![0xAA, 0xBB, 0xCC, 0xFF]);
+        assert!(!directory.join("frame_000000.ppm").exists());
+        assert!(!n64.frames_finished());
+
+        n64.step_devices(line * 2);
+        let mut expected = b"P6\n1 1\n255\n".to_vec();
+        expected.extend_from_slice(&[0xAA, 0xBB, 0xCC]);
+        assert_eq!(fs::read(directory.join("frame_000001.ppm")).unwrap(), expected);
+        assert!(n64.frames_finished());
+        fs::remove_dir_all(&directory).unwrap();
+
+        n64.frame_dump = None;
+        n64.frame_limit = Some(3);
+        assert!(!n64.frames_finished());
+        n64.step_devices(line * 2);
+        assert!(n64.frames_finished());
+    }
+}
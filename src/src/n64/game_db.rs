@@ -0,0 +1,410 @@
+use n64::rom::RomHeader;
+use n64::save_type::SaveType;
+use n64::cic::CIC;
+use std::fmt;
+use std::fs;
+use std::io;
+
+//Entries are keyed by "[CRC1-CRC2]" in hex or by game code, where a three character code covers every region
+//Later entries (such as the user override file) replace fields set by earlier ones
+//Retail CICs are identified from the boot code, so cic is only needed for carts that detection gets wrong
+//Titles without expansion_pak=false get the full 8MB of RDRAM
+const BUILTIN_DATABASE: &str = "
+[NSM]
+name=Super Mario 64
+save_type=eeprom_4k
+
+[NGE]
+name=GoldenEye 007
+save_type=eeprom_4k
+accessories=rumble_pak
+
+[NMK]
+name=Mario Kart 64
+save_type=eeprom_4k
+accessories=mempak
+
+[NFX]
+name=Star Fox 64
+save_type=eeprom_4k
+accessories=rumble_pak
+
+[NBK]
+name=Banjo-Kazooie
+save_type=eeprom_4k
+accessories=rumble_pak
+
+[NB7]
+name=Banjo-Tooie
+save_type=eeprom_16k
+accessories=rumble_pak
+
+[NYS]
+name=Yoshi's Story
+save_type=eeprom_16k
+accessories=rumble_pak
+
+[NFU]
+name=Conker's Bad Fur Day
+save_type=eeprom_16k
+accessories=rumble_pak
+
+[NZL]
+name=The Legend of Zelda: Ocarina of Time
+save_type=sram_32k
+accessories=rumble_pak
+
+[EC7011B7-7616D72B]
+name=The Legend of Zelda: Ocarina of Time (1.0)
+
+[D43DA81F-021E1E19]
+name=The Legend of Zelda: Ocarina of Time (1.1)
+
+[693BA2AE-B7F14E9F]
+name=The Legend of Zelda: Ocarina of Time (1.2)
+
+[NZS]
+name=The Legend of Zelda: Majora's Mask
+save_type=flash_ram
+accessories=rumble_pak
+expansion_pak=true
+
+[NDO]
+name=Donkey Kong 64
+save_type=eeprom_16k
+accessories=rumble_pak
+expansion_pak=true
+
+[NPD]
+name=Perfect Dark
+save_type=eeprom_16k
+accessories=rumble_pak,transfer_pak
+
+[NMQ]
+name=Paper Mario
+save_type=flash_ram
+
+[NPO]
+name=Pokemon Stadium
+save_type=flash_ram
+accessories=transfer_pak
+
+[NAF]
+name=Dobutsu no Mori
+save_type=flash_ram
+rtc=true
+";
+
+//SI DMA completes on the next step instead of after SI_DMA_DURATION
+pub const QUIRK_FAST_SI: &str = "fast_si";
+//PI DMA completes on the next step instead of being timed per byte
+pub const QUIRK_FAST_PI: &str = "fast_pi";
+const KNOWN_QUIRKS: [&str; 2] = [QUIRK_FAST_SI, QUIRK_FAST_PI];
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum Accessory
+{
+    MEMPAK,
+    RUMBLE_PAK,
+    TRANSFER_PAK,
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum GameKey
+{
+    CRC(u32, u32),
+    GAME_CODE(String),
+}
+
+impl GameKey
+{
+    //Higher is more specific, so a CRC match beats a region specific code which beats a region free one
+    fn specificity(&self, rom_header: &RomHeader) -> Option<usize>
+    {
+        match self
+        {
+            GameKey::CRC(crc1, crc2) if *crc1 == rom_header.crc1 && *crc2 == rom_header.crc2 => Some(3),
+            GameKey::GAME_CODE(code) if rom_header.game_code.starts_with(code.as_str()) => Some(code.len() - 2),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct GameEntry
+{
+    pub key: GameKey,
+    pub name: Option<String>,
+    pub save_type: Option<SaveType>,
+    pub accessories: Option<Vec<Accessory>>,
+    pub cic: Option<CIC>,
+    pub expansion_pak: Option<bool>,
+    pub rtc: Option<bool>,
+    pub quirks: Vec<String>,
+}
+
+impl GameEntry
+{
+    pub fn new(key: GameKey) -> GameEntry
+    {
+        return GameEntry
+        {
+            key: key,
+            name: None,
+            save_type: None,
+            accessories: None,
+            cic: None,
+            expansion_pak: None,
+            rtc: None,
+            quirks: Vec::new(),
+        }
+    }
+
+    fn merge(&mut self, other: &GameEntry)
+    {
+        self.key = other.key.clone();
+        if other.name.is_some() { self.name = other.name.clone(); }
+        if other.save_type.is_some() { self.save_type = other.save_type; }
+        if other.accessories.is_some() { self.accessories = other.accessories.clone(); }
+        if other.cic.is_some() { self.cic = other.cic; }
+        if other.expansion_pak.is_some() { self.expansion_pak = other.expansion_pak; }
+        if other.rtc.is_some() { self.rtc = other.rtc; }
+        for quirk in other.quirks.iter()
+        {
+            if !self.has_quirk(quirk)
+            {
+                self.quirks.push(quirk.clone());
+            }
+        }
+    }
+
+    pub fn has_quirk(&self, quirk: &str) -> bool
+    {
+        self.quirks.iter().any(|value| value == quirk)
+    }
+
+    fn set_field(&mut self, field: &str, value: &str) -> Result<(), String>
+    {
+        match field
+        {
+            "name" => self.name = Some(value.to_string()),
+            "save_type" => self.save_type = Some(parse_save_type(value)?),
+            "accessories" => self.accessories = Some(parse_list(value).iter().map(|accessory| parse_accessory(accessory)).collect::<Result<Vec<Accessory>, String>>()?),
+            "cic" => self.cic = Some(parse_cic(value)?),
+            "expansion_pak" => self.expansion_pak = Some(parse_bool(value)?),
+            "rtc" => self.rtc = Some(parse_bool(value)?),
+            "quirks" => self.quirks = parse_list(value).into_iter().map(parse_quirk).collect::<Result<Vec<String>, String>>()?,
+            _ => return Err(format!("unknown field \"{}\"", field)),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+#[allow(non_camel_case_types)]
+pub enum GameDatabaseError
+{
+    IO(String),
+    PARSE(usize, String),
+}
+
+impl fmt::Display for GameDatabaseError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self
+        {
+            GameDatabaseError::IO(s) => write!(f, "Game database read failed: {}", s),
+            GameDatabaseError::PARSE(line, s) => write!(f, "Game database line {}: {}", line, s),
+        }
+    }
+}
+
+impl From<io::Error> for GameDatabaseError
+{
+    fn from(error: io::Error) -> GameDatabaseError
+    {
+        GameDatabaseError::IO(error.to_string())
+    }
+}
+
+pub struct GameDatabase
+{
+    pub entries: Vec<GameEntry>,
+}
+
+impl GameDatabase
+{
+    pub fn new() -> GameDatabase
+    {
+        return GameDatabase
+        {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn builtin() -> GameDatabase
+    {
+        let mut database = GameDatabase::new();
+        database.extend(BUILTIN_DATABASE).expect("Built in game database is invalid");
+        database
+    }
+
+    //Entries are added after the existing ones so they take priority
+    pub fn extend(&mut self, text: &str) -> Result<(), GameDatabaseError>
+    {
+        let mut entries = parse_database(text)?;
+        self.entries.append(&mut entries);
+        Ok(())
+    }
+
+    pub fn load_overrides(&mut self, filename: &str) -> Result<(), GameDatabaseError>
+    {
+        let text = fs::read_to_string(filename)?;
+        self.extend(&text)
+    }
+
+    //Merges every matching entry, least specific first
+    pub fn lookup(&self, rom_header: &RomHeader) -> Option<GameEntry>
+    {
+        let mut matches: Vec<(usize, usize, &GameEntry)> = self.entries.iter().enumerate()
+            .filter_map(|(index, entry)| entry.key.specificity(rom_header).map(|specificity| (specificity, index, entry)))
+            .collect();
+        matches.sort_by_key(|&(specificity, index, _)| (specificity, index));
+
+        let mut matches = matches.into_iter();
+        let mut result = matches.next()?.2.clone();
+        for (_, _, entry) in matches
+        {
+            result.merge(entry);
+        }
+        Some(result)
+    }
+}
+
+fn parse_database(text: &str) -> Result<Vec<GameEntry>, GameDatabaseError>
+{
+    let mut entries: Vec<GameEntry> = Vec::new();
+    for (index, line) in text.lines().enumerate()
+    {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#')
+        {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']')
+        {
+            let key = parse_key(&line[1..(line.len() - 1)]).map_err(|e| GameDatabaseError::PARSE(line_number, e))?;
+            entries.push(GameEntry::new(key));
+            continue;
+        }
+
+        let entry = match entries.last_mut()
+        {
+            Some(entry) => entry,
+            None => return Err(GameDatabaseError::PARSE(line_number, "field outside of an entry".to_string())),
+        };
+        match line.find('=')
+        {
+            Some(split) => entry.set_field(line[..split].trim(), line[(split + 1)..].trim()).map_err(|e| GameDatabaseError::PARSE(line_number, e))?,
+            None => return Err(GameDatabaseError::PARSE(line_number, format!("expected field=value, found \"{}\"", line))),
+        }
+    }
+    Ok(entries)
+}
+
+fn parse_key(key: &str) -> Result<GameKey, String>
+{
+    let key = key.trim();
+    if let Some(split) = key.find('-')
+    {
+        let crc1 = u32::from_str_radix(&key[..split], 16).map_err(|_| format!("invalid CRC \"{}\"", key))?;
+        let crc2 = u32::from_str_radix(&key[(split + 1)..], 16).map_err(|_| format!("invalid CRC \"{}\"", key))?;
+        return Ok(GameKey::CRC(crc1, crc2));
+    }
+    match key.len()
+    {
+        3 | 4 => Ok(GameKey::GAME_CODE(key.to_string())),
+        _ => Err(format!("invalid game code \"{}\"", key)),
+    }
+}
+
+fn parse_list(value: &str) -> Vec<String>
+{
+    value.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect()
+}
+
+fn parse_quirk(value: String) -> Result<String, String>
+{
+    if !KNOWN_QUIRKS.contains(&value.as_str())
+    {
+        return Err(format!("unknown quirk \"{}\"", value));
+    }
+    Ok(value)
+}
+
+fn parse_save_type(value: &str) -> Result<SaveType, String>
+{
+    match value
+    {
+        "none" => Ok(SaveType::NONE),
+        "eeprom_4k" => Ok(SaveType::EEPROM_4K),
+        "eeprom_16k" => Ok(SaveType::EEPROM_16K),
+        "sram_32k" => Ok(SaveType::SRAM_32K),
+        "sram_96k" => Ok(SaveType::SRAM_96K),
+        "sram_128k" => Ok(SaveType::SRAM_128K),
+        "flash_ram" => Ok(SaveType::FLASH_RAM),
+        _ => Err(format!("unknown save type \"{}\"", value)),
+    }
+}
+
+fn parse_accessory(value: &str) -> Result<Accessory, String>
+{
+    match value
+    {
+        "mempak" => Ok(Accessory::MEMPAK),
+        "rumble_pak" => Ok(Accessory::RUMBLE_PAK),
+        "transfer_pak" => Ok(Accessory::TRANSFER_PAK),
+        _ => Err(format!("unknown accessory \"{}\"", value)),
+    }
+}
+
+fn parse_cic(value: &str) -> Result<CIC, String>
+{
+    match value
+    {
+        "6101" => Ok(CIC::CIC_6101),
+        "6102" => Ok(CIC::CIC_6102),
+        "6103" => Ok(CIC::CIC_6103),
+        "6105" => Ok(CIC::CIC_6105),
+        "6106" => Ok(CIC::CIC_6106),
+        "7101" => Ok(CIC::CIC_7101),
+        "7102" => Ok(CIC::CIC_7102),
+        "7103" => Ok(CIC::CIC_7103),
+        "7105" => Ok(CIC::CIC_7105),
+        "7106" => Ok(CIC::CIC_7106),
+        "8303" => Ok(CIC::CIC_8303),
+        "8401" => Ok(CIC::CIC_8401),
+        "5167" => Ok(CIC::CIC_5167),
+        _ => Err(format!("unknown CIC \"{}\"", value)),
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, String>
+{
+    match value
+    {
+        "true" | "yes" => Ok(true),
+        "false" | "no" => Ok(false),
+        _ => Err(format!("expected true or false, found \"{}\"", value)),
+    }
+}
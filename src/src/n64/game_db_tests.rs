@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod game_db_tests
+{
+    use n64::game_db::*;
+    use n64::save_type::SaveType;
+    use n64::cic::CIC;
+    use n64::rom::{Rom, RomHeader};
+    use n64::n64::N64;
+    use std::env;
+    use std::fs;
+
+    fn test_rom_data(game_code: &[u8], crc1: u32, crc2: u32) -> Vec<u8>
+    {
+        let mut rom_data: Vec<u8> = vec![0; 0x1000];
+        rom_data[0..4].copy_from_slice(&[0x80, 0x37, 0x12, 0x40]);
+        rom_data[0x10..0x14].copy_from_slice(&[(crc1 >> 24) as u8, (crc1 >> 16) as u8, (crc1 >> 8) as u8, crc1 as u8]);
+        rom_data[0x14..0x18].copy_from_slice(&[(crc2 >> 24) as u8, (crc2 >> 16) as u8, (crc2 >> 8) as u8, crc2 as u8]);
+        rom_data[0x3B..0x3F].copy_from_slice(game_code);
+        rom_data
+    }
+
+    fn test_header(game_code: &[u8], crc1: u32, crc2: u32) -> RomHeader
+    {
+        RomHeader::new(test_rom_data(game_code, crc1, crc2)).unwrap()
+    }
+
+    #[test]
+    fn builtin_database_matches_every_region()
+    {
+        let database = GameDatabase::builtin();
+        let entry = database.lookup(&test_header(b"NZSE", 0, 0)).unwrap();
+        assert_eq!(entry.name, Some("The Legend of Zelda: Majora's Mask".to_string()));
+        assert_eq!(entry.save_type, Some(SaveType::FLASH_RAM));
+        assert_eq!(entry.expansion_pak, Some(true));
+        assert_eq!(entry.accessories, Some(vec![Accessory::RUMBLE_PAK]));
+        assert_eq!(database.lookup(&test_header(b"NZSP", 0, 0)).unwrap().save_type, Some(SaveType::FLASH_RAM));
+        assert!(database.lookup(&test_header(b"NXXE", 0, 0)).is_none());
+    }
+
+    #[test]
+    fn builtin_database_tells_revisions_apart()
+    {
+        let database = GameDatabase::builtin();
+        let entry = database.lookup(&test_header(b"NZLE", 0xEC7011B7, 0x7616D72B)).unwrap();
+        assert_eq!(entry.name, Some("The Legend of Zelda: Ocarina of Time (1.0)".to_string()));
+        assert_eq!(entry.save_type, Some(SaveType::SRAM_32K));
+        let entry = database.lookup(&test_header(b"NZLE", 0x693BA2AE, 0xB7F14E9F)).unwrap();
+        assert_eq!(entry.name, Some("The Legend of Zelda: Ocarina of Time (1.2)".to_string()));
+        assert_eq!(entry.key, GameKey::CRC(0x693BA2AE, 0xB7F14E9F));
+        assert_eq!(database.lookup(&test_header(b"NZLE", 0, 0)).unwrap().name, Some("The Legend of Zelda: Ocarina of Time".to_string()));
+    }
+
+    #[test]
+    fn specific_entries_override_general_ones()
+    {
+        let mut database = GameDatabase::builtin();
+        database.extend("
+            ; CRC entries win regardless of order
+            [12345678-9ABCDEF0]
+            save_type=sram_32k
+            quirks=fast_si
+
+            [NSMJ]
+            save_type=eeprom_16k
+            accessories=rumble_pak
+            cic=7102
+            quirks=fast_pi
+        ").unwrap();
+
+        let entry = database.lookup(&test_header(b"NSMJ", 0x12345678, 0x9ABCDEF0)).unwrap();
+        assert_eq!(entry.name, Some("Super Mario 64".to_string()));
+        assert_eq!(entry.save_type, Some(SaveType::SRAM_32K));
+        assert_eq!(entry.cic, Some(CIC::CIC_7102));
+        assert_eq!(entry.key, GameKey::CRC(0x12345678, 0x9ABCDEF0));
+        assert!(entry.has_quirk("fast_si"));
+        assert!(entry.has_quirk("fast_pi"));
+
+        let entry = database.lookup(&test_header(b"NSME", 0, 0)).unwrap();
+        assert_eq!(entry.save_type, Some(SaveType::EEPROM_4K));
+        assert_eq!(entry.accessories, None);
+        assert!(entry.quirks.is_empty());
+    }
+
+    #[test]
+    fn parse_errors_report_the_line()
+    {
+        let mut database = GameDatabase::new();
+        match database.extend("[NSME]\nsave_type=eeprom_4k\nsave_type=tape")
+        {
+            Err(GameDatabaseError::PARSE(line, _)) => assert_eq!(line, 3),
+            _ => panic!("expected a parse error"),
+        }
+        assert!(database.extend("save_type=eeprom_4k").is_err());
+        assert!(database.extend("[NSME]\nspeed=fast").is_err());
+        assert!(database.extend("[NOTACODE]").is_err());
+        assert!(database.extend("[XYZ-123]").is_err());
+        assert!(database.extend("[NSME]\nquirks=fast_si,warp_speed").is_err());
+        assert!(database.entries.is_empty());
+    }
+
+    #[test]
+    fn override_file_configures_n64()
+    {
+        let path = env::temp_dir().join(format!("n8x8_game_db_test_{}.ini", ::std::process::id()));
+        fs::write(&path, "[NSME]\nsave_type=flash_ram\ncic=6105\nrtc=true\n").unwrap();
+        let mut database = GameDatabase::builtin();
+        database.load_overrides(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let n64 = N64::from_rom_with_database(Rom::from_bytes(test_rom_data(b"NSME", 0, 0)).unwrap(), &database);
+        assert_eq!(n64.connector.save_type, SaveType::FLASH_RAM);
+        assert!(n64.connector.flash_ram.is_some());
+        assert!(n64.connector.pif.cartridge.rtc.is_some());
+        assert_eq!(n64.cic(), CIC::CIC_6105);
+
+        //Titles missing from the database fall back to header detection
+        let n64 = N64::from_rom_with_database(Rom::from_bytes(test_rom_data(b"NXXE", 0, 0)).unwrap(), &database);
+        assert_eq!(n64.connector.save_type, SaveType::NONE);
+        assert!(n64.connector.pif.cartridge.eeprom.is_none());
+        assert!(n64.connector.pif.cartridge.rtc.is_none());
+        assert!(n64.connector.rdram.has_expansion_pak());
+    }
+
+    #[test]
+    fn expansion_pak_and_quirks_configure_n64()
+    {
+        let mut database = GameDatabase::builtin();
+        database.extend("[NSME]\nexpansion_pak=false\nquirks=fast_si,fast_pi").unwrap();
+        let mut n64 = N64::from_rom_with_database(Rom::from_bytes(test_rom_data(b"NSME", 0, 0)).unwrap(), &database);
+        assert!(!n64.connector.rdram.has_expansion_pak());
+        assert!(n64.connector.read_u32(0x003FFFFC).is_ok());
+        assert!(n64.connector.read_u32(0x00400000).is_err());
+        assert!(n64.connector.store_u32(0x00400000, 1).is_err());
+        assert_eq!(n64.connector.si.dma_duration, 0);
+        assert_eq!(n64.connector.pi.dma_cycles_per_byte, 0);
+
+        //Majora's Mask needs the Expansion Pak and keeps it
+        let mut n64 = N64::from_rom_with_database(Rom::from_bytes(test_rom_data(b"NZSE", 0, 0)).unwrap(), &database);
+        n64.connector.store_u32(0x00400000, 1).unwrap();
+        assert_eq!(n64.connector.read_u32(0x00400000).unwrap(), 1);
+        assert_eq!(n64.connector.si.dma_duration, ::n64::si::SI_DMA_DURATION);
+    }
+}
@@ -0,0 +1,170 @@
+// Referenced: libultra's OSTask layout and https://n64brew.dev/wiki/Reality_Signal_Processor
+use n64::connector::Connector;
+use n64::rsp::{RealitySignalProcessor, SP_STATUS_HALT, SP_STATUS_BROKE, SP_STATUS_INTR_BREAK, SP_STATUS_SIGNAL_0};
+use n64::mips_iface::MI_INTR_SP;
+use n64::hle_graphics::{GraphicsMicrocode, GraphicsHLE};
+use n64::hle_audio::{AudioABI, AudioHLE};
+use std::fmt;
+
+//libultra places the task header at the end of DMEM
+pub const OS_TASK_ADDRESS: usize = 0x0FC0;
+pub const M_GFXTASK: u32 = 1;
+pub const M_AUDTASK: u32 = 2;
+//Signal 2 is what libultra calls SP_STATUS_TASKDONE
+const SP_STATUS_TASKDONE: u32 = SP_STATUS_SIGNAL_0 << 2;
+const DEFAULT_UCODE_DATA_SIZE: usize = 0x800;
+const RDRAM_ADDRESS_MASK: u32 = 0x00FFFFFF;
+
+//Tasks that go wrong are still finished, the error is for the frontend to report
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum HLEError
+{
+    //Address of the display list call that went past the microcode's return stack
+    DISPLAY_LIST_OVERFLOW(u32),
+}
+
+impl fmt::Display for HLEError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self
+        {
+            HLEError::DISPLAY_LIST_OVERFLOW(address) => write!(f, "Display list call at 0x{:06X} is nested too deeply, the task was cut short", address),
+        }
+    }
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+pub struct OSTask
+{
+    pub task_type: u32,
+    pub flags: u32,
+    pub ucode_boot: u32,
+    pub ucode_boot_size: u32,
+    pub ucode: u32,
+    pub ucode_size: u32,
+    pub ucode_data: u32,
+    pub ucode_data_size: u32,
+    pub dram_stack: u32,
+    pub dram_stack_size: u32,
+    pub output_buffer: u32,
+    pub output_buffer_size: u32,
+    pub data_pointer: u32,
+    pub data_size: u32,
+    pub yield_data_pointer: u32,
+    pub yield_data_size: u32,
+}
+
+impl OSTask
+{
+    pub fn from_dmem(rsp: &RealitySignalProcessor) -> OSTask
+    {
+        let field = |index: usize| rsp.read_u32_from_address(OS_TASK_ADDRESS + (index * 4)).unwrap();
+        return OSTask
+        {
+            task_type: field(0),
+            flags: field(1),
+            ucode_boot: field(2),
+            ucode_boot_size: field(3),
+            ucode: field(4),
+            ucode_size: field(5),
+            ucode_data: field(6),
+            ucode_data_size: field(7),
+            dram_stack: field(8),
+            dram_stack_size: field(9),
+            output_buffer: field(10),
+            output_buffer_size: field(11),
+            data_pointer: field(12),
+            data_size: field(13),
+            yield_data_pointer: field(14),
+            yield_data_size: field(15),
+        }
+    }
+}
+
+//HLE reads go straight to RDRAM, where unmapped addresses read as zero and drop writes
+pub fn read_rdram_u8(connector: &Connector, address: u32) -> u8
+{
+    connector.rdram.read_u8((address & RDRAM_ADDRESS_MASK) as usize).unwrap_or(0)
+}
+
+pub fn read_rdram_u16(connector: &Connector, address: u32) -> u16
+{
+    (read_rdram_u8(connector, address) as u16) << 8 | read_rdram_u8(connector, address.wrapping_add(1)) as u16
+}
+
+pub fn read_rdram_u32(connector: &Connector, address: u32) -> u32
+{
+    (read_rdram_u16(connector, address) as u32) << 16 | read_rdram_u16(connector, address.wrapping_add(2)) as u32
+}
+
+pub fn store_rdram_u8(connector: &mut Connector, address: u32, value: u8)
+{
+    connector.rdram.store_u8((address & RDRAM_ADDRESS_MASK) as usize, value).ok();
+}
+
+pub fn store_rdram_u16(connector: &mut Connector, address: u32, value: u16)
+{
+    store_rdram_u8(connector, address, (value >> 8) as u8);
+    store_rdram_u8(connector, address.wrapping_add(1), value as u8);
+}
+
+pub fn store_rdram_u32(connector: &mut Connector, address: u32, value: u32)
+{
+    store_rdram_u16(connector, address, (value >> 16) as u16);
+    store_rdram_u16(connector, address.wrapping_add(2), value as u16);
+}
+
+//Microcode carries a printable identification string, such as "RSP Gfx ucode F3DEX 1.23", in its data section
+pub fn ucode_string(connector: &Connector, task: &OSTask) -> Option<String>
+{
+    //The size comes from the task in DMEM, so it is only trusted up to the usual data section size
+    let size = if task.ucode_data_size == 0 {DEFAULT_UCODE_DATA_SIZE} else {(task.ucode_data_size as usize).min(DEFAULT_UCODE_DATA_SIZE)};
+    let data: Vec<u8> = (0..size as u32).map(|offset| read_rdram_u8(connector, task.ucode_data.wrapping_add(offset))).collect();
+    let start = data.windows(4).position(|window| window == b"RSP ")?;
+    let end = data[start..].iter().position(|byte| *byte < 0x20 || *byte > 0x7E).map_or(data.len(), |length| start + length);
+    Some(String::from_utf8_lossy(&data[start..end]).into_owned())
+}
+
+//Runs the task in DMEM at a high level, returning false when it is not recognised so the RSP core can run it instead
+pub fn process_task(connector: &mut Connector) -> Result<bool, HLEError>
+{
+    let task = OSTask::from_dmem(&connector.rsp);
+    let result = match task.task_type
+    {
+        M_GFXTASK =>
+        {
+            match ucode_string(connector, &task).and_then(|ucode| GraphicsMicrocode::detect(&ucode))
+            {
+                Some(microcode) => GraphicsHLE::new(microcode).run(&task, connector),
+                None => return Ok(false),
+            }
+        },
+        M_AUDTASK =>
+        {
+            match AudioABI::detect(connector, &task)
+            {
+                Some(abi) => Ok(AudioHLE::new(abi).run(&task, connector)),
+                None => return Ok(false),
+            }
+        },
+        _ => return Ok(false),
+    };
+    //A failed task still ends so the game is not left waiting on the RSP
+    finish_task(connector);
+    result.map(|_| true)
+}
+
+//Microcode ends a task by flagging it done and executing BREAK
+fn finish_task(connector: &mut Connector)
+{
+    connector.rsp.set_status_bits(SP_STATUS_HALT | SP_STATUS_BROKE | SP_STATUS_TASKDONE);
+    if connector.rsp.status.get_value() as u32 & SP_STATUS_INTR_BREAK != 0
+    {
+        connector.mips_interface.raise_interrupt(MI_INTR_SP);
+    }
+}
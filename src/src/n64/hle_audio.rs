@@ -0,0 +1,1017 @@
+// Referenced: mupen64plus-rsp-hle's alist implementation of the audio microcode
+use n64::connector::Connector;
+use n64::hle::{OSTask, read_rdram_u8, read_rdram_u16, read_rdram_u32, store_rdram_u8, store_rdram_u16, store_rdram_u32};
+
+//The microcode works in DMEM, HLE keeps its own copy so the task header survives
+const AUDIO_BUFFER_SIZE: usize = 0x1000;
+const AUDIO_BUFFER_MASK: usize = AUDIO_BUFFER_SIZE - 1;
+const SEGMENT_COUNT: usize = 64;
+const ADPCM_TABLE_SIZE: usize = 16 * 8;
+//Stored envelope state between tasks, 40 halfwords
+const ENVELOPE_STATE_SIZE: u32 = 80;
+
+//ABI1 buffers are relative to the start of the microcode's sample area
+const ABI1_DMEM_BASE: u16 = 0x05C0;
+//ABI3 uses a fixed buffer layout
+const NAUDIO_COUNT: u16 = 0x0170;
+const NAUDIO_MAIN: u16 = 0x04F0;
+const NAUDIO_MAIN2: u16 = 0x0660;
+const NAUDIO_DRY_LEFT: u16 = 0x09D0;
+const NAUDIO_DRY_RIGHT: u16 = 0x0B40;
+const NAUDIO_WET_LEFT: u16 = 0x0CB0;
+const NAUDIO_WET_RIGHT: u16 = 0x0E20;
+
+const A_INIT: u8 = 0x01;
+const A_LOOP: u8 = 0x02;
+const A_LEFT: u8 = 0x02;
+const A_VOL: u8 = 0x04;
+const A_AUX: u8 = 0x08;
+
+//4 tap interpolation filter, the second half mirrors the first
+const RESAMPLE_LUT_HALF: [[u16; 4]; 32] = [
+    [0x0C39, 0x66AD, 0x0D46, 0xFFDF], [0x0B39, 0x6696, 0x0E5F, 0xFFD8], [0x0A44, 0x6669, 0x0F83, 0xFFD0], [0x095A, 0x6626, 0x10B4, 0xFFC8],
+    [0x087D, 0x65CD, 0x11F0, 0xFFBF], [0x07AB, 0x655E, 0x1338, 0xFFB6], [0x06E4, 0x64D9, 0x148C, 0xFFAC], [0x0628, 0x643F, 0x15EB, 0xFFA1],
+    [0x0577, 0x638F, 0x1756, 0xFF96], [0x04D1, 0x62CB, 0x18CB, 0xFF8A], [0x0435, 0x61F3, 0x1A4C, 0xFF7E], [0x03A4, 0x6106, 0x1BD7, 0xFF71],
+    [0x031C, 0x6007, 0x1D6C, 0xFF64], [0x029F, 0x5EF5, 0x1F0B, 0xFF56], [0x022A, 0x5DD0, 0x20B3, 0xFF48], [0x01BE, 0x5C9A, 0x2264, 0xFF3A],
+    [0x015B, 0x5B53, 0x241E, 0xFF2C], [0x0101, 0x59FC, 0x25E0, 0xFF1E], [0x00AE, 0x5896, 0x27A9, 0xFF10], [0x0063, 0x5720, 0x297A, 0xFF02],
+    [0x001F, 0x559D, 0x2B50, 0xFEF4], [0xFFE2, 0x540D, 0x2D2C, 0xFEE8], [0xFFAC, 0x5270, 0x2F0D, 0xFEDB], [0xFF7C, 0x50C7, 0x30F3, 0xFED0],
+    [0xFF53, 0x4F14, 0x32DC, 0xFEC6], [0xFF2E, 0x4D57, 0x34C8, 0xFEBD], [0xFF0F, 0x4B91, 0x36B6, 0xFEB6], [0xFEF5, 0x49C2, 0x38A5, 0xFEB0],
+    [0xFEDF, 0x47ED, 0x3A95, 0xFEAC], [0xFECE, 0x4611, 0x3C85, 0xFEAB], [0xFEC0, 0x4430, 0x3E74, 0xFEAC], [0xFEB6, 0x424A, 0x4060, 0xFEAF],
+];
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum AudioABI
+{
+    //libultra's aspMain
+    ABI1,
+    //Nintendo's later microcode used by Zelda, F-Zero X and others
+    ABI2,
+    //Rare's microcode
+    ABI3,
+}
+
+impl AudioABI
+{
+    //Audio microcode has no identification string, so known words of its data section are used instead
+    pub fn detect(connector: &Connector, task: &OSTask) -> Option<AudioABI>
+    {
+        let word = |offset: u32| read_rdram_u32(connector, task.ucode_data + offset);
+        if word(0x00) == 0x00000001
+        {
+            if word(0x30) == 0xF0000F00
+            {
+                return match word(0x28)
+                {
+                    0x1E24138C | 0x1DC8138C | 0x1E3C1390 => Some(AudioABI::ABI1),
+                    _ => None,
+                };
+            }
+            return match word(0x10)
+            {
+                0x1F681230 | 0x1F801250 | 0x109411F8 | 0x1F38122C | 0x1F08122C | 0x1CD01250 | 0x1EAC11B8 => Some(AudioABI::ABI2),
+                _ => None,
+            };
+        }
+        match word(0x10)
+        {
+            0x0000127C | 0x00001280 | 0x1C58126C => Some(AudioABI::ABI3),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Ramp
+{
+    value: i32,
+    target: i32,
+    step: i32,
+}
+
+impl Ramp
+{
+    fn step(&mut self) -> i16
+    {
+        self.value = self.value.wrapping_add(self.step);
+        let reached = if self.step <= 0 {self.value <= self.target} else {self.value >= self.target};
+        if reached
+        {
+            self.value = self.target;
+            self.step = 0;
+        }
+        (self.value >> 16) as i16
+    }
+}
+
+//Levels and ramps an ABI1 envelope carries between tasks
+struct ExponentialEnvelope
+{
+    dry: i16,
+    wet: i16,
+    ramps: [Ramp; 2],
+    rates: [i32; 2],
+    sequence: [i32; 2],
+}
+
+impl ExponentialEnvelope
+{
+    fn from_state(state: &[u8]) -> ExponentialEnvelope
+    {
+        let half = |offset: usize| ((state[offset] as u16) << 8 | state[offset + 1] as u16) as i16;
+        let word = |offset: usize| ((state[offset] as u32) << 24 | (state[offset + 1] as u32) << 16 | (state[offset + 2] as u32) << 8 | state[offset + 3] as u32) as i32;
+        let mut envelope = ExponentialEnvelope
+        {
+            dry: half(4),
+            wet: half(0),
+            ramps: [Ramp {value: 0, target: 0, step: 0}; 2],
+            rates: [word(16), word(20)],
+            sequence: [word(24), word(28)],
+        };
+        for (side, ramp) in envelope.ramps.iter_mut().enumerate()
+        {
+            ramp.target = word(8 + side * 4);
+            ramp.value = word(32 + side * 4);
+        }
+        envelope
+    }
+
+    fn to_state(&self) -> Vec<u8>
+    {
+        let mut state = vec![0_u8; ENVELOPE_STATE_SIZE as usize];
+        {
+            let mut put = |offset: usize, value: u32, size: usize|
+            {
+                for byte in 0..size
+                {
+                    state[offset + byte] = (value >> ((size - 1 - byte) * 8)) as u8;
+                }
+            };
+            put(0, self.wet as u16 as u32, 2);
+            put(4, self.dry as u16 as u32, 2);
+            for (side, ramp) in self.ramps.iter().enumerate()
+            {
+                put(8 + side * 4, ramp.target as u32, 4);
+                put(16 + side * 4, self.rates[side] as u32, 4);
+                put(24 + side * 4, self.sequence[side] as u32, 4);
+                put(32 + side * 4, ramp.value as u32, 4);
+            }
+        }
+        state
+    }
+}
+
+fn clamp_s16(value: i32) -> i16
+{
+    value.max(-32768).min(32767) as i16
+}
+
+fn vmulf(x: i16, y: i16) -> i16
+{
+    ((x as i32 * y as i32 + 0x4000) >> 15) as i16
+}
+
+fn align(value: u16, alignment: u16) -> u16
+{
+    value.wrapping_add(alignment - 1) & !(alignment - 1)
+}
+
+pub struct AudioHLE
+{
+    pub abi: AudioABI,
+    pub buffer: Vec<u8>,
+    segments: [u32; SEGMENT_COUNT],
+    input: u16,
+    output: u16,
+    count: u16,
+    dry_right: u16,
+    wet_left: u16,
+    wet_right: u16,
+    dry: i16,
+    wet: i16,
+    volume: [i16; 2],
+    target: [i16; 2],
+    rate: [i32; 2],
+    loop_address: u32,
+    table: [i16; ADPCM_TABLE_SIZE],
+    envelope_values: [u16; 3],
+    envelope_steps: [u16; 3],
+    filter_count: u16,
+    filter_lut_address: [u32; 2],
+}
+
+impl AudioHLE
+{
+    pub fn new(abi: AudioABI) -> AudioHLE
+    {
+        return AudioHLE
+        {
+            abi: abi,
+            buffer: vec![0; AUDIO_BUFFER_SIZE],
+            segments: [0; SEGMENT_COUNT],
+            input: 0,
+            output: 0,
+            count: 0,
+            dry_right: 0,
+            wet_left: 0,
+            wet_right: 0,
+            dry: 0,
+            wet: 0,
+            volume: [0; 2],
+            target: [0; 2],
+            rate: [0; 2],
+            loop_address: 0,
+            table: [0; ADPCM_TABLE_SIZE],
+            envelope_values: [0; 3],
+            envelope_steps: [0; 3],
+            filter_count: 0,
+            filter_lut_address: [0; 2],
+        }
+    }
+
+    //The command list is data_size bytes of 64 bit commands
+    pub fn run(&mut self, task: &OSTask, connector: &mut Connector)
+    {
+        let start = task.data_pointer;
+        for index in 0..(task.data_size / 8)
+        {
+            let w1 = read_rdram_u32(connector, start + (index * 8));
+            let w2 = read_rdram_u32(connector, start + (index * 8) + 4);
+            let command = ((w1 >> 24) & 0x7F) as u8;
+            match self.abi
+            {
+                AudioABI::ABI1 => self.execute_abi1(command, w1, w2, connector),
+                AudioABI::ABI2 => self.execute_abi2(command, w1, w2, connector),
+                AudioABI::ABI3 => self.execute_abi3(command, w1, w2, connector),
+            }
+        }
+    }
+
+    fn execute_abi1(&mut self, command: u8, w1: u32, w2: u32, connector: &mut Connector)
+    {
+        let flags = (w1 >> 16) as u8;
+        match command
+        {
+            0x01 =>
+            {
+                let address = self.address(w2);
+                let (output, input, count, loop_address) = (self.output, self.input, align(self.count, 32), self.loop_address);
+                self.adpcm(connector, flags & A_INIT != 0, flags & A_LOOP != 0, false, output, input, count, loop_address, address);
+            },
+            0x02 =>
+            {
+                let count = (w2 & 0xFFF) as u16;
+                if count != 0
+                {
+                    self.clear(w1 as u16 + ABI1_DMEM_BASE, align(count, 16));
+                }
+            },
+            0x03 =>
+            {
+                let address = self.address(w2);
+                let (dl, dr, wl, wr, input, count) = (self.output, self.dry_right, self.wet_left, self.wet_right, self.input, self.count);
+                let mut envelope = self.exponential_envelope(connector, flags & A_INIT != 0, address);
+                self.envelope_mix_exponential(&mut envelope, flags & A_AUX != 0, [dl, dr, wl, wr], input, count);
+                AudioHLE::store_envelope_state(connector, address, &envelope.to_state());
+            },
+            0x04 if self.count != 0 =>
+            {
+                let (input, count) = (self.input, self.count);
+                self.load(connector, input, self.address(w2), count);
+            },
+            0x05 =>
+            {
+                let address = self.address(w2);
+                let (output, input, count) = (self.output, self.input, align(self.count, 16));
+                self.resample(connector, flags & A_INIT != 0, output, input, count, (w1 & 0xFFFF) << 1, address);
+            },
+            0x06 if self.count != 0 =>
+            {
+                let (output, count) = (self.output, self.count);
+                self.save(connector, output, self.address(w2), count);
+            },
+            0x07 => self.segments[((w2 >> 24) & 0x3F) as usize] = w2 & 0x00FFFFFF,
+            0x08 if flags & A_AUX != 0 =>
+            {
+                self.dry_right = w1 as u16 + ABI1_DMEM_BASE;
+                self.wet_left = (w2 >> 16) as u16 + ABI1_DMEM_BASE;
+                self.wet_right = w2 as u16 + ABI1_DMEM_BASE;
+            },
+            0x08 =>
+            {
+                self.input = w1 as u16 + ABI1_DMEM_BASE;
+                self.output = (w2 >> 16) as u16 + ABI1_DMEM_BASE;
+                self.count = w2 as u16;
+            },
+            0x09 if flags & A_AUX != 0 =>
+            {
+                self.dry = w1 as i16;
+                self.wet = w2 as i16;
+            },
+            0x09 =>
+            {
+                let side = if flags & A_LEFT != 0 {0} else {1};
+                if flags & A_VOL != 0
+                {
+                    self.volume[side] = w1 as i16;
+                }
+                else
+                {
+                    self.target[side] = w1 as i16;
+                    self.rate[side] = w2 as i32;
+                }
+            },
+            0x0A if w2 as u16 != 0 => self.move_bytes((w2 >> 16) as u16 + ABI1_DMEM_BASE, w1 as u16 + ABI1_DMEM_BASE, align(w2 as u16, 16)),
+            0x0B => self.load_table(connector, self.address(w2), align(w1 as u16, 8) >> 1),
+            0x0C if self.count != 0 =>
+            {
+                let count = align(self.count, 32);
+                self.mix(w2 as u16 + ABI1_DMEM_BASE, (w2 >> 16) as u16 + ABI1_DMEM_BASE, count, w1 as i16);
+            },
+            0x0D if self.count != 0 =>
+            {
+                let (output, count) = (self.output, align(self.count, 16));
+                self.interleave(output, (w2 >> 16) as u16 + ABI1_DMEM_BASE, w2 as u16 + ABI1_DMEM_BASE, count);
+            },
+            0x0E if self.count != 0 =>
+            {
+                let address = self.address(w2);
+                let (output, input, count) = (self.output, self.input, align(self.count, 16));
+                self.pole_filter(connector, flags & A_INIT != 0, output, input, count, w1 as u16, address);
+            },
+            0x0F => self.loop_address = self.address(w2),
+            _ => (),
+        }
+    }
+
+    fn execute_abi2(&mut self, command: u8, w1: u32, w2: u32, connector: &mut Connector)
+    {
+        let flags = (w1 >> 16) as u8;
+        match command
+        {
+            0x01 =>
+            {
+                let (output, input, count, loop_address) = (self.output, self.input, align(self.count, 32), self.loop_address);
+                self.adpcm(connector, flags & A_INIT != 0, flags & A_LOOP != 0, flags & 0x4 != 0, output, input, count, loop_address, w2 & 0x00FFFFFF);
+            },
+            0x02 if w2 & 0xFFF != 0 => self.clear(w1 as u16, (w2 & 0xFFF) as u16),
+            0x04 => self.add((w2 & 0xFFFF) as u16, (w2 >> 16) as u16, ((w1 >> 12) & 0xFF0) as u16),
+            0x05 =>
+            {
+                let (output, input, count) = (self.output, self.input, align(self.count, 16));
+                self.resample(connector, flags & A_INIT != 0, output, input, count, (w1 & 0xFFFF) << 1, w2 & 0x00FFFFFF);
+            },
+            0x06 =>
+            {
+                let (output, input, count) = (self.output, self.input, self.count);
+                self.resample_zero_order_hold(output, input, count, (w1 & 0xFFFF) << 1, w2 & 0xFFFF);
+            },
+            0x07 if flags > 1 =>
+            {
+                self.filter_count = w1 as u16;
+                self.filter_lut_address[0] = w2 & 0x00FFFFFF;
+            },
+            0x07 =>
+            {
+                self.filter_lut_address[1] = (w2 & 0x00FFFFFF) + 0x10;
+                let count = self.filter_count;
+                self.filter(connector, w1 as u16, count, w2 & 0x00FFFFFF);
+            },
+            0x08 =>
+            {
+                self.input = w1 as u16;
+                self.output = (w2 >> 16) as u16;
+                self.count = w2 as u16;
+            },
+            0x09 | 0x1A => self.repeat_64((w2 >> 16) as u16, w1 as u16, flags),
+            0x0A if w2 as u16 != 0 => self.move_bytes((w2 >> 16) as u16, w1 as u16, align(w2 as u16, 4)),
+            0x0B => self.load_table(connector, w2 & 0x00FFFFFF, (w1 as u16) >> 1),
+            0x0C => self.mix(w2 as u16, (w2 >> 16) as u16, ((w1 >> 12) & 0xFF0) as u16, w1 as i16),
+            0x0D => self.interleave(w1 as u16, (w2 >> 16) as u16, w2 as u16, ((w1 >> 12) & 0xFF0) as u16),
+            0x0E | 0x18 => self.gain_q4_4((w2 >> 16) as u16, (w1 & 0xFFF) as u16, flags as i8),
+            0x0F => self.loop_address = w2 & 0x00FFFFFF,
+            0x10 => self.copy_blocks((w2 >> 16) as u16, w1 as u16, w2 as u16, flags),
+            0x11 => self.copy_every_other_sample(w2 as u16, (w2 >> 16) as u16, w1 as u16),
+            0x12 =>
+            {
+                self.envelope_values[2] = ((w1 >> 8) & 0xFF00) as u16;
+                self.envelope_steps[2] = w1 as u16;
+                self.envelope_steps[0] = (w2 >> 16) as u16;
+                self.envelope_steps[1] = w2 as u16;
+            },
+            0x13 =>
+            {
+                //Each flag bit inverts one of the outputs
+                let invert = |bit: u32| if w1 & bit != 0 {-1_i16} else {0};
+                let buffers = [((w2 >> 20) & 0xFF0) as u16, ((w2 >> 12) & 0xFF0) as u16, ((w2 >> 4) & 0xFF0) as u16, ((w2 << 4) & 0xFF0) as u16];
+                let swap_wet = (w1 >> 4) & 0x1 != 0;
+                self.envelope_mix_nead(swap_wet, buffers, ((w1 >> 12) & 0xFF0) as u16, ((w1 >> 8) & 0xFF) as u16, [invert(0x2), invert(0x1), invert(0x8), invert(0x4)]);
+            },
+            0x14 => self.load(connector, (w1 & 0xFFF) as u16, w2 & 0x00FFFFFF, ((w1 >> 12) & 0xFFF) as u16),
+            0x15 => self.save(connector, (w1 & 0xFFF) as u16, w2 & 0x00FFFFFF, ((w1 >> 12) & 0xFFF) as u16),
+            0x16 =>
+            {
+                self.envelope_values[0] = (w2 >> 16) as u16;
+                self.envelope_values[1] = w2 as u16;
+            },
+            _ => (),
+        }
+    }
+
+    fn execute_abi3(&mut self, command: u8, w1: u32, w2: u32, connector: &mut Connector)
+    {
+        let flags = (w1 >> 16) as u8;
+        match command
+        {
+            0x01 =>
+            {
+                let flags = (w2 >> 28) as u8;
+                let count = align(((w2 >> 16) & 0xFFF) as u16, 32);
+                let input = ((w2 >> 12) & 0xF) as u16 + NAUDIO_MAIN;
+                let output = (w2 & 0xFFF) as u16 + NAUDIO_MAIN;
+                let loop_address = self.loop_address;
+                self.adpcm(connector, flags & A_INIT != 0, flags & A_LOOP != 0, false, output, input, count, loop_address, w1 & 0x00FFFFFF);
+            },
+            0x02 => self.clear(w1 as u16 + NAUDIO_MAIN, (w2 & 0xFFF) as u16),
+            0x03 =>
+            {
+                self.volume[1] = w1 as i16;
+                let address = self.address(w2);
+                let buffers = [NAUDIO_DRY_LEFT, NAUDIO_DRY_RIGHT, NAUDIO_WET_LEFT, NAUDIO_WET_RIGHT];
+                self.envelope_mix_linear(connector, flags & A_INIT != 0, buffers, NAUDIO_MAIN, NAUDIO_COUNT, address);
+            },
+            0x04 => self.load(connector, (w1 & 0xFFF) as u16 + NAUDIO_MAIN, self.address(w2), ((w1 >> 12) & 0xFFF) as u16),
+            0x05 =>
+            {
+                let flags = (w2 >> 30) as u8;
+                let pitch = ((w2 >> 14) & 0xFFFF) << 1;
+                let input = ((w2 >> 2) & 0xFFF) as u16 + NAUDIO_MAIN;
+                let output = if w2 & 0x3 != 0 {NAUDIO_MAIN2} else {NAUDIO_MAIN};
+                self.resample(connector, flags & A_INIT != 0, output, input, NAUDIO_COUNT, pitch, w1 & 0x00FFFFFF);
+            },
+            0x06 => self.save(connector, (w1 & 0xFFF) as u16 + NAUDIO_MAIN, self.address(w2), ((w1 >> 12) & 0xFFF) as u16),
+            0x09 if flags & 0x4 != 0 && flags & 0x2 != 0 =>
+            {
+                self.volume[0] = w1 as i16;
+                self.dry = (w2 >> 16) as i16;
+                self.wet = w2 as i16;
+            },
+            0x09 =>
+            {
+                let side = if flags & 0x4 != 0 {1} else {0};
+                self.target[side] = w1 as i16;
+                self.rate[side] = w2 as i32;
+            },
+            0x0A if w2 as u16 != 0 => self.move_bytes((w2 >> 16) as u16 + NAUDIO_MAIN, w1 as u16 + NAUDIO_MAIN, align(w2 as u16, 4)),
+            0x0B => self.load_table(connector, self.address(w2), (w1 as u16) >> 1),
+            0x0C => self.mix(w2 as u16 + NAUDIO_MAIN, (w2 >> 16) as u16 + NAUDIO_MAIN, NAUDIO_COUNT, w1 as i16),
+            0x0D => self.interleave(NAUDIO_MAIN, NAUDIO_DRY_LEFT, NAUDIO_DRY_RIGHT, NAUDIO_COUNT),
+            //Patches the low half of the right rate, mirroring code inside SETVOL
+            0x0E => self.rate[1] = (self.rate[1] & !0xFFFF) | (w2 & 0xFFFF) as i32,
+            0x0F => self.loop_address = w2 & 0x00FFFFFF,
+            _ => (),
+        }
+    }
+
+    fn address(&self, address: u32) -> u32
+    {
+        self.segments[((address >> 24) & 0x3F) as usize].wrapping_add(address & 0x00FFFFFF)
+    }
+
+    pub fn read_u8(&self, address: u16) -> u8
+    {
+        self.buffer[address as usize & AUDIO_BUFFER_MASK]
+    }
+
+    pub fn read_i16(&self, address: u16) -> i16
+    {
+        ((self.read_u8(address) as u16) << 8 | self.read_u8(address.wrapping_add(1)) as u16) as i16
+    }
+
+    pub fn store_u8(&mut self, address: u16, value: u8)
+    {
+        self.buffer[address as usize & AUDIO_BUFFER_MASK] = value;
+    }
+
+    pub fn store_i16(&mut self, address: u16, value: i16)
+    {
+        self.store_u8(address, (value >> 8) as u8);
+        self.store_u8(address.wrapping_add(1), value as u8);
+    }
+
+    //Saturating add into the buffer
+    fn add_i16(&mut self, address: u16, value: i16)
+    {
+        let sum = clamp_s16(self.read_i16(address) as i32 + value as i32);
+        self.store_i16(address, sum);
+    }
+
+    fn sample(&self, position: u16) -> i16
+    {
+        self.read_i16(position.wrapping_mul(2))
+    }
+
+    fn set_sample(&mut self, position: u16, value: i16)
+    {
+        self.store_i16(position.wrapping_mul(2), value);
+    }
+
+    fn clear(&mut self, address: u16, count: u16)
+    {
+        for offset in 0..count
+        {
+            self.store_u8(address.wrapping_add(offset), 0);
+        }
+    }
+
+    fn move_bytes(&mut self, output: u16, input: u16, count: u16)
+    {
+        for offset in 0..count
+        {
+            let value = self.read_u8(input.wrapping_add(offset));
+            self.store_u8(output.wrapping_add(offset), value);
+        }
+    }
+
+    //DMA alignment applies to both sides of loads and saves
+    fn load(&mut self, connector: &Connector, address: u16, dram_address: u32, count: u16)
+    {
+        let (address, dram_address) = (address & !3, dram_address & !7);
+        for offset in 0..align(count, 8)
+        {
+            self.store_u8(address.wrapping_add(offset), read_rdram_u8(connector, dram_address + offset as u32));
+        }
+    }
+
+    fn save(&self, connector: &mut Connector, address: u16, dram_address: u32, count: u16)
+    {
+        let (address, dram_address) = (address & !3, dram_address & !7);
+        for offset in 0..align(count, 8)
+        {
+            store_rdram_u8(connector, dram_address + offset as u32, self.read_u8(address.wrapping_add(offset)));
+        }
+    }
+
+    fn load_table(&mut self, connector: &Connector, address: u32, count: u16)
+    {
+        for index in 0..(count as usize).min(ADPCM_TABLE_SIZE)
+        {
+            self.table[index] = read_rdram_u16(connector, address + (index as u32 * 2)) as i16;
+        }
+    }
+
+    fn mix(&mut self, output: u16, input: u16, count: u16, gain: i16)
+    {
+        for offset in (0..count).step_by(2)
+        {
+            let value = vmulf(self.read_i16(input.wrapping_add(offset)), gain);
+            self.add_i16(output.wrapping_add(offset), value);
+        }
+    }
+
+    fn add(&mut self, output: u16, input: u16, count: u16)
+    {
+        for offset in (0..count).step_by(2)
+        {
+            let value = self.read_i16(input.wrapping_add(offset));
+            self.add_i16(output.wrapping_add(offset), value);
+        }
+    }
+
+    fn gain_q4_4(&mut self, address: u16, count: u16, gain: i8)
+    {
+        for offset in (0..count).step_by(2)
+        {
+            let value = clamp_s16((self.read_i16(address.wrapping_add(offset)) as i32 * gain as i32) >> 4);
+            self.store_i16(address.wrapping_add(offset), value);
+        }
+    }
+
+    //Left and right samples end up alternating, count is bytes per channel
+    fn interleave(&mut self, output: u16, left: u16, right: u16, count: u16)
+    {
+        let samples: Vec<(i16, i16)> = (0..(count / 2)).map(|index| (self.read_i16(left.wrapping_add(index * 2)), self.read_i16(right.wrapping_add(index * 2)))).collect();
+        for (index, &(left, right)) in samples.iter().enumerate()
+        {
+            let offset = (index as u16).wrapping_mul(4);
+            self.store_i16(output.wrapping_add(offset), left);
+            self.store_i16(output.wrapping_add(offset).wrapping_add(2), right);
+        }
+    }
+
+    fn copy_every_other_sample(&mut self, output: u16, input: u16, count: u16)
+    {
+        for index in 0..count
+        {
+            let value = self.read_i16(input.wrapping_add(index.wrapping_mul(4)));
+            self.store_i16(output.wrapping_add(index.wrapping_mul(2)), value);
+        }
+    }
+
+    fn repeat_64(&mut self, output: u16, input: u16, count: u8)
+    {
+        let block: Vec<u8> = (0..128).map(|offset| self.read_u8(input.wrapping_add(offset))).collect();
+        for repeat in 0..count as u16
+        {
+            for (offset, value) in block.iter().enumerate()
+            {
+                self.store_u8(output.wrapping_add((repeat * 128) + offset as u16), *value);
+            }
+        }
+    }
+
+    fn copy_blocks(&mut self, output: u16, input: u16, block_size: u16, count: u8)
+    {
+        let (mut output, mut input) = (output, input);
+        for _ in 0..count.max(1)
+        {
+            let mut remaining = block_size as i32;
+            loop
+            {
+                self.move_bytes(output, input, 0x20);
+                input = input.wrapping_add(0x20);
+                output = output.wrapping_add(0x20);
+                remaining -= 0x20;
+                if remaining <= 0
+                {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn adpcm(&mut self, connector: &mut Connector, init: bool, use_loop: bool, two_bit: bool, output: u16, input: u16, count: u16, loop_address: u32, state_address: u32)
+    {
+        let mut last_frame = [0_i16; 16];
+        if !init
+        {
+            let address = if use_loop {loop_address} else {state_address};
+            for index in 0..16
+            {
+                last_frame[index] = read_rdram_u16(connector, address + (index as u32 * 2)) as i16;
+            }
+        }
+
+        //The previous frame is written ahead of the new samples
+        let mut output = output;
+        for sample in last_frame.iter()
+        {
+            self.store_i16(output, *sample);
+            output = output.wrapping_add(2);
+        }
+
+        let mut input = input;
+        let mut remaining = count as i32;
+        while remaining > 0
+        {
+            let code = self.read_u8(input);
+            input = input.wrapping_add(1);
+            let scale = (code >> 4) as u32;
+            let entry = ((code & 0xF) as usize) << 4;
+            let mut frame = [0_i16; 16];
+            input = input.wrapping_add(self.predict_frame(&mut frame, input, scale, two_bit));
+
+            let previous = last_frame;
+            let book: Vec<i16> = self.table[(entry % ADPCM_TABLE_SIZE)..((entry % ADPCM_TABLE_SIZE) + 16)].to_vec();
+            AudioHLE::residuals(&mut last_frame[0..8], &frame[0..8], &book, previous[14], previous[15]);
+            let first_half = [last_frame[6], last_frame[7]];
+            AudioHLE::residuals(&mut last_frame[8..16], &frame[8..16], &book, first_half[0], first_half[1]);
+            for sample in last_frame.iter()
+            {
+                self.store_i16(output, *sample);
+                output = output.wrapping_add(2);
+            }
+            remaining -= 32;
+        }
+
+        for index in 0..16
+        {
+            store_rdram_u16(connector, state_address + (index as u32 * 2), last_frame[index] as u16);
+        }
+    }
+
+    //Returns how many input bytes the frame used
+    fn predict_frame(&self, frame: &mut [i16; 16], input: u16, scale: u32, two_bit: bool) -> u16
+    {
+        let predict = |byte: u8, mask: u8, left_shift: u32, right_shift: u32| (((byte & mask) as u16) << left_shift) as i16 >> right_shift;
+        if two_bit
+        {
+            let right_shift = if scale < 14 {14 - scale} else {0};
+            for index in 0..4
+            {
+                let byte = self.read_u8(input.wrapping_add(index as u16));
+                frame[index * 4] = predict(byte, 0xC0, 8, right_shift);
+                frame[index * 4 + 1] = predict(byte, 0x30, 10, right_shift);
+                frame[index * 4 + 2] = predict(byte, 0x0C, 12, right_shift);
+                frame[index * 4 + 3] = predict(byte, 0x03, 14, right_shift);
+            }
+            4
+        }
+        else
+        {
+            let right_shift = if scale < 12 {12 - scale} else {0};
+            for index in 0..8
+            {
+                let byte = self.read_u8(input.wrapping_add(index as u16));
+                frame[index * 2] = predict(byte, 0xF0, 8, right_shift);
+                frame[index * 2 + 1] = predict(byte, 0x0F, 12, right_shift);
+            }
+            8
+        }
+    }
+
+    //Second order prediction from the codebook entry, the second book also predicts within the frame
+    fn residuals(output: &mut [i16], input: &[i16], book: &[i16], last_1: i16, last_2: i16)
+    {
+        let (book_1, book_2) = (&book[0..8], &book[8..16]);
+        for index in 0..8
+        {
+            let mut accumulator = (input[index] as i32) << 11;
+            accumulator += book_1[index] as i32 * last_1 as i32 + book_2[index] as i32 * last_2 as i32;
+            accumulator += (0..index).map(|k| book_2[k] as i32 * input[index - 1 - k] as i32).sum::<i32>();
+            output[index] = clamp_s16(accumulator >> 11);
+        }
+    }
+
+    fn resample(&mut self, connector: &mut Connector, init: bool, output: u16, input: u16, count: u16, pitch: u32, address: u32)
+    {
+        let mut input_position = (input >> 1).wrapping_sub(4);
+        let mut output_position = output >> 1;
+        let mut accumulator: u32 = if init {0} else {read_rdram_u16(connector, address + 8) as u32};
+        for index in 0..4
+        {
+            let value = if init {0} else {read_rdram_u16(connector, address + (index as u32 * 2)) as i16};
+            self.set_sample(input_position.wrapping_add(index), value);
+        }
+
+        for _ in 0..(count >> 1)
+        {
+            let row = ((accumulator & 0xFC00) >> 10) as usize;
+            let coefficients = if row < 32 {RESAMPLE_LUT_HALF[row]} else {let mirror = RESAMPLE_LUT_HALF[63 - row]; [mirror[3], mirror[2], mirror[1], mirror[0]]};
+            let sum: i32 = (0..4).map(|tap| self.sample(input_position.wrapping_add(tap as u16)) as i32 * coefficients[tap] as i16 as i32).sum();
+            self.set_sample(output_position, clamp_s16(sum >> 15));
+            output_position = output_position.wrapping_add(1);
+            accumulator += pitch;
+            input_position = input_position.wrapping_add((accumulator >> 16) as u16);
+            accumulator &= 0xFFFF;
+        }
+
+        for index in 0..4
+        {
+            store_rdram_u16(connector, address + (index as u32 * 2), self.sample(input_position.wrapping_add(index)) as u16);
+        }
+        store_rdram_u16(connector, address + 8, accumulator as u16);
+    }
+
+    fn resample_zero_order_hold(&mut self, output: u16, input: u16, count: u16, pitch: u32, accumulator: u32)
+    {
+        let (mut input_position, mut output_position, mut accumulator) = (input >> 1, output >> 1, accumulator);
+        for _ in 0..(count >> 1)
+        {
+            let value = self.sample(input_position);
+            self.set_sample(output_position, value);
+            output_position = output_position.wrapping_add(1);
+            accumulator += pitch;
+            input_position = input_position.wrapping_add((accumulator >> 16) as u16);
+            accumulator &= 0xFFFF;
+        }
+    }
+
+    fn pole_filter(&mut self, connector: &mut Connector, init: bool, output: u16, input: u16, count: u16, gain: u16, address: u32)
+    {
+        let gain = gain as i16 as i32;
+        let (mut last_1, mut last_2) = if init {(0, 0)} else {(read_rdram_u16(connector, address + 4) as i16, read_rdram_u16(connector, address + 6) as i16)};
+        let h1: Vec<i32> = self.table[0..8].iter().map(|value| *value as i32).collect();
+        let h2_before: Vec<i32> = self.table[8..16].iter().map(|value| *value as i32).collect();
+        let h2: Vec<i32> = h2_before.iter().map(|value| (value * gain) >> 14).collect();
+        for index in 0..8
+        {
+            self.table[8 + index] = h2[index] as i16;
+        }
+
+        let (mut input, mut output) = (input, output);
+        let mut remaining = align(count, 16) as i32;
+        while remaining > 0
+        {
+            let frame: Vec<i32> = (0..8).map(|index| self.read_i16(input.wrapping_add(index * 2)) as i32).collect();
+            input = input.wrapping_add(16);
+            for index in 0..8
+            {
+                let mut accumulator = frame[index] * gain;
+                accumulator += h1[index] * last_1 as i32 + h2_before[index] * last_2 as i32;
+                accumulator += (0..index).map(|k| h2[k] * frame[index - 1 - k]).sum::<i32>();
+                self.store_i16(output.wrapping_add(index as u16 * 2), clamp_s16(accumulator >> 14));
+            }
+            last_1 = self.read_i16(output.wrapping_add(12));
+            last_2 = self.read_i16(output.wrapping_add(14));
+            output = output.wrapping_add(16);
+            remaining -= 16;
+        }
+        //The last four samples carry over to the next task
+        for index in 0..4
+        {
+            store_rdram_u16(connector, address + (index * 2), self.read_i16(output.wrapping_sub(8 - (index as u16 * 2))) as u16);
+        }
+    }
+
+    //Direct form 8 tap FIR using the average of the two coefficient tables, history lives in RDRAM
+    fn filter(&mut self, connector: &mut Connector, address: u16, count: u16, history_address: u32)
+    {
+        let mut coefficients = [0_i32; 8];
+        for index in 0..8
+        {
+            let first = read_rdram_u16(connector, self.filter_lut_address[0] + (index as u32 * 2)) as i16 as i32;
+            let second = read_rdram_u16(connector, self.filter_lut_address[1] + (index as u32 * 2)) as i16 as i32;
+            coefficients[index] = (first + second) >> 1;
+        }
+        let mut history: Vec<i32> = (0..8).map(|index| read_rdram_u16(connector, history_address + (index * 2)) as i16 as i32).collect();
+        for index in 0..(count / 2)
+        {
+            let position = address.wrapping_add(index * 2);
+            history.remove(0);
+            history.push(self.read_i16(position) as i32);
+            let sum: i32 = (0..8).map(|tap| history[7 - tap] * coefficients[tap]).sum();
+            self.store_i16(position, clamp_s16(sum >> 15));
+        }
+        for index in 0..8
+        {
+            store_rdram_u16(connector, history_address + (index as u32 * 2), history[index] as u16);
+        }
+    }
+
+    fn read_envelope_state(connector: &Connector, address: u32) -> Vec<u8>
+    {
+        (0..ENVELOPE_STATE_SIZE).map(|offset| read_rdram_u8(connector, address + offset)).collect()
+    }
+
+    fn store_envelope_state(connector: &mut Connector, address: u32, state: &[u8])
+    {
+        for (offset, value) in state.iter().enumerate()
+        {
+            store_rdram_u8(connector, address + offset as u32, *value);
+        }
+    }
+
+    fn mix_envelope(&mut self, buffers: &[u16; 4], outputs: usize, position: u16, gains: [i16; 4], sample: i16)
+    {
+        for output in 0..outputs
+        {
+            self.add_i16(buffers[output].wrapping_add(position.wrapping_mul(2)), vmulf(sample, gains[output]));
+        }
+    }
+
+    fn envelope_gains(&self, left: i16, right: i16, dry: i16, wet: i16) -> [i16; 4]
+    {
+        let gain = |volume: i16, level: i16| clamp_s16((volume as i32 * level as i32 + 0x4000) >> 15);
+        [gain(left, dry), gain(right, dry), gain(left, wet), gain(right, wet)]
+    }
+
+    //A new envelope starts from the levels set by earlier commands, otherwise it continues from RDRAM
+    fn exponential_envelope(&self, connector: &Connector, init: bool, address: u32) -> ExponentialEnvelope
+    {
+        if !init
+        {
+            return ExponentialEnvelope::from_state(&AudioHLE::read_envelope_state(connector, address));
+        }
+        let mut envelope = ExponentialEnvelope
+        {
+            dry: self.dry,
+            wet: self.wet,
+            ramps: [Ramp {value: 0, target: 0, step: 0}; 2],
+            rates: self.rate,
+            sequence: [0; 2],
+        };
+        for (side, ramp) in envelope.ramps.iter_mut().enumerate()
+        {
+            ramp.value = (self.volume[side] as i32) << 16;
+            ramp.target = (self.target[side] as i32) << 16;
+            envelope.sequence[side] = (self.volume[side] as i32).wrapping_mul(self.rate[side]);
+        }
+        envelope
+    }
+
+    //ABI1 ramps approach their targets exponentially
+    fn envelope_mix_exponential(&mut self, envelope: &mut ExponentialEnvelope, aux: bool, buffers: [u16; 4], input: u16, count: u16)
+    {
+        let outputs = if aux {4} else {2};
+        for ramp in envelope.ramps.iter_mut()
+        {
+            ramp.step = ramp.target.wrapping_sub(ramp.value);
+        }
+
+        let mut position: u16 = 0;
+        for _ in 0..((count as u32 + 15) / 16)
+        {
+            for (side, ramp) in envelope.ramps.iter_mut().enumerate()
+            {
+                if ramp.step != 0
+                {
+                    envelope.sequence[side] = ((envelope.sequence[side] as i64 * envelope.rates[side] as i64) >> 16) as i32;
+                    ramp.step = (envelope.sequence[side].wrapping_sub(ramp.value)) >> 3;
+                }
+            }
+            for _ in 0..8
+            {
+                let left = envelope.ramps[0].step();
+                let right = envelope.ramps[1].step();
+                let gains = self.envelope_gains(left, right, envelope.dry, envelope.wet);
+                let sample = self.read_i16(input.wrapping_add(position.wrapping_mul(2)));
+                self.mix_envelope(&buffers, outputs, position, gains, sample);
+                position = position.wrapping_add(1);
+            }
+        }
+    }
+
+    //ABI3 ramps move linearly
+    fn envelope_mix_linear(&mut self, connector: &mut Connector, init: bool, buffers: [u16; 4], input: u16, count: u16, address: u32)
+    {
+        let (mut dry, mut wet) = (self.dry, self.wet);
+        let mut ramps = [Ramp {value: 0, target: 0, step: 0}; 2];
+        if init
+        {
+            for (side, ramp) in ramps.iter_mut().enumerate()
+            {
+                *ramp = Ramp
+                {
+                    value: (self.volume[side] as i32) << 16,
+                    target: (self.target[side] as i32) << 16,
+                    step: self.rate[side] / 8,
+                };
+            }
+        }
+        else
+        {
+            let state = AudioHLE::read_envelope_state(connector, address);
+            let half = |offset: usize| ((state[offset] as u16) << 8 | state[offset + 1] as u16) as i16;
+            let word = |offset: usize| ((state[offset] as u32) << 24 | (state[offset + 1] as u32) << 16 | (state[offset + 2] as u32) << 8 | state[offset + 3] as u32) as i32;
+            wet = half(0);
+            dry = half(4);
+            for (side, ramp) in ramps.iter_mut().enumerate()
+            {
+                *ramp = Ramp
+                {
+                    target: (half(8 + side * 4) as i32) << 16,
+                    step: word(16 + side * 4),
+                    value: word(32 + side * 4),
+                };
+            }
+        }
+
+        let mut position: u16 = 0;
+        for _ in 0..(count >> 4)
+        {
+            for _ in 0..8
+            {
+                let left = ramps[0].step();
+                let right = ramps[1].step();
+                let gains = self.envelope_gains(left, right, dry, wet);
+                let sample = self.read_i16(input.wrapping_add(position.wrapping_mul(2)));
+                self.mix_envelope(&buffers, 4, position, gains, sample);
+                position = position.wrapping_add(1);
+            }
+        }
+
+        store_rdram_u16(connector, address, wet as u16);
+        store_rdram_u16(connector, address + 4, dry as u16);
+        for (side, ramp) in ramps.iter().enumerate()
+        {
+            let offset = side as u32 * 4;
+            store_rdram_u16(connector, address + 8 + offset, (ramp.target >> 16) as u16);
+            store_rdram_u32(connector, address + 16 + offset, ramp.step as u32);
+            store_rdram_u32(connector, address + 32 + offset, ramp.value as u32);
+        }
+    }
+
+    //ABI2 scales by envelope values that step once per eight samples
+    fn envelope_mix_nead(&mut self, swap_wet: bool, buffers: [u16; 4], input: u16, count: u16, inversions: [i16; 4])
+    {
+        let [dry_left, dry_right, mut wet_left, mut wet_right] = buffers;
+        if swap_wet
+        {
+            ::std::mem::swap(&mut wet_left, &mut wet_right);
+        }
+        let mut position: u16 = 0;
+        for _ in 0..align(count, 8)
+        {
+            for _ in 0..8
+            {
+                let sample = self.read_i16(input.wrapping_add(position * 2)) as i32;
+                let left = ((sample * self.envelope_values[0] as i32) >> 16) as i16 ^ inversions[0];
+                let right = ((sample * self.envelope_values[1] as i32) >> 16) as i16 ^ inversions[1];
+                let wet_left_value = ((left as i32 * self.envelope_values[2] as i32) >> 16) as i16 ^ inversions[2];
+                let wet_right_value = ((right as i32 * self.envelope_values[2] as i32) >> 16) as i16 ^ inversions[3];
+                self.add_i16(dry_left.wrapping_add(position * 2), left);
+                self.add_i16(dry_right.wrapping_add(position * 2), right);
+                self.add_i16(wet_left.wrapping_add(position * 2), wet_left_value);
+                self.add_i16(wet_right.wrapping_add(position * 2), wet_right_value);
+                position += 1;
+            }
+            for index in 0..3
+            {
+                self.envelope_values[index] = self.envelope_values[index].wrapping_add(self.envelope_steps[index]);
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod hle_audio_tests
+{
+    use n64::hle::*;
+    use n64::hle_audio::*;
+    use n64::connector::Connector;
+    use n64::rsp::*;
+
+    const UCODE_DATA: u32 = 0x1000;
+    const AUDIO_LIST: u32 = 0x2000;
+    const SAMPLES: u32 = 0x4000;
+    const STATE: u32 = 0x6000;
+    const OUTPUT: u32 = 0x7000;
+
+    const ABI1_SIGNATURE: [(u32, u32); 3] = [(0x00, 0x00000001), (0x30, 0xF0000F00), (0x28, 0x1E24138C)];
+    const ABI2_SIGNATURE: [(u32, u32); 2] = [(0x00, 0x00000001), (0x10, 0x1F681230)];
+    const ABI3_SIGNATURE: [(u32, u32); 1] = [(0x10, 0x0000127C)];
+
+    fn setup_task(connector: &mut Connector, signature: &[(u32, u32)], commands: &[(u32, u32)])
+    {
+        for &(offset, value) in signature.iter()
+        {
+            store_rdram_u32(connector, UCODE_DATA + offset, value);
+        }
+        for (index, &(w1, w2)) in commands.iter().enumerate()
+        {
+            store_rdram_u32(connector, AUDIO_LIST + (index as u32 * 8), w1);
+            store_rdram_u32(connector, AUDIO_LIST + (index as u32 * 8) + 4, w2);
+        }
+        let task = [(0x00, M_AUDTASK), (0x18, UCODE_DATA), (0x30, AUDIO_LIST), (0x34, commands.len() as u32 * 8)];
+        for &(offset, value) in task.iter()
+        {
+            connector.rsp.load_u32_to_address(OS_TASK_ADDRESS + offset, value).unwrap();
+        }
+        connector.rsp.status.set_value(SP_STATUS_INTR_BREAK);
+    }
+
+    fn store_samples(connector: &mut Connector, address: u32, samples: &[i16])
+    {
+        for (index, sample) in samples.iter().enumerate()
+        {
+            store_rdram_u16(connector, address + (index as u32 * 2), *sample as u16);
+        }
+    }
+
+    fn read_samples(connector: &Connector, address: u32, count: u32) -> Vec<i16>
+    {
+        (0..count).map(|index| read_rdram_u16(connector, address + (index * 2)) as i16).collect()
+    }
+
+    #[test]
+    fn abi_is_detected_from_the_microcode_data()
+    {
+        let mut connector = Connector::test();
+        setup_task(&mut connector, &ABI1_SIGNATURE, &[]);
+        assert_eq!(AudioABI::detect(&connector, &OSTask::from_dmem(&connector.rsp)), Some(AudioABI::ABI1));
+        let mut connector = Connector::test();
+        setup_task(&mut connector, &ABI2_SIGNATURE, &[]);
+        assert_eq!(AudioABI::detect(&connector, &OSTask::from_dmem(&connector.rsp)), Some(AudioABI::ABI2));
+        let mut connector = Connector::test();
+        setup_task(&mut connector, &ABI3_SIGNATURE, &[]);
+        assert_eq!(AudioABI::detect(&connector, &OSTask::from_dmem(&connector.rsp)), Some(AudioABI::ABI3));
+
+        //Unknown microcode stays with the RSP core
+        let mut connector = Connector::test();
+        setup_task(&mut connector, &[(0x00, 0x00000001), (0x10, 0x12345678)], &[]);
+        assert!(!process_task(&mut connector).unwrap());
+        assert!(!connector.rsp.is_halted());
+    }
+
+    #[test]
+    fn adpcm_frames_are_decoded_and_saved()
+    {
+        let mut connector = Connector::test();
+        //Scale 0 and an empty codebook turn each nibble straight into a sample
+        let frame = [0x00_u8, 0x12, 0x12, 0x12, 0x12, 0x12, 0x12, 0x12, 0x12];
+        for (offset, byte) in frame.iter().enumerate()
+        {
+            store_rdram_u8(&mut connector, SAMPLES + offset as u32, *byte);
+        }
+        setup_task(&mut connector, &ABI1_SIGNATURE, &[
+            (0x08000000, 0x01000010),
+            (0x04000000, SAMPLES),
+            (0x0B000020, 0x5000),
+            (0x08000000, 0x01000020),
+            (0x01010000, STATE),
+            (0x08000000, 0x01200020),
+            (0x06000000, OUTPUT),
+        ]);
+        assert!(process_task(&mut connector).unwrap());
+
+        let expected: Vec<i16> = (0..16).map(|index| if index % 2 == 0 {1} else {2}).collect();
+        assert_eq!(read_samples(&connector, OUTPUT, 16), expected);
+        //The last frame is kept for the next task
+        assert_eq!(read_samples(&connector, STATE, 16), expected);
+        assert_eq!(connector.rsp.status.get_value() as u32 & SP_STATUS_HALT, SP_STATUS_HALT);
+    }
+
+    #[test]
+    fn resampling_at_unit_pitch_keeps_a_constant_level()
+    {
+        let mut connector = Connector::test();
+        store_samples(&mut connector, SAMPLES, &[1000; 8]);
+        setup_task(&mut connector, &ABI1_SIGNATURE, &[
+            (0x08000000, 0x01000010),
+            (0x04000000, SAMPLES),
+            (0x05018000, STATE),
+            (0x06000000, OUTPUT),
+        ]);
+        assert!(process_task(&mut connector).unwrap());
+
+        //The first four outputs still see the zeroed history
+        assert_eq!(read_samples(&connector, OUTPUT, 8), vec![0, -2, 102, 904, 1000, 1000, 1000, 1000]);
+        assert_eq!(read_samples(&connector, STATE, 5), vec![1000, 1000, 1000, 1000, 0]);
+    }
+
+    #[test]
+    fn channels_are_mixed_and_interleaved()
+    {
+        let mut connector = Connector::test();
+        store_samples(&mut connector, SAMPLES, &[100; 8]);
+        store_samples(&mut connector, SAMPLES + 0x10, &[-100; 8]);
+        setup_task(&mut connector, &ABI2_SIGNATURE, &[
+            (0x14010000, SAMPLES),
+            (0x14010010, SAMPLES + 0x10),
+            //Half of the left channel is added to the right
+            (0x0C014000, 0x00000010),
+            (0x0D010100, 0x00000010),
+            (0x15020100, OUTPUT),
+        ]);
+        assert!(process_task(&mut connector).unwrap());
+
+        let expected: Vec<i16> = (0..16).map(|index| if index % 2 == 0 {100} else {-50}).collect();
+        assert_eq!(read_samples(&connector, OUTPUT, 16), expected);
+    }
+}
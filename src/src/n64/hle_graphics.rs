@@ -0,0 +1,1016 @@
+// Referenced: libultra gbi.h, GLideN64 and libdragon's rdpq triangle setup
+use n64::connector::Connector;
+use n64::hle::{OSTask, HLEError, read_rdram_u8, read_rdram_u16, read_rdram_u32};
+
+const SEGMENT_COUNT: usize = 16;
+const VERTEX_BUFFER_SIZE: usize = 64;
+const LIGHT_COUNT: usize = 10;
+const MATRIX_STACK_SIZE: usize = 32;
+const DISPLAY_LIST_STACK_SIZE: usize = 18;
+//Guards against display lists that never reach G_ENDDL
+const MAX_DISPLAY_LIST_COMMANDS: usize = 0x100000;
+const VERTEX_SIZE: u32 = 16;
+const MATRIX_SIZE: u32 = 64;
+//Guard band ratio used by the microcode's clipping, the RDP scissor handles the rest
+const CLIP_RATIO: f32 = 2.0;
+
+//RDP commands produced or passed through
+pub const RDP_TRIANGLE: u64 = 0x08;
+pub const RDP_TRIANGLE_ZBUFFER: u64 = 0x01;
+pub const RDP_TRIANGLE_TEXTURE: u64 = 0x02;
+pub const RDP_TRIANGLE_SHADE: u64 = 0x04;
+pub const RDP_TEXTURE_RECTANGLE: u8 = 0xE4;
+pub const RDP_TEXTURE_RECTANGLE_FLIP: u8 = 0xE5;
+pub const RDP_SET_OTHER_MODES: u8 = 0xEF;
+const RDP_COMMANDS_START: u8 = 0xE4;
+
+//Other mode bits the vertex pipeline cares about
+const G_TP_PERSP: u32 = 0x00080000;
+
+const G_MTX_PUSH: u32 = 0x04;
+const G_MTX_LOAD: u32 = 0x02;
+const G_MTX_PROJECTION: u32 = 0x01;
+
+const G_MW_NUMLIGHT: u32 = 0x02;
+const G_MW_SEGMENT: u32 = 0x06;
+const G_MW_FOG: u32 = 0x08;
+const G_MW_LIGHTCOL: u32 = 0x0A;
+
+const CLIP_NEGATIVE_X: u8 = 0x01;
+const CLIP_POSITIVE_X: u8 = 0x02;
+const CLIP_NEGATIVE_Y: u8 = 0x04;
+const CLIP_POSITIVE_Y: u8 = 0x08;
+const CLIP_NEAR: u8 = 0x10;
+const CLIP_FAR: u8 = 0x20;
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum GraphicsMicrocode
+{
+    FAST3D,
+    F3DEX,
+    F3DEX2,
+}
+
+impl GraphicsMicrocode
+{
+    //"RSP SW Version: 2.0D, 04-01-96" is Fast3D, "RSP Gfx ucode F3DEX 1.23 ..." and "RSP Gfx ucode F3DZEX.NoN fifo 2.08 ..." name the rest
+    pub fn detect(ucode: &str) -> Option<GraphicsMicrocode>
+    {
+        if ucode.starts_with("RSP SW Version")
+        {
+            return Some(GraphicsMicrocode::FAST3D);
+        }
+        let mut words = ucode.split_whitespace();
+        if words.next() != Some("RSP") || words.next() != Some("Gfx") || words.next() != Some("ucode")
+        {
+            return None;
+        }
+        //Sprite and line microcode share the prefix but not the command set
+        if !words.next().map_or(false, |name| name.starts_with("F3D"))
+        {
+            return None;
+        }
+        match words.find(|word| word.chars().next().map_or(false, |c| c.is_digit(10)) && word.contains('.'))
+        {
+            Some(version) if version.starts_with("2.") => Some(GraphicsMicrocode::F3DEX2),
+            Some(_) => Some(GraphicsMicrocode::F3DEX),
+            None => None,
+        }
+    }
+
+    fn geometry_bits(&self) -> GeometryBits
+    {
+        match *self
+        {
+            GraphicsMicrocode::F3DEX2 => GeometryBits
+            {
+                zbuffer: 0x00000001,
+                shade: 0x00000004,
+                cull_front: 0x00000200,
+                cull_back: 0x00000400,
+                fog: 0x00010000,
+                lighting: 0x00020000,
+                texture_gen: 0x00040000,
+                shading_smooth: 0x00200000,
+            },
+            _ => GeometryBits
+            {
+                zbuffer: 0x00000001,
+                shade: 0x00000004,
+                shading_smooth: 0x00000200,
+                cull_front: 0x00001000,
+                cull_back: 0x00002000,
+                fog: 0x00010000,
+                lighting: 0x00020000,
+                texture_gen: 0x00040000,
+            },
+        }
+    }
+
+    fn vertex_buffer_size(&self) -> usize
+    {
+        match *self
+        {
+            GraphicsMicrocode::FAST3D => 16,
+            _ => 32,
+        }
+    }
+}
+
+//Geometry mode bits moved around in F3DEX2
+struct GeometryBits
+{
+    zbuffer: u32,
+    shade: u32,
+    shading_smooth: u32,
+    cull_front: u32,
+    cull_back: u32,
+    fog: u32,
+    lighting: u32,
+    texture_gen: u32,
+}
+
+//Row vector convention, a point is transformed by point * matrix
+pub type Matrix = [[f32; 4]; 4];
+
+const IDENTITY: Matrix = [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]];
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix
+{
+    let mut result = [[0.0; 4]; 4];
+    for row in 0..4
+    {
+        for column in 0..4
+        {
+            result[row][column] = (0..4).map(|index| a[row][index] * b[index][column]).sum();
+        }
+    }
+    result
+}
+
+fn transform(point: [f32; 4], matrix: &Matrix) -> [f32; 4]
+{
+    let mut result = [0.0; 4];
+    for column in 0..4
+    {
+        result[column] = (0..4).map(|index| point[index] * matrix[index][column]).sum();
+    }
+    result
+}
+
+fn normalize(vector: [f32; 3]) -> [f32; 3]
+{
+    let length = (vector[0] * vector[0] + vector[1] * vector[1] + vector[2] * vector[2]).sqrt();
+    if length == 0.0 {vector} else {[vector[0] / length, vector[1] / length, vector[2] / length]}
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32
+{
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+//Matrices are s15.16 with all the integer halves before the fractional halves
+fn read_matrix(connector: &Connector, address: u32) -> Matrix
+{
+    let mut matrix = [[0.0; 4]; 4];
+    for row in 0..4
+    {
+        for column in 0..4
+        {
+            let offset = (row * 8 + column * 2) as u32;
+            let integer = read_rdram_u16(connector, address + offset) as i16 as i32;
+            let fraction = read_rdram_u16(connector, address + 32 + offset) as i32;
+            matrix[row][column] = ((integer << 16) | fraction) as f32 / 65536.0;
+        }
+    }
+    matrix
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Default)]
+pub struct Vertex
+{
+    pub clip: [f32; 4],
+    //Screen x and y in pixels and z in RDP depth units
+    pub screen: [f32; 3],
+    pub inverse_w: f32,
+    pub color: [f32; 4],
+    //Texture coordinates in s10.5 texels
+    pub s: f32,
+    pub t: f32,
+    pub clip_flags: u8,
+}
+
+impl Vertex
+{
+    fn interpolate(&self, other: &Vertex, amount: f32) -> Vertex
+    {
+        let mix = |a: f32, b: f32| a + ((b - a) * amount);
+        let mut result = *self;
+        for index in 0..4
+        {
+            result.clip[index] = mix(self.clip[index], other.clip[index]);
+            result.color[index] = mix(self.color[index], other.color[index]);
+        }
+        result.s = mix(self.s, other.s);
+        result.t = mix(self.t, other.t);
+        result
+    }
+}
+
+#[derive(Copy, Clone)]
+#[derive(Default)]
+struct Light
+{
+    color: [f32; 3],
+    direction: [f32; 3],
+}
+
+#[derive(Copy, Clone)]
+struct Viewport
+{
+    scale: [f32; 3],
+    translate: [f32; 3],
+}
+
+#[derive(Copy, Clone)]
+#[derive(Default)]
+struct TextureState
+{
+    on: bool,
+    tile: u64,
+    level: u64,
+    scale_s: f32,
+    scale_t: f32,
+}
+
+pub struct GraphicsHLE
+{
+    pub microcode: GraphicsMicrocode,
+    pub segments: [u32; SEGMENT_COUNT],
+    pub modelview_stack: Vec<Matrix>,
+    pub projection: Matrix,
+    pub vertices: Vec<Vertex>,
+    pub geometry_mode: u32,
+    pub other_mode_high: u32,
+    pub other_mode_low: u32,
+    //RDP commands emitted by the task, 64 bit words
+    pub commands: Vec<u64>,
+    combined: Option<Matrix>,
+    forced_matrix: Option<Matrix>,
+    viewport: Viewport,
+    lights: [Light; LIGHT_COUNT],
+    light_count: usize,
+    look_at: [[f32; 3]; 2],
+    texture: TextureState,
+    fog_multiplier: f32,
+    fog_offset: f32,
+    rdp_half_1: u32,
+    program_counter: u32,
+    return_stack: Vec<u32>,
+    finished: bool,
+    error: Option<HLEError>,
+}
+
+impl GraphicsHLE
+{
+    pub fn new(microcode: GraphicsMicrocode) -> GraphicsHLE
+    {
+        return GraphicsHLE
+        {
+            microcode: microcode,
+            segments: [0; SEGMENT_COUNT],
+            modelview_stack: vec![IDENTITY],
+            projection: IDENTITY,
+            vertices: vec![Vertex::default(); VERTEX_BUFFER_SIZE],
+            geometry_mode: 0,
+            other_mode_high: 0,
+            other_mode_low: 0,
+            commands: Vec::new(),
+            combined: None,
+            forced_matrix: None,
+            viewport: Viewport {scale: [160.0, 120.0, 511.0], translate: [160.0, 120.0, 511.0]},
+            lights: [Light::default(); LIGHT_COUNT],
+            light_count: 0,
+            look_at: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            texture: TextureState::default(),
+            fog_multiplier: 0.0,
+            fog_offset: 0.0,
+            rdp_half_1: 0,
+            program_counter: 0,
+            return_stack: Vec::new(),
+            finished: false,
+            error: None,
+        }
+    }
+
+    //Walks the task's display list and hands the resulting RDP commands to the DP, including those before an error
+    pub fn run(&mut self, task: &OSTask, connector: &mut Connector) -> Result<(), HLEError>
+    {
+        self.program_counter = self.segment_address(task.data_pointer);
+        for _ in 0..MAX_DISPLAY_LIST_COMMANDS
+        {
+            if self.finished
+            {
+                break;
+            }
+            let w0 = read_rdram_u32(connector, self.program_counter);
+            let w1 = read_rdram_u32(connector, self.program_counter + 4);
+            self.program_counter += 8;
+            self.execute(w0, w1, connector);
+        }
+
+        connector.submit_rdp_commands(&self.commands);
+        match self.error.take()
+        {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    pub fn segment_address(&self, address: u32) -> u32
+    {
+        (self.segments[((address >> 24) & 0xF) as usize].wrapping_add(address & 0x00FFFFFF)) & 0x00FFFFFF
+    }
+
+    fn execute(&mut self, w0: u32, w1: u32, connector: &Connector)
+    {
+        let command = (w0 >> 24) as u8;
+        //RDP commands are shared by every microcode
+        //F3DEX2 keeps RDPHALF_2 in the middle of the RDP range
+        if command >= RDP_COMMANDS_START && !(self.microcode == GraphicsMicrocode::F3DEX2 && command == 0xF1)
+        {
+            return self.rdp_command(command, w0, w1, connector);
+        }
+        match self.microcode
+        {
+            GraphicsMicrocode::F3DEX2 => self.execute_f3dex2(command, w0, w1, connector),
+            _ => self.execute_f3d(command, w0, w1, connector),
+        }
+    }
+
+    fn execute_f3d(&mut self, command: u8, w0: u32, w1: u32, connector: &Connector)
+    {
+        let f3dex = self.microcode == GraphicsMicrocode::F3DEX;
+        //Fast3D stores vertex indices times ten and F3DEX times two
+        let index = |value: u32| -> usize { if f3dex {(value & 0xFF) as usize / 2} else {(value & 0xFF) as usize / 10} };
+        match command
+        {
+            0x00 | 0xC0 => (),
+            0x01 => self.matrix((w0 >> 16) & 0xFF, self.segment_address(w1), connector),
+            0x03 =>
+            {
+                let offset = match (w0 >> 16) & 0xFF
+                {
+                    0x80 => return self.set_viewport(self.segment_address(w1), connector),
+                    0x82 => return self.set_look_at(1, self.segment_address(w1), connector),
+                    0x84 => return self.set_look_at(0, self.segment_address(w1), connector),
+                    light @ 0x86...0x94 => (light - 0x86) / 2,
+                    _ => return,
+                };
+                self.set_light(offset as usize, self.segment_address(w1), connector);
+            },
+            0x04 =>
+            {
+                let (count, first) = if f3dex {((w0 >> 10) & 0x3F, ((w0 >> 16) & 0xFF) / 2)} else {(((w0 >> 20) & 0xF) + 1, (w0 >> 16) & 0xF)};
+                self.load_vertices(first as usize, count as usize, self.segment_address(w1), connector);
+            },
+            0x06 => self.display_list((w0 >> 16) & 0xFF, w1),
+            0xB0 if f3dex => self.branch_z((w0 & 0xFFF) as usize / 2, w1),
+            0xB1 if f3dex =>
+            {
+                self.triangle(index(w0 >> 16), index(w0 >> 8), index(w0));
+                self.triangle(index(w1 >> 16), index(w1 >> 8), index(w1));
+            },
+            0xB2 if f3dex => self.modify_vertex((w0 >> 16) & 0xFF, (w0 & 0xFFFF) as usize / 2, w1),
+            0xB4 => self.rdp_half_1 = w1,
+            0xB5 if f3dex =>
+            {
+                self.triangle(index(w1 >> 24), index(w1 >> 16), index(w1 >> 8));
+                self.triangle(index(w1 >> 24), index(w1 >> 8), index(w1));
+            },
+            0xB6 => self.geometry_mode &= !w1,
+            0xB7 => self.geometry_mode |= w1,
+            0xB8 => self.end_display_list(),
+            0xB9 => self.set_other_mode(false, (w0 >> 8) & 0xFF, w0 & 0xFF, w1),
+            0xBA => self.set_other_mode(true, (w0 >> 8) & 0xFF, w0 & 0xFF, w1),
+            0xBB => self.set_texture(w0, w0 & 0xFF != 0, w1),
+            0xBC => self.move_word(w0 & 0xFF, (w0 >> 8) & 0xFFFF, w1),
+            0xBD => self.pop_matrix(1),
+            0xBE =>
+            {
+                let divisor = if f3dex {2} else {40};
+                self.cull_display_list((w0 & 0xFFFF) as usize / divisor, (w1 & 0xFFFF) as usize / divisor);
+            },
+            0xBF => self.triangle(index(w1 >> 16), index(w1 >> 8), index(w1)),
+            _ => (),
+        }
+    }
+
+    fn execute_f3dex2(&mut self, command: u8, w0: u32, w1: u32, connector: &Connector)
+    {
+        let index = |value: u32| -> usize { (value & 0xFF) as usize / 2 };
+        match command
+        {
+            0x00 | 0xE0 => (),
+            0x01 =>
+            {
+                let count = (w0 >> 12) & 0xFF;
+                let first = ((w0 >> 1) & 0x7F).wrapping_sub(count);
+                self.load_vertices(first as usize, count as usize, self.segment_address(w1), connector);
+            },
+            0x02 => self.modify_vertex((w0 >> 16) & 0xFF, (w0 & 0xFFFF) as usize / 2, w1),
+            0x03 => self.cull_display_list((w0 & 0xFFFF) as usize / 2, (w1 & 0xFFFF) as usize / 2),
+            0x04 => self.branch_z((w0 & 0xFFF) as usize / 2, w1),
+            0x05 => self.triangle(index(w0 >> 16), index(w0 >> 8), index(w0)),
+            0x06 | 0x07 =>
+            {
+                self.triangle(index(w0 >> 16), index(w0 >> 8), index(w0));
+                self.triangle(index(w1 >> 16), index(w1 >> 8), index(w1));
+            },
+            0xD7 => self.set_texture(w0, (w0 >> 1) & 0x7F != 0, w1),
+            0xD8 => self.pop_matrix((w1 / MATRIX_SIZE) as usize),
+            0xD9 => self.geometry_mode = (self.geometry_mode & (w0 & 0x00FFFFFF)) | w1,
+            //The push flag is inverted and projection moved to bit 2
+            0xDA =>
+            {
+                let parameters = (w0 & 0xFF) ^ 0x01;
+                let flags = ((parameters & 0x01) << 2) | (parameters & 0x02) | ((parameters >> 2) & 0x01);
+                self.matrix(flags, self.segment_address(w1), connector);
+            },
+            0xDB => self.move_word((w0 >> 16) & 0xFF, w0 & 0xFFFF, w1),
+            0xDC =>
+            {
+                let offset = ((w0 >> 8) & 0xFF) * 8;
+                match w0 & 0xFF
+                {
+                    0x08 => self.set_viewport(self.segment_address(w1), connector),
+                    0x0A if offset < 48 => self.set_look_at((offset / 24) as usize, self.segment_address(w1), connector),
+                    0x0A => self.set_light((offset / 24) as usize - 2, self.segment_address(w1), connector),
+                    0x0E => self.forced_matrix = Some(read_matrix(connector, self.segment_address(w1))),
+                    _ => (),
+                }
+            },
+            0xDE => self.display_list((w0 >> 16) & 0xFF, w1),
+            0xDF => self.end_display_list(),
+            0xE1 => self.rdp_half_1 = w1,
+            0xE2 =>
+            {
+                let length = (w0 & 0xFF) + 1;
+                //A field that does not fit in the word is skipped
+                if let Some(shift) = 32_u32.checked_sub((w0 >> 8) & 0xFF).and_then(|end| end.checked_sub(length))
+                {
+                    self.set_other_mode(false, shift, length, w1);
+                }
+            },
+            0xE3 =>
+            {
+                let length = (w0 & 0xFF) + 1;
+                if let Some(shift) = 32_u32.checked_sub((w0 >> 8) & 0xFF).and_then(|end| end.checked_sub(length))
+                {
+                    self.set_other_mode(true, shift, length, w1);
+                }
+            },
+            _ => (),
+        }
+    }
+
+    fn rdp_command(&mut self, command: u8, w0: u32, w1: u32, connector: &Connector)
+    {
+        match command
+        {
+            //Texture rectangles take their coordinates from the two RDPHALF commands that follow
+            RDP_TEXTURE_RECTANGLE | RDP_TEXTURE_RECTANGLE_FLIP =>
+            {
+                let half_1 = read_rdram_u32(connector, self.program_counter + 4);
+                let half_2 = read_rdram_u32(connector, self.program_counter + 12);
+                self.program_counter += 16;
+                self.emit((w0 as u64) << 32 | w1 as u64);
+                self.emit((half_1 as u64) << 32 | half_2 as u64);
+            },
+            RDP_SET_OTHER_MODES =>
+            {
+                self.other_mode_high = w0 & 0x00FFFFFF;
+                self.other_mode_low = w1;
+                self.emit((w0 as u64) << 32 | w1 as u64);
+            },
+            _ => self.emit((w0 as u64) << 32 | w1 as u64),
+        }
+    }
+
+    fn emit(&mut self, command: u64)
+    {
+        self.commands.push(command);
+    }
+
+    fn display_list(&mut self, parameter: u32, address: u32)
+    {
+        //Parameter 0 calls and 1 branches
+        if parameter == 0
+        {
+            //The microcode has nowhere to keep the return address, so the task ends here
+            if self.return_stack.len() >= DISPLAY_LIST_STACK_SIZE
+            {
+                self.error = Some(HLEError::DISPLAY_LIST_OVERFLOW(self.program_counter - 8));
+                self.finished = true;
+                return;
+            }
+            self.return_stack.push(self.program_counter);
+        }
+        self.program_counter = self.segment_address(address);
+    }
+
+    fn end_display_list(&mut self)
+    {
+        match self.return_stack.pop()
+        {
+            Some(address) => self.program_counter = address,
+            None => self.finished = true,
+        }
+    }
+
+    fn branch_z(&mut self, vertex: usize, z: u32)
+    {
+        let vertex = &self.vertices[vertex % VERTEX_BUFFER_SIZE];
+        //The compare value is screen depth in 16.16
+        if (vertex.screen[2] / 32.0 * 65536.0) as i32 <= z as i32
+        {
+            self.program_counter = self.segment_address(self.rdp_half_1);
+        }
+    }
+
+    fn cull_display_list(&mut self, first: usize, last: usize)
+    {
+        let mut flags: u8 = 0xFF;
+        for index in first..(last + 1).min(VERTEX_BUFFER_SIZE)
+        {
+            flags &= self.vertices[index].clip_flags;
+        }
+        //Everything outside one plane means nothing in the list can be visible
+        if flags != 0 && first <= last
+        {
+            self.end_display_list();
+        }
+    }
+
+    fn matrix(&mut self, flags: u32, address: u32, connector: &Connector)
+    {
+        let matrix = read_matrix(connector, address);
+        if flags & G_MTX_PROJECTION != 0
+        {
+            self.projection = if flags & G_MTX_LOAD != 0 {matrix} else {multiply(&matrix, &self.projection)};
+        }
+        else
+        {
+            let current = *self.modelview_stack.last().unwrap();
+            if flags & G_MTX_PUSH != 0 && self.modelview_stack.len() < MATRIX_STACK_SIZE
+            {
+                self.modelview_stack.push(current);
+            }
+            let new_matrix = if flags & G_MTX_LOAD != 0 {matrix} else {multiply(&matrix, &current)};
+            *self.modelview_stack.last_mut().unwrap() = new_matrix;
+        }
+        self.combined = None;
+        self.forced_matrix = None;
+    }
+
+    fn pop_matrix(&mut self, count: usize)
+    {
+        for _ in 0..count
+        {
+            if self.modelview_stack.len() > 1
+            {
+                self.modelview_stack.pop();
+            }
+        }
+        self.combined = None;
+    }
+
+    fn combined_matrix(&mut self) -> Matrix
+    {
+        if let Some(matrix) = self.forced_matrix
+        {
+            return matrix;
+        }
+        if self.combined.is_none()
+        {
+            self.combined = Some(multiply(self.modelview_stack.last().unwrap(), &self.projection));
+        }
+        self.combined.unwrap()
+    }
+
+    //Viewport x and y carry two fractional bits
+    fn set_viewport(&mut self, address: u32, connector: &Connector)
+    {
+        let value = |offset: u32| read_rdram_u16(connector, address + offset) as i16 as f32;
+        self.viewport = Viewport
+        {
+            scale: [value(0) / 4.0, value(2) / 4.0, value(4)],
+            translate: [value(8) / 4.0, value(10) / 4.0, value(12)],
+        };
+    }
+
+    fn read_direction(address: u32, connector: &Connector) -> [f32; 3]
+    {
+        let value = |offset: u32| read_rdram_u8(connector, address + offset) as i8 as f32 / 127.0;
+        normalize([value(8), value(9), value(10)])
+    }
+
+    fn set_look_at(&mut self, index: usize, address: u32, connector: &Connector)
+    {
+        self.look_at[index & 1] = GraphicsHLE::read_direction(address, connector);
+    }
+
+    fn set_light(&mut self, index: usize, address: u32, connector: &Connector)
+    {
+        if index >= LIGHT_COUNT
+        {
+            return;
+        }
+        let color = |offset: u32| read_rdram_u8(connector, address + offset) as f32;
+        self.lights[index] = Light
+        {
+            color: [color(0), color(1), color(2)],
+            direction: GraphicsHLE::read_direction(address, connector),
+        };
+    }
+
+    fn move_word(&mut self, index: u32, offset: u32, value: u32)
+    {
+        match index
+        {
+            G_MW_NUMLIGHT =>
+            {
+                self.light_count = match self.microcode
+                {
+                    GraphicsMicrocode::F3DEX2 => (value / 24) as usize,
+                    _ => ((value.wrapping_sub(0x80000000) / 32) as usize).saturating_sub(1),
+                }.min(LIGHT_COUNT - 1);
+            },
+            G_MW_SEGMENT => self.segments[((offset / 4) & 0xF) as usize] = value & 0x00FFFFFF,
+            G_MW_FOG =>
+            {
+                self.fog_multiplier = (value >> 16) as i16 as f32;
+                self.fog_offset = value as i16 as f32;
+            },
+            G_MW_LIGHTCOL =>
+            {
+                let stride = if self.microcode == GraphicsMicrocode::F3DEX2 {0x18} else {0x20};
+                let light = (offset / stride) as usize;
+                if light < LIGHT_COUNT
+                {
+                    self.lights[light].color = [(value >> 24) as u8 as f32, (value >> 16) as u8 as f32, (value >> 8) as u8 as f32];
+                }
+            },
+            //Matrix element writes and the remaining indices do not change what is drawn here
+            _ => (),
+        }
+    }
+
+    fn set_other_mode(&mut self, high: bool, shift: u32, length: u32, value: u32)
+    {
+        let mask = match 1_u64.checked_shl(length).and_then(|bit| (bit - 1).checked_shl(shift))
+        {
+            Some(mask) => mask as u32,
+            None => return,
+        };
+        if high
+        {
+            self.other_mode_high = (self.other_mode_high & !mask) | (value & mask);
+        }
+        else
+        {
+            self.other_mode_low = (self.other_mode_low & !mask) | (value & mask);
+        }
+        let command = (RDP_SET_OTHER_MODES as u64) << 56 | ((self.other_mode_high & 0x00FFFFFF) as u64) << 32 | self.other_mode_low as u64;
+        self.emit(command);
+    }
+
+    fn set_texture(&mut self, w0: u32, on: bool, w1: u32)
+    {
+        self.texture = TextureState
+        {
+            on: on,
+            tile: ((w0 >> 8) & 0x7) as u64,
+            level: ((w0 >> 11) & 0x7) as u64,
+            scale_s: (w1 >> 16) as f32 / 65536.0,
+            scale_t: (w1 & 0xFFFF) as f32 / 65536.0,
+        };
+    }
+
+    fn load_vertices(&mut self, first: usize, count: usize, address: u32, connector: &Connector)
+    {
+        let matrix = self.combined_matrix();
+        let modelview = *self.modelview_stack.last().unwrap();
+        let bits = self.microcode.geometry_bits();
+        let lighting = self.geometry_mode & bits.lighting != 0;
+        //Lights are moved into model space so normals can be used untransformed
+        let to_model = |direction: [f32; 3]| normalize([
+            dot(direction, [modelview[0][0], modelview[0][1], modelview[0][2]]),
+            dot(direction, [modelview[1][0], modelview[1][1], modelview[1][2]]),
+            dot(direction, [modelview[2][0], modelview[2][1], modelview[2][2]]),
+        ]);
+        let lights: Vec<Light> = self.lights[0..self.light_count].iter().map(|light| Light {color: light.color, direction: to_model(light.direction)}).collect();
+        let look_at = [to_model(self.look_at[0]), to_model(self.look_at[1])];
+
+        for index in 0..count
+        {
+            let slot = first + index;
+            if slot >= self.microcode.vertex_buffer_size()
+            {
+                break;
+            }
+            let base = address + (index as u32 * VERTEX_SIZE);
+            let half = |offset: u32| read_rdram_u16(connector, base + offset) as i16 as f32;
+            let byte = |offset: u32| read_rdram_u8(connector, base + offset);
+            let position = [half(0), half(2), half(4), 1.0];
+            let mut vertex = Vertex::default();
+            vertex.clip = transform(position, &matrix);
+            vertex.color = [byte(12) as f32, byte(13) as f32, byte(14) as f32, byte(15) as f32];
+            vertex.s = half(8) * self.texture.scale_s;
+            vertex.t = half(10) * self.texture.scale_t;
+
+            if lighting
+            {
+                let normal = normalize([byte(12) as i8 as f32, byte(13) as i8 as f32, byte(14) as i8 as f32]);
+                let mut color = self.lights[self.light_count].color;
+                for light in lights.iter()
+                {
+                    let intensity = dot(normal, light.direction).max(0.0);
+                    for channel in 0..3
+                    {
+                        color[channel] += light.color[channel] * intensity;
+                    }
+                }
+                vertex.color = [color[0].min(255.0), color[1].min(255.0), color[2].min(255.0), vertex.color[3]];
+                //Texture generation maps the normal onto the texture for environment mapping
+                if self.geometry_mode & bits.texture_gen != 0
+                {
+                    vertex.s = (dot(normal, look_at[0]) + 1.0) * self.texture.scale_s * 65536.0 / 4.0;
+                    vertex.t = (dot(normal, look_at[1]) + 1.0) * self.texture.scale_t * 65536.0 / 4.0;
+                }
+            }
+            self.project(&mut vertex);
+            if self.geometry_mode & bits.fog != 0
+            {
+                let depth = vertex.clip[2] * vertex.inverse_w;
+                vertex.color[3] = (depth * self.fog_multiplier + self.fog_offset).max(0.0).min(255.0);
+            }
+            self.vertices[slot] = vertex;
+        }
+    }
+
+    fn modify_vertex(&mut self, location: u32, index: usize, value: u32)
+    {
+        let vertex = &mut self.vertices[index % VERTEX_BUFFER_SIZE];
+        match location
+        {
+            0x10 => vertex.color = [(value >> 24) as u8 as f32, (value >> 16) as u8 as f32, (value >> 8) as u8 as f32, value as u8 as f32],
+            0x14 =>
+            {
+                vertex.s = (value >> 16) as i16 as f32;
+                vertex.t = value as i16 as f32;
+            },
+            //Screen coordinates are in 13.2 and override the projection
+            0x18 =>
+            {
+                vertex.screen[0] = (value >> 16) as i16 as f32 / 4.0;
+                vertex.screen[1] = value as i16 as f32 / 4.0;
+            },
+            0x1C => vertex.screen[2] = value as f32 / 65536.0 * 32.0,
+            _ => (),
+        }
+    }
+
+    fn project(&self, vertex: &mut Vertex)
+    {
+        let [x, y, z, w] = vertex.clip;
+        let mut flags = 0;
+        if x < -w { flags |= CLIP_NEGATIVE_X; }
+        if x > w { flags |= CLIP_POSITIVE_X; }
+        if y < -w { flags |= CLIP_NEGATIVE_Y; }
+        if y > w { flags |= CLIP_POSITIVE_Y; }
+        if z < -w { flags |= CLIP_NEAR; }
+        if z > w { flags |= CLIP_FAR; }
+        vertex.clip_flags = flags;
+
+        let inverse_w = if w.abs() < 1e-6 {1e6} else {1.0 / w};
+        vertex.inverse_w = inverse_w;
+        vertex.screen = [
+            (x * inverse_w * self.viewport.scale[0]) + self.viewport.translate[0],
+            (-y * inverse_w * self.viewport.scale[1]) + self.viewport.translate[1],
+            ((z * inverse_w * self.viewport.scale[2]) + self.viewport.translate[2]) * 32.0,
+        ];
+    }
+
+    fn triangle(&mut self, a: usize, b: usize, c: usize)
+    {
+        let size = VERTEX_BUFFER_SIZE;
+        let mut vertices = [self.vertices[a % size], self.vertices[b % size], self.vertices[c % size]];
+        if vertices[0].clip_flags & vertices[1].clip_flags & vertices[2].clip_flags != 0
+        {
+            return;
+        }
+        let bits = self.microcode.geometry_bits();
+        //Flat shading uses the first vertex's color
+        if self.geometry_mode & bits.shading_smooth == 0
+        {
+            let color = vertices[0].color;
+            for vertex in vertices.iter_mut()
+            {
+                vertex.color = color;
+            }
+        }
+
+        let needs_clipping = vertices.iter().any(|vertex|
+        {
+            let [x, y, z, w] = vertex.clip;
+            z < -w || w <= 0.0 || x.abs() > w * CLIP_RATIO || y.abs() > w * CLIP_RATIO
+        });
+        if !needs_clipping
+        {
+            return self.emit_triangle(&vertices);
+        }
+
+        let polygon = self.clip_polygon(vertices.to_vec());
+        for index in 1..polygon.len().saturating_sub(1)
+        {
+            self.emit_triangle(&[polygon[0], polygon[index], polygon[index + 1]]);
+        }
+    }
+
+    //Sutherland-Hodgman against the near plane and the guard band
+    fn clip_polygon(&self, mut polygon: Vec<Vertex>) -> Vec<Vertex>
+    {
+        let planes: [fn(&[f32; 4]) -> f32; 5] = [
+            |clip| clip[2] + clip[3],
+            |clip| clip[3] * CLIP_RATIO - clip[0],
+            |clip| clip[3] * CLIP_RATIO + clip[0],
+            |clip| clip[3] * CLIP_RATIO - clip[1],
+            |clip| clip[3] * CLIP_RATIO + clip[1],
+        ];
+        for plane in planes.iter()
+        {
+            let mut output = Vec::new();
+            for index in 0..polygon.len()
+            {
+                let current = polygon[index];
+                let next = polygon[(index + 1) % polygon.len()];
+                let current_distance = plane(&current.clip);
+                let next_distance = plane(&next.clip);
+                if current_distance >= 0.0
+                {
+                    output.push(current);
+                }
+                if (current_distance >= 0.0) != (next_distance >= 0.0)
+                {
+                    output.push(current.interpolate(&next, current_distance / (current_distance - next_distance)));
+                }
+            }
+            polygon = output;
+            if polygon.len() < 3
+            {
+                return Vec::new();
+            }
+        }
+        for vertex in polygon.iter_mut()
+        {
+            self.project(vertex);
+        }
+        polygon
+    }
+
+    fn emit_triangle(&mut self, vertices: &[Vertex; 3])
+    {
+        let bits = self.microcode.geometry_bits();
+        //Screen y points down, so front faces wind clockwise on screen
+        let area = (vertices[1].screen[0] - vertices[0].screen[0]) * (vertices[2].screen[1] - vertices[0].screen[1])
+            - (vertices[2].screen[0] - vertices[0].screen[0]) * (vertices[1].screen[1] - vertices[0].screen[1]);
+        if area == 0.0
+            || (area < 0.0 && self.geometry_mode & bits.cull_front != 0)
+            || (area > 0.0 && self.geometry_mode & bits.cull_back != 0)
+        {
+            return;
+        }
+
+        let mut command = RDP_TRIANGLE;
+        if self.geometry_mode & bits.shade != 0
+        {
+            command |= RDP_TRIANGLE_SHADE;
+        }
+        if self.texture.on
+        {
+            command |= RDP_TRIANGLE_TEXTURE;
+        }
+        if self.geometry_mode & bits.zbuffer != 0
+        {
+            command |= RDP_TRIANGLE_ZBUFFER;
+        }
+        let perspective = self.other_mode_high & G_TP_PERSP != 0;
+        let words = encode_triangle(command, self.texture.tile, self.texture.level, vertices, perspective);
+        self.commands.extend(words);
+    }
+}
+
+fn to_fixed(value: f64) -> u32
+{
+    (value * 65536.0).max(i32::min_value() as f64).min(i32::max_value() as f64) as i32 as u32
+}
+
+//Interleaves 16.16 attributes into the RDP's integer then fraction word layout
+fn attribute_words(values: [f64; 4], dx: [f64; 4], de: [f64; 4], dy: [f64; 4]) -> [u64; 8]
+{
+    let pack = |values: [f64; 4], integer: bool| -> u64
+    {
+        values.iter().fold(0_u64, |word, value|
+        {
+            let fixed = to_fixed(*value);
+            (word << 16) | if integer {(fixed >> 16) as u64} else {(fixed & 0xFFFF) as u64}
+        })
+    };
+    [pack(values, true), pack(dx, true), pack(values, false), pack(dx, false), pack(de, true), pack(dy, true), pack(de, false), pack(dy, false)]
+}
+
+// Referenced: libdragon's rdpq_triangle, which mirrors what the microcode's setup produces
+pub fn encode_triangle(command: u64, tile: u64, level: u64, vertices: &[Vertex; 3], perspective: bool) -> Vec<u64>
+{
+    let mut sorted = [vertices[0], vertices[1], vertices[2]];
+    sorted.sort_by(|a, b| a.screen[1].partial_cmp(&b.screen[1]).unwrap_or(::std::cmp::Ordering::Equal));
+    let [v1, v2, v3] = sorted;
+    let quantize = |value: f32| (value as f64 * 4.0).floor() / 4.0;
+    let (x1, y1) = (v1.screen[0] as f64, quantize(v1.screen[1]));
+    let (x2, y2) = (v2.screen[0] as f64, quantize(v2.screen[1]));
+    let (x3, y3) = (v3.screen[0] as f64, quantize(v3.screen[1]));
+
+    let (hx, hy) = (x3 - x1, y3 - y1);
+    let (mx, my) = (x2 - x1, y2 - y1);
+    let (lx, ly) = (x3 - x2, y3 - y2);
+    let nz = (hx * my) - (hy * mx);
+    let left_major = (nz < 0.0) as u64;
+    let slope = |dx: f64, dy: f64| if dy.abs() > 1e-9 {dx / dy} else {0.0};
+    let (ish, ism, isl) = (slope(hx, hy), slope(mx, my), slope(lx, ly));
+    //Edges are evaluated from the top of the first scanline
+    let fy = y1.floor() - y1;
+    let xh = x1 + (fy * ish);
+    let xm = x1 + (fy * ism);
+
+    let fixed_11_2 = |value: f64| ((value * 4.0) as i64 as u64) & 0x3FFF;
+    let mut words = vec![
+        (command << 56) | (left_major << 55) | ((level & 0x7) << 51) | ((tile & 0x7) << 48) | (fixed_11_2(y3) << 32) | (fixed_11_2(y2) << 16) | fixed_11_2(y1),
+        (to_fixed(x2) as u64) << 32 | to_fixed(isl) as u64,
+        (to_fixed(xh) as u64) << 32 | to_fixed(ish) as u64,
+        (to_fixed(xm) as u64) << 32 | to_fixed(ism) as u64,
+    ];
+
+    let attribute_factor = if nz.abs() > 1e-9 {-1.0 / nz} else {0.0};
+    //Value at the top of the triangle plus its x, edge and y slopes
+    let gradient = |a1: f64, a2: f64, a3: f64| -> (f64, f64, f64, f64)
+    {
+        let (ha, ma) = (a3 - a1, a2 - a1);
+        let dx = ((hy * ma) - (ha * my)) * attribute_factor;
+        let dy = ((ha * mx) - (hx * ma)) * attribute_factor;
+        let de = dy + (dx * ish);
+        (a1 + (fy * de), dx, de, dy)
+    };
+    let gradients = |values: [[f64; 3]; 4]| -> [u64; 8]
+    {
+        let mut result = ([0.0; 4], [0.0; 4], [0.0; 4], [0.0; 4]);
+        for (index, value) in values.iter().enumerate()
+        {
+            let (start, dx, de, dy) = gradient(value[0], value[1], value[2]);
+            result.0[index] = start;
+            result.1[index] = dx;
+            result.2[index] = de;
+            result.3[index] = dy;
+        }
+        attribute_words(result.0, result.1, result.2, result.3)
+    };
+
+    if command & RDP_TRIANGLE_SHADE != 0
+    {
+        let channel = |index: usize| [v1.color[index] as f64, v2.color[index] as f64, v3.color[index] as f64];
+        words.extend(gradients([channel(0), channel(1), channel(2), channel(3)]).iter());
+    }
+    if command & RDP_TRIANGLE_TEXTURE != 0
+    {
+        //Perspective correct coordinates are divided by a normalised W the rasterizer divides back out
+        let max_inverse_w = v1.inverse_w.max(v2.inverse_w).max(v3.inverse_w) as f64;
+        let w = |vertex: &Vertex| if perspective && max_inverse_w > 0.0 {vertex.inverse_w as f64 / max_inverse_w} else {1.0};
+        let (w1, w2, w3) = (w(&v1), w(&v2), w(&v3));
+        let s = [v1.s as f64 * w1, v2.s as f64 * w2, v3.s as f64 * w3];
+        let t = [v1.t as f64 * w1, v2.t as f64 * w2, v3.t as f64 * w3];
+        let inverse = [w1 * 32767.0, w2 * 32767.0, w3 * 32767.0];
+        words.extend(gradients([s, t, inverse, [0.0; 3]]).iter());
+    }
+    if command & RDP_TRIANGLE_ZBUFFER != 0
+    {
+        let (z, dzdx, dzde, dzdy) = gradient(v1.screen[2] as f64, v2.screen[2] as f64, v3.screen[2] as f64);
+        words.push((to_fixed(z) as u64) << 32 | to_fixed(dzdx) as u64);
+        words.push((to_fixed(dzde) as u64) << 32 | to_fixed(dzdy) as u64);
+    }
+    words
+}
@@ -0,0 +1,191 @@
+#[cfg(test)]
+mod hle_graphics_tests
+{
+    use n64::hle::*;
+    use n64::hle_graphics::*;
+    use n64::connector::Connector;
+    use n64::rsp::*;
+    use n64::rdp_commands::*;
+    use n64::mips_iface::{MI_INTR_SP, MI_INTR_DP};
+
+    const UCODE_DATA: u32 = 0x1000;
+    const DISPLAY_LIST: u32 = 0x2000;
+    const MATRIX: u32 = 0x3000;
+    const VIEWPORT: u32 = 0x3100;
+    const VERTICES: u32 = 0x3200;
+
+    fn setup_task(connector: &mut Connector, ucode: &str, commands: &[(u32, u32)])
+    {
+        for (offset, byte) in ucode.bytes().enumerate()
+        {
+            store_rdram_u8(connector, UCODE_DATA + 0x100 + offset as u32, byte);
+        }
+        for (index, &(w0, w1)) in commands.iter().enumerate()
+        {
+            store_rdram_u32(connector, DISPLAY_LIST + (index as u32 * 8), w0);
+            store_rdram_u32(connector, DISPLAY_LIST + (index as u32 * 8) + 4, w1);
+        }
+        let task = [(0x00, M_GFXTASK), (0x18, UCODE_DATA), (0x1C, 0x800), (0x30, DISPLAY_LIST)];
+        for &(offset, value) in task.iter()
+        {
+            connector.rsp.load_u32_to_address(OS_TASK_ADDRESS + offset, value).unwrap();
+        }
+        connector.rsp.status.set_value(SP_STATUS_INTR_BREAK);
+    }
+
+    //Identity matrix and a 320x240 viewport so model coordinates map straight to the screen
+    fn setup_scene(connector: &mut Connector, vertices: &[(i16, i16, i16)])
+    {
+        for diagonal in 0..4
+        {
+            store_rdram_u16(connector, MATRIX + (diagonal * 10), 1);
+        }
+        for (offset, value) in [640_u16, 480, 511, 0, 640, 480, 511, 0].iter().enumerate()
+        {
+            store_rdram_u16(connector, VIEWPORT + (offset as u32 * 2), *value);
+        }
+        for (index, &(x, y, z)) in vertices.iter().enumerate()
+        {
+            let base = VERTICES + (index as u32 * 16);
+            store_rdram_u16(connector, base, x as u16);
+            store_rdram_u16(connector, base + 2, y as u16);
+            store_rdram_u16(connector, base + 4, z as u16);
+            store_rdram_u32(connector, base + 12, 0xFF0000FF);
+        }
+    }
+
+    fn f3dex2_triangle_list(geometry_mode: u32) -> Vec<(u32, u32)>
+    {
+        vec![
+            (0xDA380007, MATRIX),
+            (0xDA380003, MATRIX),
+            (0xDC080008, VIEWPORT),
+            (0xD9FFFFFF, geometry_mode),
+            (0x01003006, VERTICES),
+            (0x05000204, 0),
+            (0xE9000000, 0),
+            (0xDF000000, 0),
+        ]
+    }
+
+    fn triangles(connector: &Connector) -> Vec<Triangle>
+    {
+        connector.dp.commands.iter().filter_map(|command| match *command
+        {
+            RDPCommand::TRIANGLE(triangle) => Some(triangle),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn microcode_is_detected_from_its_string()
+    {
+        assert_eq!(GraphicsMicrocode::detect("RSP SW Version: 2.0D, 04-01-96"), Some(GraphicsMicrocode::FAST3D));
+        assert_eq!(GraphicsMicrocode::detect("RSP Gfx ucode F3DEX       1.23 Yoshitaka Yasumoto Nintendo."), Some(GraphicsMicrocode::F3DEX));
+        assert_eq!(GraphicsMicrocode::detect("RSP Gfx ucode F3DZEX.NoN  fifo 2.08  Yoshitaka Yasumoto 1999 Nintendo."), Some(GraphicsMicrocode::F3DEX2));
+        assert_eq!(GraphicsMicrocode::detect("RSP Gfx ucode S2DEX  1.06 Yoshitaka Yasumoto Nintendo."), None);
+    }
+
+    #[test]
+    fn rdp_commands_pass_through_and_task_completes()
+    {
+        let mut connector = Connector::test();
+        setup_task(&mut connector, "RSP Gfx ucode F3DEX.NoN   fifo 2.05  Yoshitaka Yasumoto 1998 Nintendo.", &[
+            (0xFF10013F, 0x00100000),
+            (0xF7000000, 0x00010001),
+            (0xF64FC3BC, 0x00000000),
+            (0xE9000000, 0x00000000),
+            (0xDF000000, 0x00000000),
+        ]);
+        assert!(process_task(&mut connector).unwrap());
+        assert_eq!(connector.dp.commands, vec![
+            RDPCommand::SET_COLOR_IMAGE(ImageDescriptor {format: 0, size: 2, width: 320, address: 0x100000}),
+            RDPCommand::SET_FILL_COLOR(0x00010001),
+            RDPCommand::FILL_RECTANGLE(Rectangle {xl: 0x4FC, yl: 0x3BC, xh: 0, yh: 0}),
+            RDPCommand::SYNC_FULL,
+        ]);
+        assert_eq!(connector.rsp.status.get_value() as u32 & (SP_STATUS_HALT | SP_STATUS_BROKE), SP_STATUS_HALT | SP_STATUS_BROKE);
+        assert_eq!(connector.mips_interface.interrupt.get_value() as u32 & (MI_INTR_SP | MI_INTR_DP), MI_INTR_SP);
+        connector.step(1);
+        assert_eq!(connector.mips_interface.interrupt.get_value() as u32 & (MI_INTR_SP | MI_INTR_DP), MI_INTR_SP | MI_INTR_DP);
+    }
+
+    #[test]
+    fn nesting_past_the_return_stack_ends_the_task_with_an_error()
+    {
+        let mut connector = Connector::test();
+        //The list calls itself until the microcode runs out of return addresses
+        setup_task(&mut connector, "RSP Gfx ucode F3DEX.NoN   fifo 2.05  Yoshitaka Yasumoto 1998 Nintendo.", &[
+            (0xF7000000, 0x00010001),
+            (0xDE000000, DISPLAY_LIST),
+        ]);
+        assert_eq!(process_task(&mut connector), Err(HLEError::DISPLAY_LIST_OVERFLOW(DISPLAY_LIST + 8)));
+        assert_eq!(connector.dp.commands.len(), 19);
+        assert_eq!(connector.rsp.status.get_value() as u32 & (SP_STATUS_HALT | SP_STATUS_BROKE), SP_STATUS_HALT | SP_STATUS_BROKE);
+        assert_eq!(connector.mips_interface.interrupt.get_value() as u32 & MI_INTR_SP, MI_INTR_SP);
+    }
+
+    #[test]
+    fn unknown_microcode_is_left_to_the_rsp()
+    {
+        let mut connector = Connector::test();
+        setup_task(&mut connector, "RSP Gfx ucode S2DEX  1.06 Yoshitaka Yasumoto Nintendo.", &[(0xDF000000, 0)]);
+        assert!(!process_task(&mut connector).unwrap());
+        assert!(connector.dp.commands.is_empty());
+        assert!(!connector.rsp.is_halted());
+    }
+
+    #[test]
+    fn vertices_are_transformed_into_shaded_triangles()
+    {
+        let mut connector = Connector::test();
+        setup_task(&mut connector, "RSP Gfx ucode F3DEX.NoN   fifo 2.05  Yoshitaka Yasumoto 1998 Nintendo.", &f3dex2_triangle_list(0x00200004));
+        setup_scene(&mut connector, &[(0, 1, 0), (-1, -1, 0), (1, -1, 0)]);
+        assert!(process_task(&mut connector).unwrap());
+
+        let triangle = triangles(&connector)[0];
+        //Right major triangle from y 0 to 240, the apex at x 160
+        assert!(!triangle.edges.left_major);
+        assert_eq!((triangle.edges.yh, triangle.edges.ym, triangle.edges.yl), (0, 0x3C0, 0x3C0));
+        assert_eq!(triangle.edges.xh, 160 << 16);
+        assert_eq!(triangle.edges.dxhdy, 0xAAAA);
+        assert_eq!(triangle.edges.dxmdy, -0xAAAA);
+        //Red with full alpha and no change across the triangle
+        let shade = triangle.shade.unwrap();
+        assert_eq!(shade.value, [255 << 16, 0, 0, 255 << 16]);
+        assert_eq!(shade.dx, [0; 4]);
+        assert!(triangle.texture.is_none() && triangle.z.is_none());
+        assert_eq!(connector.dp.commands.last(), Some(&RDPCommand::SYNC_FULL));
+    }
+
+    #[test]
+    fn triangles_are_culled_and_clipped()
+    {
+        let mut connector = Connector::test();
+        setup_task(&mut connector, "RSP Gfx ucode F3DEX.NoN   fifo 2.05  Yoshitaka Yasumoto 1998 Nintendo.", &f3dex2_triangle_list(0x00200204));
+        setup_scene(&mut connector, &[(0, 1, 0), (-1, -1, 0), (1, -1, 0)]);
+        process_task(&mut connector).unwrap();
+        assert!(triangles(&connector).is_empty());
+
+        //A vertex behind the near plane splits the triangle in two
+        let mut connector = Connector::test();
+        setup_task(&mut connector, "RSP Gfx ucode F3DEX.NoN   fifo 2.05  Yoshitaka Yasumoto 1998 Nintendo.", &f3dex2_triangle_list(0x00200004));
+        setup_scene(&mut connector, &[(0, 1, -3), (-1, -1, 0), (1, -1, 0)]);
+        process_task(&mut connector).unwrap();
+        assert_eq!(triangles(&connector).len(), 2);
+    }
+
+    #[test]
+    fn other_mode_fields_outside_the_word_are_skipped()
+    {
+        let mut connector = Connector::test();
+        setup_task(&mut connector, "RSP Gfx ucode F3DEX.NoN   fifo 2.05  Yoshitaka Yasumoto 1998 Nintendo.", &[
+            (0xE200FF00, 0xFFFFFFFF),
+            (0xE2001040, 0xFFFFFFFF),
+            (0xE3001401, 0x00000C00),
+            (0xDF000000, 0),
+        ]);
+        assert!(process_task(&mut connector).unwrap());
+        assert_eq!(connector.dp.commands, vec![RDPCommand::SET_OTHER_MODES(0x00000C00_00000000)]);
+    }
+}
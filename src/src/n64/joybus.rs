@@ -0,0 +1,60 @@
+use n64::save_file::SaveError;
+
+pub const JOYBUS_CHANNELS: usize = 5;
+pub const JOYBUS_CARTRIDGE_CHANNEL: usize = 4;
+
+//Commands shared by every joybus device
+pub const JOYBUS_COMMAND_STATUS: u8 = 0x00;
+pub const JOYBUS_COMMAND_RESET: u8 = 0xFF;
+
+//Error flags returned in the receive length byte
+pub const JOYBUS_NO_RESPONSE: u8 = 0x80;
+pub const JOYBUS_SIZE_MISMATCH: u8 = 0x40;
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum JoybusError
+{
+    NO_RESPONSE,
+    SIZE_MISMATCH,
+}
+
+impl JoybusError
+{
+    pub fn flag(self) -> u8
+    {
+        match self
+        {
+            JoybusError::NO_RESPONSE => JOYBUS_NO_RESPONSE,
+            JoybusError::SIZE_MISMATCH => JOYBUS_SIZE_MISMATCH,
+        }
+    }
+}
+
+pub trait JoybusDevice
+{
+    //send holds the command byte followed by its parameters, receive is sized by the request
+    fn process_command(&mut self, send: &[u8], receive: &mut [u8]) -> Result<(), JoybusError>;
+
+    //Devices with persistent storage write it out here
+    fn flush(&mut self) -> Result<(), SaveError>
+    {
+        Ok(())
+    }
+
+    //Number of VI frames finished, given before each batch of commands
+    fn set_frame(&mut self, _frame: u64)
+    {
+    }
+}
+
+pub fn check_command_size(send: &[u8], receive: &[u8], send_size: usize, receive_size: usize) -> Result<(), JoybusError>
+{
+    if send.len() != send_size || receive.len() != receive_size
+    {
+        return Err(JoybusError::SIZE_MISMATCH);
+    }
+    Ok(())
+}
@@ -0,0 +1,302 @@
+use n64::controller::ControllerAccessory;
+use n64::save_file::{SaveFile, SaveError};
+use std::path::Path;
+
+pub const MEMPAK_SIZE: usize = 0x8000;
+pub const MEMPAK_PAGE_SIZE: usize = 0x100;
+pub const MEMPAK_NOTE_ENTRY_SIZE: usize = 0x20;
+const MEMPAK_PAGES: usize = 128;
+const MEMPAK_NOTES: usize = 16;
+
+//DexDrive dumps put a 0x1040 byte comment header in front of the pak
+const DEXDRIVE_MAGIC: &[u8] = b"123-456-STD";
+const DEXDRIVE_HEADER_SIZE: usize = 0x1040;
+
+// Referenced: https://n64brew.dev/wiki/Controller_Pak/Filesystem
+const ID_BLOCK_LOCATIONS: [usize; 4] = [0x20, 0x60, 0x80, 0xC0];
+const ID_BLOCK_SIZE: usize = 0x20;
+const ID_BLOCK_TEMPLATE: [u8; 0x1C] = [
+    0xFF, 0xFF, 0xFF, 0xFF, 0x05, 0x1A, 0x5F, 0x13, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01, 0xFF,
+];
+const INODE_TABLE_PAGE: usize = 1;
+const INODE_TABLE_BACKUP_PAGE: usize = 2;
+const NOTE_TABLE_START: usize = 3 * MEMPAK_PAGE_SIZE;
+const FIRST_DATA_PAGE: u16 = 5;
+const INODE_END_OF_NOTE: u16 = 0x0001;
+const INODE_FREE: u16 = 0x0003;
+const NOTE_STATUS_VALID: u8 = 0x02;
+
+pub struct MemPak
+{
+    pub save: SaveFile,
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct Note
+{
+    pub index: usize,
+    pub game_code: u32,
+    pub publisher_code: u16,
+    pub name: String,
+    pub extension: String,
+    pub pages: Vec<u16>,
+}
+
+impl MemPak
+{
+    //Pak that is never written to disk, formatted like a new pak
+    pub fn new() -> MemPak
+    {
+        let mut mempak = MemPak
+        {
+            save: SaveFile::new(MEMPAK_SIZE, 0),
+        };
+        mempak.format();
+        mempak
+    }
+
+    //Opens a .mpk file or DexDrive dump, formatting a fresh pak if the file does not exist yet
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<MemPak, SaveError>
+    {
+        let save = SaveFile::open_with_header(path, MEMPAK_SIZE, 0, dexdrive_header_size)?;
+        let exists = save.exists();
+        let mut mempak = MemPak
+        {
+            save: save,
+        };
+        if !exists
+        {
+            mempak.format();
+        }
+        Ok(mempak)
+    }
+
+    // Referenced: mupen64plus format_mempak
+    pub fn format(&mut self)
+    {
+        let mut data: Vec<u8> = vec![0; MEMPAK_SIZE];
+        for index in 0..ID_BLOCK_SIZE
+        {
+            data[index] = index as u8;
+        }
+        data[0] = 0x81;
+        let id_block = build_id_block();
+        for location in ID_BLOCK_LOCATIONS.iter()
+        {
+            data[*location..(*location + ID_BLOCK_SIZE)].copy_from_slice(&id_block);
+        }
+        self.save.write(0, &data);
+
+        let mut inodes: Vec<u16> = vec![0; MEMPAK_PAGES];
+        for page in (FIRST_DATA_PAGE as usize)..MEMPAK_PAGES
+        {
+            inodes[page] = INODE_FREE;
+        }
+        self.write_inodes(&inodes);
+    }
+
+    pub fn free_pages(&self) -> usize
+    {
+        self.read_inodes().iter().skip(FIRST_DATA_PAGE as usize).filter(|inode| **inode == INODE_FREE).count()
+    }
+
+    pub fn notes(&self) -> Vec<Note>
+    {
+        (0..MEMPAK_NOTES).filter_map(|index| self.note(index)).collect()
+    }
+
+    pub fn note(&self, index: usize) -> Option<Note>
+    {
+        if index >= MEMPAK_NOTES
+        {
+            return None;
+        }
+        let entry = self.note_entry(index);
+        let start_page = read_u16(entry, 0x06);
+        if !is_data_page(start_page)
+        {
+            return None;
+        }
+
+        let inodes = self.read_inodes();
+        let mut pages: Vec<u16> = Vec::new();
+        let mut page = start_page;
+        while is_data_page(page) && pages.len() < MEMPAK_PAGES
+        {
+            pages.push(page);
+            page = inodes[page as usize];
+        }
+
+        return Some(Note
+        {
+            index: index,
+            game_code: (read_u16(entry, 0x00) as u32) << 16 | read_u16(entry, 0x02) as u32,
+            publisher_code: read_u16(entry, 0x04),
+            name: decode_note_text(&entry[0x10..0x20]),
+            extension: decode_note_text(&entry[0x0C..0x10]),
+            pages: pages,
+        })
+    }
+
+    //Exports a note as its 32 byte note table entry followed by its pages
+    pub fn export_note(&self, index: usize) -> Result<Vec<u8>, SaveError>
+    {
+        let note = self.note(index).ok_or(SaveError::NOTE_NOT_FOUND(index))?;
+        let mut exported: Vec<u8> = self.note_entry(index).to_vec();
+        for page in note.pages.iter()
+        {
+            let start = *page as usize * MEMPAK_PAGE_SIZE;
+            exported.extend_from_slice(&self.save.data[start..(start + MEMPAK_PAGE_SIZE)]);
+        }
+        Ok(exported)
+    }
+
+    //Imports a note in the export_note layout, returning the note slot it was written to
+    pub fn import_note(&mut self, note_data: &[u8]) -> Result<usize, SaveError>
+    {
+        if note_data.len() <= MEMPAK_NOTE_ENTRY_SIZE || (note_data.len() - MEMPAK_NOTE_ENTRY_SIZE) % MEMPAK_PAGE_SIZE != 0
+        {
+            return Err(SaveError::INVALID_NOTE(format!("unexpected size of 0x{:X} bytes", note_data.len())));
+        }
+        let page_count = (note_data.len() - MEMPAK_NOTE_ENTRY_SIZE) / MEMPAK_PAGE_SIZE;
+        let index = (0..MEMPAK_NOTES).find(|index| self.note(*index).is_none()).ok_or(SaveError::NO_FREE_NOTE)?;
+
+        let mut inodes = self.read_inodes();
+        let free_pages: Vec<u16> = (FIRST_DATA_PAGE..(MEMPAK_PAGES as u16)).filter(|page| inodes[*page as usize] == INODE_FREE).take(page_count).collect();
+        if free_pages.len() < page_count
+        {
+            return Err(SaveError::NO_FREE_PAGES(page_count));
+        }
+
+        for (position, page) in free_pages.iter().enumerate()
+        {
+            inodes[*page as usize] = if position + 1 < free_pages.len() {free_pages[position + 1]} else {INODE_END_OF_NOTE};
+            let source = MEMPAK_NOTE_ENTRY_SIZE + (position * MEMPAK_PAGE_SIZE);
+            self.save.write(*page as usize * MEMPAK_PAGE_SIZE, &note_data[source..(source + MEMPAK_PAGE_SIZE)]);
+        }
+        self.write_inodes(&inodes);
+
+        let mut entry = note_data[0..MEMPAK_NOTE_ENTRY_SIZE].to_vec();
+        entry[0x06] = (free_pages[0] >> 8) as u8;
+        entry[0x07] = free_pages[0] as u8;
+        entry[0x08] |= NOTE_STATUS_VALID;
+        self.save.write(NOTE_TABLE_START + (index * MEMPAK_NOTE_ENTRY_SIZE), &entry);
+        Ok(index)
+    }
+
+    pub fn delete_note(&mut self, index: usize) -> Result<(), SaveError>
+    {
+        let note = self.note(index).ok_or(SaveError::NOTE_NOT_FOUND(index))?;
+        let mut inodes = self.read_inodes();
+        for page in note.pages.iter()
+        {
+            inodes[*page as usize] = INODE_FREE;
+        }
+        self.write_inodes(&inodes);
+        self.save.write(NOTE_TABLE_START + (index * MEMPAK_NOTE_ENTRY_SIZE), &[0; MEMPAK_NOTE_ENTRY_SIZE]);
+        Ok(())
+    }
+
+    fn note_entry(&self, index: usize) -> &[u8]
+    {
+        let start = NOTE_TABLE_START + (index * MEMPAK_NOTE_ENTRY_SIZE);
+        &self.save.data[start..(start + MEMPAK_NOTE_ENTRY_SIZE)]
+    }
+
+    fn read_inodes(&self) -> Vec<u16>
+    {
+        let start = INODE_TABLE_PAGE * MEMPAK_PAGE_SIZE;
+        (0..MEMPAK_PAGES).map(|page| read_u16(&self.save.data, start + (page * 2))).collect()
+    }
+
+    //Writes both copies of the inode table, with the checksum over the data page entries
+    fn write_inodes(&mut self, inodes: &Vec<u16>)
+    {
+        let mut table: Vec<u8> = vec![0; MEMPAK_PAGE_SIZE];
+        for page in (FIRST_DATA_PAGE as usize)..MEMPAK_PAGES
+        {
+            table[page * 2] = (inodes[page] >> 8) as u8;
+            table[(page * 2) + 1] = inodes[page] as u8;
+        }
+        let checksum = table[(FIRST_DATA_PAGE as usize * 2)..].iter().fold(0_u8, |sum, value| sum.wrapping_add(*value));
+        table[1] = checksum;
+        self.save.write(INODE_TABLE_PAGE * MEMPAK_PAGE_SIZE, &table);
+        self.save.write(INODE_TABLE_BACKUP_PAGE * MEMPAK_PAGE_SIZE, &table);
+    }
+}
+
+impl ControllerAccessory for MemPak
+{
+    fn read_block(&mut self, address: u16, data: &mut [u8])
+    {
+        let address = address as usize;
+        //Nothing answers above the 32KB of pak RAM
+        if address + data.len() <= MEMPAK_SIZE
+        {
+            self.save.read(address, data);
+        }
+    }
+
+    fn write_block(&mut self, address: u16, data: &[u8])
+    {
+        let address = address as usize;
+        if address + data.len() <= MEMPAK_SIZE
+        {
+            self.save.write(address, data);
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), SaveError>
+    {
+        self.save.flush()
+    }
+}
+
+fn build_id_block() -> Vec<u8>
+{
+    let mut id_block = ID_BLOCK_TEMPLATE.to_vec();
+    let checksum = (0..(ID_BLOCK_TEMPLATE.len() / 2)).fold(0_u16, |sum, word| sum.wrapping_add(read_u16(&ID_BLOCK_TEMPLATE, word * 2)));
+    let inverted_checksum = 0xFFF2_u16.wrapping_sub(checksum);
+    id_block.extend_from_slice(&[(checksum >> 8) as u8, checksum as u8, (inverted_checksum >> 8) as u8, inverted_checksum as u8]);
+    id_block
+}
+
+//DexDrive dumps put a header with note comments in front of the pak data
+fn dexdrive_header_size(data: &[u8]) -> usize
+{
+    if data.starts_with(DEXDRIVE_MAGIC) && data.len() >= DEXDRIVE_HEADER_SIZE + MEMPAK_SIZE
+    {
+        DEXDRIVE_HEADER_SIZE
+    }
+    else
+    {
+        0
+    }
+}
+
+fn is_data_page(page: u16) -> bool
+{
+    page >= FIRST_DATA_PAGE && (page as usize) < MEMPAK_PAGES
+}
+
+fn read_u16(data: &[u8], location: usize) -> u16
+{
+    (data[location] as u16) << 8 | (data[location + 1] as u16)
+}
+
+//Note names use the N64 font, not ASCII
+pub fn decode_note_text(text: &[u8]) -> String
+{
+    let symbols = b"!\"#`*+,-./:=?@";
+    text.iter().take_while(|character| **character != 0).map(|character| match *character
+    {
+        0x0F => ' ',
+        0x10...0x19 => (b'0' + (character - 0x10)) as char,
+        0x1A...0x33 => (b'A' + (character - 0x1A)) as char,
+        0x34...0x41 => symbols[(character - 0x34) as usize] as char,
+        _ => ' ',
+    }).collect::<String>().trim_end().to_string()
+}
@@ -0,0 +1,169 @@
+#[cfg(test)]
+mod mempak_tests
+{
+    use n64::mempak::*;
+    use n64::controller::*;
+    use n64::joybus::JoybusDevice;
+    use n64::save_file::SaveError;
+    use std::env;
+    use std::fs;
+
+    //Inverse of decode_note_text
+    fn encode_note_text(text: &str, length: usize) -> Vec<u8>
+    {
+        let symbols = b"!\"#`*+,-./:=?@";
+        let mut encoded: Vec<u8> = text.to_uppercase().bytes().take(length).map(|character| match character
+        {
+            b'0'...b'9' => 0x10 + (character - b'0'),
+            b'A'...b'Z' => 0x1A + (character - b'A'),
+            _ => match symbols.iter().position(|symbol| *symbol == character)
+            {
+                Some(position) => 0x34 + position as u8,
+                None => 0x0F,
+            },
+        }).collect();
+        encoded.resize(length, 0);
+        encoded
+    }
+
+    fn test_note(name: &str, pages: usize) -> Vec<u8>
+    {
+        let mut note: Vec<u8> = vec![0; MEMPAK_NOTE_ENTRY_SIZE];
+        note[0..4].copy_from_slice(b"NSME");
+        note[4..6].copy_from_slice(b"01");
+        note[0x10..0x20].copy_from_slice(&encode_note_text(name, 16));
+        for page in 0..pages
+        {
+            note.extend_from_slice(&vec![page as u8 + 1; MEMPAK_PAGE_SIZE]);
+        }
+        note
+    }
+
+    #[test]
+    fn format_builds_empty_filesystem()
+    {
+        let mempak = MemPak::new();
+        let data = &mempak.save.data;
+        assert_eq!(data[0x3C..0x40].to_vec(), vec![0x66, 0x25, 0x99, 0xCD]);
+        assert_eq!(data[0x20..0x40].to_vec(), data[0xC0..0xE0].to_vec());
+        assert_eq!(data[0x100..0x10C].to_vec(), vec![0x00, 0x71, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03]);
+        assert_eq!(data[0x100..0x200].to_vec(), data[0x200..0x300].to_vec());
+        assert_eq!(mempak.free_pages(), 123);
+        assert!(mempak.notes().is_empty());
+    }
+
+    #[test]
+    fn notes_import_export_and_delete()
+    {
+        let mut mempak = MemPak::new();
+        let note = test_note("mario 64", 3);
+        assert_eq!(mempak.import_note(&note).unwrap(), 0);
+        assert_eq!(mempak.import_note(&test_note("other", 1)).unwrap(), 1);
+        assert_eq!(mempak.free_pages(), 119);
+
+        let notes = mempak.notes();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].name, "MARIO 64");
+        assert_eq!(notes[0].game_code, 0x4E534D45);
+        assert_eq!(notes[0].publisher_code, 0x3031);
+        assert_eq!(notes[0].pages, vec![5, 6, 7]);
+        assert_eq!(notes[1].pages, vec![8]);
+
+        let exported = mempak.export_note(0).unwrap();
+        assert_eq!(exported.len(), MEMPAK_NOTE_ENTRY_SIZE + (3 * MEMPAK_PAGE_SIZE));
+        assert_eq!(exported[MEMPAK_NOTE_ENTRY_SIZE..].to_vec(), note[MEMPAK_NOTE_ENTRY_SIZE..].to_vec());
+
+        mempak.delete_note(0).unwrap();
+        assert_eq!(mempak.free_pages(), 122);
+        assert!(mempak.export_note(0).is_err());
+        assert!(mempak.import_note(&vec![0; 0x30]).is_err());
+
+        //Pages freed by the delete are reused
+        mempak.import_note(&exported).unwrap();
+        assert_eq!(mempak.note(0).unwrap().pages, vec![5, 6, 7]);
+    }
+
+    #[test]
+    fn accessory_crcs()
+    {
+        assert_eq!(accessory_address_crc(0x8000), 0x01);
+        assert_eq!(accessory_address_crc(0xC000), 0x1B);
+        assert_eq!(accessory_address_crc(0x0000), 0x00);
+        assert_eq!(accessory_data_crc(&[0; 32]), 0x00);
+    }
+
+    #[test]
+    fn controller_reads_and_writes_pak()
+    {
+        let mut controller = Controller::with_accessory(Box::new(ControllerState::neutral()), Box::new(MemPak::new()));
+        let mut status = [0_u8; 3];
+        controller.process_command(&[0x00], &mut status).unwrap();
+        assert_eq!(status[2], 0x01);
+
+        let block: Vec<u8> = (0..32).collect();
+        let address: u16 = 0x0600 | accessory_address_crc(0x0600) as u16;
+        let mut write: Vec<u8> = vec![0x03, (address >> 8) as u8, address as u8];
+        write.extend_from_slice(&block);
+        let mut write_response = [0_u8; 1];
+        controller.process_command(&write, &mut write_response).unwrap();
+        assert_eq!(write_response[0], accessory_data_crc(&block));
+
+        let mut read_response = [0_u8; 33];
+        controller.process_command(&[0x02, (address >> 8) as u8, address as u8], &mut read_response).unwrap();
+        assert_eq!(read_response[0..32].to_vec(), block);
+        assert_eq!(read_response[32], accessory_data_crc(&block));
+
+        //A corrupt address CRC is answered with an inverted data CRC
+        controller.process_command(&[0x02, 0x06, 0x00], &mut read_response).unwrap();
+        assert_eq!(read_response[32], !accessory_data_crc(&[0; 32]));
+    }
+
+    #[test]
+    fn mpk_file_is_created_and_persisted()
+    {
+        let path = env::temp_dir().join(format!("n8x8_mempak_test_{}.mpk", ::std::process::id()));
+        let _ = fs::remove_file(&path);
+        {
+            let mut mempak = MemPak::open(&path).unwrap();
+            mempak.import_note(&test_note("zelda", 2)).unwrap();
+            mempak.write_block(0x7FE0, &[0xAA; 32]);
+        }
+        let saved = fs::read(&path).unwrap();
+        assert_eq!(saved.len(), 0x8000);
+        assert_eq!(saved[0x7FE0], 0xAA);
+
+        let mempak = MemPak::open(&path).unwrap();
+        assert_eq!(mempak.notes()[0].name, "ZELDA");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn dexdrive_files_keep_their_header_on_write_back()
+    {
+        let path = env::temp_dir().join(format!("n8x8_mempak_dexdrive_test_{}.n64", ::std::process::id()));
+        let mut dump: Vec<u8> = b"123-456-STD".to_vec();
+        dump.resize(0x1040, 0x20);
+        dump.extend_from_slice(&MemPak::new().save.data);
+        fs::write(&path, &dump).unwrap();
+        {
+            let mut mempak = MemPak::open(&path).unwrap();
+            assert_eq!(mempak.free_pages(), 123);
+            mempak.write_block(0x7FE0, &[0xAA; 32]);
+        }
+        let saved = fs::read(&path).unwrap();
+        assert_eq!(saved.len(), 0x1040 + 0x8000);
+        assert_eq!(saved[0..0x1040].to_vec(), dump[0..0x1040].to_vec());
+        assert_eq!(saved[0x1040 + 0x7FE0], 0xAA);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn wrong_size_files_are_left_alone()
+    {
+        let path = env::temp_dir().join(format!("n8x8_mempak_size_test_{}.mpk", ::std::process::id()));
+        fs::write(&path, &vec![0x55; 0x800]).unwrap();
+        assert_eq!(MemPak::open(&path).err(), Some(SaveError::INVALID_SIZE(0x800)));
+        assert_eq!(fs::read(&path).unwrap(), vec![0x55; 0x800]);
+        fs::remove_file(&path).unwrap();
+    }
+}
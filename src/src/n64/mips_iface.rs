@@ -7,6 +7,13 @@ const MI_INTR_REG_END: usize = 0x0000000B;
 const MI_INTR_MASK_REG_START: usize = 0x0000000C;
 const MI_INTR_MASK_REG_END: usize = 0x0000000F;
 
+//Interrupt bits shared by MI_INTR_REG and MI_INTR_MASK_REG
+pub const MI_INTR_SP: u32 = 0x01;
+pub const MI_INTR_SI: u32 = 0x02;
+pub const MI_INTR_AI: u32 = 0x04;
+pub const MI_INTR_VI: u32 = 0x08;
+pub const MI_INTR_PI: u32 = 0x10;
+pub const MI_INTR_DP: u32 = 0x20;
 
 use n64::arch::Reg;
 use n64::exceptions::Exception;
@@ -37,6 +44,24 @@ impl MipsInterface
         self.version.set_value(0x01010101_u32);
     }
 
+    pub fn raise_interrupt(&mut self, interrupt: u32)
+    {
+        let value = self.interrupt.get_value() as u32 | interrupt;
+        self.interrupt.set_value(value);
+    }
+
+    pub fn clear_interrupt(&mut self, interrupt: u32)
+    {
+        let value = self.interrupt.get_value() as u32 & !interrupt;
+        self.interrupt.set_value(value);
+    }
+
+    //Drives the CPU's IP2 line
+    pub fn interrupt_pending(&self) -> bool
+    {
+        self.interrupt.get_value() & self.interrupt_mask.get_value() != 0
+    }
+
     pub fn read_u32_from_address(&self, address: usize) -> Result<u32, Exception>
     {
         //Only allow alligned addresses (unaligned handled exterior to function)
@@ -12,6 +12,38 @@ pub mod rdram_iface;
 pub mod rdram_registers;
 pub mod rdram;
 pub mod icache;
+pub mod cic;
+pub mod rom_archive;
+pub mod rom_patch;
+pub mod pif;
+pub mod joybus;
+pub mod si;
+pub mod controller;
+pub mod save_file;
+pub mod mempak;
+pub mod rumble_pak;
+pub mod transfer_pak;
+pub mod save_type;
+pub mod eeprom;
+pub mod sram;
+pub mod flash_ram;
+pub mod rtc;
+pub mod cartridge_joybus;
+pub mod game_db;
+pub mod dp;
+pub mod rsp_opcodes;
+pub mod rsp_core;
+pub mod rsp_vector;
+pub mod rsp_vector_opcodes;
+pub mod hle;
+pub mod hle_graphics;
+pub mod hle_audio;
+pub mod rdp_commands;
+pub mod rdp;
+pub mod vi;
+pub mod frame_dump;
+pub mod ai;
+pub mod pi;
 
 pub mod memory_tests;
 pub mod rom_tests;
@@ -21,3 +53,27 @@ pub mod rsp_tests;
 pub mod cpu_opcodes_tests;
 pub mod rdram_iface_tests;
 pub mod icache_tests;
+pub mod cic_tests;
+pub mod rom_archive_tests;
+pub mod rom_patch_tests;
+pub mod pif_tests;
+pub mod si_tests;
+pub mod controller_tests;
+pub mod mempak_tests;
+pub mod rumble_pak_tests;
+pub mod transfer_pak_tests;
+pub mod eeprom_tests;
+pub mod sram_tests;
+pub mod flash_ram_tests;
+pub mod rtc_tests;
+pub mod game_db_tests;
+pub mod rsp_core_tests;
+pub mod rsp_vector_tests;
+pub mod hle_graphics_tests;
+pub mod hle_audio_tests;
+pub mod dp_tests;
+pub mod rdp_tests;
+pub mod vi_tests;
+pub mod frame_dump_tests;
+pub mod ai_tests;
+pub mod pi_tests;
@@ -2,39 +2,182 @@ use n64::connector::Connector;
 use n64::cpu_opcodes::Opcode;
 use n64::cpu_opcodes::Command;
 use n64::cpu;
+use n64::rsp_core::RSPCore;
+use n64::hle;
+use n64::hle::HLEError;
+use n64::rsp::SP_STATUS_HALT;
+use n64::controller::{Controller, ControllerAccessory, InputSource, CONTROLLER_PORTS};
+use n64::save_file::{SaveFile, SaveError};
+use n64::save_type::SaveType;
+use n64::sram::SRAM;
+use n64::flash_ram::FlashRAM;
+use n64::eeprom::Eeprom;
+use n64::rtc::{RTC, ClockSource, HostClock, detect_rtc};
+use n64::game_db::{GameDatabase, GameEntry, QUIRK_FAST_SI, QUIRK_FAST_PI};
+use n64::rdram::RDRAM;
+use n64::cic::CIC;
+use n64::rom::{Rom, RomError};
+use n64::vi::Frame;
+use n64::frame_dump::FrameDump;
+use n64::ai::AudioSink;
 use binary_helpers::add_u16_to_u32_as_i16_overflow;
 use std::collections::VecDeque;
 
 const OPCODE_LOG_SIZE: usize = 5;
 const PC_LOG_SIZE:usize = 1000;
+const CYCLES_PER_INSTRUCTION: u32 = 1;
+const CAUSE_IP2: u32 = 0x00000400;
+//Dirty saves are written out every so often so they survive the process being killed
+const SAVE_FLUSH_INTERVAL: u64 = 0x1000000;
 
 
 pub struct N64
 {
     pub connector: Connector,
     pub cpu: cpu::CPU,
+    pub rsp_core: RSPCore,
     pub opcode_log: VecDeque<Opcode>,
     pub pc_log: VecDeque<u32>,
     pub executed_count: u64,
+    pub game_entry: Option<GameEntry>,
+    //Recognised RSP tasks run at a high level instead of on the RSP core
+    pub hle: bool,
+    hle_task_checked: bool,
+    //Last task HLE could not complete, for the frontend to report
+    pub hle_error: Option<HLEError>,
+    //Decoded each time the VI finishes a field
+    pub last_frame: Option<Frame>,
+    pub frame_count: u64,
+    pub frame_dump: Option<FrameDump>,
+    //run returns after this many frames, for headless runs that have to end
+    pub frame_limit: Option<u64>,
 }
 
 impl N64 {
-    pub fn new(filename: &str) -> N64
+    pub fn new(filename: &str) -> Result<N64, RomError>
     {
-        return N64
+        Ok(N64::from_rom(Rom::new(filename)?))
+    }
+
+    pub fn from_rom(rom: Rom) -> N64
+    {
+        N64::from_rom_with_database(rom, &GameDatabase::builtin())
+    }
+
+    pub fn from_rom_with_database(rom: Rom, game_database: &GameDatabase) -> N64
+    {
+        let game_entry = game_database.lookup(&rom.rom_header);
+        let mut n64 = N64
         {
-            connector: Connector::new(filename),
+            connector: Connector::from_rom(rom),
             cpu: cpu::CPU::new(),
+            rsp_core: RSPCore::new(),
             opcode_log: VecDeque::new(),
             pc_log: VecDeque::new(),
             executed_count: 0,
+            game_entry: game_entry,
+            hle: false,
+            hle_task_checked: false,
+            hle_error: None,
+            last_frame: None,
+            frame_count: 0,
+            frame_dump: None,
+            frame_limit: None,
+        };
+
+        //Database entries take priority over anything detected from the header
+        let mut rtc = detect_rtc(&n64.connector.rom.rom_header);
+        if let Some(ref game_entry) = n64.game_entry
+        {
+            if let Some(save_type) = game_entry.save_type
+            {
+                n64.connector.save_type = save_type;
+            }
+            rtc = game_entry.rtc.unwrap_or(rtc);
+            if game_entry.expansion_pak == Some(false)
+            {
+                n64.connector.rdram = RDRAM::without_expansion_pak();
+            }
+            if game_entry.has_quirk(QUIRK_FAST_SI)
+            {
+                n64.connector.si.dma_duration = 0;
+            }
+            if game_entry.has_quirk(QUIRK_FAST_PI)
+            {
+                n64.connector.pi.dma_cycles_per_byte = 0;
+            }
+        }
+
+        //Saves stay in memory until open_save_files is called
+        let save_type = n64.connector.save_type;
+        n64.connect_cartridge_save(SaveFile::new(save_type.size(), save_type.fill_value()));
+        if rtc
+        {
+            n64.connector.pif.cartridge.rtc = Some(RTC::new(Box::new(HostClock)));
+        }
+        n64
+    }
+
+    pub fn cic(&self) -> CIC
+    {
+        match self.game_entry
+        {
+            Some(GameEntry {cic: Some(cic), ..}) => cic,
+            _ => self.connector.rom.rom_header.cic(),
         }
     }
 
+    //Backs cartridge saves with files next to the ROM
+    pub fn open_save_files(&mut self) -> Result<(), SaveError>
+    {
+        let save_type = self.connector.save_type;
+        if save_type == SaveType::NONE
+        {
+            return Ok(());
+        }
+        if let Some(path) = self.connector.rom.save_path(save_type.file_extension())
+        {
+            let save = SaveFile::open(path, save_type.size(), save_type.fill_value())?;
+            self.connect_cartridge_save(save);
+        }
+        Ok(())
+    }
+
+    fn connect_cartridge_save(&mut self, save: SaveFile)
+    {
+        let save_type = self.connector.save_type;
+        match save_type
+        {
+            SaveType::NONE => (),
+            SaveType::EEPROM_4K | SaveType::EEPROM_16K => self.connector.pif.cartridge.eeprom = Some(Eeprom::new(save_type, save)),
+            SaveType::SRAM_32K | SaveType::SRAM_96K | SaveType::SRAM_128K => self.connector.sram = Some(SRAM::new(save_type, save)),
+            SaveType::FLASH_RAM => self.connector.flash_ram = Some(FlashRAM::new(save)),
+        }
+    }
+
+    //Swaps the clock behind the cartridge RTC, a deterministic clock keeps traces reproducible
+    pub fn set_rtc_clock(&mut self, clock: Box<dyn ClockSource>)
+    {
+        if let Some(ref mut rtc) = self.connector.pif.cartridge.rtc
+        {
+            rtc.clock = clock;
+        }
+    }
+
+    //Samples played by the AI are dropped until a sink is attached
+    pub fn set_audio_sink(&mut self, sink: Box<dyn AudioSink>)
+    {
+        self.connector.ai.sink = Some(sink);
+    }
+
     pub fn run_pif_rom(&mut self)
     {
+        let tv_type = self.connector.rom.rom_header.tv_type();
+        let cic = self.cic();
+
         //Init CPU
         self.cpu.cpu_registers.set_pif_rom_values();
+        cic.set_pif_rom_values(&mut self.cpu.cpu_registers, tv_type);
         self.cpu.cop0_registers.set_pif_rom_values();
         self.cpu.set_pif_rom_values();
 
@@ -44,6 +187,126 @@ impl N64 {
         //Copy ROM data
         let rom_data: Vec<u8> = self.connector.rom.rom_data[0..0x1000].to_vec();
         self.connector.rsp.copy_bytes_from_u8_vector(0x0000, rom_data, 0x1000);
+
+        //CIC specific setup
+        cic.set_rsp_pif_rom_values(&mut self.connector.rsp).unwrap();
+        self.connector.pif.set_cic_seed(cic);
+    }
+
+    pub fn run_pif_boot_rom(&mut self, boot_rom: Vec<u8>) -> Result<(), RomError>
+    {
+        self.connector.pif.load_boot_rom(boot_rom)?;
+        let cic = self.cic();
+        self.connector.pif.set_cic_seed(cic);
+
+        //Init CPU, everything past the reset vector is left to the PIF code and IPL3
+        self.cpu.cop0_registers.set_cold_reset_values();
+        self.cpu.set_cold_reset_values();
+        Ok(())
+    }
+
+    pub fn connect_controller(&mut self, port: usize, input: Box<dyn InputSource>)
+    {
+        assert!(port < CONTROLLER_PORTS, "Controller port {} does not exist", port);
+        self.connector.pif.connect_device(port, Box::new(Controller::new(input)));
+    }
+
+    pub fn connect_controller_with_accessory(&mut self, port: usize, input: Box<dyn InputSource>, accessory: Box<dyn ControllerAccessory>)
+    {
+        assert!(port < CONTROLLER_PORTS, "Controller port {} does not exist", port);
+        self.connector.pif.connect_device(port, Box::new(Controller::with_accessory(input, accessory)));
+    }
+
+    pub fn disconnect_controller(&mut self, port: usize)
+    {
+        assert!(port < CONTROLLER_PORTS, "Controller port {} does not exist", port);
+        self.connector.pif.disconnect_device(port);
+    }
+
+    pub fn flush_saves(&mut self) -> Result<(), SaveError>
+    {
+        self.connector.pif.flush_saves()?;
+        if let Some(ref mut sram) = self.connector.sram
+        {
+            sram.flush()?;
+        }
+        if let Some(ref mut flash_ram) = self.connector.flash_ram
+        {
+            flash_ram.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn step_devices(&mut self, cycles: u32)
+    {
+        //Each task gets one chance at HLE when the RSP is started
+        if self.connector.rsp.is_halted()
+        {
+            self.hle_task_checked = false;
+        }
+        else if self.hle && !self.hle_task_checked
+        {
+            self.hle_task_checked = true;
+            if let Err(e) = hle::process_task(&mut self.connector)
+            {
+                self.hle_error = Some(e);
+            }
+        }
+        //Microcode that hits something unsupported stops the RSP rather than the whole machine
+        if self.rsp_core.run(&mut self.connector, cycles).is_err()
+        {
+            self.connector.rsp.set_status_bits(SP_STATUS_HALT);
+        }
+        self.connector.step(cycles);
+
+        //MI interrupts are wired to IP2
+        let cause = self.cpu.cop0_registers.register[cpu::COP0RegisterName::Cause as usize].get_value() as u32;
+        let cause = if self.connector.mips_interface.interrupt_pending() {cause | CAUSE_IP2} else {cause & !CAUSE_IP2};
+        self.cpu.cop0_registers.register[cpu::COP0RegisterName::Cause as usize].set_value(cause);
+
+        if self.connector.vi.field_count != self.frame_count
+        {
+            self.frame_count = self.connector.vi.field_count;
+            self.finish_frame();
+        }
+    }
+
+    fn finish_frame(&mut self)
+    {
+        let frame = self.connector.scanout();
+        let index = self.frame_count - 1;
+        if let Some(ref frame_dump) = self.frame_dump
+        {
+            if frame_dump.wants(index)
+            {
+                if let Err(e) = frame_dump.write(index, &frame)
+                {
+                    eprintln!("Failed to write frame {}: {}", index, e);
+                }
+            }
+        }
+        self.last_frame = Some(frame);
+    }
+
+    pub fn last_frame(&self) -> Option<&Frame>
+    {
+        self.last_frame.as_ref()
+    }
+
+    pub fn frames_finished(&self) -> bool
+    {
+        if let Some(frame_limit) = self.frame_limit
+        {
+            if self.frame_count >= frame_limit
+            {
+                return true;
+            }
+        }
+        match self.frame_dump
+        {
+            Some(ref frame_dump) => frame_dump.is_complete(self.frame_count),
+            None => false,
+        }
     }
 
     pub fn register_debug(&self)
@@ -54,7 +317,7 @@ impl N64 {
 
     pub fn run(&mut self)
     {
-        while true == true
+        while !self.frames_finished()
         {
             let current_pc = self.cpu.program_counter.get_value();
             let opcode = self.cpu.retrieve_opcode(&self.connector);
@@ -87,6 +350,14 @@ impl N64 {
                 Ok(_o) =>
                 {
                     self.executed_count += 1;
+                    self.step_devices(CYCLES_PER_INSTRUCTION);
+                    if self.executed_count % SAVE_FLUSH_INTERVAL == 0
+                    {
+                        if let Err(e) = self.flush_saves()
+                        {
+                            eprintln!("{}", e);
+                        }
+                    }
                     // if current_pc == 0xA4000894
                     // {
                     //     println!("PC: 0x{:08x}", current_pc);
@@ -106,6 +377,11 @@ impl N64 {
                 },
             };
         }
+        //Headless runs end here, keep whatever the game saved
+        if let Err(e) = self.flush_saves()
+        {
+            eprintln!("{}", e);
+        }
     }
 }
 
@@ -0,0 +1,203 @@
+const PI_DRAM_ADDR_REG_START: usize = 0x00000000;
+const PI_DRAM_ADDR_REG_END: usize = 0x00000003;
+const PI_CART_ADDR_REG_START: usize = 0x00000004;
+const PI_CART_ADDR_REG_END: usize = 0x00000007;
+const PI_RD_LEN_REG_START: usize = 0x00000008;
+const PI_RD_LEN_REG_END: usize = 0x0000000B;
+const PI_WR_LEN_REG_START: usize = 0x0000000C;
+const PI_WR_LEN_REG_END: usize = 0x0000000F;
+const PI_STATUS_REG_START: usize = 0x00000010;
+const PI_STATUS_REG_END: usize = 0x00000013;
+const PI_BSD_DOM1_LAT_REG_START: usize = 0x00000014;
+const PI_BSD_DOM2_RLS_REG_END: usize = 0x00000033;
+
+pub const PI_STATUS_DMA_BUSY: u32 = 0x00000001;
+pub const PI_STATUS_IO_BUSY: u32 = 0x00000002;
+pub const PI_STATUS_ERROR: u32 = 0x00000004;
+pub const PI_STATUS_INTERRUPT: u32 = 0x00000008;
+//Bits written to PI_STATUS
+const PI_STATUS_RESET: u32 = 0x00000001;
+const PI_STATUS_CLEAR_INTERRUPT: u32 = 0x00000002;
+
+const PI_DRAM_ADDR_MASK: u32 = 0x00FFFFFE;
+const PI_CART_ADDR_MASK: u32 = 0xFFFFFFFE;
+const PI_LEN_MASK: u32 = 0x00FFFFFF;
+const PI_BSD_MASK: u32 = 0x000000FF;
+//Roughly the 5MB/s the bus manages with the timings IPL3 programs
+pub const PI_DMA_CYCLES_PER_BYTE: u32 = 9;
+
+use n64::arch::Reg;
+use n64::exceptions::Exception;
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum PIDMADirection
+{
+    RDRAM_TO_CART,
+    CART_TO_RDRAM,
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+pub struct PIDMARequest
+{
+    pub direction: PIDMADirection,
+    pub dram_address: u32,
+    pub cart_address: u32,
+    pub length: u32,
+}
+
+// Referenced: https://n64brew.dev/wiki/Peripheral_Interface
+pub struct PeripheralInterface
+{
+    pub dram_address: Reg,
+    pub cart_address: Reg,
+    pub read_length: Reg,
+    pub write_length: Reg,
+    pub status: Reg,
+    //Latency, pulse width, page size and release for domains 1 and 2
+    pub bsd: [Reg; 8],
+    pub pending_interrupt: Option<bool>,
+    pub active_dma: Option<PIDMARequest>,
+    pub dma_cycles_remaining: u32,
+    pub dma_cycles_per_byte: u32,
+}
+
+impl PeripheralInterface
+{
+    pub fn new() -> PeripheralInterface
+    {
+        return PeripheralInterface
+        {
+            dram_address: Reg::default(),
+            cart_address: Reg::default(),
+            read_length: Reg::default(),
+            write_length: Reg::default(),
+            status: Reg::default(),
+            bsd: [Reg::default(); 8],
+            pending_interrupt: None,
+            active_dma: None,
+            dma_cycles_remaining: 0,
+            dma_cycles_per_byte: PI_DMA_CYCLES_PER_BYTE,
+        }
+    }
+
+    pub fn is_busy(&self) -> bool
+    {
+        self.active_dma.is_some()
+    }
+
+    pub fn read_u32_from_address(&self, address: usize) -> Result<u32, Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match address
+        {
+            PI_DRAM_ADDR_REG_START...PI_DRAM_ADDR_REG_END => Ok(self.dram_address.get_value() as u32),
+            PI_CART_ADDR_REG_START...PI_CART_ADDR_REG_END => Ok(self.cart_address.get_value() as u32),
+            PI_RD_LEN_REG_START...PI_RD_LEN_REG_END => Ok(self.read_length.get_value() as u32),
+            PI_WR_LEN_REG_START...PI_WR_LEN_REG_END => Ok(self.write_length.get_value() as u32),
+            PI_STATUS_REG_START...PI_STATUS_REG_END => Ok(self.status.get_value() as u32),
+            PI_BSD_DOM1_LAT_REG_START...PI_BSD_DOM2_RLS_REG_END => Ok(self.bsd[(address - PI_BSD_DOM1_LAT_REG_START) / 4].get_value() as u32),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    pub fn load_u32_to_address(&mut self, address: usize, value: u32) -> Result<(), Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match address
+        {
+            PI_DRAM_ADDR_REG_START...PI_DRAM_ADDR_REG_END => Ok(self.dram_address.set_value(value & PI_DRAM_ADDR_MASK)),
+            PI_CART_ADDR_REG_START...PI_CART_ADDR_REG_END => Ok(self.cart_address.set_value(value & PI_CART_ADDR_MASK)),
+            PI_RD_LEN_REG_START...PI_RD_LEN_REG_END =>
+            {
+                self.read_length.set_value(value & PI_LEN_MASK);
+                Ok(self.start_dma(PIDMADirection::RDRAM_TO_CART, value & PI_LEN_MASK))
+            },
+            PI_WR_LEN_REG_START...PI_WR_LEN_REG_END =>
+            {
+                self.write_length.set_value(value & PI_LEN_MASK);
+                Ok(self.start_dma(PIDMADirection::CART_TO_RDRAM, value & PI_LEN_MASK))
+            },
+            PI_STATUS_REG_START...PI_STATUS_REG_END =>
+            {
+                if value & PI_STATUS_RESET != 0
+                {
+                    self.active_dma = None;
+                    self.dma_cycles_remaining = 0;
+                    let status = self.status.get_value() as u32 & !(PI_STATUS_DMA_BUSY | PI_STATUS_IO_BUSY | PI_STATUS_ERROR);
+                    self.status.set_value(status);
+                }
+                //The connector clears the MI side
+                if value & PI_STATUS_CLEAR_INTERRUPT != 0
+                {
+                    let status = self.status.get_value() as u32 & !PI_STATUS_INTERRUPT;
+                    self.status.set_value(status);
+                    self.pending_interrupt = Some(false);
+                }
+                Ok(())
+            },
+            PI_BSD_DOM1_LAT_REG_START...PI_BSD_DOM2_RLS_REG_END => Ok(self.bsd[(address - PI_BSD_DOM1_LAT_REG_START) / 4].set_value(value & PI_BSD_MASK)),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    //Length registers hold the byte count minus one, a transfer started while busy sets the error bit
+    fn start_dma(&mut self, direction: PIDMADirection, length: u32)
+    {
+        if self.is_busy()
+        {
+            let status = self.status.get_value() as u32 | PI_STATUS_ERROR;
+            self.status.set_value(status);
+            return;
+        }
+        let request = PIDMARequest
+        {
+            direction: direction,
+            dram_address: self.dram_address.get_value() as u32,
+            cart_address: self.cart_address.get_value() as u32,
+            length: length + 1,
+        };
+        self.active_dma = Some(request);
+        self.dma_cycles_remaining = request.length * self.dma_cycles_per_byte;
+        let status = self.status.get_value() as u32 | PI_STATUS_DMA_BUSY;
+        self.status.set_value(status);
+    }
+
+    //Returns the transfer once it completes, the connector moves the data and raises the PI interrupt
+    pub fn step(&mut self, cycles: u32) -> Option<PIDMARequest>
+    {
+        if !self.is_busy()
+        {
+            return None;
+        }
+        self.dma_cycles_remaining = self.dma_cycles_remaining.saturating_sub(cycles);
+        if self.dma_cycles_remaining > 0
+        {
+            return None;
+        }
+        let request = self.active_dma.take();
+        if let Some(request) = request
+        {
+            //Both addresses are left pointing past the transfer
+            self.dram_address.set_value(request.dram_address.wrapping_add(request.length) & PI_DRAM_ADDR_MASK);
+            self.cart_address.set_value(request.cart_address.wrapping_add(request.length) & PI_CART_ADDR_MASK);
+        }
+        let status = (self.status.get_value() as u32 & !PI_STATUS_DMA_BUSY) | PI_STATUS_INTERRUPT;
+        self.status.set_value(status);
+        request
+    }
+}
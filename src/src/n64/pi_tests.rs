@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod pi_tests
+{
+    use n64::pi::*;
+    use n64::connector::Connector;
+    use n64::rom::Rom;
+    use n64::mips_iface::MI_INTR_PI;
+
+    const PI_DRAM_ADDR: u32 = 0x04600000;
+    const PI_CART_ADDR: u32 = 0x04600004;
+    const PI_RD_LEN: u32 = 0x04600008;
+    const PI_WR_LEN: u32 = 0x0460000C;
+    const PI_STATUS: u32 = 0x04600010;
+    const PI_BSD_DOM2_RLS: u32 = 0x04600030;
+
+    fn test_connector() -> Connector
+    {
+        let mut rom_data: Vec<u8> = (0..0x2000).map(|index| index as u8).collect();
+        rom_data[0..4].copy_from_slice(&[0x80, 0x37, 0x12, 0x40]);
+        Connector::from_rom(Rom::from_bytes(rom_data).unwrap())
+    }
+
+    fn pi_interrupt(connector: &Connector) -> bool
+    {
+        connector.mips_interface.interrupt.get_value() as u32 & MI_INTR_PI != 0
+    }
+
+    #[test]
+    fn cart_rom_is_mapped_with_open_bus_past_the_end()
+    {
+        let mut connector = test_connector();
+        assert_eq!(connector.read_u32(0xB0000000).unwrap(), 0x80371240);
+        assert_eq!(connector.read_u32(0x10001004).unwrap(), 0x04050607);
+        assert_eq!(connector.read_u32(0xB0002000).unwrap(), 0x20002002);
+        //Writes to the cart are dropped
+        connector.store_u32(0xB0001000, 0xFFFFFFFF).unwrap();
+        assert_eq!(connector.read_u32(0xB0001000).unwrap(), 0x00010203);
+        assert_eq!(connector.read_u8(0xB0001FFF).unwrap(), 0xFF);
+    }
+
+    #[test]
+    fn cart_dma_lands_when_the_transfer_completes()
+    {
+        let mut connector = test_connector();
+        connector.store_u32(PI_DRAM_ADDR, 0x00200001).unwrap();
+        connector.store_u32(PI_CART_ADDR, 0x10001010).unwrap();
+        connector.store_u32(PI_WR_LEN, 0x7).unwrap();
+        assert_eq!(connector.read_u32(PI_STATUS).unwrap(), PI_STATUS_DMA_BUSY);
+
+        //A second transfer while busy is refused
+        connector.store_u32(PI_RD_LEN, 0x7).unwrap();
+        assert_eq!(connector.read_u32(PI_STATUS).unwrap(), PI_STATUS_DMA_BUSY | PI_STATUS_ERROR);
+
+        connector.step(8 * PI_DMA_CYCLES_PER_BYTE - 1);
+        assert_eq!(connector.read_u32(0x00200000).unwrap(), 0);
+        assert!(!pi_interrupt(&connector));
+        connector.step(1);
+        assert_eq!(connector.read_u32(0x00200000).unwrap(), 0x10111213);
+        assert_eq!(connector.read_u32(0x00200004).unwrap(), 0x14151617);
+        assert!(pi_interrupt(&connector));
+        assert_eq!(connector.read_u32(PI_STATUS).unwrap() & (PI_STATUS_DMA_BUSY | PI_STATUS_INTERRUPT), PI_STATUS_INTERRUPT);
+        assert_eq!(connector.read_u32(PI_DRAM_ADDR).unwrap(), 0x00200008);
+        assert_eq!(connector.read_u32(PI_CART_ADDR).unwrap(), 0x10001018);
+
+        //Clearing the interrupt and resetting the controller
+        connector.store_u32(PI_STATUS, 0x3).unwrap();
+        assert!(!pi_interrupt(&connector));
+        assert_eq!(connector.read_u32(PI_STATUS).unwrap(), 0);
+    }
+
+    #[test]
+    fn domain_timing_registers_are_masked()
+    {
+        let mut connector = test_connector();
+        connector.store_u32(PI_BSD_DOM2_RLS, 0xFFFFFFFF).unwrap();
+        assert_eq!(connector.read_u32(PI_BSD_DOM2_RLS).unwrap(), 0xFF);
+        assert!(connector.read_u32(0x04600034).is_err());
+    }
+}
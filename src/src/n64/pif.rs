@@ -0,0 +1,327 @@
+const PIF_BOOT_ROM_START: usize = 0x00000000;
+const PIF_BOOT_ROM_END: usize = 0x000007BF;
+pub const PIF_BOOT_ROM_SIZE: usize = 0x7C0;
+//Some dumps also contain the 64 bytes of PIF RAM after the boot code
+const PIF_BOOT_ROM_DUMP_MAX_SIZE: usize = 0x800;
+
+const PIF_RAM_START: usize = 0x00000000;
+const PIF_RAM_END: usize = 0x0000003F;
+pub const PIF_RAM_SIZE: usize = 0x40;
+pub const PIF_CONTROL_BYTE: usize = 0x3F;
+const PIF_CONTROL_WORD: usize = 0x3C;
+const PIF_SEED_LOCATION: usize = 0x24;
+
+//Control byte flags
+pub const PIF_CONTROL_JOYBUS: u8 = 0x01;
+pub const PIF_CONTROL_CHALLENGE: u8 = 0x02;
+pub const PIF_CONTROL_TERMINATE_BOOT: u8 = 0x08;
+pub const PIF_CONTROL_LOCK_ROM: u8 = 0x10;
+pub const PIF_CONTROL_ACQUIRE_CHECKSUM: u8 = 0x20;
+pub const PIF_CONTROL_RUN_CHECKSUM: u8 = 0x40;
+pub const PIF_CONTROL_ACKNOWLEDGE: u8 = 0x80;
+
+//Joybus command block markers
+const JOYBUS_SKIP_CHANNEL: u8 = 0x00;
+const JOYBUS_END_OF_COMMANDS: u8 = 0xFE;
+const JOYBUS_PADDING: u8 = 0xFF;
+const JOYBUS_LENGTH_MASK: u8 = 0x3F;
+
+// Referenced: X-Scale's CIC-NUS-6105 challenge algorithm
+const CHALLENGE_START: usize = 0x30;
+const CHALLENGE_LENGTH: usize = 15;
+const CHALLENGE_LUT_0: [u8; 16] = [0x4, 0x7, 0xA, 0x7, 0xE, 0x5, 0xE, 0x1, 0xC, 0xF, 0x8, 0xF, 0x6, 0x3, 0x6, 0x9];
+const CHALLENGE_LUT_1: [u8; 16] = [0x4, 0x1, 0xA, 0x7, 0xE, 0x5, 0xE, 0x1, 0xC, 0x9, 0x8, 0x5, 0x6, 0x3, 0xC, 0x9];
+
+use n64::exceptions::Exception;
+use n64::rom::RomError;
+use n64::cic::CIC;
+use n64::joybus::{JoybusDevice, JoybusError, JOYBUS_CHANNELS, JOYBUS_CARTRIDGE_CHANNEL};
+use n64::cartridge_joybus::CartridgeJoybus;
+use n64::save_file::SaveError;
+use binary_helpers::*;
+
+pub struct PIF
+{
+    pub boot_rom: Vec<u8>,
+    pub ram: Vec<u8>,
+    pub rom_locked: bool,
+    pub boot_terminated: bool,
+    pub channels: Vec<Option<Box<dyn JoybusDevice>>>,
+    pub cartridge: CartridgeJoybus,
+}
+
+impl PIF
+{
+    pub fn new() -> PIF
+    {
+        return PIF
+        {
+            boot_rom: Vec::new(),
+            ram: vec![0; PIF_RAM_SIZE],
+            rom_locked: false,
+            boot_terminated: false,
+            //The last channel is the cartridge port
+            channels: (0..JOYBUS_CARTRIDGE_CHANNEL).map(|_| None).collect(),
+            cartridge: CartridgeJoybus::new(),
+        }
+    }
+
+    pub fn load_boot_rom(&mut self, boot_rom: Vec<u8>) -> Result<(), RomError>
+    {
+        if boot_rom.len() < PIF_BOOT_ROM_SIZE || boot_rom.len() > PIF_BOOT_ROM_DUMP_MAX_SIZE
+        {
+            return Err(RomError::INVALID_PIF_ROM(boot_rom.len()));
+        }
+        self.boot_rom = boot_rom[0..PIF_BOOT_ROM_SIZE].to_vec();
+        Ok(())
+    }
+
+    pub fn set_cic_seed(&mut self, cic: CIC)
+    {
+        //The PIF leaves the CIC seed in RAM for IPL2/IPL3 to pick up
+        let seed = cic.seed();
+        self.ram[PIF_SEED_LOCATION..(PIF_SEED_LOCATION + 4)].copy_from_slice(&[0x00, 0x00, seed, seed]);
+    }
+
+    pub fn connect_device(&mut self, channel: usize, device: Box<dyn JoybusDevice>)
+    {
+        self.channels[channel] = Some(device);
+    }
+
+    pub fn disconnect_device(&mut self, channel: usize)
+    {
+        self.channels[channel] = None;
+    }
+
+    pub fn set_frame(&mut self, frame: u64)
+    {
+        for device in self.channels.iter_mut()
+        {
+            if let Some(ref mut device) = *device
+            {
+                device.set_frame(frame);
+            }
+        }
+    }
+
+    pub fn flush_saves(&mut self) -> Result<(), SaveError>
+    {
+        for device in self.channels.iter_mut()
+        {
+            if let Some(ref mut device) = *device
+            {
+                device.flush()?;
+            }
+        }
+        self.cartridge.flush()
+    }
+
+    pub fn read_boot_rom_u32(&self, address: usize) -> Result<u32, Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+        //HLE boot never maps the boot rom
+        if self.boot_rom.is_empty()
+        {
+            return Err(Exception::UNIMPLEMENTED_ADDRESS);
+        }
+        //Boot rom reads as zero once locked out
+        if self.rom_locked
+        {
+            return Ok(0);
+        }
+
+        match address
+        {
+            PIF_BOOT_ROM_START...PIF_BOOT_ROM_END => Ok(u8_slice_to_u32(self.boot_rom[(address - PIF_BOOT_ROM_START)..((address - PIF_BOOT_ROM_START) + 4)].to_vec())),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    pub fn read_u32_from_address(&self, address: usize) -> Result<u32, Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match address
+        {
+            PIF_RAM_START...PIF_RAM_END => Ok(u8_slice_to_u32(self.ram[(address - PIF_RAM_START)..((address - PIF_RAM_START) + 4)].to_vec())),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    pub fn load_u32_to_address(&mut self, address: usize, value: u32) -> Result<(), Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match address
+        {
+            PIF_RAM_START...PIF_RAM_END =>
+            {
+                let location = address - PIF_RAM_START;
+                u32_to_u8_vector_by_loc(value, location, &mut self.ram);
+                if location == PIF_CONTROL_WORD
+                {
+                    self.process_control_byte();
+                }
+                Ok(())
+            },
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    //Called once RAM has been written by the CPU or SI DMA
+    pub fn process_control_byte(&mut self)
+    {
+        let control = self.ram[PIF_CONTROL_BYTE];
+        if control & PIF_CONTROL_CHALLENGE != 0
+        {
+            self.process_challenge();
+            return;
+        }
+        if control & PIF_CONTROL_TERMINATE_BOOT != 0
+        {
+            self.boot_terminated = true;
+            self.ram[PIF_CONTROL_BYTE] &= !PIF_CONTROL_TERMINATE_BOOT;
+        }
+        if control & PIF_CONTROL_LOCK_ROM != 0
+        {
+            self.rom_locked = true;
+            self.ram[PIF_CONTROL_BYTE] &= !PIF_CONTROL_LOCK_ROM;
+        }
+        if control & PIF_CONTROL_ACQUIRE_CHECKSUM != 0
+        {
+            self.ram[PIF_CONTROL_BYTE] = (self.ram[PIF_CONTROL_BYTE] & !PIF_CONTROL_ACQUIRE_CHECKSUM) | PIF_CONTROL_ACKNOWLEDGE;
+        }
+        if control & PIF_CONTROL_RUN_CHECKSUM != 0
+        {
+            //Checksum always matches as the CIC is not emulated at that level
+            self.ram[PIF_CONTROL_BYTE] &= !PIF_CONTROL_RUN_CHECKSUM;
+        }
+    }
+
+    fn process_challenge(&mut self)
+    {
+        let mut challenge: Vec<u8> = Vec::with_capacity(CHALLENGE_LENGTH * 2);
+        for byte in self.ram[CHALLENGE_START..(CHALLENGE_START + CHALLENGE_LENGTH)].iter()
+        {
+            challenge.push(byte >> 4);
+            challenge.push(byte & 0xF);
+        }
+        let response = compute_challenge_response(&challenge);
+
+        self.ram[CHALLENGE_START - 2] = 0;
+        self.ram[CHALLENGE_START - 1] = 0;
+        for index in 0..CHALLENGE_LENGTH
+        {
+            self.ram[CHALLENGE_START + index] = (response[index * 2] << 4) | response[(index * 2) + 1];
+        }
+        self.ram[PIF_CONTROL_BYTE] = 0;
+    }
+
+    //Called before RAM is read back by SI DMA
+    pub fn process_joybus_commands(&mut self)
+    {
+        if self.ram[PIF_CONTROL_BYTE] & PIF_CONTROL_JOYBUS == 0
+        {
+            return;
+        }
+
+        // Referenced: https://n64brew.dev/wiki/PIF-NUS#Joybus_commands
+        let mut channel: usize = 0;
+        let mut index: usize = 0;
+        while index < PIF_CONTROL_BYTE && channel < JOYBUS_CHANNELS
+        {
+            match self.ram[index]
+            {
+                JOYBUS_SKIP_CHANNEL =>
+                {
+                    channel += 1;
+                    index += 1;
+                },
+                JOYBUS_END_OF_COMMANDS => break,
+                JOYBUS_PADDING => index += 1,
+                tx_byte =>
+                {
+                    //Receive length marks the end of the block when it is a terminator
+                    if index + 1 >= PIF_CONTROL_BYTE || self.ram[index + 1] == JOYBUS_END_OF_COMMANDS
+                    {
+                        break;
+                    }
+                    let tx_length = (tx_byte & JOYBUS_LENGTH_MASK) as usize;
+                    let rx_length = (self.ram[index + 1] & JOYBUS_LENGTH_MASK) as usize;
+                    let send_start = index + 2;
+                    let receive_start = send_start + tx_length;
+                    let block_end = receive_start + rx_length;
+                    if block_end > PIF_CONTROL_BYTE
+                    {
+                        break;
+                    }
+
+                    let result = self.process_joybus_block(channel, send_start, receive_start, block_end);
+                    if let Err(error) = result
+                    {
+                        self.ram[index + 1] |= error.flag();
+                    }
+                    channel += 1;
+                    index = block_end;
+                },
+            }
+        }
+        self.ram[PIF_CONTROL_BYTE] &= !PIF_CONTROL_JOYBUS;
+    }
+
+    fn process_joybus_block(&mut self, channel: usize, send_start: usize, receive_start: usize, block_end: usize) -> Result<(), JoybusError>
+    {
+        if send_start == receive_start
+        {
+            return Err(JoybusError::NO_RESPONSE);
+        }
+        let (send, receive) = self.ram[send_start..block_end].split_at_mut(receive_start - send_start);
+        if channel == JOYBUS_CARTRIDGE_CHANNEL
+        {
+            return self.cartridge.process_command(send, receive);
+        }
+        match self.channels[channel]
+        {
+            Some(ref mut device) => device.process_command(send, receive),
+            None => Err(JoybusError::NO_RESPONSE),
+        }
+    }
+}
+
+pub fn compute_challenge_response(challenge: &Vec<u8>) -> Vec<u8>
+{
+    let mut response: Vec<u8> = Vec::with_capacity(challenge.len());
+    let mut key: u8 = 0xB;
+    let mut alternate_lut = false;
+    for nibble in challenge.iter()
+    {
+        let value = key.wrapping_add(nibble.wrapping_mul(5)) & 0xF;
+        response.push(value);
+        key = if alternate_lut {CHALLENGE_LUT_1[value as usize]} else {CHALLENGE_LUT_0[value as usize]};
+        let sign = (value >> 3) & 0x1;
+        let magnitude = (if sign == 1 {!value} else {value}) & 0x7;
+        let mut modifier = if magnitude % 3 == 1 {sign} else {1 - sign};
+        if alternate_lut && (value == 0x1 || value == 0x9)
+        {
+            modifier = 1;
+        }
+        if alternate_lut && (value == 0xB || value == 0xE)
+        {
+            modifier = 0;
+        }
+        alternate_lut = modifier == 1;
+    }
+    response
+}
@@ -0,0 +1,222 @@
+#[cfg(test)]
+mod pif_tests
+{
+    use n64::pif::*;
+    use n64::n64::N64;
+    use n64::rom::{Rom, RomError};
+    use n64::cpu::COP0RegisterName;
+    use n64::exceptions::Exception;
+    use n64::joybus::*;
+    use n64::cic::CIC;
+    use n64::mips_iface::MI_INTR_PI;
+
+    struct TestDevice
+    {
+        commands: Vec<Vec<u8>>,
+    }
+
+    impl JoybusDevice for TestDevice
+    {
+        fn process_command(&mut self, send: &[u8], receive: &mut [u8]) -> Result<(), JoybusError>
+        {
+            self.commands.push(send.to_vec());
+            match send[0]
+            {
+                JOYBUS_COMMAND_STATUS =>
+                {
+                    check_command_size(send, receive, 1, 3)?;
+                    receive.copy_from_slice(&[0x05, 0x00, 0x01]);
+                    Ok(())
+                },
+                _ => Err(JoybusError::NO_RESPONSE),
+            }
+        }
+    }
+
+    fn test_device() -> Box<TestDevice>
+    {
+        Box::new(TestDevice { commands: Vec::new() })
+    }
+
+    fn test_boot_rom() -> Vec<u8>
+    {
+        let mut boot_rom: Vec<u8> = vec![0; PIF_BOOT_ROM_SIZE];
+        boot_rom[0..4].copy_from_slice(&[0x40, 0x80, 0x68, 0x00]);
+        boot_rom[0x7BC..0x7C0].copy_from_slice(&[0x12, 0x34, 0x56, 0x78]);
+        boot_rom
+    }
+
+    #[test]
+    fn boot_rom_size_is_validated()
+    {
+        let mut pif = PIF::new();
+        assert_eq!(pif.load_boot_rom(vec![0; 0x100]).err(), Some(RomError::INVALID_PIF_ROM(0x100)));
+        assert_eq!(pif.load_boot_rom(vec![0; 0x1000]).err(), Some(RomError::INVALID_PIF_ROM(0x1000)));
+        assert!(pif.load_boot_rom(vec![0; 0x800]).is_ok());
+        assert_eq!(pif.boot_rom.len(), PIF_BOOT_ROM_SIZE);
+    }
+
+    #[test]
+    fn boot_rom_is_unmapped_without_image()
+    {
+        let pif = PIF::new();
+        assert_eq!(pif.read_boot_rom_u32(0x0).err(), Some(Exception::UNIMPLEMENTED_ADDRESS));
+    }
+
+    #[test]
+    fn low_level_boot_starts_at_reset_vector()
+    {
+        let mut n64 = N64::from_rom(Rom::test());
+        n64.run_pif_boot_rom(test_boot_rom()).unwrap();
+
+        assert_eq!(n64.cpu.program_counter.get_value(), 0xBFC00000);
+        assert_eq!(n64.cpu.cop0_registers.register[COP0RegisterName::Status as usize].get_value(), 0x00400004);
+        assert_eq!(n64.cpu.cop0_registers.register[COP0RegisterName::Random as usize].get_value(), 0x1F);
+        assert_eq!(n64.connector.read_u32(0xBFC00000).unwrap(), 0x40806800);
+        assert_eq!(n64.connector.read_u32(0xBFC007BC).unwrap(), 0x12345678);
+
+        //Reset vector is fetched through the boot rom mapping
+        let opcode = n64.cpu.retrieve_opcode(&n64.connector);
+        assert_eq!(opcode.opcode, 0x40806800);
+        assert_eq!(n64.cpu.program_counter.get_value(), 0xBFC00004);
+    }
+
+    #[test]
+    fn low_level_boot_reads_the_cart_and_runs_pi_dma()
+    {
+        let mut rom_data = vec![0; 0x2000];
+        rom_data[0..4].copy_from_slice(&[0x80, 0x37, 0x12, 0x40]);
+        rom_data[0x1000..0x1010].copy_from_slice(&[0xDE, 0xAD, 0xBE, 0xEF, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01]);
+        let mut n64 = N64::from_rom(Rom::from_bytes(rom_data).unwrap());
+
+        //Reads the header into the domain 1 timing like the PIF code, then copies the first 16 bytes after it like IPL3
+        let program: [u32; 20] = [
+            0x3C08B000, //lui t0, 0xB000
+            0x8D090000, //lw t1, 0(t0)
+            0x3C0AA460, //lui t2, 0xA460
+            0xAD490014, //sw t1, PI_BSD_DOM1_LAT(t2)
+            0x3C0B0010, //lui t3, 0x0010
+            0xAD4B0000, //sw t3, PI_DRAM_ADDR(t2)
+            0x3C0C1000, //lui t4, 0x1000
+            0x358C1000, //ori t4, t4, 0x1000
+            0xAD4C0004, //sw t4, PI_CART_ADDR(t2)
+            0x340D000F, //ori t5, zero, 0xF
+            0xAD4D000C, //sw t5, PI_WR_LEN(t2)
+            0x8D4E0010, //lw t6, PI_STATUS(t2)
+            0x31CE0001, //andi t6, t6, DMA_BUSY
+            0x15C0FFFD, //bne t6, zero, -3
+            0x00000000,
+            0x3C0FA010, //lui t7, 0xA010
+            0x8DF80000, //lw t8, 0(t7)
+            0x1000FFFF, //beq zero, zero, -1
+            0x00000000,
+            0x00000000,
+        ];
+        let mut boot_rom = vec![0; PIF_BOOT_ROM_SIZE];
+        for (index, word) in program.iter().enumerate()
+        {
+            boot_rom[(index * 4)..((index * 4) + 4)].copy_from_slice(&[(word >> 24) as u8, (word >> 16) as u8, (word >> 8) as u8, *word as u8]);
+        }
+        n64.run_pif_boot_rom(boot_rom).unwrap();
+
+        let mut executed = 0;
+        while n64.cpu.program_counter.get_value() as u32 != 0xBFC00044
+        {
+            let opcode = n64.cpu.retrieve_opcode(&n64.connector);
+            n64.cpu.execute_opcode(&opcode, &mut n64.connector).unwrap();
+            n64.step_devices(1);
+            executed += 1;
+            assert!(executed < 1000, "Boot code never finished waiting for the PI");
+        }
+        assert_eq!(n64.connector.pi.bsd[0].get_value(), 0x40);
+        assert_eq!(n64.cpu.cpu_registers.register[24].get_value() as u32, 0xDEADBEEF);
+        assert_eq!(n64.connector.read_u32(0xA010000C).unwrap(), 0x00000001);
+        assert_eq!(n64.connector.mips_interface.interrupt.get_value() as u32 & MI_INTR_PI, MI_INTR_PI);
+        //The copy alone takes 16 bytes worth of PI time
+        assert!(executed > 16);
+    }
+
+    #[test]
+    fn pif_ram_is_mapped_through_connector()
+    {
+        let mut n64 = N64::from_rom(Rom::test());
+        n64.connector.store_u32(0xBFC007C0, 0x11223344).unwrap();
+        assert_eq!(n64.connector.read_u32(0xBFC007C0).unwrap(), 0x11223344);
+        assert_eq!(n64.connector.pif.ram[0..4].to_vec(), vec![0x11, 0x22, 0x33, 0x44]);
+        n64.connector.store_u8(0xBFC007C5, 0xAB).unwrap();
+        assert_eq!(n64.connector.read_u32(0xBFC007C4).unwrap(), 0x00AB0000);
+    }
+
+    #[test]
+    fn joybus_commands_are_routed_by_channel()
+    {
+        let mut pif = PIF::new();
+        pif.connect_device(1, test_device());
+        let mut commands: Vec<u8> = vec![
+            0xFF,                       //Padding
+            0x00,                       //Skip channel 0
+            0x01, 0x03, 0x00, 0xFF, 0xFF, 0xFF, //Status on channel 1
+            0x01, 0x03, 0x00, 0xFF, 0xFF, 0xFF, //Channel 2 has nothing connected
+            0x01, 0x02, 0x00, 0xFF, 0xFF,       //Channel 3 short receive buffer
+            0xFE,
+        ];
+        commands.resize(PIF_RAM_SIZE, 0);
+        pif.ram = commands;
+        pif.connect_device(3, test_device());
+        pif.ram[PIF_CONTROL_BYTE] = PIF_CONTROL_JOYBUS;
+        pif.process_joybus_commands();
+
+        assert_eq!(pif.ram[2..8].to_vec(), vec![0x01, 0x03, 0x00, 0x05, 0x00, 0x01]);
+        assert_eq!(pif.ram[9], 0x03 | JOYBUS_NO_RESPONSE);
+        assert_eq!(pif.ram[15], 0x02 | JOYBUS_SIZE_MISMATCH);
+        assert_eq!(pif.ram[PIF_CONTROL_BYTE], 0);
+    }
+
+    #[test]
+    fn joybus_commands_wait_for_control_flag()
+    {
+        let mut pif = PIF::new();
+        pif.connect_device(0, test_device());
+        pif.ram[0..6].copy_from_slice(&[0x01, 0x03, 0x00, 0xFF, 0xFF, 0xFF]);
+        pif.process_joybus_commands();
+        assert_eq!(pif.ram[3..6].to_vec(), vec![0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn control_byte_terminates_boot_and_locks_rom()
+    {
+        let mut pif = PIF::new();
+        pif.load_boot_rom(test_boot_rom()).unwrap();
+        pif.load_u32_to_address(0x3C, PIF_CONTROL_TERMINATE_BOOT as u32).unwrap();
+        assert!(pif.boot_terminated);
+        assert_eq!(pif.ram[PIF_CONTROL_BYTE], 0);
+
+        pif.load_u32_to_address(0x3C, PIF_CONTROL_ACQUIRE_CHECKSUM as u32).unwrap();
+        assert_eq!(pif.ram[PIF_CONTROL_BYTE], PIF_CONTROL_ACKNOWLEDGE);
+
+        assert_eq!(pif.read_boot_rom_u32(0x0).unwrap(), 0x40806800);
+        pif.load_u32_to_address(0x3C, PIF_CONTROL_LOCK_ROM as u32).unwrap();
+        assert_eq!(pif.read_boot_rom_u32(0x0).unwrap(), 0);
+    }
+
+    #[test]
+    fn challenge_response_is_written_back()
+    {
+        assert_eq!(compute_challenge_response(&vec![0; 4]), vec![0xB, 0xF, 0x9, 0xF]);
+
+        let mut pif = PIF::new();
+        pif.ram[0x2E] = 0xAA;
+        pif.ram[PIF_CONTROL_BYTE] = PIF_CONTROL_CHALLENGE;
+        pif.process_control_byte();
+        assert_eq!(pif.ram[0x2E..0x32].to_vec(), vec![0x00, 0x00, 0xBF, 0x9F]);
+        assert_eq!(pif.ram[PIF_CONTROL_BYTE], 0);
+    }
+
+    #[test]
+    fn boot_seed_is_left_in_ram()
+    {
+        let mut pif = PIF::new();
+        pif.set_cic_seed(CIC::CIC_6105);
+        assert_eq!(pif.ram[0x24..0x28].to_vec(), vec![0x00, 0x00, 0x91, 0x91]);
+    }
+}
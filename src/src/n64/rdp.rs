@@ -0,0 +1,1186 @@
+use n64::rdram::RDRAM;
+use n64::rdp_commands::*;
+
+// Referenced: https://n64brew.dev/wiki/Reality_Display_Processor/Pipeline
+// Referenced: https://github.com/ata4/angrylion-rdp-plus
+const TMEM_SIZE: usize = 0x1000;
+const TMEM_HALF_MASK: usize = 0x7FF;
+const TMEM_HIGH_HALF: usize = 0x800;
+const TLUT_ADDRESS: usize = 0x800;
+const TILE_COUNT: usize = 8;
+
+//Anything else, normally 0, renders in one cycle mode
+pub const CYCLE_TWO: u8 = 1;
+pub const CYCLE_COPY: u8 = 2;
+pub const CYCLE_FILL: u8 = 3;
+
+//Image formats and texel sizes shared by tiles and images
+//CI (2) and I (4) texels take the default arms in read_texel, so they are not named
+pub const FORMAT_RGBA: u8 = 0;
+pub const FORMAT_YUV: u8 = 1;
+pub const FORMAT_IA: u8 = 3;
+pub const SIZE_4: u8 = 0;
+pub const SIZE_8: u8 = 1;
+pub const SIZE_16: u8 = 2;
+pub const SIZE_32: u8 = 3;
+
+const Z_MODE_TRANSPARENT: u8 = 2;
+const Z_MODE_DECAL: u8 = 3;
+const COVERAGE_WRAP: u8 = 1;
+const COVERAGE_ZAP: u8 = 2;
+const COVERAGE_SAVE: u8 = 3;
+
+//Four sub-scanlines with two samples each
+const FULL_COVERAGE: u8 = 8;
+const SUBSCANLINES: usize = 4;
+const Z_MAX: u32 = 0x3FFFF;
+const DECAL_TOLERANCE: u32 = 0x40;
+
+fn bits(value: u64, shift: u32, count: u32) -> u64
+{
+    (value >> shift) & ((1 << count) - 1)
+}
+
+fn clamp_channel(value: i32) -> i32
+{
+    value.max(0).min(0xFF)
+}
+
+fn expand_5(value: u16) -> i32
+{
+    let value = (value & 0x1F) as i32;
+    (value << 3) | (value >> 2)
+}
+
+fn bytes_per_texel(size: u8, texels: usize) -> usize
+{
+    match size
+    {
+        SIZE_4 => (texels + 1) / 2,
+        SIZE_8 => texels,
+        SIZE_16 => texels * 2,
+        _ => texels * 4,
+    }
+}
+
+//Hashes the position so the noise input is the same on every run
+fn noise(x: i32, y: i32) -> i32
+{
+    let hash = (x as u32).wrapping_mul(0x9E3779B1) ^ (y as u32).wrapping_mul(0x85EBCA77);
+    ((hash ^ (hash >> 15)) & 0xFF) as i32
+}
+
+//18-bit depth is stored as a 3-bit exponent and 11-bit mantissa
+const Z_SHIFTS: [u32; 8] = [6, 5, 4, 3, 2, 1, 0, 0];
+const Z_BASES: [u32; 8] = [0x00000, 0x20000, 0x30000, 0x38000, 0x3C000, 0x3E000, 0x3F000, 0x3F800];
+
+pub fn compress_z(z: u32) -> u16
+{
+    let z = z.min(Z_MAX);
+    let exponent = (0..8).rev().find(|&exponent| z >= Z_BASES[exponent]).unwrap_or(0);
+    ((exponent << 11) as u32 | (((z - Z_BASES[exponent]) >> Z_SHIFTS[exponent]) & 0x7FF)) as u16
+}
+
+pub fn decompress_z(value: u16) -> u32
+{
+    let exponent = ((value >> 11) & 0x7) as usize;
+    ((value as u32 & 0x7FF) << Z_SHIFTS[exponent]) + Z_BASES[exponent]
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[derive(Default)]
+pub struct Color
+{
+    pub r: i32,
+    pub g: i32,
+    pub b: i32,
+    pub a: i32,
+}
+
+impl Color
+{
+    pub fn new(r: i32, g: i32, b: i32, a: i32) -> Color
+    {
+        return Color
+        {
+            r: r,
+            g: g,
+            b: b,
+            a: a,
+        }
+    }
+
+    fn splat(value: i32) -> Color
+    {
+        Color::new(value, value, value, value)
+    }
+
+    pub fn from_u32(value: u32) -> Color
+    {
+        Color::new((value >> 24) as u8 as i32, (value >> 16) as u8 as i32, (value >> 8) as u8 as i32, value as u8 as i32)
+    }
+
+    pub fn from_rgba16(value: u16) -> Color
+    {
+        Color::new(expand_5(value >> 11), expand_5(value >> 6), expand_5(value >> 1), if value & 1 != 0 {0xFF} else {0})
+    }
+
+    fn from_ia16(value: u16) -> Color
+    {
+        let intensity = (value >> 8) as i32;
+        Color::new(intensity, intensity, intensity, (value & 0xFF) as i32)
+    }
+
+    fn intensity(intensity: i32, alpha: i32) -> Color
+    {
+        Color::new(intensity, intensity, intensity, alpha)
+    }
+
+    pub fn to_rgba16(&self, alpha: bool) -> u16
+    {
+        ((self.r as u16 >> 3) << 11) | ((self.g as u16 >> 3) << 6) | ((self.b as u16 >> 3) << 1) | alpha as u16
+    }
+
+    pub fn to_u32(&self) -> u32
+    {
+        ((self.r as u32) << 24) | ((self.g as u32) << 16) | ((self.b as u32) << 8) | (self.a as u32 & 0xFF)
+    }
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[derive(Default)]
+pub struct OtherModes
+{
+    pub cycle_type: u8,
+    pub perspective: bool,
+    pub detail: bool,
+    pub sharpen: bool,
+    pub lod: bool,
+    pub tlut: bool,
+    pub tlut_ia: bool,
+    pub bilinear: bool,
+    pub key: bool,
+    //Pixel, pixel factor, memory and memory factor selectors for each cycle
+    pub blend: [[u8; 4]; 2],
+    pub force_blend: bool,
+    pub alpha_coverage_select: bool,
+    pub coverage_times_alpha: bool,
+    pub z_mode: u8,
+    pub coverage_destination: u8,
+    pub color_on_coverage: bool,
+    pub image_read: bool,
+    pub z_update: bool,
+    pub z_compare: bool,
+    pub antialias: bool,
+    pub z_source_primitive: bool,
+    pub dither_alpha: bool,
+    pub alpha_compare: bool,
+}
+
+impl OtherModes
+{
+    pub fn from_bits(value: u64) -> OtherModes
+    {
+        let flag = |shift: u32| bits(value, shift, 1) != 0;
+        let mux = |shift: u32| bits(value, shift, 2) as u8;
+        return OtherModes
+        {
+            cycle_type: bits(value, 52, 2) as u8,
+            perspective: flag(51),
+            detail: flag(50),
+            sharpen: flag(49),
+            lod: flag(48),
+            tlut: flag(47),
+            tlut_ia: flag(46),
+            bilinear: flag(45),
+            key: flag(40),
+            blend: [[mux(30), mux(26), mux(22), mux(18)], [mux(28), mux(24), mux(20), mux(16)]],
+            force_blend: flag(14),
+            alpha_coverage_select: flag(13),
+            coverage_times_alpha: flag(12),
+            z_mode: bits(value, 10, 2) as u8,
+            coverage_destination: bits(value, 8, 2) as u8,
+            color_on_coverage: flag(7),
+            image_read: flag(6),
+            z_update: flag(5),
+            z_compare: flag(4),
+            antialias: flag(3),
+            z_source_primitive: flag(2),
+            dither_alpha: flag(1),
+            alpha_compare: flag(0),
+        }
+    }
+}
+
+//Subtract A, subtract B, multiply and add selectors for each cycle
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[derive(Default)]
+pub struct CombineMode
+{
+    pub color: [[u8; 4]; 2],
+    pub alpha: [[u8; 4]; 2],
+}
+
+impl CombineMode
+{
+    pub fn from_bits(value: u64) -> CombineMode
+    {
+        let select = |shift: u32, count: u32| bits(value, shift, count) as u8;
+        return CombineMode
+        {
+            color: [[select(52, 4), select(28, 4), select(47, 5), select(15, 3)], [select(37, 4), select(24, 4), select(32, 5), select(6, 3)]],
+            alpha: [[select(44, 3), select(12, 3), select(41, 3), select(9, 3)], [select(21, 3), select(3, 3), select(18, 3), select(0, 3)]],
+        }
+    }
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Default)]
+pub struct Tile
+{
+    pub descriptor: TileDescriptor,
+    //10.2 bounds from the last size or load
+    pub sl: u16,
+    pub tl: u16,
+    pub sh: u16,
+    pub th: u16,
+}
+
+//Everything the combiner and blender need for one pixel
+#[derive(Copy, Clone)]
+#[derive(Default)]
+struct Fragment
+{
+    shade: Color,
+    texel0: Color,
+    texel1: Color,
+    lod_fraction: i32,
+    z: u32,
+    coverage: u8,
+}
+
+//Software rasterizer that draws parsed commands straight into RDRAM
+pub struct Renderer
+{
+    pub other_modes: OtherModes,
+    pub combine: CombineMode,
+    pub fill_color: u32,
+    pub fog_color: Color,
+    pub blend_color: Color,
+    pub prim_color: Color,
+    pub env_color: Color,
+    pub prim_min_level: u8,
+    pub prim_level_fraction: i32,
+    pub prim_z: u16,
+    pub prim_delta_z: u16,
+    pub key_center: Color,
+    pub key_scale: Color,
+    pub convert: [i32; 6],
+    pub scissor: Scissor,
+    pub texture_image: ImageDescriptor,
+    pub color_image: ImageDescriptor,
+    pub mask_image: u32,
+    pub tiles: [Tile; TILE_COUNT],
+    pub tmem: Vec<u8>,
+}
+
+impl Renderer
+{
+    pub fn new() -> Renderer
+    {
+        return Renderer
+        {
+            other_modes: OtherModes::default(),
+            combine: CombineMode::default(),
+            fill_color: 0,
+            fog_color: Color::default(),
+            blend_color: Color::default(),
+            prim_color: Color::default(),
+            env_color: Color::default(),
+            prim_min_level: 0,
+            prim_level_fraction: 0,
+            prim_z: 0,
+            prim_delta_z: 0,
+            key_center: Color::default(),
+            key_scale: Color::default(),
+            convert: [0; 6],
+            scissor: Scissor::default(),
+            texture_image: ImageDescriptor::default(),
+            color_image: ImageDescriptor::default(),
+            mask_image: 0,
+            tiles: [Tile::default(); TILE_COUNT],
+            tmem: vec![0; TMEM_SIZE],
+        }
+    }
+
+    pub fn execute(&mut self, command: &RDPCommand, rdram: &mut RDRAM)
+    {
+        match *command
+        {
+            RDPCommand::TRIANGLE(ref triangle) => self.draw_triangle(triangle, rdram),
+            RDPCommand::TEXTURE_RECTANGLE(ref rectangle) => self.draw_texture_rectangle(rectangle, rdram),
+            RDPCommand::FILL_RECTANGLE(ref rectangle) => self.fill_rectangle(rectangle, rdram),
+            RDPCommand::SET_KEY_GB(value) =>
+            {
+                self.key_center.g = bits(value, 24, 8) as i32;
+                self.key_scale.g = bits(value, 16, 8) as i32;
+                self.key_center.b = bits(value, 8, 8) as i32;
+                self.key_scale.b = bits(value, 0, 8) as i32;
+            },
+            RDPCommand::SET_KEY_R(value) =>
+            {
+                self.key_center.r = bits(value, 8, 8) as i32;
+                self.key_scale.r = bits(value, 0, 8) as i32;
+            },
+            RDPCommand::SET_CONVERT(value) =>
+            {
+                for index in 0..6
+                {
+                    let shift = 45 - (index as u32 * 9);
+                    self.convert[index] = ((bits(value, shift, 9) << 55) as i64 >> 55) as i32;
+                }
+            },
+            RDPCommand::SET_SCISSOR(scissor) => self.scissor = scissor,
+            RDPCommand::SET_PRIM_DEPTH(z, delta_z) =>
+            {
+                self.prim_z = z;
+                self.prim_delta_z = delta_z;
+            },
+            RDPCommand::SET_OTHER_MODES(value) => self.other_modes = OtherModes::from_bits(value),
+            RDPCommand::LOAD_TLUT(range) => self.load_tlut(&range, rdram),
+            RDPCommand::SET_TILE_SIZE(range) => self.set_tile_size(&range),
+            RDPCommand::LOAD_BLOCK(range) => self.load_block(&range, rdram),
+            RDPCommand::LOAD_TILE(range) => self.load_tile(&range, rdram),
+            RDPCommand::SET_TILE(descriptor) => self.tiles[descriptor.tile as usize].descriptor = descriptor,
+            RDPCommand::SET_FILL_COLOR(color) => self.fill_color = color,
+            RDPCommand::SET_FOG_COLOR(color) => self.fog_color = Color::from_u32(color),
+            RDPCommand::SET_BLEND_COLOR(color) => self.blend_color = Color::from_u32(color),
+            RDPCommand::SET_PRIM_COLOR(min_level, fraction, color) =>
+            {
+                self.prim_min_level = min_level;
+                self.prim_level_fraction = fraction as i32;
+                self.prim_color = Color::from_u32(color);
+            },
+            RDPCommand::SET_ENV_COLOR(color) => self.env_color = Color::from_u32(color),
+            RDPCommand::SET_COMBINE(value) => self.combine = CombineMode::from_bits(value),
+            RDPCommand::SET_TEXTURE_IMAGE(image) => self.texture_image = image,
+            RDPCommand::SET_MASK_IMAGE(address) => self.mask_image = address,
+            RDPCommand::SET_COLOR_IMAGE(image) => self.color_image = image,
+            //Commands run in order so the syncs have nothing to wait for
+            _ => (),
+        }
+    }
+
+    fn read_rdram_u16(rdram: &RDRAM, address: usize) -> u16
+    {
+        ((rdram.read_u8(address).unwrap_or(0) as u16) << 8) | rdram.read_u8(address + 1).unwrap_or(0) as u16
+    }
+
+    fn read_rdram_u32(rdram: &RDRAM, address: usize) -> u32
+    {
+        ((Renderer::read_rdram_u16(rdram, address) as u32) << 16) | Renderer::read_rdram_u16(rdram, address + 2) as u32
+    }
+
+    fn store_rdram_u16(rdram: &mut RDRAM, address: usize, value: u16)
+    {
+        let _ = rdram.store_u8(address, (value >> 8) as u8);
+        let _ = rdram.store_u8(address + 1, value as u8);
+    }
+
+    fn store_rdram_u32(rdram: &mut RDRAM, address: usize, value: u32)
+    {
+        Renderer::store_rdram_u16(rdram, address, (value >> 16) as u16);
+        Renderer::store_rdram_u16(rdram, address + 2, value as u16);
+    }
+
+    fn read_tmem_u16(&self, address: usize) -> u16
+    {
+        let address = address & (TMEM_SIZE - 2);
+        ((self.tmem[address] as u16) << 8) | self.tmem[address + 1] as u16
+    }
+
+    fn store_tmem_u16(&mut self, address: usize, value: u16)
+    {
+        let address = address & (TMEM_SIZE - 2);
+        self.tmem[address] = (value >> 8) as u8;
+        self.tmem[address + 1] = value as u8;
+    }
+
+    fn set_tile_size(&mut self, range: &TileRange)
+    {
+        let tile = &mut self.tiles[range.tile as usize];
+        tile.sl = range.sl;
+        tile.tl = range.tl;
+        tile.sh = range.sh;
+        tile.th = range.th;
+    }
+
+    //Odd rows have their 32-bit words swapped so both TMEM banks are read together
+    fn load_tile(&mut self, range: &TileRange, rdram: &RDRAM)
+    {
+        self.set_tile_size(range);
+        let image = self.texture_image;
+        let descriptor = self.tiles[range.tile as usize].descriptor;
+        let base = descriptor.tmem_address as usize * 8;
+        let line = descriptor.line as usize * 8;
+        let (sl, sh) = ((range.sl >> 2) as usize, (range.sh >> 2) as usize);
+        let (tl, th) = ((range.tl >> 2) as usize, (range.th >> 2) as usize);
+        if sh < sl || th < tl
+        {
+            return;
+        }
+        for t in tl..(th + 1)
+        {
+            let row = t - tl;
+            let swap = if row & 1 != 0 {4} else {0};
+            let first_texel = (t * image.width as usize) + sl;
+            if image.size == SIZE_32
+            {
+                for s in 0..(sh - sl + 1)
+                {
+                    let value = Renderer::read_rdram_u32(rdram, image.address as usize + ((first_texel + s) * 4));
+                    let address = ((base + (row * line) + (s * 2)) ^ swap) & TMEM_HALF_MASK;
+                    self.store_tmem_u16(address, (value >> 16) as u16);
+                    self.store_tmem_u16(address | TMEM_HIGH_HALF, value as u16);
+                }
+            }
+            else
+            {
+                let start = image.address as usize + bytes_per_texel(image.size, first_texel);
+                for offset in 0..bytes_per_texel(image.size, sh - sl + 1)
+                {
+                    self.tmem[((base + (row * line) + offset) ^ swap) & (TMEM_SIZE - 1)] = rdram.read_u8(start + offset).unwrap_or(0);
+                }
+            }
+        }
+    }
+
+    //Loads a run of texels, with dxt counting rows to know which words to swap
+    fn load_block(&mut self, range: &TileRange, rdram: &RDRAM)
+    {
+        {
+            let tile = &mut self.tiles[range.tile as usize];
+            tile.sl = range.sl << 2;
+            tile.tl = range.tl << 2;
+            tile.sh = range.sh << 2;
+            tile.th = range.tl << 2;
+        }
+        if range.sh < range.sl
+        {
+            return;
+        }
+        let image = self.texture_image;
+        let base = self.tiles[range.tile as usize].descriptor.tmem_address as usize * 8;
+        let dxt = range.th as usize;
+        let texels = (range.sh - range.sl) as usize + 1;
+        let first_texel = (range.tl as usize * image.width as usize) + range.sl as usize;
+        if image.size == SIZE_32
+        {
+            for texel in 0..texels
+            {
+                let swap = if (((texel / 2) * dxt) >> 11) & 1 != 0 {4} else {0};
+                let value = Renderer::read_rdram_u32(rdram, image.address as usize + ((first_texel + texel) * 4));
+                let address = ((base + (texel * 2)) ^ swap) & TMEM_HALF_MASK;
+                self.store_tmem_u16(address, (value >> 16) as u16);
+                self.store_tmem_u16(address | TMEM_HIGH_HALF, value as u16);
+            }
+            return;
+        }
+        let start = image.address as usize + bytes_per_texel(image.size, first_texel);
+        let words = (bytes_per_texel(image.size, texels) + 7) / 8;
+        for word in 0..words
+        {
+            let swap = if ((word * dxt) >> 11) & 1 != 0 {4} else {0};
+            for byte in 0..8
+            {
+                let offset = (word * 8) + byte;
+                self.tmem[((base + offset) ^ swap) & (TMEM_SIZE - 1)] = rdram.read_u8(start + offset).unwrap_or(0);
+            }
+        }
+    }
+
+    //Palette entries are repeated once per bank
+    fn load_tlut(&mut self, range: &TileRange, rdram: &RDRAM)
+    {
+        self.set_tile_size(range);
+        let image = self.texture_image;
+        let base = self.tiles[range.tile as usize].descriptor.tmem_address as usize * 8;
+        let (first, last) = ((range.sl >> 2) as usize, (range.sh >> 2) as usize);
+        let row = (range.tl >> 2) as usize * image.width as usize;
+        for entry in first..(last + 1)
+        {
+            let value = Renderer::read_rdram_u16(rdram, image.address as usize + ((row + entry) * 2));
+            for bank in 0..4
+            {
+                self.store_tmem_u16(base + ((entry - first) * 8) + (bank * 2), value);
+            }
+        }
+    }
+
+    fn palette(&self, index: usize) -> Color
+    {
+        let value = self.read_tmem_u16(TLUT_ADDRESS + ((index & 0xFF) * 8));
+        if self.other_modes.tlut_ia
+        {
+            Color::from_ia16(value)
+        }
+        else
+        {
+            Color::from_rgba16(value)
+        }
+    }
+
+    fn convert_yuv(&self, y: i32, u: i32, v: i32) -> Color
+    {
+        let (u, v) = (u - 0x80, v - 0x80);
+        let k = &self.convert;
+        Color::new(
+            clamp_channel(y + ((k[0] * v) >> 7)),
+            clamp_channel(y + (((k[1] * u) + (k[2] * v)) >> 7)),
+            clamp_channel(y + ((k[3] * u) >> 7)),
+            0xFF)
+    }
+
+    fn read_texel(&self, descriptor: &TileDescriptor, s: usize, t: usize) -> Color
+    {
+        let row = (descriptor.tmem_address as usize * 8) + (t * descriptor.line as usize * 8);
+        let swap = if t & 1 != 0 {4} else {0};
+        let tlut = self.other_modes.tlut;
+        match descriptor.size
+        {
+            SIZE_4 =>
+            {
+                let byte = self.tmem[((row + (s / 2)) ^ swap) & (TMEM_SIZE - 1)];
+                let nibble = if s & 1 == 0 {byte >> 4} else {byte & 0xF};
+                if tlut
+                {
+                    return self.palette(((descriptor.palette as usize) << 4) | nibble as usize);
+                }
+                match descriptor.format
+                {
+                    FORMAT_IA =>
+                    {
+                        let intensity = (nibble >> 1) as i32;
+                        Color::intensity((intensity << 5) | (intensity << 2) | (intensity >> 1), if nibble & 1 != 0 {0xFF} else {0})
+                    },
+                    _ => Color::splat(nibble as i32 * 0x11),
+                }
+            },
+            SIZE_8 =>
+            {
+                let byte = self.tmem[((row + s) ^ swap) & (TMEM_SIZE - 1)];
+                if tlut
+                {
+                    return self.palette(byte as usize);
+                }
+                match descriptor.format
+                {
+                    FORMAT_IA => Color::intensity((byte >> 4) as i32 * 0x11, (byte & 0xF) as i32 * 0x11),
+                    _ => Color::splat(byte as i32),
+                }
+            },
+            SIZE_16 =>
+            {
+                let texel = |s: usize| self.read_tmem_u16((row + (s * 2)) ^ swap);
+                let value = texel(s);
+                match descriptor.format
+                {
+                    FORMAT_RGBA if tlut => self.palette((value >> 8) as usize),
+                    FORMAT_RGBA => Color::from_rgba16(value),
+                    //Pairs of texels share U and V, each with its own Y
+                    FORMAT_YUV => self.convert_yuv((value & 0xFF) as i32, (texel(s & !1) >> 8) as i32, (texel(s | 1) >> 8) as i32),
+                    _ => Color::from_ia16(value),
+                }
+            },
+            _ =>
+            {
+                let address = ((row + (s * 2)) ^ swap) & TMEM_HALF_MASK;
+                let (high, low) = (self.read_tmem_u16(address), self.read_tmem_u16(address | TMEM_HIGH_HALF));
+                Color::new((high >> 8) as i32, (high & 0xFF) as i32, (low >> 8) as i32, (low & 0xFF) as i32)
+            },
+        }
+    }
+
+    fn wrap_coordinate(value: i32, clamp: bool, mirror: bool, mask: u8, maximum: i32) -> i32
+    {
+        let mut value = value;
+        if clamp || mask == 0
+        {
+            value = value.max(0).min(maximum.max(0));
+        }
+        if mask > 0
+        {
+            let mask = mask.min(10) as u32;
+            let wrap = (1 << mask) - 1;
+            value = if mirror && (value >> mask) & 1 != 0 {!value & wrap} else {value & wrap};
+        }
+        value
+    }
+
+    fn fetch_texel(&self, tile: &Tile, s: i32, t: i32) -> Color
+    {
+        let descriptor = &tile.descriptor;
+        let s = Renderer::wrap_coordinate(s, descriptor.clamp_s, descriptor.mirror_s, descriptor.mask_s, (tile.sh as i32 - tile.sl as i32) >> 2);
+        let t = Renderer::wrap_coordinate(t, descriptor.clamp_t, descriptor.mirror_t, descriptor.mask_t, (tile.th as i32 - tile.tl as i32) >> 2);
+        self.read_texel(descriptor, s as usize, t as usize)
+    }
+
+    fn shift_coordinate(value: i32, shift: u8) -> i32
+    {
+        if shift < 11
+        {
+            value >> shift
+        }
+        else
+        {
+            value << (16 - shift as u32)
+        }
+    }
+
+    //Coordinates are s10.5, filtering blends the three closest texels
+    pub fn sample_tile(&self, index: u8, s: i32, t: i32, filter: bool) -> Color
+    {
+        let tile = &self.tiles[(index as usize) % TILE_COUNT];
+        let s = Renderer::shift_coordinate(s, tile.descriptor.shift_s) - ((tile.sl as i32) << 3);
+        let t = Renderer::shift_coordinate(t, tile.descriptor.shift_t) - ((tile.tl as i32) << 3);
+        let (si, ti) = (s >> 5, t >> 5);
+        if !filter
+        {
+            return self.fetch_texel(tile, si, ti);
+        }
+        let (fs, ft) = (s & 0x1F, t & 0x1F);
+        let (base, across, down, fs, ft) = if fs + ft < 0x20
+        {
+            (self.fetch_texel(tile, si, ti), self.fetch_texel(tile, si + 1, ti), self.fetch_texel(tile, si, ti + 1), fs, ft)
+        }
+        else
+        {
+            (self.fetch_texel(tile, si + 1, ti + 1), self.fetch_texel(tile, si, ti + 1), self.fetch_texel(tile, si + 1, ti), 0x20 - fs, 0x20 - ft)
+        };
+        let blend = |base: i32, across: i32, down: i32| base + ((((across - base) * fs) + ((down - base) * ft) + 0x10) >> 5);
+        Color::new(blend(base.r, across.r, down.r), blend(base.g, across.g, down.g), blend(base.b, across.b, down.b), blend(base.a, across.a, down.a))
+    }
+
+    //The level of detail picks the tiles and the fraction between them
+    fn sample_textures(&self, tile: u8, max_level: u8, s: i32, t: i32, lod: f64) -> (Color, Color, i32)
+    {
+        let modes = self.other_modes;
+        let (mut first_tile, mut lod_fraction) = (tile, 0);
+        if modes.lod && lod >= 1.0
+        {
+            let level = lod.log2().floor() as u8;
+            if level >= max_level
+            {
+                first_tile = tile.wrapping_add(max_level);
+                lod_fraction = 0xFF;
+            }
+            else
+            {
+                first_tile = tile.wrapping_add(level);
+                lod_fraction = (((lod / (1 << level) as f64) - 1.0) * 256.0).min(255.0) as i32;
+            }
+            lod_fraction = lod_fraction.max(self.prim_min_level as i32);
+        }
+        let texel0 = self.sample_tile(first_tile, s, t, modes.bilinear);
+        let texel1 = if modes.cycle_type == CYCLE_TWO
+        {
+            self.sample_tile(first_tile.wrapping_add(1), s, t, modes.bilinear)
+        }
+        else
+        {
+            texel0
+        };
+        (texel0, texel1, lod_fraction)
+    }
+
+    //(A - B) * C + D for each channel, one equation per cycle
+    fn combine_cycle(&self, cycle: usize, combined: &Color, fragment: &Fragment, noise: i32) -> Color
+    {
+        //The second cycle sees the texels the other way around
+        let (texel0, texel1) = if self.other_modes.cycle_type == CYCLE_TWO && cycle == 1
+        {
+            (fragment.texel1, fragment.texel0)
+        }
+        else
+        {
+            (fragment.texel0, fragment.texel1)
+        };
+        let shade = fragment.shade;
+        let common = |index: u8| -> Option<Color>
+        {
+            match index
+            {
+                0 => Some(*combined),
+                1 => Some(texel0),
+                2 => Some(texel1),
+                3 => Some(self.prim_color),
+                4 => Some(shade),
+                5 => Some(self.env_color),
+                _ => None,
+            }
+        };
+        let [sub_a, sub_b, multiply, add] = self.combine.color[cycle];
+        let a = common(sub_a).unwrap_or_else(|| match sub_a {6 => Color::splat(0xFF), 7 => Color::splat(noise), _ => Color::splat(0)});
+        let b = common(sub_b).unwrap_or_else(|| match sub_b {6 => self.key_center, 7 => Color::splat(self.convert[4]), _ => Color::splat(0)});
+        let c = common(multiply).unwrap_or_else(|| Color::splat(match multiply
+        {
+            6 => return self.key_scale,
+            7 => combined.a,
+            8 => texel0.a,
+            9 => texel1.a,
+            10 => self.prim_color.a,
+            11 => shade.a,
+            12 => self.env_color.a,
+            13 => fragment.lod_fraction,
+            14 => self.prim_level_fraction,
+            15 => self.convert[5],
+            _ => 0,
+        }));
+        let d = common(add).unwrap_or_else(|| Color::splat(if add == 6 {0xFF} else {0}));
+
+        let alpha = |index: u8| -> i32
+        {
+            match index
+            {
+                0 => combined.a,
+                1 => texel0.a,
+                2 => texel1.a,
+                3 => self.prim_color.a,
+                4 => shade.a,
+                5 => self.env_color.a,
+                6 => 0xFF,
+                _ => 0,
+            }
+        };
+        let [alpha_a, alpha_b, alpha_multiply, alpha_add] = self.combine.alpha[cycle];
+        let alpha_c = match alpha_multiply
+        {
+            0 => fragment.lod_fraction,
+            6 => self.prim_level_fraction,
+            index => alpha(index),
+        };
+        let equation = |a: i32, b: i32, c: i32, d: i32| clamp_channel(((((a - b) * c) + 0x80) >> 8) + d);
+        Color::new(
+            equation(a.r, b.r, c.r, d.r),
+            equation(a.g, b.g, c.g, d.g),
+            equation(a.b, b.b, c.b, d.b),
+            equation(alpha(alpha_a), alpha(alpha_b), alpha_c, alpha(alpha_add)))
+    }
+
+    //(P * A + M * B) / (A + B), only normalised when not forced
+    fn blend_cycle(&self, cycle: usize, pixel: &Color, memory: &Color, memory_alpha: i32, shade_alpha: i32, normalise: bool) -> Color
+    {
+        let [p, a, m, b] = self.other_modes.blend[cycle];
+        let select = |index: u8| match index
+        {
+            0 => *pixel,
+            1 => *memory,
+            2 => self.blend_color,
+            _ => self.fog_color,
+        };
+        let (p, m) = (select(p), select(m));
+        let a = match a
+        {
+            0 => pixel.a,
+            1 => self.fog_color.a,
+            2 => shade_alpha,
+            _ => 0,
+        };
+        let b = match b
+        {
+            0 => 0xFF - a,
+            1 => memory_alpha,
+            2 => 0xFF,
+            _ => 0,
+        };
+        let divisor = if normalise {a + b} else {0xFF};
+        if divisor == 0
+        {
+            return p;
+        }
+        let channel = |p: i32, m: i32| clamp_channel(((p * a) + (m * b)) / divisor);
+        Color::new(channel(p.r, m.r), channel(p.g, m.g), channel(p.b, m.b), pixel.a)
+    }
+
+    fn pixel_address(&self, x: i32, y: i32) -> usize
+    {
+        let image = &self.color_image;
+        image.address as usize + bytes_per_texel(image.size, (y as usize * image.width as usize) + x as usize)
+    }
+
+    fn z_address(&self, x: i32, y: i32) -> usize
+    {
+        self.mask_image as usize + (((y as usize * self.color_image.width as usize) + x as usize) * 2)
+    }
+
+    //Returns the color along with its 3-bit coverage
+    fn read_pixel(&self, rdram: &RDRAM, x: i32, y: i32) -> (Color, u8)
+    {
+        let address = self.pixel_address(x, y);
+        match self.color_image.size
+        {
+            SIZE_16 =>
+            {
+                let value = Renderer::read_rdram_u16(rdram, address);
+                (Color::from_rgba16(value), if value & 1 != 0 {7} else {0})
+            },
+            SIZE_32 =>
+            {
+                let value = Renderer::read_rdram_u32(rdram, address);
+                (Color::from_u32(value), (value as u8) >> 5)
+            },
+            _ => (Color::splat(rdram.read_u8(address).unwrap_or(0) as i32), 7),
+        }
+    }
+
+    fn write_pixel(&self, rdram: &mut RDRAM, x: i32, y: i32, color: &Color, coverage: u8)
+    {
+        let address = self.pixel_address(x, y);
+        match self.color_image.size
+        {
+            SIZE_16 => Renderer::store_rdram_u16(rdram, address, color.to_rgba16(coverage & 0x4 != 0)),
+            SIZE_32 =>
+            {
+                let alpha = (coverage << 5) | (coverage << 2) | (coverage >> 1);
+                Renderer::store_rdram_u32(rdram, address, Color {a: alpha as i32, ..*color}.to_u32());
+            },
+            _ => { let _ = rdram.store_u8(address, color.r as u8); },
+        }
+    }
+
+    fn in_color_image(&self, x: i32, y: i32) -> bool
+    {
+        x >= 0 && y >= 0 && x < self.color_image.width as i32
+    }
+
+    //Fill mode writes the raw fill color, two pixels per word at 16 bits
+    fn fill_pixel(&self, rdram: &mut RDRAM, x: i32, y: i32)
+    {
+        let address = self.pixel_address(x, y);
+        match self.color_image.size
+        {
+            SIZE_16 => Renderer::store_rdram_u16(rdram, address, if x & 1 == 0 {(self.fill_color >> 16) as u16} else {self.fill_color as u16}),
+            SIZE_32 => Renderer::store_rdram_u32(rdram, address, self.fill_color),
+            _ => { let _ = rdram.store_u8(address, (self.fill_color >> (24 - ((x & 3) * 8))) as u8); },
+        }
+    }
+
+    fn render_pixel(&self, rdram: &mut RDRAM, x: i32, y: i32, fragment: &Fragment)
+    {
+        if !self.in_color_image(x, y)
+        {
+            return;
+        }
+        let modes = self.other_modes;
+        let two_cycle = modes.cycle_type == CYCLE_TWO;
+        let noise = noise(x, y);
+        //One cycle mode uses the second cycle's combiner and the first cycle's blender
+        let mut combined = if two_cycle
+        {
+            let first = self.combine_cycle(0, &Color::default(), fragment, noise);
+            self.combine_cycle(1, &first, fragment, noise)
+        }
+        else
+        {
+            self.combine_cycle(1, &Color::default(), fragment, noise)
+        };
+
+        let coverage = fragment.coverage as i32;
+        if modes.coverage_times_alpha
+        {
+            combined.a = (combined.a * coverage) >> 3;
+        }
+        if modes.alpha_coverage_select
+        {
+            combined.a = (coverage << 5).min(0xFF);
+        }
+        if modes.alpha_compare
+        {
+            let threshold = if modes.dither_alpha {noise} else {self.blend_color.a};
+            if combined.a < threshold
+            {
+                return;
+            }
+        }
+
+        let z = if modes.z_source_primitive {(self.prim_z as u32 & 0x7FFF) << 3} else {fragment.z};
+        if modes.z_compare
+        {
+            let stored = decompress_z(Renderer::read_rdram_u16(rdram, self.z_address(x, y)) >> 2);
+            let visible = match modes.z_mode
+            {
+                Z_MODE_TRANSPARENT => z < stored,
+                Z_MODE_DECAL => (z as i32 - stored as i32).abs() as u32 <= DECAL_TOLERANCE,
+                _ => z <= stored,
+            };
+            if !visible
+            {
+                return;
+            }
+        }
+
+        let (memory, memory_coverage) = if modes.image_read {self.read_pixel(rdram, x, y)} else {(Color::default(), 7)};
+        let memory_alpha = (((memory_coverage as i32) + 1) << 5).min(0xFF);
+        //Coverage counts are stored minus one so a fully covered pixel is 7
+        let sum = (fragment.coverage - 1) + memory_coverage + 1;
+        let overflow = sum > 7;
+        let new_coverage = match modes.coverage_destination
+        {
+            COVERAGE_WRAP => sum & 0x7,
+            COVERAGE_ZAP => 7,
+            COVERAGE_SAVE => memory_coverage,
+            _ if modes.antialias && !modes.force_blend && modes.image_read => sum.min(7),
+            _ => fragment.coverage - 1,
+        };
+
+        //Partially covered edges blend with memory when antialiasing
+        let blend = modes.force_blend || (modes.antialias && fragment.coverage < FULL_COVERAGE);
+        let color = if two_cycle
+        {
+            let first = self.blend_cycle(0, &combined, &memory, memory_alpha, fragment.shade.a, false);
+            let first = Color {a: combined.a, ..first};
+            if blend {self.blend_cycle(1, &first, &memory, memory_alpha, fragment.shade.a, !modes.force_blend)} else {first}
+        }
+        else if blend
+        {
+            self.blend_cycle(0, &combined, &memory, memory_alpha, fragment.shade.a, !modes.force_blend)
+        }
+        else
+        {
+            let [p, _, _, _] = modes.blend[0];
+            match p
+            {
+                0 => combined,
+                1 => memory,
+                2 => self.blend_color,
+                _ => self.fog_color,
+            }
+        };
+
+        if modes.color_on_coverage && !overflow
+        {
+            self.write_pixel(rdram, x, y, &memory, new_coverage);
+        }
+        else
+        {
+            self.write_pixel(rdram, x, y, &color, new_coverage);
+        }
+        if modes.z_update
+        {
+            Renderer::store_rdram_u16(rdram, self.z_address(x, y), compress_z(z) << 2);
+        }
+    }
+
+    //Fill and copy modes include the far edge, the others stop short of it
+    fn clip_rectangle(&self, rectangle: &Rectangle) -> (i32, i32, i32, i32)
+    {
+        let scissor = &self.scissor.rectangle;
+        let inclusive = self.other_modes.cycle_type >= CYCLE_COPY;
+        let end = |edge: u16| if inclusive {(edge as i32 >> 2) + 1} else {(edge as i32 + 3) >> 2};
+        let left = rectangle.xh.max(scissor.xh) as i32 >> 2;
+        let top = rectangle.yh.max(scissor.yh) as i32 >> 2;
+        let right = end(rectangle.xl).min((scissor.xl as i32 + 3) >> 2);
+        let bottom = end(rectangle.yl).min((scissor.yl as i32 + 3) >> 2);
+        (left, top, right, bottom)
+    }
+
+    fn rectangle_fragment(&self) -> Fragment
+    {
+        Fragment
+        {
+            coverage: FULL_COVERAGE,
+            ..Fragment::default()
+        }
+    }
+
+    fn fill_rectangle(&mut self, rectangle: &Rectangle, rdram: &mut RDRAM)
+    {
+        let (left, top, right, bottom) = self.clip_rectangle(rectangle);
+        let fill = self.other_modes.cycle_type == CYCLE_FILL;
+        let fragment = self.rectangle_fragment();
+        for y in top..bottom
+        {
+            for x in left..right
+            {
+                if fill
+                {
+                    if self.in_color_image(x, y)
+                    {
+                        self.fill_pixel(rdram, x, y);
+                    }
+                }
+                else
+                {
+                    self.render_pixel(rdram, x, y, &fragment);
+                }
+            }
+        }
+    }
+
+    //Copy mode moves texels straight to memory, stepping four texels per clock
+    fn draw_texture_rectangle(&mut self, rectangle: &TextureRectangle, rdram: &mut RDRAM)
+    {
+        let (left, top, right, bottom) = self.clip_rectangle(&rectangle.rectangle);
+        let copy = self.other_modes.cycle_type == CYCLE_COPY;
+        let origin_x = rectangle.rectangle.xh as i32 >> 2;
+        let origin_y = rectangle.rectangle.yh as i32 >> 2;
+        let dsdx = if copy {rectangle.dsdx as i32 >> 2} else {rectangle.dsdx as i32};
+        let dtdy = rectangle.dtdy as i32;
+        let lod = (dsdx.abs().max(dtdy.abs()) as f64) / 1024.0;
+        for y in top..bottom
+        {
+            for x in left..right
+            {
+                let (dx, dy) = (x - origin_x, y - origin_y);
+                let (u, v) = if rectangle.flip {(dy, dx)} else {(dx, dy)};
+                let s = rectangle.s as i32 + ((dsdx * u) >> 5);
+                let t = rectangle.t as i32 + ((dtdy * v) >> 5);
+                if copy
+                {
+                    self.copy_pixel(rdram, x, y, rectangle.tile, s, t);
+                    continue;
+                }
+                let (texel0, texel1, lod_fraction) = self.sample_textures(rectangle.tile, 0, s, t, lod);
+                let fragment = Fragment
+                {
+                    texel0: texel0,
+                    texel1: texel1,
+                    lod_fraction: lod_fraction,
+                    ..self.rectangle_fragment()
+                };
+                self.render_pixel(rdram, x, y, &fragment);
+            }
+        }
+    }
+
+    fn copy_pixel(&self, rdram: &mut RDRAM, x: i32, y: i32, tile: u8, s: i32, t: i32)
+    {
+        if !self.in_color_image(x, y)
+        {
+            return;
+        }
+        let texel = self.sample_tile(tile, s, t, false);
+        if self.other_modes.alpha_compare && texel.a == 0
+        {
+            return;
+        }
+        self.write_pixel(rdram, x, y, &texel, if texel.a != 0 {7} else {0});
+    }
+
+    //Walks the edges a quarter scanline at a time, sampling twice along each
+    fn draw_triangle(&mut self, triangle: &Triangle, rdram: &mut RDRAM)
+    {
+        let edges = &triangle.edges;
+        let fixed = |value: i32| value as f64 / 65536.0;
+        let (yh, ym, yl) = (edges.yh as f64 / 4.0, edges.ym as f64 / 4.0, edges.yl as f64 / 4.0);
+        let top_line = yh.floor();
+        let major = |y: f64| fixed(edges.xh) + (fixed(edges.dxhdy) * (y - top_line));
+        let minor = |y: f64| if y < ym {fixed(edges.xm) + (fixed(edges.dxmdy) * (y - top_line))} else {fixed(edges.xl) + (fixed(edges.dxldy) * (y - ym))};
+
+        let scissor = &self.scissor.rectangle;
+        let (clip_left, clip_top) = (scissor.xh as f64 / 4.0, scissor.yh as f64 / 4.0);
+        let (clip_right, clip_bottom) = (scissor.xl as f64 / 4.0, scissor.yl as f64 / 4.0);
+        let first_row = yh.max(clip_top).floor() as i32;
+        let last_row = yl.min(clip_bottom).ceil() as i32;
+
+        for y in first_row..last_row
+        {
+            let mut spans = [(0.0, 0.0); SUBSCANLINES];
+            let (mut span_left, mut span_right) = (::std::f64::MAX, ::std::f64::MIN);
+            for (index, span) in spans.iter_mut().enumerate()
+            {
+                let sample_y = y as f64 + ((index as f64 + 0.5) / SUBSCANLINES as f64);
+                if sample_y < yh || sample_y >= yl || sample_y < clip_top || sample_y >= clip_bottom
+                {
+                    continue;
+                }
+                let (left, right) = if edges.left_major {(major(sample_y), minor(sample_y))} else {(minor(sample_y), major(sample_y))};
+                *span = (left.max(clip_left), right.min(clip_right));
+                if span.0 < span.1
+                {
+                    span_left = span_left.min(span.0);
+                    span_right = span_right.max(span.1);
+                }
+            }
+            if span_left >= span_right
+            {
+                continue;
+            }
+            for x in (span_left.floor() as i32)..(span_right.ceil() as i32)
+            {
+                let mut coverage = 0;
+                for &(left, right) in spans.iter()
+                {
+                    for &offset in [0.25, 0.75].iter()
+                    {
+                        let sample_x = x as f64 + offset;
+                        if sample_x >= left && sample_x < right
+                        {
+                            coverage += 1;
+                        }
+                    }
+                }
+                if coverage == 0
+                {
+                    continue;
+                }
+                let (center_x, center_y) = (x as f64 + 0.5, y as f64 + 0.5);
+                let fragment = self.triangle_fragment(triangle, center_x, center_y, top_line, &major, coverage);
+                self.render_pixel(rdram, x, y, &fragment);
+            }
+        }
+    }
+
+    //Attributes start at the major edge on the first scanline and step along it by de
+    fn triangle_fragment<F: Fn(f64) -> f64>(&self, triangle: &Triangle, x: f64, y: f64, top_line: f64, major: &F, coverage: u8) -> Fragment
+    {
+        let evaluate = |value: i32, dx: i32, de: i32, x: f64, y: f64| -> f64
+        {
+            (value as f64 + (de as f64 * (y - top_line)) + (dx as f64 * (x - major(y)))) / 65536.0
+        };
+        let mut fragment = Fragment
+        {
+            coverage: coverage,
+            ..Fragment::default()
+        };
+        if let Some(ref shade) = triangle.shade
+        {
+            let channel = |index: usize| clamp_channel(evaluate(shade.value[index], shade.dx[index], shade.de[index], x, y) as i32);
+            fragment.shade = Color::new(channel(0), channel(1), channel(2), channel(3));
+        }
+        if let Some(ref texture) = triangle.texture
+        {
+            let perspective = self.other_modes.perspective;
+            let coordinates = |x: f64, y: f64| -> (f64, f64)
+            {
+                let s = evaluate(texture.value[0], texture.dx[0], texture.de[0], x, y);
+                let t = evaluate(texture.value[1], texture.dx[1], texture.de[1], x, y);
+                let w = evaluate(texture.value[2], texture.dx[2], texture.de[2], x, y);
+                //W was normalised to 0x7FFF at the nearest vertex
+                if perspective && w > 0.0 {(s * 32767.0 / w, t * 32767.0 / w)} else {(s, t)}
+            };
+            let (s, t) = coordinates(x, y);
+            let (right_s, right_t) = coordinates(x + 1.0, y);
+            let (below_s, below_t) = coordinates(x, y + 1.0);
+            let lod = (right_s - s).abs().max((right_t - t).abs()).max((below_s - s).abs()).max((below_t - t).abs()) / 32.0;
+            let (texel0, texel1, lod_fraction) = self.sample_textures(triangle.edges.tile, triangle.edges.level, s.floor() as i32, t.floor() as i32, lod);
+            fragment.texel0 = texel0;
+            fragment.texel1 = texel1;
+            fragment.lod_fraction = lod_fraction;
+        }
+        if let Some(ref z) = triangle.z
+        {
+            //Depth is s15.16 with the integer part in 0 to 0x7FFF, kept to 3 fractional bits
+            let depth = evaluate(z.z, z.dx, z.de, x, y);
+            fragment.z = (depth * 8.0).max(0.0).min(Z_MAX as f64) as u32;
+        }
+        fragment
+    }
+}
@@ -0,0 +1,443 @@
+// Referenced: https://n64brew.dev/wiki/Reality_Display_Processor/Commands
+const COMMAND_NOOP: u8 = 0x00;
+const COMMAND_TRIANGLE_START: u8 = 0x08;
+const COMMAND_TRIANGLE_END: u8 = 0x0F;
+const COMMAND_TEXTURE_RECTANGLE: u8 = 0x24;
+const COMMAND_TEXTURE_RECTANGLE_FLIP: u8 = 0x25;
+const COMMAND_SYNC_LOAD: u8 = 0x26;
+const COMMAND_SYNC_PIPE: u8 = 0x27;
+const COMMAND_SYNC_TILE: u8 = 0x28;
+const COMMAND_SYNC_FULL: u8 = 0x29;
+const COMMAND_SET_KEY_GB: u8 = 0x2A;
+const COMMAND_SET_KEY_R: u8 = 0x2B;
+const COMMAND_SET_CONVERT: u8 = 0x2C;
+const COMMAND_SET_SCISSOR: u8 = 0x2D;
+const COMMAND_SET_PRIM_DEPTH: u8 = 0x2E;
+const COMMAND_SET_OTHER_MODES: u8 = 0x2F;
+const COMMAND_LOAD_TLUT: u8 = 0x30;
+const COMMAND_SET_TILE_SIZE: u8 = 0x32;
+const COMMAND_LOAD_BLOCK: u8 = 0x33;
+const COMMAND_LOAD_TILE: u8 = 0x34;
+const COMMAND_SET_TILE: u8 = 0x35;
+const COMMAND_FILL_RECTANGLE: u8 = 0x36;
+const COMMAND_SET_FILL_COLOR: u8 = 0x37;
+const COMMAND_SET_FOG_COLOR: u8 = 0x38;
+const COMMAND_SET_BLEND_COLOR: u8 = 0x39;
+const COMMAND_SET_PRIM_COLOR: u8 = 0x3A;
+const COMMAND_SET_ENV_COLOR: u8 = 0x3B;
+const COMMAND_SET_COMBINE: u8 = 0x3C;
+const COMMAND_SET_TEXTURE_IMAGE: u8 = 0x3D;
+const COMMAND_SET_MASK_IMAGE: u8 = 0x3E;
+const COMMAND_SET_COLOR_IMAGE: u8 = 0x3F;
+
+//Triangle command bits selecting the optional coefficient blocks
+const TRIANGLE_ZBUFFER: u8 = 0x01;
+const TRIANGLE_TEXTURE: u8 = 0x02;
+const TRIANGLE_SHADE: u8 = 0x04;
+const EDGE_WORDS: usize = 4;
+const ATTRIBUTE_WORDS: usize = 8;
+const Z_WORDS: usize = 2;
+
+fn field(word: u64, shift: u32, bits: u32) -> u64
+{
+    (word >> shift) & ((1 << bits) - 1)
+}
+
+fn signed_field(word: u64, shift: u32, bits: u32) -> i32
+{
+    ((field(word, shift, bits) << (64 - bits)) as i64 >> (64 - bits)) as i32
+}
+
+//Edges are s11.2 in y and s15.16 in x
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+pub struct TriangleEdges
+{
+    pub left_major: bool,
+    pub level: u8,
+    pub tile: u8,
+    pub yl: i32,
+    pub ym: i32,
+    pub yh: i32,
+    pub xl: i32,
+    pub dxldy: i32,
+    pub xh: i32,
+    pub dxhdy: i32,
+    pub xm: i32,
+    pub dxmdy: i32,
+}
+
+//s15.16 start value and slopes for up to four attributes, RGBA for shade and S, T, W for texture
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+pub struct AttributeCoefficients
+{
+    pub value: [i32; 4],
+    pub dx: [i32; 4],
+    pub de: [i32; 4],
+    pub dy: [i32; 4],
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+pub struct ZCoefficients
+{
+    pub z: i32,
+    pub dx: i32,
+    pub de: i32,
+    pub dy: i32,
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+pub struct Triangle
+{
+    pub edges: TriangleEdges,
+    pub shade: Option<AttributeCoefficients>,
+    pub texture: Option<AttributeCoefficients>,
+    pub z: Option<ZCoefficients>,
+}
+
+//Coordinates are u10.2
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[derive(Default)]
+pub struct Rectangle
+{
+    pub xl: u16,
+    pub yl: u16,
+    pub xh: u16,
+    pub yh: u16,
+}
+
+//S and T are s10.5, their slopes s5.10
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+pub struct TextureRectangle
+{
+    pub rectangle: Rectangle,
+    pub tile: u8,
+    pub flip: bool,
+    pub s: i16,
+    pub t: i16,
+    pub dsdx: i16,
+    pub dtdy: i16,
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[derive(Default)]
+pub struct Scissor
+{
+    pub rectangle: Rectangle,
+    pub field: bool,
+    pub odd: bool,
+}
+
+//Shared by LOAD_TLUT, SET_TILE_SIZE, LOAD_BLOCK and LOAD_TILE, coordinates are u10.2
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+pub struct TileRange
+{
+    pub tile: u8,
+    pub sl: u16,
+    pub tl: u16,
+    pub sh: u16,
+    pub th: u16,
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[derive(Default)]
+pub struct TileDescriptor
+{
+    pub tile: u8,
+    pub format: u8,
+    pub size: u8,
+    //Line width and address are in 64 bit TMEM words
+    pub line: u16,
+    pub tmem_address: u16,
+    pub palette: u8,
+    pub clamp_t: bool,
+    pub mirror_t: bool,
+    pub mask_t: u8,
+    pub shift_t: u8,
+    pub clamp_s: bool,
+    pub mirror_s: bool,
+    pub mask_s: u8,
+    pub shift_s: u8,
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[derive(Default)]
+pub struct ImageDescriptor
+{
+    pub format: u8,
+    pub size: u8,
+    pub width: u16,
+    pub address: u32,
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum RDPCommand
+{
+    NOOP,
+    TRIANGLE(Triangle),
+    TEXTURE_RECTANGLE(TextureRectangle),
+    SYNC_LOAD,
+    SYNC_PIPE,
+    SYNC_TILE,
+    SYNC_FULL,
+    SET_KEY_GB(u64),
+    SET_KEY_R(u64),
+    SET_CONVERT(u64),
+    SET_SCISSOR(Scissor),
+    //Primitive z and delta z
+    SET_PRIM_DEPTH(u16, u16),
+    SET_OTHER_MODES(u64),
+    LOAD_TLUT(TileRange),
+    SET_TILE_SIZE(TileRange),
+    LOAD_BLOCK(TileRange),
+    LOAD_TILE(TileRange),
+    SET_TILE(TileDescriptor),
+    FILL_RECTANGLE(Rectangle),
+    SET_FILL_COLOR(u32),
+    SET_FOG_COLOR(u32),
+    SET_BLEND_COLOR(u32),
+    //Minimum level, level fraction and color
+    SET_PRIM_COLOR(u8, u8, u32),
+    SET_ENV_COLOR(u32),
+    SET_COMBINE(u64),
+    SET_TEXTURE_IMAGE(ImageDescriptor),
+    SET_MASK_IMAGE(u32),
+    SET_COLOR_IMAGE(ImageDescriptor),
+    INVALID(u64),
+}
+
+pub fn command_id(word: u64) -> u8
+{
+    field(word, 56, 6) as u8
+}
+
+//Number of 64 bit words the command starting with this word occupies
+pub fn command_length(word: u64) -> usize
+{
+    match command_id(word)
+    {
+        id @ COMMAND_TRIANGLE_START...COMMAND_TRIANGLE_END =>
+        {
+            EDGE_WORDS
+                + if id & TRIANGLE_SHADE != 0 {ATTRIBUTE_WORDS} else {0}
+                + if id & TRIANGLE_TEXTURE != 0 {ATTRIBUTE_WORDS} else {0}
+                + if id & TRIANGLE_ZBUFFER != 0 {Z_WORDS} else {0}
+        },
+        COMMAND_TEXTURE_RECTANGLE | COMMAND_TEXTURE_RECTANGLE_FLIP => 2,
+        _ => 1,
+    }
+}
+
+//Parses the command at the start of the words, None until all of its words are present
+pub fn parse_command(words: &[u64]) -> Option<(RDPCommand, usize)>
+{
+    let word = *words.first()?;
+    let length = command_length(word);
+    if words.len() < length
+    {
+        return None;
+    }
+    let command = match command_id(word)
+    {
+        COMMAND_NOOP => RDPCommand::NOOP,
+        COMMAND_TRIANGLE_START...COMMAND_TRIANGLE_END => RDPCommand::TRIANGLE(parse_triangle(&words[0..length])),
+        COMMAND_TEXTURE_RECTANGLE | COMMAND_TEXTURE_RECTANGLE_FLIP => RDPCommand::TEXTURE_RECTANGLE(TextureRectangle
+        {
+            rectangle: parse_rectangle(word),
+            tile: field(word, 24, 3) as u8,
+            flip: command_id(word) == COMMAND_TEXTURE_RECTANGLE_FLIP,
+            s: field(words[1], 48, 16) as i16,
+            t: field(words[1], 32, 16) as i16,
+            dsdx: field(words[1], 16, 16) as i16,
+            dtdy: field(words[1], 0, 16) as i16,
+        }),
+        COMMAND_SYNC_LOAD => RDPCommand::SYNC_LOAD,
+        COMMAND_SYNC_PIPE => RDPCommand::SYNC_PIPE,
+        COMMAND_SYNC_TILE => RDPCommand::SYNC_TILE,
+        COMMAND_SYNC_FULL => RDPCommand::SYNC_FULL,
+        COMMAND_SET_KEY_GB => RDPCommand::SET_KEY_GB(field(word, 0, 56)),
+        COMMAND_SET_KEY_R => RDPCommand::SET_KEY_R(field(word, 0, 56)),
+        COMMAND_SET_CONVERT => RDPCommand::SET_CONVERT(field(word, 0, 56)),
+        //Scissor swaps which corner comes first compared to the rectangles
+        COMMAND_SET_SCISSOR => RDPCommand::SET_SCISSOR(Scissor
+        {
+            rectangle: Rectangle
+            {
+                xh: field(word, 44, 12) as u16,
+                yh: field(word, 32, 12) as u16,
+                xl: field(word, 12, 12) as u16,
+                yl: field(word, 0, 12) as u16,
+            },
+            field: field(word, 25, 1) != 0,
+            odd: field(word, 24, 1) != 0,
+        }),
+        COMMAND_SET_PRIM_DEPTH => RDPCommand::SET_PRIM_DEPTH(field(word, 16, 16) as u16, field(word, 0, 16) as u16),
+        COMMAND_SET_OTHER_MODES => RDPCommand::SET_OTHER_MODES(field(word, 0, 56)),
+        COMMAND_LOAD_TLUT => RDPCommand::LOAD_TLUT(parse_tile_range(word)),
+        COMMAND_SET_TILE_SIZE => RDPCommand::SET_TILE_SIZE(parse_tile_range(word)),
+        COMMAND_LOAD_BLOCK => RDPCommand::LOAD_BLOCK(parse_tile_range(word)),
+        COMMAND_LOAD_TILE => RDPCommand::LOAD_TILE(parse_tile_range(word)),
+        COMMAND_SET_TILE => RDPCommand::SET_TILE(TileDescriptor
+        {
+            tile: field(word, 24, 3) as u8,
+            format: field(word, 53, 3) as u8,
+            size: field(word, 51, 2) as u8,
+            line: field(word, 41, 9) as u16,
+            tmem_address: field(word, 32, 9) as u16,
+            palette: field(word, 20, 4) as u8,
+            clamp_t: field(word, 19, 1) != 0,
+            mirror_t: field(word, 18, 1) != 0,
+            mask_t: field(word, 14, 4) as u8,
+            shift_t: field(word, 10, 4) as u8,
+            clamp_s: field(word, 9, 1) != 0,
+            mirror_s: field(word, 8, 1) != 0,
+            mask_s: field(word, 4, 4) as u8,
+            shift_s: field(word, 0, 4) as u8,
+        }),
+        COMMAND_FILL_RECTANGLE => RDPCommand::FILL_RECTANGLE(parse_rectangle(word)),
+        COMMAND_SET_FILL_COLOR => RDPCommand::SET_FILL_COLOR(word as u32),
+        COMMAND_SET_FOG_COLOR => RDPCommand::SET_FOG_COLOR(word as u32),
+        COMMAND_SET_BLEND_COLOR => RDPCommand::SET_BLEND_COLOR(word as u32),
+        COMMAND_SET_PRIM_COLOR => RDPCommand::SET_PRIM_COLOR(field(word, 40, 5) as u8, field(word, 32, 8) as u8, word as u32),
+        COMMAND_SET_ENV_COLOR => RDPCommand::SET_ENV_COLOR(word as u32),
+        COMMAND_SET_COMBINE => RDPCommand::SET_COMBINE(field(word, 0, 56)),
+        COMMAND_SET_TEXTURE_IMAGE => RDPCommand::SET_TEXTURE_IMAGE(parse_image(word)),
+        COMMAND_SET_MASK_IMAGE => RDPCommand::SET_MASK_IMAGE(field(word, 0, 26) as u32),
+        COMMAND_SET_COLOR_IMAGE => RDPCommand::SET_COLOR_IMAGE(parse_image(word)),
+        _ => RDPCommand::INVALID(word),
+    };
+    Some((command, length))
+}
+
+fn parse_rectangle(word: u64) -> Rectangle
+{
+    Rectangle
+    {
+        xl: field(word, 44, 12) as u16,
+        yl: field(word, 32, 12) as u16,
+        xh: field(word, 12, 12) as u16,
+        yh: field(word, 0, 12) as u16,
+    }
+}
+
+fn parse_tile_range(word: u64) -> TileRange
+{
+    TileRange
+    {
+        tile: field(word, 24, 3) as u8,
+        sl: field(word, 44, 12) as u16,
+        tl: field(word, 32, 12) as u16,
+        sh: field(word, 12, 12) as u16,
+        th: field(word, 0, 12) as u16,
+    }
+}
+
+fn parse_image(word: u64) -> ImageDescriptor
+{
+    ImageDescriptor
+    {
+        format: field(word, 53, 3) as u8,
+        size: field(word, 51, 2) as u8,
+        width: field(word, 32, 10) as u16 + 1,
+        address: field(word, 0, 26) as u32,
+    }
+}
+
+fn parse_triangle(words: &[u64]) -> Triangle
+{
+    let id = command_id(words[0]);
+    let edges = TriangleEdges
+    {
+        left_major: field(words[0], 55, 1) != 0,
+        level: field(words[0], 51, 3) as u8,
+        tile: field(words[0], 48, 3) as u8,
+        yl: signed_field(words[0], 32, 14),
+        ym: signed_field(words[0], 16, 14),
+        yh: signed_field(words[0], 0, 14),
+        xl: (words[1] >> 32) as i32,
+        dxldy: words[1] as i32,
+        xh: (words[2] >> 32) as i32,
+        dxhdy: words[2] as i32,
+        xm: (words[3] >> 32) as i32,
+        dxmdy: words[3] as i32,
+    };
+    let mut next = EDGE_WORDS;
+    let mut attributes = |present: bool|
+    {
+        if !present
+        {
+            return None;
+        }
+        let block = &words[next..(next + ATTRIBUTE_WORDS)];
+        next += ATTRIBUTE_WORDS;
+        Some(parse_attributes(block))
+    };
+    let shade = attributes(id & TRIANGLE_SHADE != 0);
+    let texture = attributes(id & TRIANGLE_TEXTURE != 0);
+    let z = if id & TRIANGLE_ZBUFFER != 0
+    {
+        let block = &words[(words.len() - Z_WORDS)..];
+        Some(ZCoefficients
+        {
+            z: (block[0] >> 32) as i32,
+            dx: block[0] as i32,
+            de: (block[1] >> 32) as i32,
+            dy: block[1] as i32,
+        })
+    }
+    else
+    {
+        None
+    };
+    Triangle
+    {
+        edges: edges,
+        shade: shade,
+        texture: texture,
+        z: z,
+    }
+}
+
+//Integer halves come in one word and fractions in another
+fn parse_attributes(words: &[u64]) -> AttributeCoefficients
+{
+    let combine = |integer: u64, fraction: u64| -> [i32; 4]
+    {
+        let mut values = [0; 4];
+        for index in 0..4
+        {
+            let shift = 48 - (index as u32 * 16);
+            values[index] = ((field(integer, shift, 16) << 16) | field(fraction, shift, 16)) as u32 as i32;
+        }
+        values
+    };
+    AttributeCoefficients
+    {
+        value: combine(words[0], words[2]),
+        dx: combine(words[1], words[3]),
+        de: combine(words[4], words[6]),
+        dy: combine(words[5], words[7]),
+    }
+}
@@ -0,0 +1,227 @@
+#[cfg(test)]
+mod rdp_tests
+{
+    use n64::rdp::*;
+    use n64::rdp_commands::*;
+    use n64::hle::{read_rdram_u16, read_rdram_u32, store_rdram_u8, store_rdram_u16};
+    use n64::hle_graphics::{encode_triangle, Vertex};
+    use n64::connector::Connector;
+
+    const CYCLE_ONE: u8 = 0;
+    const FORMAT_CI: u8 = 2;
+    const FORMAT_I: u8 = 4;
+
+    //Shade only and texel only combiners for both cycles
+    const COMBINE_SHADE: u64 = 0xFFFFFFFFFE793C;
+    const COMBINE_TEXEL0: CombineMode = CombineMode {color: [[15, 15, 31, 1]; 2], alpha: [[7, 7, 7, 1]; 2]};
+    const COMBINE_PRIM: CombineMode = CombineMode {color: [[15, 15, 31, 3]; 2], alpha: [[7, 7, 7, 3]; 2]};
+
+    fn image(format: u8, size: u8, width: u16, address: u32) -> ImageDescriptor
+    {
+        ImageDescriptor {format: format, size: size, width: width, address: address}
+    }
+
+    fn rectangle(left: u16, top: u16, right: u16, bottom: u16) -> Rectangle
+    {
+        Rectangle {xl: right << 2, yl: bottom << 2, xh: left << 2, yh: top << 2}
+    }
+
+    fn setup(connector: &mut Connector, color_image: ImageDescriptor, other_modes: u64)
+    {
+        let commands = [
+            RDPCommand::SET_COLOR_IMAGE(color_image),
+            RDPCommand::SET_SCISSOR(Scissor {rectangle: rectangle(0, 0, 16, 16), field: false, odd: false}),
+            RDPCommand::SET_OTHER_MODES(other_modes),
+        ];
+        run(connector, &commands);
+    }
+
+    fn run(connector: &mut Connector, commands: &[RDPCommand])
+    {
+        for command in commands.iter()
+        {
+            connector.rdp.execute(command, &mut connector.rdram);
+        }
+    }
+
+    #[test]
+    fn fill_rectangles_are_drawn_from_the_command_buffer()
+    {
+        let mut connector = Connector::test();
+        connector.submit_rdp_commands(&[
+            0x3F10_0007_0000_1000,
+            0x2D00_0000_0002_0020,
+            0x2F30_0000_0000_0000,
+            0x3700_0000_F801_07C1,
+            0x3600_C004_0000_0000,
+            0xE900_0000_0000_0000,
+        ]);
+        connector.step(1);
+        assert!(connector.dp.commands.is_empty());
+
+        for y in 0..3
+        {
+            let row: Vec<u16> = (0..5).map(|x| read_rdram_u16(&connector, 0x1000 + (y * 16) + (x * 2))).collect();
+            if y < 2
+            {
+                assert_eq!(row, vec![0xF801, 0x07C1, 0xF801, 0x07C1, 0]);
+            }
+            else
+            {
+                assert_eq!(row, vec![0; 5]);
+            }
+        }
+    }
+
+    #[test]
+    fn shaded_triangles_are_rasterized_with_coverage()
+    {
+        let mut connector = Connector::test();
+        setup(&mut connector, image(FORMAT_RGBA, SIZE_32, 16, 0x2000), 0);
+        run(&mut connector, &[RDPCommand::SET_COMBINE(COMBINE_SHADE)]);
+        assert_eq!(connector.rdp.combine, CombineMode {color: [[15, 15, 31, 4]; 2], alpha: [[7, 7, 7, 4]; 2]});
+
+        let vertex = |x: f32, y: f32| Vertex {screen: [x, y, 0.0], color: [255.0, 0.0, 0.0, 255.0], ..Vertex::default()};
+        let words = encode_triangle(0x0C, 0, 0, &[vertex(0.0, 0.0), vertex(8.0, 0.0), vertex(0.0, 8.0)], false);
+        let (triangle, _) = parse_command(&words).unwrap();
+        run(&mut connector, &[triangle]);
+
+        let pixel = |x: u32, y: u32| read_rdram_u32(&connector, 0x2000 + (((y * 16) + x) * 4));
+        assert_eq!(pixel(0, 0), 0xFF0000FF);
+        assert_eq!(pixel(2, 2), 0xFF0000FF);
+        assert_eq!(pixel(6, 0), 0xFF0000FF);
+        assert_eq!((pixel(6, 6), pixel(8, 0), pixel(0, 8)), (0, 0, 0));
+        //Edge pixels keep how much of them was covered
+        assert_eq!(pixel(3, 4) >> 8, 0xFF0000);
+        assert!(pixel(3, 4) & 0xFF < 0xFF);
+    }
+
+    #[test]
+    fn copy_mode_moves_loaded_tiles_to_the_framebuffer()
+    {
+        let mut connector = Connector::test();
+        let texels = [[0xF801, 0x07C1, 0x003F, 0xFFFF], [0x8421, 0x4211, 0x2109, 0x1085]];
+        for (t, row) in texels.iter().enumerate()
+        {
+            for (s, texel) in row.iter().enumerate()
+            {
+                store_rdram_u16(&mut connector, 0x3000 + (t as u32 * 8) + (s as u32 * 2), *texel);
+            }
+        }
+        setup(&mut connector, image(FORMAT_RGBA, SIZE_16, 8, 0x4000), (CYCLE_COPY as u64) << 52);
+        run(&mut connector, &[
+            RDPCommand::SET_TEXTURE_IMAGE(image(FORMAT_RGBA, SIZE_16, 4, 0x3000)),
+            RDPCommand::SET_TILE(TileDescriptor {format: FORMAT_RGBA, size: SIZE_16, line: 1, ..TileDescriptor::default()}),
+            RDPCommand::LOAD_TILE(TileRange {tile: 0, sl: 0, tl: 0, sh: 3 << 2, th: 1 << 2}),
+            RDPCommand::TEXTURE_RECTANGLE(TextureRectangle {rectangle: rectangle(0, 0, 3, 1), tile: 0, flip: false, s: 0, t: 0, dsdx: 0x1000, dtdy: 0x400}),
+        ]);
+
+        //The second row sits in TMEM with its words swapped
+        assert_eq!(&connector.rdp.tmem[8..10], &[0x21, 0x09]);
+        assert_eq!(&connector.rdp.tmem[12..14], &[0x84, 0x21]);
+        for (t, row) in texels.iter().enumerate()
+        {
+            let drawn: Vec<u16> = (0..4).map(|x| read_rdram_u16(&connector, 0x4000 + (t as u32 * 16) + (x * 2))).collect();
+            assert_eq!(&drawn[..], &row[..]);
+        }
+    }
+
+    #[test]
+    fn color_indexed_textures_are_looked_up_in_the_tlut()
+    {
+        let mut connector = Connector::test();
+        store_rdram_u16(&mut connector, 0x5002, 0xF801);
+        store_rdram_u16(&mut connector, 0x5004, 0x07C1);
+        store_rdram_u8(&mut connector, 0x5100, 0x12);
+        store_rdram_u8(&mut connector, 0x5101, 0x21);
+        //Texture lookups through the palette in one cycle mode
+        setup(&mut connector, image(FORMAT_RGBA, SIZE_32, 8, 0x6000), (1 << 47) | ((CYCLE_ONE as u64) << 52));
+        run(&mut connector, &[
+            RDPCommand::SET_TEXTURE_IMAGE(image(FORMAT_RGBA, SIZE_16, 16, 0x5000)),
+            RDPCommand::SET_TILE(TileDescriptor {tile: 7, format: FORMAT_RGBA, size: SIZE_4, tmem_address: 0x100, ..TileDescriptor::default()}),
+            RDPCommand::LOAD_TLUT(TileRange {tile: 7, sl: 0, tl: 0, sh: 15 << 2, th: 0}),
+            RDPCommand::SET_TEXTURE_IMAGE(image(FORMAT_CI, SIZE_8, 2, 0x5100)),
+            RDPCommand::SET_TILE(TileDescriptor {tile: 1, format: FORMAT_CI, size: SIZE_8, ..TileDescriptor::default()}),
+            RDPCommand::LOAD_BLOCK(TileRange {tile: 1, sl: 0, tl: 0, sh: 1, th: 0}),
+            RDPCommand::SET_TILE(TileDescriptor {tile: 0, format: FORMAT_CI, size: SIZE_4, line: 1, ..TileDescriptor::default()}),
+            RDPCommand::SET_TILE_SIZE(TileRange {tile: 0, sl: 0, tl: 0, sh: 3 << 2, th: 0}),
+        ]);
+        connector.rdp.combine = COMBINE_TEXEL0;
+        run(&mut connector, &[
+            RDPCommand::TEXTURE_RECTANGLE(TextureRectangle {rectangle: rectangle(0, 0, 4, 1), tile: 0, flip: false, s: 0, t: 0, dsdx: 0x400, dtdy: 0x400}),
+        ]);
+
+        //Each palette entry is repeated across the four banks
+        assert_eq!(&connector.rdp.tmem[0x808..0x810], &[0xF8, 0x01, 0xF8, 0x01, 0xF8, 0x01, 0xF8, 0x01]);
+        let drawn: Vec<u32> = (0..5).map(|x| read_rdram_u32(&connector, 0x6000 + (x * 4))).collect();
+        assert_eq!(drawn, vec![0xFF0000FF, 0x00FF00FF, 0x00FF00FF, 0xFF0000FF, 0]);
+    }
+
+    #[test]
+    fn depth_is_tested_and_colors_are_blended_with_memory()
+    {
+        let mut connector = Connector::test();
+        //Clearing the depth buffer is a fill into it
+        setup(&mut connector, image(FORMAT_RGBA, SIZE_16, 4, 0x7000), (CYCLE_FILL as u64) << 52);
+        run(&mut connector, &[
+            RDPCommand::SET_FILL_COLOR(0xFFFCFFFC),
+            RDPCommand::FILL_RECTANGLE(rectangle(0, 0, 3, 0)),
+            RDPCommand::SET_COLOR_IMAGE(image(FORMAT_RGBA, SIZE_16, 4, 0x6000)),
+            RDPCommand::SET_MASK_IMAGE(0x7000),
+        ]);
+        connector.rdp.combine = COMBINE_PRIM;
+
+        //Primitive depth, compared and updated
+        let depth_modes = (1 << 2) | (1 << 4) | (1 << 5);
+        let draw = |connector: &mut Connector, color: u32, z: u16, other_modes: u64|
+        {
+            run(connector, &[
+                RDPCommand::SET_OTHER_MODES(depth_modes | other_modes),
+                RDPCommand::SET_PRIM_COLOR(0, 0, color),
+                RDPCommand::SET_PRIM_DEPTH(z, 0),
+                RDPCommand::FILL_RECTANGLE(rectangle(0, 0, 4, 1)),
+            ]);
+        };
+        draw(&mut connector, 0xFF0000FF, 0x100, 0);
+        draw(&mut connector, 0x00FF00FF, 0x200, 0);
+        assert_eq!(read_rdram_u16(&connector, 0x6000), 0xF801);
+        assert_eq!(read_rdram_u16(&connector, 0x7000), compress_z(0x100 << 3) << 2);
+
+        //Half transparent blue mixed over the red already in memory
+        draw(&mut connector, 0x0000FF80, 0x80, (1 << 14) | (1 << 6) | (1 << 22));
+        assert_eq!(read_rdram_u16(&connector, 0x6006), 0x7821);
+        assert_eq!(read_rdram_u16(&connector, 0x7006), 0x0040);
+        assert_eq!(decompress_z(compress_z(0x3F812)), 0x3F812);
+        assert_eq!(decompress_z(0x3FFF), 0x3FFFF);
+    }
+
+    #[test]
+    fn texel_formats_are_decoded()
+    {
+        let mut connector = Connector::test();
+        let tile = |format: u8, size: u8| RDPCommand::SET_TILE(TileDescriptor {format: format, size: size, line: 1, ..TileDescriptor::default()});
+        let sample = |connector: &mut Connector, command: RDPCommand, bytes: &[u8], s: i32| -> Color
+        {
+            connector.rdp.tmem[0..bytes.len()].copy_from_slice(bytes);
+            run(connector, &[command, RDPCommand::SET_TILE_SIZE(TileRange {tile: 0, sl: 0, tl: 0, sh: 3 << 2, th: 0})]);
+            connector.rdp.sample_tile(0, s << 5, 0, false)
+        };
+
+        assert_eq!(sample(&mut connector, tile(FORMAT_IA, SIZE_8), &[0xA5], 0), Color::new(0xAA, 0xAA, 0xAA, 0x55));
+        assert_eq!(sample(&mut connector, tile(FORMAT_IA, SIZE_4), &[0x0B], 1), Color::new(0xB6, 0xB6, 0xB6, 0xFF));
+        assert_eq!(sample(&mut connector, tile(FORMAT_I, SIZE_4), &[0x0A], 1), Color::new(0xAA, 0xAA, 0xAA, 0xAA));
+        assert_eq!(sample(&mut connector, tile(FORMAT_IA, SIZE_16), &[0x12, 0x34], 0), Color::new(0x12, 0x12, 0x12, 0x34));
+        connector.rdp.tmem[0x800..0x802].copy_from_slice(&[0x56, 0x78]);
+        assert_eq!(sample(&mut connector, tile(FORMAT_RGBA, SIZE_32), &[0x12, 0x34], 0), Color::new(0x12, 0x34, 0x56, 0x78));
+
+        //The default YUV to RGB conversion keeps a neutral texel grey
+        run(&mut connector, &[RDPCommand::SET_CONVERT((175 << 45) | ((-43i64 as u64 & 0x1FF) << 36) | ((-89i64 as u64 & 0x1FF) << 27) | (222 << 18) | (114 << 9) | 42)]);
+        assert_eq!(connector.rdp.convert, [175, -43, -89, 222, 114, 42]);
+        assert_eq!(sample(&mut connector, tile(FORMAT_YUV, SIZE_16), &[0x80, 0x40, 0x80, 0x90], 1), Color::new(0x90, 0x90, 0x90, 0xFF));
+        assert_eq!(sample(&mut connector, tile(FORMAT_YUV, SIZE_16), &[0x80, 0x40, 0xFF, 0x90], 1).r, 0xFF);
+
+        //Filtering halfway between two texels
+        sample(&mut connector, tile(FORMAT_I, SIZE_8), &[0x00, 0x80], 0);
+        assert_eq!(connector.rdp.sample_tile(0, 0x10, 0, true), Color::new(0x40, 0x40, 0x40, 0x40));
+    }
+}
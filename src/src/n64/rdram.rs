@@ -27,6 +27,22 @@ impl RDRAM
         }
     }
 
+    //Without the Expansion Pak only the first 4MB are fitted
+    pub fn without_expansion_pak() -> RDRAM
+    {
+        return RDRAM
+        {
+            range0: vec![0; 0x200000],
+            range1: vec![0; 0x200000],
+            range2: Vec::new(),
+        }
+    }
+
+    pub fn has_expansion_pak(&self) -> bool
+    {
+        !self.range2.is_empty()
+    }
+
     pub fn read_u32_from_address(&self, address: usize) -> Result<u32, Exception>
     {
         //Only allow alligned addresses (unaligned handled exterior to function)
@@ -39,7 +55,7 @@ impl RDRAM
         {
             RDRAM_RANGE_0_START...RDRAM_RANGE_0_END => Ok(u8_slice_to_u32(self.range0[(address - RDRAM_RANGE_0_START)..((address - RDRAM_RANGE_0_START) + 4)].to_vec())),
             RDRAM_RANGE_1_START...RDRAM_RANGE_1_END => Ok(u8_slice_to_u32(self.range1[(address - RDRAM_RANGE_1_START)..((address - RDRAM_RANGE_1_START) + 4)].to_vec())),
-            RDRAM_RANGE_2_START...RDRAM_RANGE_2_END => Ok(u8_slice_to_u32(self.range2[(address - RDRAM_RANGE_2_START)..((address - RDRAM_RANGE_2_START) + 4)].to_vec())),
+            RDRAM_RANGE_2_START...RDRAM_RANGE_2_END if self.has_expansion_pak() => Ok(u8_slice_to_u32(self.range2[(address - RDRAM_RANGE_2_START)..((address - RDRAM_RANGE_2_START) + 4)].to_vec())),
             _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
         }
     }
@@ -56,8 +72,45 @@ impl RDRAM
         {
             RDRAM_RANGE_0_START...RDRAM_RANGE_0_END => Ok(u32_to_u8_vector_by_loc(value, address - RDRAM_RANGE_0_START, &mut self.range0)),
             RDRAM_RANGE_1_START...RDRAM_RANGE_1_END => Ok(u32_to_u8_vector_by_loc(value, address - RDRAM_RANGE_1_START, &mut self.range1)),
-            RDRAM_RANGE_2_START...RDRAM_RANGE_2_END => Ok(u32_to_u8_vector_by_loc(value, address - RDRAM_RANGE_2_START, &mut self.range2)),
+            RDRAM_RANGE_2_START...RDRAM_RANGE_2_END if self.has_expansion_pak() => Ok(u32_to_u8_vector_by_loc(value, address - RDRAM_RANGE_2_START, &mut self.range2)),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    pub fn read_u8(&self, address: usize) -> Result<u8, Exception>
+    {
+        match address
+        {
+            RDRAM_RANGE_0_START...RDRAM_RANGE_0_END => Ok(self.range0[address - RDRAM_RANGE_0_START]),
+            RDRAM_RANGE_1_START...RDRAM_RANGE_1_END => Ok(self.range1[address - RDRAM_RANGE_1_START]),
+            RDRAM_RANGE_2_START...RDRAM_RANGE_2_END if self.has_expansion_pak() => Ok(self.range2[address - RDRAM_RANGE_2_START]),
             _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
         }
     }
-}
\ No newline at end of file
+
+    pub fn store_u8(&mut self, address: usize, value: u8) -> Result<(), Exception>
+    {
+        match address
+        {
+            RDRAM_RANGE_0_START...RDRAM_RANGE_0_END => Ok(self.range0[address - RDRAM_RANGE_0_START] = value),
+            RDRAM_RANGE_1_START...RDRAM_RANGE_1_END => Ok(self.range1[address - RDRAM_RANGE_1_START] = value),
+            RDRAM_RANGE_2_START...RDRAM_RANGE_2_END if self.has_expansion_pak() => Ok(self.range2[address - RDRAM_RANGE_2_START] = value),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    //Used by DMA engines, which are not bound by word alignment
+    pub fn read_bytes(&self, address: usize, length: usize) -> Result<Vec<u8>, Exception>
+    {
+        (address..(address + length)).map(|location| self.read_u8(location)).collect()
+    }
+
+    pub fn store_bytes(&mut self, address: usize, data: &[u8]) -> Result<(), Exception>
+    {
+        for (offset, value) in data.iter().enumerate()
+        {
+            self.store_u8(address + offset, *value)?;
+        }
+        Ok(())
+    }
+}
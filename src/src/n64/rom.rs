@@ -3,6 +3,33 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::io;
 use std::fs;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use n64::cic::CIC;
+use n64::rom_archive;
+use n64::rom_patch;
+use n64::exceptions::Exception;
+use encoding_rs::SHIFT_JIS;
+
+const HEADER_SIZE: usize = 0x1000;
+//Largest commercial cartridge is 64MB
+pub const MAX_ROM_SIZE: usize = 0x4000000;
+const CRC1_LOCATION: usize = 0x10;
+const CRC2_LOCATION: usize = 0x14;
+
+const CRC_START: usize = 0x1000;
+const CRC_LENGTH: usize = 0x100000;
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum TvType
+{
+    PAL = 0,
+    NTSC = 1,
+    MPAL = 2,
+}
 
 pub struct RomHeader
 {
@@ -17,13 +44,22 @@ pub struct RomHeader
     pub cartridge_id: u16,
     pub country_code: u16,
     pub boot_code: Vec<u32>,
+    pub media_format: MediaFormat,
+    pub game_code: String,
+    pub region: Region,
+    pub revision: u8,
 }
 
 impl RomHeader
 {
-    pub fn new(header_data: Vec<u8>) -> RomHeader
+    pub fn new(header_data: Vec<u8>) -> Result<RomHeader, RomError>
     {
-        return RomHeader
+        if header_data.len() < HEADER_SIZE
+        {
+            return Err(RomError::TOO_SMALL(header_data.len()));
+        }
+
+        return Ok(RomHeader
         {
             pi_reg_initializers: header_data[0x0000..0x0004].to_vec(),
             clock_rate: u8_slice_to_u32(header_data[0x0004..0x0008].to_vec()),
@@ -36,9 +72,30 @@ impl RomHeader
             cartridge_id: u8_slice_to_u16(header_data[0x003C..0x003E].to_vec()),
             country_code: u8_slice_to_u16(header_data[0x003E..0x0040].to_vec()),
             boot_code: u8_vector_to_u32_vector(header_data[0x0040..0x1000].to_vec()),
-        }
+            media_format: MediaFormat::from_u8(header_data[0x003B]),
+            game_code: header_data[0x003B..0x003F].iter().map(|byte| if byte.is_ascii_graphic() {*byte as char} else {'?'}).collect(),
+            region: Region::from_u8(header_data[0x003E]),
+            revision: header_data[0x003F],
+        })
+    }
+
+    pub fn title(&self) -> String
+    {
+        //Japanese titles are stored as Shift-JIS, everything else is plain ASCII
+        let (title, _, _) = SHIFT_JIS.decode(&self.image_name);
+        title.trim_end_matches(|c: char| c == ' ' || c == '\0').to_string()
     }
     
+    pub fn tv_type(&self) -> TvType
+    {
+        self.region.tv_type()
+    }
+
+    pub fn cic(&self) -> CIC
+    {
+        CIC::from_boot_code(&self.boot_code, self.tv_type())
+    }
+
     pub fn debug(&self)
     {
         println!("PI Register Init Values 0x{:x} 0x{:x} 0x{:x} 0x{:x}", self.pi_reg_initializers[0], self.pi_reg_initializers[1], self.pi_reg_initializers[2], self.pi_reg_initializers[3]);
@@ -46,9 +103,11 @@ impl RomHeader
         println!("Program Counter {:x}", self.program_counter);
         println!("Release {:x}", self.release);
         println!("CRC {:x} {:x}", self.crc1, self.crc2);
-        println!("Image Name: {}", String::from_utf8(self.image_name.to_vec()).unwrap());
+        println!("Image Name: {}", self.title());
         println!("Manufacturer ID {:x}", self.manufacturer_id);
         println!("Country Code {:x}", self.country_code);
+        println!("Game Code: {} ({:?}, {:?}, Revision {})", self.game_code, self.media_format, self.region, self.revision);
+        println!("CIC: {}", self.cic());
     }
 }
 
@@ -56,47 +115,367 @@ pub struct Rom
 {
     pub rom_data: Vec<u8>,
     pub rom_header: RomHeader,
+    pub filename: Option<String>,
 }
 
 impl Rom 
 {
-    pub fn new(filename: &str) -> Rom 
+    pub fn new(filename: &str) -> Result<Rom, RomError>
+    {
+        let rom_data = read_rom_from_filename(filename)?;
+        let mut rom = Rom::from_bytes(rom_data)?;
+        rom.filename = Some(filename.to_string());
+        Ok(rom)
+    }
+
+    pub fn from_reader<R: Read>(reader: R) -> Result<Rom, RomError>
+    {
+        let rom_data = read_rom_from_reader(reader)?;
+        Rom::from_bytes(rom_data)
+    }
+
+    pub fn from_bytes(rom_data: Vec<u8>) -> Result<Rom, RomError>
+    {
+        let mut rom_data = rom_archive::extract_rom(rom_data)?;
+        if rom_data.len() > MAX_ROM_SIZE
+        {
+            return Err(RomError::OVERSIZE(rom_data.len()));
+        }
+        if rom_data.len() < HEADER_SIZE
+        {
+            return Err(RomError::TOO_SMALL(rom_data.len()));
+        }
+        convert_to_big_endian(&mut rom_data)?;
+        let rom_header = RomHeader::new(rom_data[0..HEADER_SIZE].to_vec())?;
+        return Ok(Rom
+        {
+            rom_data: rom_data,
+            rom_header: rom_header,
+            filename: None,
+        })
+    }
+
+    pub fn apply_patch(&mut self, patch: &[u8]) -> Result<(), RomError>
+    {
+        let patched = rom_patch::apply_patch(&self.rom_data, patch)?;
+        if patched.len() < HEADER_SIZE
+        {
+            return Err(RomError::TOO_SMALL(patched.len()));
+        }
+        self.rom_header = RomHeader::new(patched[0..HEADER_SIZE].to_vec())?;
+        self.rom_data = patched;
+        self.recompute_crcs();
+        Ok(())
+    }
+
+    pub fn apply_patch_file(&mut self, filename: &str) -> Result<(), RomError>
     {
-        let rom_data = read_rom_from_filename(filename).unwrap();
-        return Rom 
+        let patch = read_rom_from_filename(filename)?;
+        self.apply_patch(&patch)
+    }
+
+    //Saves sit next to the ROM, sharing its name, so game.z64.gz saves to game.eep like game.z64 would
+    pub fn save_path(&self, extension: &str) -> Option<PathBuf>
+    {
+        match self.filename
         {
-            rom_data: rom_data.clone(),
-            rom_header: RomHeader::new(rom_data[0..0x1000].to_vec()),
+            Some(ref filename) if rom_archive::has_archive_extension(filename) => Some(Path::new(filename).with_extension("").with_extension(extension)),
+            Some(ref filename) => Some(Path::new(filename).with_extension(extension)),
+            None => None,
         }
     }
 
+    pub fn recompute_crcs(&mut self)
+    {
+        let (crc1, crc2) = self.compute_crcs();
+        u32_to_u8_vector_by_loc(crc1, CRC1_LOCATION, &mut self.rom_data);
+        u32_to_u8_vector_by_loc(crc2, CRC2_LOCATION, &mut self.rom_data);
+        self.rom_header.crc1 = crc1;
+        self.rom_header.crc2 = crc2;
+    }
+
+    pub fn compute_crcs(&self) -> (u32, u32)
+    {
+        compute_crcs(&self.rom_data, self.rom_header.cic())
+    }
+
+    pub fn crcs_match(&self) -> bool
+    {
+        self.compute_crcs() == (self.rom_header.crc1, self.rom_header.crc2)
+    }
+
+    //Reads past the end of the cart see the low half of the address on the bus
+    pub fn read_u8(&self, address: usize) -> u8
+    {
+        match self.rom_data.get(address)
+        {
+            Some(value) => *value,
+            None if address % 2 == 0 => (address >> 8) as u8,
+            None => (address - 1) as u8,
+        }
+    }
+
+    pub fn read_u32_from_address(&self, address: usize) -> Result<u32, Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+        Ok((0..4).fold(0, |value, offset| (value << 8) | self.read_u8(address + offset) as u32))
+    }
+
     pub fn test() -> Rom
     {
         return Rom
         {
             rom_data: vec![0;0],
-            rom_header: RomHeader::new(vec![0x00; 0x1000]),
+            rom_header: RomHeader::new(vec![0x00; HEADER_SIZE]).unwrap(),
+            filename: None,
         }
     }
 }
 
-pub fn read_rom_from_filename(filename: &str) -> Option<Vec<u8>>
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum MediaFormat
+{
+    CARTRIDGE,
+    DISK,
+    EXPANDABLE_CARTRIDGE,
+    DISK_EXPANSION,
+    ALECK64,
+    UNKNOWN(u8),
+}
+
+impl MediaFormat
 {
-    let mut metadata = fs::metadata(filename).unwrap();
-    let mut buffer: Vec<u8> = vec![0; metadata.len() as usize];
-    let file = File::open(filename);
-    if file.is_ok()
+    pub fn from_u8(value: u8) -> MediaFormat
     {
-        if file.unwrap().read(&mut buffer).is_ok()
+        match value
         {
-            return Some(buffer);
+            b'N' => MediaFormat::CARTRIDGE,
+            b'D' => MediaFormat::DISK,
+            b'C' => MediaFormat::EXPANDABLE_CARTRIDGE,
+            b'E' => MediaFormat::DISK_EXPANSION,
+            b'Z' => MediaFormat::ALECK64,
+            _ => MediaFormat::UNKNOWN(value),
+        }
+    }
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum Region
+{
+    BETA,
+    ASIA,
+    BRAZIL,
+    CHINA,
+    GERMANY,
+    NORTH_AMERICA,
+    FRANCE,
+    GATEWAY_NTSC,
+    NETHERLANDS,
+    ITALY,
+    JAPAN,
+    KOREA,
+    GATEWAY_PAL,
+    CANADA,
+    EUROPE,
+    SPAIN,
+    AUSTRALIA,
+    SCANDINAVIA,
+    OTHER_PAL(u8),
+    UNKNOWN(u8),
+}
+
+impl Region
+{
+    pub fn from_u8(value: u8) -> Region
+    {
+        match value
+        {
+            b'7' => Region::BETA,
+            b'A' => Region::ASIA,
+            b'B' => Region::BRAZIL,
+            b'C' => Region::CHINA,
+            b'D' => Region::GERMANY,
+            b'E' => Region::NORTH_AMERICA,
+            b'F' => Region::FRANCE,
+            b'G' => Region::GATEWAY_NTSC,
+            b'H' => Region::NETHERLANDS,
+            b'I' => Region::ITALY,
+            b'J' => Region::JAPAN,
+            b'K' => Region::KOREA,
+            b'L' => Region::GATEWAY_PAL,
+            b'N' => Region::CANADA,
+            b'P' => Region::EUROPE,
+            b'S' => Region::SPAIN,
+            b'U' => Region::AUSTRALIA,
+            b'W' => Region::SCANDINAVIA,
+            b'X' | b'Y' | b'Z' => Region::OTHER_PAL(value),
+            _ => Region::UNKNOWN(value),
+        }
+    }
+
+    pub fn tv_type(self) -> TvType
+    {
+        match self
+        {
+            Region::GERMANY | Region::FRANCE | Region::NETHERLANDS | Region::ITALY | Region::GATEWAY_PAL |
+            Region::EUROPE | Region::SPAIN | Region::AUSTRALIA | Region::SCANDINAVIA | Region::OTHER_PAL(_) => TvType::PAL,
+            Region::BRAZIL => TvType::MPAL,
+            _ => TvType::NTSC,
+        }
+    }
+}
+
+// Referenced: n64crc.c by Parasyte, covers the first megabyte after the boot code
+pub fn compute_crcs(rom_data: &Vec<u8>, cic: CIC) -> (u32, u32)
+{
+    let read_word = |location: usize| -> u32
+    {
+        let mut word: u32 = 0;
+        for offset in 0..4
+        {
+            word = (word << 8) | (*rom_data.get(location + offset).unwrap_or(&0) as u32);
+        }
+        word
+    };
+
+    let seed = cic.crc_seed();
+    let (mut t1, mut t2, mut t3, mut t4, mut t5, mut t6) = (seed, seed, seed, seed, seed, seed);
+    for location in (CRC_START..(CRC_START + CRC_LENGTH)).step_by(4)
+    {
+        let d = read_word(location);
+        if t6.wrapping_add(d) < t6
+        {
+            t4 = t4.wrapping_add(1);
+        }
+        t6 = t6.wrapping_add(d);
+        t3 ^= d;
+        let r = d.rotate_left(d & 0x1F);
+        t5 = t5.wrapping_add(r);
+        if t2 > d
+        {
+            t2 ^= r;
+        }
+        else
+        {
+            t2 ^= t6 ^ d;
+        }
+
+        if cic.is_x105()
+        {
+            t1 = t1.wrapping_add(read_word(0x0750 + (location & 0xFF)) ^ d);
         }
         else
         {
-            return None;
+            t1 = t1.wrapping_add(t5 ^ d);
         }
     }
-    else {
-        return None;
+
+    match cic
+    {
+        CIC::CIC_6103 | CIC::CIC_7103 => ((t6 ^ t4).wrapping_add(t3), (t5 ^ t2).wrapping_add(t1)),
+        CIC::CIC_6106 | CIC::CIC_7106 => (t6.wrapping_mul(t4).wrapping_add(t3), t5.wrapping_mul(t2).wrapping_add(t1)),
+        _ => (t6 ^ t4 ^ t3, t5 ^ t2 ^ t1),
     }
-}
\ No newline at end of file
+}
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum RomError
+{
+    FILE_NOT_FOUND(String),
+    IO(String),
+    TOO_SMALL(usize),
+    OVERSIZE(usize),
+    UNKNOWN_BYTE_ORDER(u32),
+    ARCHIVE(String),
+    PATCH(String),
+    INVALID_PIF_ROM(usize),
+}
+
+impl fmt::Display for RomError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self
+        {
+            RomError::FILE_NOT_FOUND(filename) => write!(f, "File not found: {}", filename),
+            RomError::IO(s) => write!(f, "ROM read failed: {}", s),
+            RomError::TOO_SMALL(size) => write!(f, "ROM is too small (0x{:X} bytes, need at least 0x{:X})", size, HEADER_SIZE),
+            RomError::OVERSIZE(size) => write!(f, "ROM is too large (0x{:X} bytes, limit is 0x{:X})", size, MAX_ROM_SIZE),
+            RomError::UNKNOWN_BYTE_ORDER(first_word) => write!(f, "Unknown ROM byte order, first word is 0x{:08X}", first_word),
+            RomError::ARCHIVE(s) => write!(f, "ROM archive could not be extracted: {}", s),
+            RomError::PATCH(s) => write!(f, "ROM patch could not be applied: {}", s),
+            RomError::INVALID_PIF_ROM(size) => write!(f, "PIF boot ROM has an unexpected size (0x{:X} bytes)", size),
+        }
+    }
+}
+
+impl From<io::Error> for RomError
+{
+    fn from(error: io::Error) -> RomError
+    {
+        RomError::IO(error.to_string())
+    }
+}
+
+// Referenced: .z64 is big endian, .v64 swaps each half word, .n64 reverses each word
+pub fn convert_to_big_endian(rom_data: &mut Vec<u8>) -> Result<(), RomError>
+{
+    match rom_data[0]
+    {
+        0x80 => (),
+        0x37 => 
+        {
+            for half_word in rom_data.chunks_mut(2)
+            {
+                half_word.reverse();
+            }
+        },
+        0x40 =>
+        {
+            for word in rom_data.chunks_mut(4)
+            {
+                word.reverse();
+            }
+        },
+        _ => return Err(RomError::UNKNOWN_BYTE_ORDER(u8_slice_to_u32(rom_data[0..4].to_vec()))),
+    }
+    Ok(())
+}
+
+pub fn read_rom_from_filename(filename: &str) -> Result<Vec<u8>, RomError>
+{
+    let metadata = match fs::metadata(filename)
+    {
+        Ok(metadata) => metadata,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Err(RomError::FILE_NOT_FOUND(filename.to_string())),
+        Err(e) => return Err(RomError::from(e)),
+    };
+    if metadata.len() > MAX_ROM_SIZE as u64
+    {
+        return Err(RomError::OVERSIZE(metadata.len() as usize));
+    }
+    read_rom_from_reader(File::open(filename)?)
+}
+
+pub fn read_rom_from_reader<R: Read>(reader: R) -> Result<Vec<u8>, RomError>
+{
+    //Read one byte past the limit so oversized streams can be told apart
+    let mut buffer: Vec<u8> = Vec::new();
+    reader.take(MAX_ROM_SIZE as u64 + 1).read_to_end(&mut buffer)?;
+    if buffer.len() > MAX_ROM_SIZE
+    {
+        return Err(RomError::OVERSIZE(buffer.len()));
+    }
+    Ok(buffer)
+}
@@ -0,0 +1,216 @@
+use n64::rom::{RomError, read_rom_from_reader};
+use flate2::Crc;
+use flate2::read::{GzDecoder, DeflateDecoder};
+use std::io::Read;
+
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+const ZIP_LOCAL_HEADER_SIGNATURE: u32 = 0x04034B50;
+const ZIP_CENTRAL_HEADER_SIGNATURE: u32 = 0x02014B50;
+const ZIP_END_OF_DIRECTORY_SIGNATURE: u32 = 0x06054B50;
+const ZIP_END_OF_DIRECTORY_SIZE: usize = 22;
+const ZIP_MAX_COMMENT_SIZE: usize = 0xFFFF;
+const ZIP_METHOD_STORED: u16 = 0;
+const ZIP_METHOD_DEFLATE: u16 = 8;
+const ZIP_FLAG_ENCRYPTED: u16 = 0x0001;
+
+const ROM_EXTENSIONS: [&str; 3] = [".z64", ".n64", ".v64"];
+const ARCHIVE_EXTENSIONS: [&str; 2] = [".gz", ".zip"];
+const ROM_MAGIC: [[u8; 4]; 3] = [[0x80, 0x37, 0x12, 0x40], [0x37, 0x80, 0x40, 0x12], [0x40, 0x12, 0x37, 0x80]];
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum ArchiveFormat
+{
+    RAW,
+    GZIP,
+    ZIP,
+}
+
+pub struct ZipEntry
+{
+    pub filename: String,
+    pub method: u16,
+    pub flags: u16,
+    pub crc32: u32,
+    pub compressed_size: usize,
+    pub uncompressed_size: usize,
+    pub local_header_offset: usize,
+}
+
+pub fn identify_archive(data: &Vec<u8>) -> ArchiveFormat
+{
+    if data.len() >= 2 && data[0..2] == GZIP_MAGIC
+    {
+        ArchiveFormat::GZIP
+    }
+    else if data.len() >= 4 && read_u32_le(data, 0).unwrap() == ZIP_LOCAL_HEADER_SIGNATURE
+    {
+        ArchiveFormat::ZIP
+    }
+    else
+    {
+        ArchiveFormat::RAW
+    }
+}
+
+pub fn extract_rom(data: Vec<u8>) -> Result<Vec<u8>, RomError>
+{
+    match identify_archive(&data)
+    {
+        ArchiveFormat::RAW => Ok(data),
+        ArchiveFormat::GZIP => read_rom_from_reader(GzDecoder::new(&data[..])),
+        ArchiveFormat::ZIP => extract_rom_from_zip(&data),
+    }
+}
+
+pub fn has_rom_magic(data: &[u8]) -> bool
+{
+    data.len() >= 4 && ROM_MAGIC.iter().any(|magic| data[0..4] == *magic)
+}
+
+pub fn has_rom_extension(filename: &str) -> bool
+{
+    let filename = filename.to_lowercase();
+    ROM_EXTENSIONS.iter().any(|extension| filename.ends_with(extension))
+}
+
+pub fn has_archive_extension(filename: &str) -> bool
+{
+    let filename = filename.to_lowercase();
+    ARCHIVE_EXTENSIONS.iter().any(|extension| filename.ends_with(extension))
+}
+
+//Entries that cannot be extracted are skipped, the first failing ROM entry explains an archive without a usable ROM
+fn extract_rom_from_zip(data: &Vec<u8>) -> Result<Vec<u8>, RomError>
+{
+    let mut rom_error: Option<RomError> = None;
+    for entry in read_zip_directory(data)?
+    {
+        if entry.filename.ends_with("/")
+        {
+            continue;
+        }
+        let rom_extension = has_rom_extension(&entry.filename);
+        match extract_zip_entry(data, &entry)
+        {
+            Ok(entry_data) =>
+            {
+                if rom_extension || has_rom_magic(&entry_data)
+                {
+                    return Ok(entry_data);
+                }
+            },
+            Err(e) =>
+            {
+                if rom_extension && rom_error.is_none()
+                {
+                    rom_error = Some(e);
+                }
+            },
+        }
+    }
+    Err(rom_error.unwrap_or_else(|| RomError::ARCHIVE("no N64 ROM found in zip archive".to_string())))
+}
+
+pub fn read_zip_directory(data: &Vec<u8>) -> Result<Vec<ZipEntry>, RomError>
+{
+    //End of central directory sits at the very end, only followed by an optional comment
+    if data.len() < ZIP_END_OF_DIRECTORY_SIZE
+    {
+        return Err(RomError::ARCHIVE("zip archive is truncated".to_string()));
+    }
+    let search_start = data.len().saturating_sub(ZIP_END_OF_DIRECTORY_SIZE + ZIP_MAX_COMMENT_SIZE);
+    let mut end_of_directory: Option<usize> = None;
+    for offset in (search_start..(data.len() - ZIP_END_OF_DIRECTORY_SIZE + 1)).rev()
+    {
+        if read_u32_le(data, offset)? == ZIP_END_OF_DIRECTORY_SIGNATURE
+        {
+            end_of_directory = Some(offset);
+            break;
+        }
+    }
+    let end_of_directory = match end_of_directory
+    {
+        Some(offset) => offset,
+        None => return Err(RomError::ARCHIVE("zip central directory not found".to_string())),
+    };
+
+    let entry_count = read_u16_le(data, end_of_directory + 10)? as usize;
+    let mut offset = read_u32_le(data, end_of_directory + 16)? as usize;
+    let mut entries: Vec<ZipEntry> = Vec::new();
+    for _ in 0..entry_count
+    {
+        if read_u32_le(data, offset)? != ZIP_CENTRAL_HEADER_SIGNATURE
+        {
+            return Err(RomError::ARCHIVE("corrupt zip central directory".to_string()));
+        }
+        let filename_length = read_u16_le(data, offset + 28)? as usize;
+        let extra_length = read_u16_le(data, offset + 30)? as usize;
+        let comment_length = read_u16_le(data, offset + 32)? as usize;
+        let filename_bytes = read_bytes(data, offset + 46, filename_length)?;
+        entries.push(ZipEntry
+        {
+            filename: String::from_utf8_lossy(filename_bytes).to_string(),
+            flags: read_u16_le(data, offset + 8)?,
+            method: read_u16_le(data, offset + 10)?,
+            crc32: read_u32_le(data, offset + 16)?,
+            compressed_size: read_u32_le(data, offset + 20)? as usize,
+            uncompressed_size: read_u32_le(data, offset + 24)? as usize,
+            local_header_offset: read_u32_le(data, offset + 42)? as usize,
+        });
+        offset += 46 + filename_length + extra_length + comment_length;
+    }
+    Ok(entries)
+}
+
+pub fn extract_zip_entry(data: &Vec<u8>, entry: &ZipEntry) -> Result<Vec<u8>, RomError>
+{
+    if entry.flags & ZIP_FLAG_ENCRYPTED != 0
+    {
+        return Err(RomError::ARCHIVE(format!("{} is encrypted", entry.filename)));
+    }
+    let header = entry.local_header_offset;
+    if read_u32_le(data, header)? != ZIP_LOCAL_HEADER_SIGNATURE
+    {
+        return Err(RomError::ARCHIVE(format!("corrupt local header for {}", entry.filename)));
+    }
+    let data_start = header + 30 + read_u16_le(data, header + 26)? as usize + read_u16_le(data, header + 28)? as usize;
+    let compressed = read_bytes(data, data_start, entry.compressed_size)?;
+
+    let extracted = match entry.method
+    {
+        ZIP_METHOD_STORED => read_rom_from_reader(compressed)?,
+        ZIP_METHOD_DEFLATE => read_rom_from_reader(DeflateDecoder::new(compressed))?,
+        method => return Err(RomError::ARCHIVE(format!("{} uses unsupported compression method {}", entry.filename, method))),
+    };
+
+    let mut crc = Crc::new();
+    crc.update(&extracted);
+    if extracted.len() != entry.uncompressed_size || crc.sum() != entry.crc32
+    {
+        return Err(RomError::ARCHIVE(format!("{} failed its CRC check", entry.filename)));
+    }
+    Ok(extracted)
+}
+
+fn read_bytes(data: &Vec<u8>, offset: usize, length: usize) -> Result<&[u8], RomError>
+{
+    match data.get(offset..(offset + length))
+    {
+        Some(bytes) => Ok(bytes),
+        None => Err(RomError::ARCHIVE("zip archive is truncated".to_string())),
+    }
+}
+
+fn read_u16_le(data: &Vec<u8>, offset: usize) -> Result<u16, RomError>
+{
+    let bytes = read_bytes(data, offset, 2)?;
+    Ok((bytes[1] as u16) << 8 | (bytes[0] as u16))
+}
+
+fn read_u32_le(data: &Vec<u8>, offset: usize) -> Result<u32, RomError>
+{
+    let bytes = read_bytes(data, offset, 4)?;
+    Ok((bytes[3] as u32) << 24 | (bytes[2] as u32) << 16 | (bytes[1] as u32) << 8 | (bytes[0] as u32))
+}
@@ -0,0 +1,157 @@
+#[cfg(test)]
+mod rom_archive_tests
+{
+    use n64::rom_archive::*;
+    use n64::rom::{Rom, RomError};
+    use flate2::Crc;
+    use flate2::Compression;
+    use flate2::write::{GzEncoder, DeflateEncoder};
+    use std::io::Write;
+
+    fn test_rom() -> Vec<u8>
+    {
+        let mut rom_data: Vec<u8> = vec![0; 0x2000];
+        rom_data[0..4].copy_from_slice(&[0x80, 0x37, 0x12, 0x40]);
+        rom_data[0x3B..0x3F].copy_from_slice(b"NSME");
+        rom_data
+    }
+
+    fn push_u16(data: &mut Vec<u8>, value: u16)
+    {
+        data.extend_from_slice(&[value as u8, (value >> 8) as u8]);
+    }
+
+    fn push_u32(data: &mut Vec<u8>, value: u32)
+    {
+        data.extend_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+    }
+
+    fn build_zip(files: Vec<(&str, Vec<u8>, bool)>) -> Vec<u8>
+    {
+        let mut archive: Vec<u8> = Vec::new();
+        let mut directory: Vec<u8> = Vec::new();
+        for (filename, contents, deflate) in files.iter()
+        {
+            let mut crc = Crc::new();
+            crc.update(contents);
+            let compressed = if *deflate
+            {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(contents).unwrap();
+                encoder.finish().unwrap()
+            }
+            else
+            {
+                contents.to_vec()
+            };
+            let method: u16 = if *deflate {8} else {0};
+
+            let local_header_offset = archive.len() as u32;
+            push_u32(&mut archive, 0x04034B50);
+            push_u16(&mut archive, 20);
+            push_u16(&mut archive, 0);
+            push_u16(&mut archive, method);
+            push_u32(&mut archive, 0);
+            push_u32(&mut archive, crc.sum());
+            push_u32(&mut archive, compressed.len() as u32);
+            push_u32(&mut archive, contents.len() as u32);
+            push_u16(&mut archive, filename.len() as u16);
+            push_u16(&mut archive, 0);
+            archive.extend_from_slice(filename.as_bytes());
+            archive.extend_from_slice(&compressed);
+
+            push_u32(&mut directory, 0x02014B50);
+            push_u16(&mut directory, 20);
+            push_u16(&mut directory, 20);
+            push_u16(&mut directory, 0);
+            push_u16(&mut directory, method);
+            push_u32(&mut directory, 0);
+            push_u32(&mut directory, crc.sum());
+            push_u32(&mut directory, compressed.len() as u32);
+            push_u32(&mut directory, contents.len() as u32);
+            push_u16(&mut directory, filename.len() as u16);
+            push_u16(&mut directory, 0);
+            push_u16(&mut directory, 0);
+            push_u16(&mut directory, 0);
+            push_u16(&mut directory, 0);
+            push_u32(&mut directory, 0);
+            push_u32(&mut directory, local_header_offset);
+            directory.extend_from_slice(filename.as_bytes());
+        }
+
+        let directory_offset = archive.len() as u32;
+        archive.extend_from_slice(&directory);
+        push_u32(&mut archive, 0x06054B50);
+        push_u16(&mut archive, 0);
+        push_u16(&mut archive, 0);
+        push_u16(&mut archive, files.len() as u16);
+        push_u16(&mut archive, files.len() as u16);
+        push_u32(&mut archive, directory.len() as u32);
+        push_u32(&mut archive, directory_offset);
+        push_u16(&mut archive, 0);
+        archive
+    }
+
+    #[test]
+    fn loads_rom_from_gzip()
+    {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&test_rom()).unwrap();
+        let archive = encoder.finish().unwrap();
+
+        assert_eq!(identify_archive(&archive), ArchiveFormat::GZIP);
+        let rom = Rom::from_bytes(archive).unwrap();
+        assert_eq!(rom.rom_data, test_rom());
+    }
+
+    #[test]
+    fn loads_first_rom_from_zip_by_extension()
+    {
+        let mut other_rom = test_rom();
+        other_rom[0x3B..0x3F].copy_from_slice(b"NZLE");
+        let archive = build_zip(vec![("readme.txt", b"not a rom".to_vec(), false), ("Game.Z64", test_rom(), true), ("other.n64", other_rom, false)]);
+
+        assert_eq!(identify_archive(&archive), ArchiveFormat::ZIP);
+        let rom = Rom::from_bytes(archive).unwrap();
+        assert_eq!(rom.rom_header.game_code, "NSME");
+    }
+
+    #[test]
+    fn loads_rom_from_zip_by_header_magic()
+    {
+        let archive = build_zip(vec![("folder/", vec![], false), ("notes.txt", b"hello".to_vec(), true), ("game.bin", test_rom(), false)]);
+        let rom = Rom::from_bytes(archive).unwrap();
+        assert_eq!(rom.rom_data, test_rom());
+    }
+
+    #[test]
+    fn unreadable_zip_entries_are_skipped()
+    {
+        let mut other_rom = test_rom();
+        other_rom[0x3B..0x3F].copy_from_slice(b"NZLE");
+        let mut archive = build_zip(vec![("broken.z64", other_rom, false), ("game.z64", test_rom(), true)]);
+        archive[0x100] ^= 0xFF;
+        assert_eq!(Rom::from_bytes(archive).unwrap().rom_header.game_code, "NSME");
+
+        //Give the first entry a compression method nothing supports
+        let mut archive = build_zip(vec![("packed.z64", test_rom(), false), ("game.bin", test_rom(), false)]);
+        let end_of_directory = archive.len() - 22;
+        let directory = archive[end_of_directory + 16] as usize | (archive[end_of_directory + 17] as usize) << 8;
+        archive[directory + 10] = 99;
+        assert_eq!(Rom::from_bytes(archive).unwrap().rom_data, test_rom());
+    }
+
+    #[test]
+    fn zip_errors_are_reported()
+    {
+        let archive = build_zip(vec![("notes.txt", b"hello".to_vec(), true)]);
+        assert_eq!(Rom::from_bytes(archive).err(), Some(RomError::ARCHIVE("no N64 ROM found in zip archive".to_string())));
+
+        let mut archive = build_zip(vec![("game.z64", test_rom(), false)]);
+        archive[0x100] ^= 0xFF;
+        assert_eq!(Rom::from_bytes(archive).err(), Some(RomError::ARCHIVE("game.z64 failed its CRC check".to_string())));
+
+        let archive = build_zip(vec![("game.z64", test_rom(), false)]);
+        assert!(Rom::from_bytes(archive[0..0x40].to_vec()).is_err());
+    }
+}
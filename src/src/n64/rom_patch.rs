@@ -0,0 +1,290 @@
+use n64::rom::{RomError, MAX_ROM_SIZE};
+use flate2::Crc;
+
+const IPS_MAGIC: &[u8] = b"PATCH";
+const IPS_EOF: u32 = 0x454F46;
+const BPS_MAGIC: &[u8] = b"BPS1";
+const BPS_FOOTER_SIZE: usize = 12;
+
+const BPS_SOURCE_READ: u64 = 0;
+const BPS_TARGET_READ: u64 = 1;
+const BPS_SOURCE_COPY: u64 = 2;
+const BPS_TARGET_COPY: u64 = 3;
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum PatchFormat
+{
+    IPS,
+    BPS,
+}
+
+pub fn identify_patch(patch: &[u8]) -> Option<PatchFormat>
+{
+    if patch.starts_with(IPS_MAGIC)
+    {
+        Some(PatchFormat::IPS)
+    }
+    else if patch.starts_with(BPS_MAGIC)
+    {
+        Some(PatchFormat::BPS)
+    }
+    else
+    {
+        None
+    }
+}
+
+pub fn apply_patch(rom_data: &Vec<u8>, patch: &[u8]) -> Result<Vec<u8>, RomError>
+{
+    match identify_patch(patch)
+    {
+        Some(PatchFormat::IPS) => apply_ips(rom_data, patch),
+        Some(PatchFormat::BPS) => apply_bps(rom_data, patch),
+        None => Err(RomError::PATCH("unknown patch format".to_string())),
+    }
+}
+
+// Referenced: https://zerosoft.zophar.net/ips.php
+pub fn apply_ips(rom_data: &Vec<u8>, patch: &[u8]) -> Result<Vec<u8>, RomError>
+{
+    let mut patched = rom_data.to_vec();
+    let mut reader = PatchReader::new(patch, IPS_MAGIC.len());
+    loop
+    {
+        let offset = reader.read_u24()?;
+        if offset == IPS_EOF
+        {
+            break;
+        }
+        let offset = offset as usize;
+        let size = reader.read_u16()? as usize;
+        if size == 0
+        {
+            //Run length encoded record
+            let run_length = reader.read_u16()? as usize;
+            let value = reader.read_u8()?;
+            write_ips_record(&mut patched, offset, &vec![value; run_length]);
+        }
+        else
+        {
+            let record = reader.read_bytes(size)?;
+            write_ips_record(&mut patched, offset, record);
+        }
+    }
+
+    //Optional truncation extension
+    if reader.remaining() >= 3
+    {
+        let truncated_size = reader.read_u24()? as usize;
+        patched.truncate(truncated_size);
+    }
+    Ok(patched)
+}
+
+fn write_ips_record(patched: &mut Vec<u8>, offset: usize, record: &[u8])
+{
+    if patched.len() < offset + record.len()
+    {
+        patched.resize(offset + record.len(), 0);
+    }
+    patched[offset..(offset + record.len())].copy_from_slice(record);
+}
+
+// Referenced: byuu's BPS specification
+pub fn apply_bps(rom_data: &Vec<u8>, patch: &[u8]) -> Result<Vec<u8>, RomError>
+{
+    if patch.len() < BPS_MAGIC.len() + BPS_FOOTER_SIZE
+    {
+        return Err(RomError::PATCH("BPS patch is truncated".to_string()));
+    }
+    let footer_start = patch.len() - BPS_FOOTER_SIZE;
+    let mut footer = PatchReader::new(patch, footer_start);
+    let source_crc = footer.read_u32_le()?;
+    let target_crc = footer.read_u32_le()?;
+    let patch_crc = footer.read_u32_le()?;
+
+    if crc32(&patch[0..(patch.len() - 4)]) != patch_crc
+    {
+        return Err(RomError::PATCH("BPS patch failed its CRC check".to_string()));
+    }
+    if crc32(rom_data) != source_crc
+    {
+        return Err(RomError::PATCH("ROM does not match the BPS source CRC".to_string()));
+    }
+
+    let mut reader = PatchReader::new(&patch[0..footer_start], BPS_MAGIC.len());
+    let source_size = reader.read_varint()? as usize;
+    let target_size = reader.read_varint()?;
+    let metadata_size = reader.read_varint()? as usize;
+    reader.read_bytes(metadata_size)?;
+    if source_size != rom_data.len()
+    {
+        return Err(RomError::PATCH("ROM does not match the BPS source size".to_string()));
+    }
+    //The size comes from the patch file, check it before allocating
+    if target_size > MAX_ROM_SIZE as u64
+    {
+        return Err(RomError::PATCH(format!("BPS target size 0x{:X} is larger than any cartridge", target_size)));
+    }
+    let target_size = target_size as usize;
+
+    let mut target: Vec<u8> = Vec::with_capacity(target_size);
+    let mut source_relative_offset: i64 = 0;
+    let mut target_relative_offset: i64 = 0;
+    while reader.remaining() > 0
+    {
+        let data = reader.read_varint()?;
+        let length = ((data >> 2) + 1) as usize;
+        if target.len() + length > target_size
+        {
+            return Err(RomError::PATCH("BPS patch writes past the target size".to_string()));
+        }
+        match data & 0x3
+        {
+            BPS_SOURCE_READ =>
+            {
+                let start = target.len();
+                match rom_data.get(start..(start + length))
+                {
+                    Some(bytes) => target.extend_from_slice(bytes),
+                    None => return Err(RomError::PATCH("BPS source read out of range".to_string())),
+                }
+            },
+            BPS_TARGET_READ =>
+            {
+                let bytes = reader.read_bytes(length)?;
+                target.extend_from_slice(bytes);
+            },
+            BPS_SOURCE_COPY =>
+            {
+                source_relative_offset += reader.read_signed_varint()?;
+                let start = source_relative_offset as usize;
+                if source_relative_offset < 0 || start + length > rom_data.len()
+                {
+                    return Err(RomError::PATCH("BPS source copy out of range".to_string()));
+                }
+                target.extend_from_slice(&rom_data[start..(start + length)]);
+                source_relative_offset += length as i64;
+            },
+            BPS_TARGET_COPY =>
+            {
+                target_relative_offset += reader.read_signed_varint()?;
+                if target_relative_offset < 0 || target_relative_offset as usize >= target.len()
+                {
+                    return Err(RomError::PATCH("BPS target copy out of range".to_string()));
+                }
+                //Copies may overlap the bytes being written, so go one byte at a time
+                for _ in 0..length
+                {
+                    let value = target[target_relative_offset as usize];
+                    target.push(value);
+                    target_relative_offset += 1;
+                }
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    if target.len() != target_size
+    {
+        return Err(RomError::PATCH("BPS patch did not fill the target".to_string()));
+    }
+    if crc32(&target) != target_crc
+    {
+        return Err(RomError::PATCH("patched ROM does not match the BPS target CRC".to_string()));
+    }
+    Ok(target)
+}
+
+fn crc32(data: &[u8]) -> u32
+{
+    let mut crc = Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+struct PatchReader<'a>
+{
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> PatchReader<'a>
+{
+    fn new(data: &'a [u8], offset: usize) -> PatchReader<'a>
+    {
+        return PatchReader
+        {
+            data: data,
+            offset: offset,
+        }
+    }
+
+    fn remaining(&self) -> usize
+    {
+        self.data.len().saturating_sub(self.offset)
+    }
+
+    fn read_bytes(&mut self, length: usize) -> Result<&'a [u8], RomError>
+    {
+        match self.data.get(self.offset..(self.offset + length))
+        {
+            Some(bytes) =>
+            {
+                self.offset += length;
+                Ok(bytes)
+            },
+            None => Err(RomError::PATCH("patch is truncated".to_string())),
+        }
+    }
+
+    fn read_u8(&mut self) -> Result<u8, RomError>
+    {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, RomError>
+    {
+        let bytes = self.read_bytes(2)?;
+        Ok((bytes[0] as u16) << 8 | (bytes[1] as u16))
+    }
+
+    fn read_u24(&mut self) -> Result<u32, RomError>
+    {
+        let bytes = self.read_bytes(3)?;
+        Ok((bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | (bytes[2] as u32))
+    }
+
+    fn read_u32_le(&mut self) -> Result<u32, RomError>
+    {
+        let bytes = self.read_bytes(4)?;
+        Ok((bytes[3] as u32) << 24 | (bytes[2] as u32) << 16 | (bytes[1] as u32) << 8 | (bytes[0] as u32))
+    }
+
+    fn read_varint(&mut self) -> Result<u64, RomError>
+    {
+        let mut data: u64 = 0;
+        let mut shift: u64 = 1;
+        loop
+        {
+            let value = self.read_u8()? as u64;
+            data = data.wrapping_add((value & 0x7F).wrapping_mul(shift));
+            if value & 0x80 != 0
+            {
+                break;
+            }
+            shift = shift.wrapping_shl(7);
+            data = data.wrapping_add(shift);
+        }
+        Ok(data)
+    }
+
+    fn read_signed_varint(&mut self) -> Result<i64, RomError>
+    {
+        let data = self.read_varint()?;
+        let magnitude = (data >> 1) as i64;
+        Ok(if data & 1 != 0 {-magnitude} else {magnitude})
+    }
+}
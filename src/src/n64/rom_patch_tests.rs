@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod rom_patch_tests
+{
+    use n64::rom_patch::*;
+    use n64::rom::{Rom, RomError};
+    use flate2::Crc;
+
+    fn crc32(data: &[u8]) -> u32
+    {
+        let mut crc = Crc::new();
+        crc.update(data);
+        crc.sum()
+    }
+
+    fn push_varint(patch: &mut Vec<u8>, mut data: u64)
+    {
+        loop
+        {
+            let value = (data & 0x7F) as u8;
+            data >>= 7;
+            if data == 0
+            {
+                patch.push(0x80 | value);
+                break;
+            }
+            patch.push(value);
+            data -= 1;
+        }
+    }
+
+    fn push_u32_le(patch: &mut Vec<u8>, value: u32)
+    {
+        patch.extend_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+    }
+
+    fn build_bps(source: &Vec<u8>, target: &Vec<u8>, actions: Vec<(u64, u64, Vec<u8>)>) -> Vec<u8>
+    {
+        let mut patch: Vec<u8> = b"BPS1".to_vec();
+        push_varint(&mut patch, source.len() as u64);
+        push_varint(&mut patch, target.len() as u64);
+        push_varint(&mut patch, 0);
+        for (command, length, payload) in actions
+        {
+            push_varint(&mut patch, ((length - 1) << 2) | command);
+            patch.extend_from_slice(&payload);
+        }
+        push_u32_le(&mut patch, crc32(source));
+        push_u32_le(&mut patch, crc32(target));
+        let patch_crc = crc32(&patch);
+        push_u32_le(&mut patch, patch_crc);
+        patch
+    }
+
+    #[test]
+    fn identifies_patch_formats()
+    {
+        assert_eq!(identify_patch(b"PATCHEOF"), Some(PatchFormat::IPS));
+        assert_eq!(identify_patch(b"BPS1"), Some(PatchFormat::BPS));
+        assert_eq!(identify_patch(b"UPS1"), None);
+    }
+
+    #[test]
+    fn applies_ips_records()
+    {
+        let rom_data: Vec<u8> = vec![0; 0x10];
+        let mut patch: Vec<u8> = b"PATCH".to_vec();
+        //Regular record
+        patch.extend_from_slice(&[0x00, 0x00, 0x02, 0x00, 0x02, 0xAA, 0xBB]);
+        //RLE record
+        patch.extend_from_slice(&[0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x03, 0xCC]);
+        //Record past the end grows the ROM
+        patch.extend_from_slice(&[0x00, 0x00, 0x12, 0x00, 0x01, 0xDD]);
+        patch.extend_from_slice(b"EOF");
+
+        let patched = apply_ips(&rom_data, &patch).unwrap();
+        assert_eq!(patched.len(), 0x13);
+        assert_eq!(patched[0x02..0x04].to_vec(), vec![0xAA, 0xBB]);
+        assert_eq!(patched[0x08..0x0B].to_vec(), vec![0xCC, 0xCC, 0xCC]);
+        assert_eq!(patched[0x12], 0xDD);
+
+        //Truncation extension
+        patch.extend_from_slice(&[0x00, 0x00, 0x04]);
+        assert_eq!(apply_ips(&rom_data, &patch).unwrap(), vec![0x00, 0x00, 0xAA, 0xBB]);
+
+        assert!(apply_ips(&rom_data, b"PATCH\x00\x00\x02\x00\x04\xAA").is_err());
+    }
+
+    #[test]
+    fn applies_bps_actions()
+    {
+        let source: Vec<u8> = vec![0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17];
+        let target: Vec<u8> = vec![0x10, 0x11, 0xAA, 0xBB, 0x16, 0x17, 0x16, 0x17, 0x16, 0x12];
+        let patch = build_bps(&source, &target, vec![
+            (0, 2, vec![]),                 //SourceRead 0x10 0x11
+            (1, 2, vec![0xAA, 0xBB]),       //TargetRead
+            (2, 2, vec![0x8C]),             //SourceCopy from +6
+            (3, 3, vec![0x88]),             //TargetCopy from +4, overlapping the output
+            (2, 1, vec![0x8D]),             //SourceCopy from 8 - 6
+        ]);
+        assert_eq!(apply_bps(&source, &patch).unwrap(), target);
+    }
+
+    #[test]
+    fn bps_crc_mismatches_are_rejected()
+    {
+        let source: Vec<u8> = vec![0x00; 4];
+        let target: Vec<u8> = vec![0x01; 4];
+        let patch = build_bps(&source, &target, vec![(1, 4, vec![0x01; 4])]);
+        assert_eq!(apply_bps(&vec![0xFF; 4], &patch).err(), Some(RomError::PATCH("ROM does not match the BPS source CRC".to_string())));
+
+        let mut corrupt_patch = patch.to_vec();
+        corrupt_patch[8] ^= 0xFF;
+        assert_eq!(apply_bps(&source, &corrupt_patch).err(), Some(RomError::PATCH("BPS patch failed its CRC check".to_string())));
+    }
+
+    #[test]
+    fn bps_target_size_is_bounded()
+    {
+        //A corrupt size must fail before anything is allocated for it
+        let source: Vec<u8> = vec![0x00; 4];
+        let mut patch: Vec<u8> = b"BPS1".to_vec();
+        push_varint(&mut patch, source.len() as u64);
+        push_varint(&mut patch, 0xFFFF_FFFF_FFFF);
+        push_varint(&mut patch, 0);
+        push_u32_le(&mut patch, crc32(&source));
+        push_u32_le(&mut patch, 0);
+        let patch_crc = crc32(&patch);
+        push_u32_le(&mut patch, patch_crc);
+        assert_eq!(apply_bps(&source, &patch).err(), Some(RomError::PATCH("BPS target size 0xFFFFFFFFFFFF is larger than any cartridge".to_string())));
+    }
+
+    #[test]
+    fn patching_rom_updates_header_and_crcs()
+    {
+        let mut rom_data: Vec<u8> = vec![0; 0x2000];
+        rom_data[0..4].copy_from_slice(&[0x80, 0x37, 0x12, 0x40]);
+        let mut rom = Rom::from_bytes(rom_data).unwrap();
+
+        let mut patch: Vec<u8> = b"PATCH".to_vec();
+        patch.extend_from_slice(&[0x00, 0x00, 0x3B, 0x00, 0x04]);
+        patch.extend_from_slice(b"NSMJ");
+        patch.extend_from_slice(&[0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x04, 0xFF]);
+        patch.extend_from_slice(b"EOF");
+        rom.apply_patch(&patch).unwrap();
+
+        assert_eq!(rom.rom_header.game_code, "NSMJ");
+        assert!(rom.crcs_match());
+        assert_eq!(rom.rom_data[0x10..0x14].to_vec(), vec![(rom.rom_header.crc1 >> 24) as u8, (rom.rom_header.crc1 >> 16) as u8, (rom.rom_header.crc1 >> 8) as u8, rom.rom_header.crc1 as u8]);
+        assert!(rom.apply_patch(b"NOT A PATCH").is_err());
+    }
+}
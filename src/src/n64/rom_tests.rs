@@ -2,6 +2,21 @@
 mod rom_tests
 {
     use RomHeader;
+    use Rom;
+    use n64::rom::{MediaFormat, Region, TvType, RomError};
+    use std::env;
+    use std::fs;
+    use std::io;
+    use std::io::Read;
+    use std::path::PathBuf;
+
+    fn big_endian_test_rom() -> Vec<u8>
+    {
+        let mut rom_data: Vec<u8> = vec![0; 0x2000];
+        rom_data[0..4].copy_from_slice(&[0x80, 0x37, 0x12, 0x40]);
+        rom_data[0x3B..0x3F].copy_from_slice(b"NSME");
+        rom_data
+    }
 
     #[test]
     fn rom_header_parsed_values() 
@@ -19,7 +34,7 @@ mod rom_tests
 
         let boot_code_compare = vec![0xFFFFFFFF; 0x3F0];
 
-        let rom_header = RomHeader::new(test_vec);
+        let rom_header = RomHeader::new(test_vec).unwrap();
 
         assert_eq!(rom_header.pi_reg_initializers, vec![0x00, 0x01, 0x02, 0x03]);
         assert_eq!(rom_header.clock_rate, 0x04050607);
@@ -33,4 +48,102 @@ mod rom_tests
         assert_eq!(rom_header.country_code, 0x3E3F);
         assert_eq!(rom_header.boot_code, boot_code_compare);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn rom_header_typed_values()
+    {
+        let mut test_vec: Vec<u8> = vec![0; 0x1000];
+        test_vec[0x3B..0x40].copy_from_slice(b"NSMEA");
+
+        let rom_header = RomHeader::new(test_vec).unwrap();
+
+        assert_eq!(rom_header.media_format, MediaFormat::CARTRIDGE);
+        assert_eq!(rom_header.game_code, "NSME");
+        assert_eq!(rom_header.region, Region::NORTH_AMERICA);
+        assert_eq!(rom_header.tv_type(), TvType::NTSC);
+        assert_eq!(rom_header.revision, 0x41);
+        assert_eq!(Region::from_u8(b'P').tv_type(), TvType::PAL);
+        assert_eq!(Region::from_u8(b'B').tv_type(), TvType::MPAL);
+        assert_eq!(MediaFormat::from_u8(b'Q'), MediaFormat::UNKNOWN(b'Q'));
+    }
+
+    #[test]
+    fn rom_header_title_decoding()
+    {
+        let mut test_vec: Vec<u8> = vec![0x20; 0x1000];
+        test_vec[0x20..0x2E].copy_from_slice(b"SUPER MARIO 64");
+        assert_eq!(RomHeader::new(test_vec.to_vec()).unwrap().title(), "SUPER MARIO 64");
+
+        //Shift-JIS katakana "ゼルダ" followed by padding
+        let mut test_vec: Vec<u8> = vec![0x00; 0x1000];
+        test_vec[0x20..0x26].copy_from_slice(&[0x83, 0x5B, 0x83, 0x8B, 0x83, 0x5F]);
+        assert_eq!(RomHeader::new(test_vec.to_vec()).unwrap().title(), "ゼルダ");
+
+        //Invalid sequences are replaced instead of panicking
+        test_vec[0x20] = 0xFF;
+        assert!(RomHeader::new(test_vec).unwrap().title().contains('\u{FFFD}'));
+    }
+
+    #[test]
+    fn rom_crc_recomputation()
+    {
+        let mut rom = Rom::test();
+        rom.rom_data = vec![0; 0x101000];
+        assert_eq!(rom.compute_crcs(), (0xF8CA4DDC, 0x303A4DDC));
+        assert!(!rom.crcs_match());
+
+        rom.rom_header.crc1 = 0xF8CA4DDC;
+        rom.rom_header.crc2 = 0x303A4DDC;
+        assert!(rom.crcs_match());
+    }
+
+    #[test]
+    fn rom_from_bytes_normalizes_byte_order()
+    {
+        let big_endian = big_endian_test_rom();
+        let byte_swapped: Vec<u8> = big_endian.chunks(2).flat_map(|half_word| vec![half_word[1], half_word[0]]).collect();
+        let little_endian: Vec<u8> = big_endian.chunks(4).flat_map(|word| vec![word[3], word[2], word[1], word[0]]).collect();
+
+        for rom_data in vec![big_endian.to_vec(), byte_swapped, little_endian]
+        {
+            let rom = Rom::from_bytes(rom_data).unwrap();
+            assert_eq!(rom.rom_data, big_endian);
+            assert_eq!(rom.rom_header.game_code, "NSME");
+        }
+    }
+
+    #[test]
+    fn save_path_replaces_rom_and_archive_extensions()
+    {
+        let mut rom = Rom::from_bytes(big_endian_test_rom()).unwrap();
+        assert_eq!(rom.save_path("eep"), None);
+        for filename in ["roms/game.z64", "roms/game.z64.gz", "roms/game.Z64.ZIP", "roms/game.zip"].iter()
+        {
+            rom.filename = Some(filename.to_string());
+            assert_eq!(rom.save_path("eep"), Some(PathBuf::from("roms/game.eep")));
+        }
+    }
+
+    #[test]
+    fn rom_loading_reports_errors()
+    {
+        assert_eq!(Rom::from_bytes(vec![0x80; 0x20]).err(), Some(RomError::TOO_SMALL(0x20)));
+        assert_eq!(RomHeader::new(vec![0x00; 0x40]).err(), Some(RomError::TOO_SMALL(0x40)));
+        assert_eq!(Rom::from_bytes(vec![0x12; 0x1000]).err(), Some(RomError::UNKNOWN_BYTE_ORDER(0x12121212)));
+        assert_eq!(Rom::from_reader(io::repeat(0x80).take(0x4000001)).err(), Some(RomError::OVERSIZE(0x4000001)));
+        assert_eq!(Rom::new("this/rom/does/not/exist.z64").err(), Some(RomError::FILE_NOT_FOUND("this/rom/does/not/exist.z64".to_string())));
+    }
+
+    #[test]
+    fn rom_from_file_and_reader()
+    {
+        let rom_path = env::temp_dir().join("n8x8_rom_from_file_test.z64");
+        fs::write(&rom_path, big_endian_test_rom()).unwrap();
+        let rom = Rom::new(rom_path.to_str().unwrap()).unwrap();
+        fs::remove_file(&rom_path).unwrap();
+        assert_eq!(rom.rom_data.len(), 0x2000);
+
+        let rom = Rom::from_reader(io::Cursor::new(big_endian_test_rom())).unwrap();
+        assert_eq!(rom.rom_header.game_code, "NSME");
+    }
+}
@@ -22,9 +22,103 @@ const SP_PC_REG_START: usize = 0x00080000;
 const SP_PC_REG_END: usize =  0x00080003;
 const SP_IBIST_REG_START: usize = 0x00080004;
 const SP_IBIST_REG_END: usize = 0x00080007;
+pub const SP_REGISTERS_START: usize = SP_MEM_ADDR_REG_START;
+
+//SP_STATUS read bits
+pub const SP_STATUS_HALT: u32 = 0x0001;
+pub const SP_STATUS_BROKE: u32 = 0x0002;
+pub const SP_STATUS_DMA_BUSY: u32 = 0x0004;
+pub const SP_STATUS_DMA_FULL: u32 = 0x0008;
+pub const SP_STATUS_IO_FULL: u32 = 0x0010;
+pub const SP_STATUS_SSTEP: u32 = 0x0020;
+pub const SP_STATUS_INTR_BREAK: u32 = 0x0040;
+pub const SP_STATUS_SIGNAL_0: u32 = 0x0080;
+
+//SP_STATUS write bits, each pair clears or sets one status bit
+const SP_CLR_HALT: u32 = 0x00000001;
+const SP_SET_HALT: u32 = 0x00000002;
+const SP_CLR_BROKE: u32 = 0x00000004;
+const SP_CLR_INTR: u32 = 0x00000008;
+const SP_SET_INTR: u32 = 0x00000010;
+const SP_CLR_SSTEP: u32 = 0x00000020;
+const SP_SET_SSTEP: u32 = 0x00000040;
+const SP_CLR_INTR_BREAK: u32 = 0x00000080;
+const SP_SET_INTR_BREAK: u32 = 0x00000100;
+const SP_CLR_SIGNAL_0: u32 = 0x00000200;
+const SP_SIGNAL_COUNT: u32 = 8;
+
+//IMEM and DMEM are 4KB and the RSP only sees the low 12 bits of an address
+pub const SP_MEMORY_MASK: usize = 0x0FFF;
+//Bit 12 of SP_MEM_ADDR selects IMEM
+const SP_MEM_ADDR_MASK: u32 = 0x00001FF8;
+const SP_MEM_ADDR_IMEM: u32 = 0x00001000;
+const SP_DRAM_ADDR_MASK: u32 = 0x00FFFFF8;
+const SP_PC_MASK: u32 = 0x00000FFC;
+//Length registers hold length - 1 in bits 0-11, count - 1 in bits 12-19 and the skip in bits 20-31
+const SP_DMA_LENGTH_MASK: u32 = 0x00000FFF;
+const SP_DMA_COUNT_SHIFT: u32 = 12;
+const SP_DMA_COUNT_MASK: u32 = 0x000000FF;
+const SP_DMA_SKIP_SHIFT: u32 = 20;
+const SP_DMA_SKIP_MASK: u32 = 0x00000FFF;
+//A finished transfer reads back with the length at its maximum and the count at zero
+const SP_DMA_LENGTH_DONE: u32 = 0x00000FF8;
+// Referenced: ares moves 8 bytes per RCP cycle for SP DMA
+pub const SP_DMA_BYTES_PER_CYCLE: u32 = 8;
 
 use n64::arch::Reg;
 use binary_helpers::*;
+use std::cell::Cell;
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum SPDMADirection
+{
+    RDRAM_TO_SP,
+    SP_TO_RDRAM,
+}
+
+//Addresses are latched when the length register is written
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+pub struct SPDMARequest
+{
+    pub direction: SPDMADirection,
+    pub memory_address: u32,
+    pub dram_address: u32,
+    pub length: u32,
+}
+
+impl SPDMARequest
+{
+    //Rows are rounded up to whole 8 byte words
+    pub fn row_length(&self) -> usize
+    {
+        ((self.length & SP_DMA_LENGTH_MASK) | 0x7) as usize + 1
+    }
+
+    pub fn row_count(&self) -> usize
+    {
+        ((self.length >> SP_DMA_COUNT_SHIFT) & SP_DMA_COUNT_MASK) as usize + 1
+    }
+
+    pub fn skip(&self) -> usize
+    {
+        ((self.length >> SP_DMA_SKIP_SHIFT) & SP_DMA_SKIP_MASK) as usize
+    }
+
+    pub fn is_imem(&self) -> bool
+    {
+        self.memory_address & SP_MEM_ADDR_IMEM != 0
+    }
+
+    pub fn cycles(&self) -> u32
+    {
+        ((self.row_length() * self.row_count()) as u32 / SP_DMA_BYTES_PER_CYCLE).max(1)
+    }
+}
 
 
 pub struct RealitySignalProcessor
@@ -36,11 +130,15 @@ pub struct RealitySignalProcessor
     pub read_dma_length: Reg,
     pub write_dma_length: Reg,
     pub status: Reg,
-    pub dma_full: Reg,
-    pub dma_busy: Reg,
-    pub sempahore: Reg,
+    //Reading the semaphore acquires it, so it has to change behind a shared reference
+    pub semaphore: Cell<bool>,
     pub program_counter: Reg,
     pub instruction_memory_self_test: Reg,
+    pub pending_dma: Option<SPDMARequest>,
+    pub active_dma: Option<SPDMARequest>,
+    pub queued_dma: Option<SPDMARequest>,
+    pub dma_cycles_remaining: u32,
+    pub pending_interrupt: Option<bool>,
 }
 
 impl RealitySignalProcessor
@@ -55,12 +153,16 @@ impl RealitySignalProcessor
             dram_dam_address: Reg::default(),
             read_dma_length: Reg::default(),
             write_dma_length: Reg::default(),
-            status: Reg::default(),
-            dma_full: Reg::default(),
-            dma_busy: Reg::default(),
-            sempahore: Reg::default(),
+            //The RSP comes out of reset halted
+            status: Reg::new(SP_STATUS_HALT as u64, false),
+            semaphore: Cell::new(false),
             program_counter: Reg::default(),
             instruction_memory_self_test: Reg::default(),
+            pending_dma: None,
+            active_dma: None,
+            queued_dma: None,
+            dma_cycles_remaining: 0,
+            pending_interrupt: None,
         }
     }
 
@@ -81,9 +183,9 @@ impl RealitySignalProcessor
             SP_RD_LEN_REG_START...SP_RD_LEN_REG_END => Some(self.read_dma_length.get_value() as u32),
             SP_WR_LEN_REG_START...SP_WR_LEN_REG_END => Some(self.write_dma_length.get_value() as u32),
             SP_STATUS_REG_START...SP_STATUS_REG_END => Some(self.status.get_value() as u32),
-            SP_DMA_FULL_REG_START...SP_DMA_FULL_REG_END => Some(self.dma_full.get_value() as u32),
-            SP_DMA_BUSY_REG_START...SP_DMA_BUSY_REG_END => Some(self.dma_busy.get_value() as u32),
-            SP_SEMAPHORE_REG_START...SP_SEMAPHORE_REG_END => Some(self.sempahore.get_value() as u32),
+            SP_DMA_FULL_REG_START...SP_DMA_FULL_REG_END => Some(self.is_dma_full() as u32),
+            SP_DMA_BUSY_REG_START...SP_DMA_BUSY_REG_END => Some(self.is_dma_busy() as u32),
+            SP_SEMAPHORE_REG_START...SP_SEMAPHORE_REG_END => Some(self.semaphore.replace(true) as u32),
             SP_PC_REG_START...SP_PC_REG_END => Some(self.program_counter.get_value() as u32),
             SP_IBIST_REG_START...SP_IBIST_REG_END => Some(self.instruction_memory_self_test.get_value() as u32),
             _ => None,
@@ -101,21 +203,178 @@ impl RealitySignalProcessor
         {
             SP_DMEM_START...SP_DMEM_END => Ok(u32_to_u8_vector_by_loc(value, address - SP_DMEM_START, &mut self.dynamic_memory)),
             SP_IMEM_START...SP_IMEM_END => Ok(u32_to_u8_vector_by_loc(value, address - SP_IMEM_START, &mut self.instruction_memory)),
-            SP_MEM_ADDR_REG_START...SP_MEM_ADDR_REG_END => Ok(self.memory_address.set_value(value)),
-            SP_DRAM_ADDR_REG_START...SP_DRAM_ADDR_REG_END => Ok(self.dram_dam_address.set_value(value)),
-            SP_RD_LEN_REG_START...SP_RD_LEN_REG_END => Ok(self.read_dma_length.set_value(value)),
-            SP_WR_LEN_REG_START...SP_WR_LEN_REG_END => Ok(self.write_dma_length.set_value(value)),
-            SP_STATUS_REG_START...SP_STATUS_REG_END => Ok(self.status.set_value(value)),
-            SP_DMA_FULL_REG_START...SP_DMA_FULL_REG_END => Ok(self.dma_full.set_value(value)),
-            SP_DMA_BUSY_REG_START...SP_DMA_BUSY_REG_END => Ok(self.dma_busy.set_value(value)),
-            SP_SEMAPHORE_REG_START...SP_SEMAPHORE_REG_END => Ok(self.sempahore.set_value(value)),
-            SP_PC_REG_START...SP_PC_REG_END => Ok(self.program_counter.set_value(value)),
+            SP_MEM_ADDR_REG_START...SP_MEM_ADDR_REG_END => Ok(self.memory_address.set_value(value & SP_MEM_ADDR_MASK)),
+            SP_DRAM_ADDR_REG_START...SP_DRAM_ADDR_REG_END => Ok(self.dram_dam_address.set_value(value & SP_DRAM_ADDR_MASK)),
+            SP_RD_LEN_REG_START...SP_RD_LEN_REG_END => Ok(self.request_dma(SPDMADirection::RDRAM_TO_SP, value)),
+            SP_WR_LEN_REG_START...SP_WR_LEN_REG_END => Ok(self.request_dma(SPDMADirection::SP_TO_RDRAM, value)),
+            SP_STATUS_REG_START...SP_STATUS_REG_END => Ok(self.write_status(value)),
+            //DMA_FULL and DMA_BUSY mirror status bits and are read only
+            SP_DMA_FULL_REG_START...SP_DMA_BUSY_REG_END => Ok(()),
+            //Any write releases the semaphore
+            SP_SEMAPHORE_REG_START...SP_SEMAPHORE_REG_END => Ok(self.semaphore.set(false)),
+            SP_PC_REG_START...SP_PC_REG_END => Ok(self.program_counter.set_value(value & SP_PC_MASK)),
             SP_IBIST_REG_START...SP_IBIST_REG_END => Ok(self.instruction_memory_self_test.set_value(value)),
             _ => Err(address),
         }
     }
 
 
+    pub fn is_halted(&self) -> bool
+    {
+        self.status.get_value() as u32 & SP_STATUS_HALT != 0
+    }
+
+    pub fn set_status_bits(&mut self, bits: u32)
+    {
+        let value = self.status.get_value() as u32 | bits;
+        self.status.set_value(value);
+    }
+
+    pub fn clear_status_bits(&mut self, bits: u32)
+    {
+        let value = self.status.get_value() as u32 & !bits;
+        self.status.set_value(value);
+    }
+
+    // Referenced: https://n64brew.dev/wiki/Reality_Signal_Processor/Interface#SP_STATUS
+    fn write_status(&mut self, value: u32)
+    {
+        let mut set: u32 = 0;
+        let mut clear: u32 = 0;
+        let pairs = [(SP_CLR_HALT, SP_SET_HALT, SP_STATUS_HALT), (SP_CLR_SSTEP, SP_SET_SSTEP, SP_STATUS_SSTEP), (SP_CLR_INTR_BREAK, SP_SET_INTR_BREAK, SP_STATUS_INTR_BREAK)];
+        for &(clear_bit, set_bit, status_bit) in pairs.iter()
+        {
+            //Writing both bits of a pair leaves the status unchanged
+            match (value & clear_bit != 0, value & set_bit != 0)
+            {
+                (true, false) => clear |= status_bit,
+                (false, true) => set |= status_bit,
+                _ => (),
+            }
+        }
+        for signal in 0..SP_SIGNAL_COUNT
+        {
+            let clear_bit = SP_CLR_SIGNAL_0 << (signal * 2);
+            match (value & clear_bit != 0, value & (clear_bit << 1) != 0)
+            {
+                (true, false) => clear |= SP_STATUS_SIGNAL_0 << signal,
+                (false, true) => set |= SP_STATUS_SIGNAL_0 << signal,
+                _ => (),
+            }
+        }
+        if value & SP_CLR_BROKE != 0
+        {
+            clear |= SP_STATUS_BROKE;
+        }
+        self.clear_status_bits(clear);
+        self.set_status_bits(set);
+
+        //The interrupt itself lives in the MI, the connector applies it
+        match (value & SP_CLR_INTR != 0, value & SP_SET_INTR != 0)
+        {
+            (true, false) => self.pending_interrupt = Some(false),
+            (false, true) => self.pending_interrupt = Some(true),
+            _ => (),
+        }
+    }
+
+    //Latches the addresses, the connector starts or queues the transfer
+    fn request_dma(&mut self, direction: SPDMADirection, length: u32)
+    {
+        match direction
+        {
+            SPDMADirection::RDRAM_TO_SP => self.read_dma_length.set_value(length),
+            SPDMADirection::SP_TO_RDRAM => self.write_dma_length.set_value(length),
+        }
+        self.pending_dma = Some(SPDMARequest
+        {
+            direction: direction,
+            memory_address: self.memory_address.get_value() as u32,
+            dram_address: self.dram_dam_address.get_value() as u32,
+            length: length,
+        });
+    }
+
+    pub fn is_dma_busy(&self) -> bool
+    {
+        self.status.get_value() as u32 & SP_STATUS_DMA_BUSY != 0
+    }
+
+    pub fn is_dma_full(&self) -> bool
+    {
+        self.status.get_value() as u32 & SP_STATUS_DMA_FULL != 0
+    }
+
+    //A second request waits behind the active one, further requests are dropped while full
+    pub fn queue_dma(&mut self, request: SPDMARequest)
+    {
+        if self.queued_dma.is_none()
+        {
+            self.queued_dma = Some(request);
+            self.set_status_bits(SP_STATUS_DMA_FULL);
+        }
+    }
+
+    //Completion is timed by step, the data moves when it completes
+    pub fn start_dma(&mut self, request: SPDMARequest)
+    {
+        self.active_dma = Some(request);
+        self.set_status_bits(SP_STATUS_DMA_BUSY);
+        self.dma_cycles_remaining = request.cycles();
+    }
+
+    //Registers read back where the transfer ended
+    pub fn finish_dma(&mut self, request: &SPDMARequest, memory_address: u32, dram_address: u32)
+    {
+        self.memory_address.set_value(memory_address & SP_MEM_ADDR_MASK);
+        self.dram_dam_address.set_value(dram_address & SP_DRAM_ADDR_MASK);
+        let length = SP_DMA_LENGTH_DONE | (request.skip() as u32) << SP_DMA_SKIP_SHIFT;
+        self.read_dma_length.set_value(length);
+        self.write_dma_length.set_value(length);
+    }
+
+    //Returns the active request once it completes, the connector copies the data and starts the queued one
+    pub fn step(&mut self, cycles: u32) -> Option<SPDMARequest>
+    {
+        if !self.is_dma_busy()
+        {
+            return None;
+        }
+        self.dma_cycles_remaining = self.dma_cycles_remaining.saturating_sub(cycles);
+        if self.dma_cycles_remaining > 0
+        {
+            return None;
+        }
+        self.clear_status_bits(SP_STATUS_DMA_BUSY);
+        self.active_dma.take()
+    }
+
+    pub fn start_queued_dma(&mut self)
+    {
+        if let Some(request) = self.queued_dma.take()
+        {
+            self.clear_status_bits(SP_STATUS_DMA_FULL);
+            self.start_dma(request);
+        }
+    }
+
+    //Accesses from the RSP core itself, which wrap around DMEM
+    pub fn read_dmem_u8(&self, address: usize) -> u8
+    {
+        self.dynamic_memory[address & SP_MEMORY_MASK]
+    }
+
+    pub fn store_dmem_u8(&mut self, address: usize, value: u8)
+    {
+        self.dynamic_memory[address & SP_MEMORY_MASK] = value;
+    }
+
+    pub fn read_imem_u32(&self, address: usize) -> u32
+    {
+        let address = address & SP_MEMORY_MASK & !0x3;
+        u8_slice_to_u32(self.instruction_memory[address..(address + 4)].to_vec())
+    }
+
     pub fn read_u16_from_address(&self, address: usize) -> Option<u16>
     {
         //Only allow alligned addresses (unaligned handled exterior to function)
@@ -0,0 +1,100 @@
+use n64::arch::Reg;
+use n64::exceptions::Exception;
+use n64::connector::Connector;
+use n64::rsp::{SP_STATUS_HALT, SP_STATUS_SSTEP, SP_MEMORY_MASK};
+use n64::rsp_opcodes::RSPOpcode;
+use n64::rsp_vector::VectorUnit;
+
+//Executes microcode from IMEM, the memory and control registers live in connector.rsp
+pub struct RSPCore
+{
+    pub registers: Vec<Reg>,
+    pub pc_save: u32,
+    pub pc_save_count: u8,
+    pub vector_unit: VectorUnit,
+    //PC and cause of the last instruction that halted the RSP, for the frontend to report
+    pub fault: Option<(u32, Exception)>,
+}
+
+impl RSPCore
+{
+    pub fn new() -> RSPCore
+    {
+        return RSPCore
+        {
+            registers: vec![Reg::default(); 0x20],
+            pc_save: 0,
+            pc_save_count: 0,
+            vector_unit: VectorUnit::new(),
+            fault: None,
+        }
+    }
+
+    pub fn read_register(&self, register: u8) -> u32
+    {
+        self.registers[register as usize].get_value() as u32
+    }
+
+    pub fn write_register(&mut self, register: u8, value: u32)
+    {
+        //r0 is hardwired to zero
+        if register != 0
+        {
+            self.registers[register as usize].set_value(value);
+        }
+    }
+
+    pub fn retrieve_opcode(&mut self, connector: &mut Connector) -> RSPOpcode
+    {
+        //The PC is 12 bits wide and wraps around IMEM
+        let pc = connector.rsp.program_counter.get_value() as usize & SP_MEMORY_MASK;
+        let value = connector.rsp.read_imem_u32(pc);
+        connector.rsp.program_counter.set_value(((pc + 4) & SP_MEMORY_MASK) as u32);
+        RSPOpcode::new(value)
+    }
+
+    pub fn step(&mut self, connector: &mut Connector) -> Result<(), Exception>
+    {
+        if connector.rsp.is_halted()
+        {
+            return Ok(());
+        }
+
+        let opcode = self.retrieve_opcode(connector);
+        opcode.execute(self, connector)?;
+        if self.pc_save_count > 0
+        {
+            if self.pc_save_count == 1
+            {
+                connector.rsp.program_counter.set_value(self.pc_save);
+            }
+
+            self.pc_save_count -= 1;
+        }
+
+        if connector.rsp.status.get_value() as u32 & SP_STATUS_SSTEP != 0
+        {
+            connector.rsp.set_status_bits(SP_STATUS_HALT);
+        }
+        Ok(())
+    }
+
+    //Runs until the cycle budget is spent or the RSP halts
+    pub fn run(&mut self, connector: &mut Connector, cycles: u32) -> Result<(), Exception>
+    {
+        for _ in 0..cycles
+        {
+            if connector.rsp.is_halted()
+            {
+                break;
+            }
+            let program_counter = connector.rsp.program_counter.get_value() as u32;
+            if let Err(e) = self.step(connector)
+            {
+                self.fault = Some((program_counter, e.clone()));
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+#[cfg(test)]
+mod rsp_core_tests
+{
+    use n64::rsp_core::*;
+    use n64::rsp::*;
+    use n64::connector::Connector;
+    use n64::mips_iface::MI_INTR_SP;
+    use n64::exceptions::Exception;
+    use n64::n64::N64;
+    use n64::rom::Rom;
+
+    const BREAK: u32 = 0x0000000D;
+
+    fn i_type(op: u32, rs: u32, rt: u32, imm: u16) -> u32
+    {
+        (op << 26) | (rs << 21) | (rt << 16) | imm as u32
+    }
+
+    fn r_type(rs: u32, rt: u32, rd: u32, sa: u32, funct: u32) -> u32
+    {
+        (rs << 21) | (rt << 16) | (rd << 11) | (sa << 6) | funct
+    }
+
+    fn load_program(connector: &mut Connector, address: usize, program: &[u32])
+    {
+        for (index, word) in program.iter().enumerate()
+        {
+            connector.rsp.load_u32_to_address(0x1000 + ((address + (index * 4)) & SP_MEMORY_MASK), *word).unwrap();
+        }
+        connector.rsp.program_counter.set_value(address as u32);
+        connector.rsp.status.set_value(0_u32);
+    }
+
+    #[test]
+    fn rsp_starts_halted()
+    {
+        let mut connector = Connector::test();
+        let mut core = RSPCore::new();
+        core.run(&mut connector, 10).unwrap();
+        assert!(connector.rsp.is_halted());
+        assert_eq!(connector.rsp.program_counter.get_value(), 0);
+    }
+
+    #[test]
+    fn arithmetic_and_stores_run_until_break()
+    {
+        let mut connector = Connector::test();
+        let mut core = RSPCore::new();
+        load_program(&mut connector, 0, &[
+            i_type(0x09, 0, 1, 5),
+            i_type(0x09, 0, 2, 0xFFFF),
+            r_type(1, 2, 3, 0, 0x21),
+            i_type(0x2B, 0, 3, 0x10),
+            //Unaligned halfword store wraps around the end of DMEM
+            i_type(0x29, 0, 2, 0x0FFF),
+            i_type(0x20, 0, 4, 0x0FFF),
+            i_type(0x09, 0, 0, 7),
+            BREAK,
+        ]);
+        core.run(&mut connector, 100).unwrap();
+
+        assert_eq!(connector.rsp.dynamic_memory[0x10..0x14].to_vec(), vec![0, 0, 0, 4]);
+        assert_eq!(connector.rsp.dynamic_memory[0xFFF], 0xFF);
+        assert_eq!(connector.rsp.dynamic_memory[0x000], 0xFF);
+        assert_eq!(core.read_register(4), 0xFFFFFFFF);
+        assert_eq!(core.read_register(0), 0);
+        assert_eq!(connector.rsp.status.get_value() as u32, SP_STATUS_HALT | SP_STATUS_BROKE);
+        assert_eq!(connector.mips_interface.interrupt.get_value() as u32 & MI_INTR_SP, 0);
+    }
+
+    #[test]
+    fn branches_have_delay_slots_and_pc_wraps()
+    {
+        let mut connector = Connector::test();
+        let mut core = RSPCore::new();
+        load_program(&mut connector, 0xFF8, &[
+            i_type(0x04, 0, 0, 2),
+            i_type(0x09, 0, 1, 1),
+            i_type(0x09, 0, 2, 1),
+            BREAK,
+        ]);
+        core.run(&mut connector, 100).unwrap();
+        assert_eq!(core.read_register(1), 1);
+        assert_eq!(core.read_register(2), 0);
+        assert_eq!(connector.rsp.program_counter.get_value(), 0x008);
+
+        //JAL links past its delay slot
+        load_program(&mut connector, 0x100, &[(0x03 << 26) | (0x200 >> 2), 0, 0, 0]);
+        load_program(&mut connector, 0x200, &[BREAK]);
+        connector.rsp.program_counter.set_value(0x100_u32);
+        core.run(&mut connector, 100).unwrap();
+        assert_eq!(core.read_register(31), 0x108);
+    }
+
+    #[test]
+    fn break_raises_interrupt_when_enabled()
+    {
+        let mut connector = Connector::test();
+        let mut core = RSPCore::new();
+        load_program(&mut connector, 0, &[BREAK]);
+        connector.rsp.status.set_value(SP_STATUS_INTR_BREAK);
+        core.step(&mut connector).unwrap();
+        assert!(connector.rsp.is_halted());
+        assert_eq!(connector.mips_interface.interrupt.get_value() as u32 & MI_INTR_SP, MI_INTR_SP);
+    }
+
+    #[test]
+    fn cop0_reaches_sp_and_dp_registers()
+    {
+        let mut connector = Connector::test();
+        let mut core = RSPCore::new();
+        load_program(&mut connector, 0, &[
+            i_type(0x09, 0, 1, 0x100),
+            (0x10 << 26) | (0x04 << 21) | (1 << 16) | (8 << 11),
+            (0x10 << 26) | (2 << 16) | (8 << 11),
+            (0x10 << 26) | (3 << 16) | (4 << 11),
+            BREAK,
+        ]);
+        connector.rsp.status.set_value(SP_STATUS_SIGNAL_0);
+        core.run(&mut connector, 100).unwrap();
+        assert_eq!(connector.dp.start.get_value(), 0x100);
+        assert_eq!(core.read_register(2), 0x100);
+        assert_eq!(core.read_register(3), SP_STATUS_SIGNAL_0);
+    }
+
+    #[test]
+    fn unknown_instructions_are_reported()
+    {
+        let mut connector = Connector::test();
+        let mut core = RSPCore::new();
+        load_program(&mut connector, 0, &[0xFC000000]);
+        assert!(core.step(&mut connector).is_err());
+    }
+
+    #[test]
+    fn unsupported_instructions_halt_the_rsp_and_record_the_fault()
+    {
+        let mut n64 = N64::from_rom(Rom::test());
+        load_program(&mut n64.connector, 0x10, &[i_type(0x09, 0, 1, 1), 0xFC000000, i_type(0x09, 0, 1, 2)]);
+        n64.step_devices(10);
+        assert!(n64.connector.rsp.is_halted());
+        assert_eq!(n64.rsp_core.read_register(1), 1);
+        assert_eq!(n64.rsp_core.fault, Some((0x14, Exception::UNIMPLEMENTED_OPCODE)));
+    }
+}
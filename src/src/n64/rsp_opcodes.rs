@@ -0,0 +1,852 @@
+//Handlers are named after the instruction mnemonics
+#![allow(non_snake_case)]
+
+use n64::exceptions::Exception;
+use n64::rsp_core::RSPCore;
+use n64::rsp::{SP_STATUS_HALT, SP_STATUS_BROKE, SP_STATUS_INTR_BREAK, SP_MEMORY_MASK};
+use n64::mips_iface::MI_INTR_SP;
+use n64::connector::Connector;
+use n64::rsp_vector_opcodes::*;
+use std::fmt;
+
+//Return addresses and jump targets stay inside IMEM
+const RSP_PC_MASK: u32 = 0x00000FFC;
+const RSP_LINK_MASK: u32 = 0x00000FFF;
+
+pub struct RSPOpcode
+{
+    pub opcode: u32,
+    pub command: RSPCommand,
+    pub rs: u8,
+    pub rt: u8,
+    pub rd: u8,
+    pub sa: u8,
+    pub base: u8,
+    pub imm: u16,
+    pub offset: u16,
+    pub target: u32,
+    pub vt: u8,
+    pub vs: u8,
+    pub vd: u8,
+    pub element: u8,
+    pub memory_element: u8,
+    pub vector_offset: i8,
+}
+
+impl RSPOpcode
+{
+    pub fn new(opcode: u32) -> RSPOpcode
+    {
+        return RSPOpcode
+        {
+            opcode: opcode,
+            command: RSPCommand::from_opcode(opcode),
+            rs: ((opcode >> 21) & 0x1F) as u8,
+            rt: ((opcode >> 16) & 0x1F) as u8,
+            rd: ((opcode >> 11) & 0x1F) as u8,
+            sa: ((opcode >> 6) & 0x1F) as u8,
+            base: ((opcode >> 21) & 0x1F) as u8,
+            imm: (opcode & 0x0000FFFF) as u16,
+            offset: (opcode & 0x0000FFFF) as u16,
+            target: opcode & 0x03FFFFFF,
+            vt: ((opcode >> 16) & 0x1F) as u8,
+            vs: ((opcode >> 11) & 0x1F) as u8,
+            vd: ((opcode >> 6) & 0x1F) as u8,
+            element: ((opcode >> 21) & 0xF) as u8,
+            memory_element: ((opcode >> 7) & 0xF) as u8,
+            //Signed 7 bit offset, scaled by the access size
+            vector_offset: (((opcode & 0x7F) << 1) as u8 as i8) >> 1,
+        }
+    }
+
+    pub fn Debug(&self)
+    {
+        println!("RSP OPCODE DEBUG - 0x{:08x}", self.opcode);
+        println!("COMMAND - {}", self.command);
+        println!("rs: 0x{:02x}\trt: 0x{:02x}\trd: 0x{:02x}\tsa: 0x{:02x}", self.rs, self.rt, self.rd, self.sa);
+        println!("imm: 0x{:04x}\toffset: 0x{:04x}\ttarget: 0x{:08x}", self.imm, self.offset, self.target);
+        println!("vt: 0x{:02x}\tvs: 0x{:02x}\tvd: 0x{:02x}\te: 0x{:x}", self.vt, self.vs, self.vd, self.element);
+    }
+
+    pub fn execute(&self, core: &mut RSPCore, connector: &mut Connector) -> Result<(), Exception>
+    {
+        self.command.parse(self, core, connector)
+    }
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum RSPCommand
+{
+    LB,
+    LBU,
+    LH,
+    LHU,
+    LW,
+    LWU,
+    SB,
+    SH,
+    SW,
+    ADD,
+    ADDI,
+    ADDIU,
+    ADDU,
+    AND,
+    ANDI,
+    LUI,
+    NOR,
+    OR,
+    ORI,
+    SLL,
+    SLLV,
+    SLT,
+    SLTI,
+    SLTIU,
+    SLTU,
+    SRA,
+    SRAV,
+    SRL,
+    SRLV,
+    SUB,
+    SUBU,
+    XOR,
+    XORI,
+    BEQ,
+    BGEZ,
+    BGEZAL,
+    BGTZ,
+    BLEZ,
+    BLTZ,
+    BLTZAL,
+    BNE,
+    J,
+    JAL,
+    JALR,
+    JR,
+    BREAK,
+    MFC0,
+    MTC0,
+    MFC2,
+    CFC2,
+    MTC2,
+    CTC2,
+    LBV,
+    LSV,
+    LLV,
+    LDV,
+    LQV,
+    LRV,
+    LPV,
+    LUV,
+    LHV,
+    LFV,
+    LTV,
+    SBV,
+    SSV,
+    SLV,
+    SDV,
+    SQV,
+    SRV,
+    SPV,
+    SUV,
+    SHV,
+    SFV,
+    SWV,
+    STV,
+    VMULF,
+    VMULU,
+    VRNDP,
+    VMULQ,
+    VMUDL,
+    VMUDM,
+    VMUDN,
+    VMUDH,
+    VMACF,
+    VMACU,
+    VRNDN,
+    VMACQ,
+    VMADL,
+    VMADM,
+    VMADN,
+    VMADH,
+    VADD,
+    VSUB,
+    VABS,
+    VADDC,
+    VSUBC,
+    VSAR,
+    VLT,
+    VEQ,
+    VNE,
+    VGE,
+    VCL,
+    VCH,
+    VCR,
+    VMRG,
+    VAND,
+    VNAND,
+    VOR,
+    VNOR,
+    VXOR,
+    VNXOR,
+    VRCP,
+    VRCPL,
+    VRCPH,
+    VMOV,
+    VRSQ,
+    VRSQL,
+    VRSQH,
+    VNOP,
+    VZERO,
+    UNIMPLEMENTED,
+}
+
+impl RSPCommand
+{
+    // Referenced: https://n64brew.dev/wiki/Reality_Signal_Processor/CPU_Core
+    pub fn from_opcode(opcode: u32) -> RSPCommand
+    {
+        let command_value: u8 = (opcode >> 26) as u8;
+        let command2_value: u8 = ((opcode >> 21) & 0x0000001F) as u8;
+        let branch_value: u8 = ((opcode >> 16) & 0x0000001F) as u8;
+        let secondary_value: u8 = (opcode & 0x0000003F) as u8;
+        let vector_memory_value: u8 = ((opcode >> 11) & 0x0000001F) as u8;
+        match command_value
+        {
+            0b000000 =>
+            {
+                match secondary_value
+                {
+                    0b000000 => RSPCommand::SLL,
+                    0b000010 => RSPCommand::SRL,
+                    0b000011 => RSPCommand::SRA,
+                    0b000100 => RSPCommand::SLLV,
+                    0b000110 => RSPCommand::SRLV,
+                    0b000111 => RSPCommand::SRAV,
+                    0b001000 => RSPCommand::JR,
+                    0b001001 => RSPCommand::JALR,
+                    0b001101 => RSPCommand::BREAK,
+                    0b100000 => RSPCommand::ADD,
+                    0b100001 => RSPCommand::ADDU,
+                    0b100010 => RSPCommand::SUB,
+                    0b100011 => RSPCommand::SUBU,
+                    0b100100 => RSPCommand::AND,
+                    0b100101 => RSPCommand::OR,
+                    0b100110 => RSPCommand::XOR,
+                    0b100111 => RSPCommand::NOR,
+                    0b101010 => RSPCommand::SLT,
+                    0b101011 => RSPCommand::SLTU,
+                    _ => RSPCommand::UNIMPLEMENTED,
+                }
+            },
+            0b000001 =>
+            {
+                match branch_value
+                {
+                    0b00000 => RSPCommand::BLTZ,
+                    0b00001 => RSPCommand::BGEZ,
+                    0b10000 => RSPCommand::BLTZAL,
+                    0b10001 => RSPCommand::BGEZAL,
+                    _ => RSPCommand::UNIMPLEMENTED,
+                }
+            },
+            0b000010 => RSPCommand::J,
+            0b000011 => RSPCommand::JAL,
+            0b000100 => RSPCommand::BEQ,
+            0b000101 => RSPCommand::BNE,
+            0b000110 => RSPCommand::BLEZ,
+            0b000111 => RSPCommand::BGTZ,
+            0b001000 => RSPCommand::ADDI,
+            0b001001 => RSPCommand::ADDIU,
+            0b001010 => RSPCommand::SLTI,
+            0b001011 => RSPCommand::SLTIU,
+            0b001100 => RSPCommand::ANDI,
+            0b001101 => RSPCommand::ORI,
+            0b001110 => RSPCommand::XORI,
+            0b001111 => RSPCommand::LUI,
+            0b010000 =>
+            {
+                match command2_value
+                {
+                    0b00000 => RSPCommand::MFC0,
+                    0b00100 => RSPCommand::MTC0,
+                    _ => RSPCommand::UNIMPLEMENTED,
+                }
+            },
+            0b010010 =>
+            {
+                if opcode & 0x02000000 != 0
+                {
+                    RSPCommand::from_vector_opcode(secondary_value)
+                }
+                else
+                {
+                    match command2_value
+                    {
+                        0b00000 => RSPCommand::MFC2,
+                        0b00010 => RSPCommand::CFC2,
+                        0b00100 => RSPCommand::MTC2,
+                        0b00110 => RSPCommand::CTC2,
+                        _ => RSPCommand::UNIMPLEMENTED,
+                    }
+                }
+            },
+            0b100000 => RSPCommand::LB,
+            0b100001 => RSPCommand::LH,
+            0b100011 => RSPCommand::LW,
+            0b100100 => RSPCommand::LBU,
+            0b100101 => RSPCommand::LHU,
+            0b100111 => RSPCommand::LWU,
+            0b101000 => RSPCommand::SB,
+            0b101001 => RSPCommand::SH,
+            0b101011 => RSPCommand::SW,
+            0b110010 =>
+            {
+                match vector_memory_value
+                {
+                    0b00000 => RSPCommand::LBV,
+                    0b00001 => RSPCommand::LSV,
+                    0b00010 => RSPCommand::LLV,
+                    0b00011 => RSPCommand::LDV,
+                    0b00100 => RSPCommand::LQV,
+                    0b00101 => RSPCommand::LRV,
+                    0b00110 => RSPCommand::LPV,
+                    0b00111 => RSPCommand::LUV,
+                    0b01000 => RSPCommand::LHV,
+                    0b01001 => RSPCommand::LFV,
+                    0b01011 => RSPCommand::LTV,
+                    _ => RSPCommand::UNIMPLEMENTED,
+                }
+            },
+            0b111010 =>
+            {
+                match vector_memory_value
+                {
+                    0b00000 => RSPCommand::SBV,
+                    0b00001 => RSPCommand::SSV,
+                    0b00010 => RSPCommand::SLV,
+                    0b00011 => RSPCommand::SDV,
+                    0b00100 => RSPCommand::SQV,
+                    0b00101 => RSPCommand::SRV,
+                    0b00110 => RSPCommand::SPV,
+                    0b00111 => RSPCommand::SUV,
+                    0b01000 => RSPCommand::SHV,
+                    0b01001 => RSPCommand::SFV,
+                    0b01010 => RSPCommand::SWV,
+                    0b01011 => RSPCommand::STV,
+                    _ => RSPCommand::UNIMPLEMENTED,
+                }
+            },
+            _ => RSPCommand::UNIMPLEMENTED,
+        }
+    }
+
+    fn from_vector_opcode(secondary_value: u8) -> RSPCommand
+    {
+        match secondary_value
+        {
+            0b000000 => RSPCommand::VMULF,
+            0b000001 => RSPCommand::VMULU,
+            0b000010 => RSPCommand::VRNDP,
+            0b000011 => RSPCommand::VMULQ,
+            0b000100 => RSPCommand::VMUDL,
+            0b000101 => RSPCommand::VMUDM,
+            0b000110 => RSPCommand::VMUDN,
+            0b000111 => RSPCommand::VMUDH,
+            0b001000 => RSPCommand::VMACF,
+            0b001001 => RSPCommand::VMACU,
+            0b001010 => RSPCommand::VRNDN,
+            0b001011 => RSPCommand::VMACQ,
+            0b001100 => RSPCommand::VMADL,
+            0b001101 => RSPCommand::VMADM,
+            0b001110 => RSPCommand::VMADN,
+            0b001111 => RSPCommand::VMADH,
+            0b010000 => RSPCommand::VADD,
+            0b010001 => RSPCommand::VSUB,
+            0b010011 => RSPCommand::VABS,
+            0b010100 => RSPCommand::VADDC,
+            0b010101 => RSPCommand::VSUBC,
+            0b011101 => RSPCommand::VSAR,
+            0b100000 => RSPCommand::VLT,
+            0b100001 => RSPCommand::VEQ,
+            0b100010 => RSPCommand::VNE,
+            0b100011 => RSPCommand::VGE,
+            0b100100 => RSPCommand::VCL,
+            0b100101 => RSPCommand::VCH,
+            0b100110 => RSPCommand::VCR,
+            0b100111 => RSPCommand::VMRG,
+            0b101000 => RSPCommand::VAND,
+            0b101001 => RSPCommand::VNAND,
+            0b101010 => RSPCommand::VOR,
+            0b101011 => RSPCommand::VNOR,
+            0b101100 => RSPCommand::VXOR,
+            0b101101 => RSPCommand::VNXOR,
+            0b110000 => RSPCommand::VRCP,
+            0b110001 => RSPCommand::VRCPL,
+            0b110010 => RSPCommand::VRCPH,
+            0b110011 => RSPCommand::VMOV,
+            0b110100 => RSPCommand::VRSQ,
+            0b110101 => RSPCommand::VRSQL,
+            0b110110 => RSPCommand::VRSQH,
+            0b110111 | 0b111111 => RSPCommand::VNOP,
+            _ => RSPCommand::VZERO,
+        }
+    }
+
+    pub fn parse(self, opcode: &RSPOpcode, core: &mut RSPCore, connector: &mut Connector) -> Result<(), Exception>
+    {
+        match self
+        {
+            //The RSP has no overflow exceptions, so the trapping forms behave like the unsigned ones
+            RSPCommand::ADD | RSPCommand::ADDU => execute_ADDU(opcode, core),
+            RSPCommand::ADDI | RSPCommand::ADDIU => execute_ADDIU(opcode, core),
+            RSPCommand::AND => execute_AND(opcode, core),
+            RSPCommand::ANDI => execute_ANDI(opcode, core),
+            RSPCommand::BEQ => execute_BEQ(opcode, core, connector),
+            RSPCommand::BGEZ => execute_BGEZ(opcode, core, connector),
+            RSPCommand::BGEZAL => execute_BGEZAL(opcode, core, connector),
+            RSPCommand::BGTZ => execute_BGTZ(opcode, core, connector),
+            RSPCommand::BLEZ => execute_BLEZ(opcode, core, connector),
+            RSPCommand::BLTZ => execute_BLTZ(opcode, core, connector),
+            RSPCommand::BLTZAL => execute_BLTZAL(opcode, core, connector),
+            RSPCommand::BNE => execute_BNE(opcode, core, connector),
+            RSPCommand::BREAK => execute_BREAK(connector),
+            RSPCommand::J => execute_J(opcode, core),
+            RSPCommand::JAL => execute_JAL(opcode, core, connector),
+            RSPCommand::JALR => execute_JALR(opcode, core, connector),
+            RSPCommand::JR => execute_JR(opcode, core),
+            RSPCommand::LB => execute_LB(opcode, core, connector),
+            RSPCommand::LBU => execute_LBU(opcode, core, connector),
+            RSPCommand::LH => execute_LH(opcode, core, connector),
+            RSPCommand::LHU => execute_LHU(opcode, core, connector),
+            RSPCommand::LW | RSPCommand::LWU => execute_LW(opcode, core, connector),
+            RSPCommand::LUI => execute_LUI(opcode, core),
+            RSPCommand::MFC0 => execute_MFC0(opcode, core, connector)?,
+            RSPCommand::MTC0 => execute_MTC0(opcode, core, connector)?,
+            RSPCommand::NOR => execute_NOR(opcode, core),
+            RSPCommand::OR => execute_OR(opcode, core),
+            RSPCommand::ORI => execute_ORI(opcode, core),
+            RSPCommand::SB => execute_SB(opcode, core, connector),
+            RSPCommand::SH => execute_SH(opcode, core, connector),
+            RSPCommand::SLL => execute_SLL(opcode, core),
+            RSPCommand::SLLV => execute_SLLV(opcode, core),
+            RSPCommand::SLT => execute_SLT(opcode, core),
+            RSPCommand::SLTI => execute_SLTI(opcode, core),
+            RSPCommand::SLTIU => execute_SLTIU(opcode, core),
+            RSPCommand::SLTU => execute_SLTU(opcode, core),
+            RSPCommand::SRA => execute_SRA(opcode, core),
+            RSPCommand::SRAV => execute_SRAV(opcode, core),
+            RSPCommand::SRL => execute_SRL(opcode, core),
+            RSPCommand::SRLV => execute_SRLV(opcode, core),
+            RSPCommand::SUB | RSPCommand::SUBU => execute_SUBU(opcode, core),
+            RSPCommand::SW => execute_SW(opcode, core, connector),
+            RSPCommand::XOR => execute_XOR(opcode, core),
+            RSPCommand::XORI => execute_XORI(opcode, core),
+            RSPCommand::MFC2 => execute_MFC2(opcode, core),
+            RSPCommand::CFC2 => execute_CFC2(opcode, core),
+            RSPCommand::MTC2 => execute_MTC2(opcode, core),
+            RSPCommand::CTC2 => execute_CTC2(opcode, core),
+            RSPCommand::LBV => execute_load_bytes(opcode, core, connector, 1),
+            RSPCommand::LSV => execute_load_bytes(opcode, core, connector, 2),
+            RSPCommand::LLV => execute_load_bytes(opcode, core, connector, 4),
+            RSPCommand::LDV => execute_load_bytes(opcode, core, connector, 8),
+            RSPCommand::LQV => execute_LQV(opcode, core, connector),
+            RSPCommand::LRV => execute_LRV(opcode, core, connector),
+            RSPCommand::LPV => execute_LPV(opcode, core, connector),
+            RSPCommand::LUV => execute_LUV(opcode, core, connector),
+            RSPCommand::LHV => execute_LHV(opcode, core, connector),
+            RSPCommand::LFV => execute_LFV(opcode, core, connector),
+            RSPCommand::LTV => execute_LTV(opcode, core, connector),
+            RSPCommand::SBV => execute_store_bytes(opcode, core, connector, 1),
+            RSPCommand::SSV => execute_store_bytes(opcode, core, connector, 2),
+            RSPCommand::SLV => execute_store_bytes(opcode, core, connector, 4),
+            RSPCommand::SDV => execute_store_bytes(opcode, core, connector, 8),
+            RSPCommand::SQV => execute_SQV(opcode, core, connector),
+            RSPCommand::SRV => execute_SRV(opcode, core, connector),
+            RSPCommand::SPV => execute_SPV(opcode, core, connector),
+            RSPCommand::SUV => execute_SUV(opcode, core, connector),
+            RSPCommand::SHV => execute_SHV(opcode, core, connector),
+            RSPCommand::SFV => execute_SFV(opcode, core, connector),
+            RSPCommand::SWV => execute_SWV(opcode, core, connector),
+            RSPCommand::STV => execute_STV(opcode, core, connector),
+            RSPCommand::VMULF => execute_VMULF(opcode, &mut core.vector_unit),
+            RSPCommand::VMULU => execute_VMULU(opcode, &mut core.vector_unit),
+            RSPCommand::VRNDP => execute_VRNDP(opcode, &mut core.vector_unit),
+            RSPCommand::VMULQ => execute_VMULQ(opcode, &mut core.vector_unit),
+            RSPCommand::VMUDL => execute_VMUDL(opcode, &mut core.vector_unit),
+            RSPCommand::VMUDM => execute_VMUDM(opcode, &mut core.vector_unit),
+            RSPCommand::VMUDN => execute_VMUDN(opcode, &mut core.vector_unit),
+            RSPCommand::VMUDH => execute_VMUDH(opcode, &mut core.vector_unit),
+            RSPCommand::VMACF => execute_VMACF(opcode, &mut core.vector_unit),
+            RSPCommand::VMACU => execute_VMACU(opcode, &mut core.vector_unit),
+            RSPCommand::VRNDN => execute_VRNDN(opcode, &mut core.vector_unit),
+            RSPCommand::VMACQ => execute_VMACQ(opcode, &mut core.vector_unit),
+            RSPCommand::VMADL => execute_VMADL(opcode, &mut core.vector_unit),
+            RSPCommand::VMADM => execute_VMADM(opcode, &mut core.vector_unit),
+            RSPCommand::VMADN => execute_VMADN(opcode, &mut core.vector_unit),
+            RSPCommand::VMADH => execute_VMADH(opcode, &mut core.vector_unit),
+            RSPCommand::VADD => execute_VADD(opcode, &mut core.vector_unit),
+            RSPCommand::VSUB => execute_VSUB(opcode, &mut core.vector_unit),
+            RSPCommand::VABS => execute_VABS(opcode, &mut core.vector_unit),
+            RSPCommand::VADDC => execute_VADDC(opcode, &mut core.vector_unit),
+            RSPCommand::VSUBC => execute_VSUBC(opcode, &mut core.vector_unit),
+            RSPCommand::VSAR => execute_VSAR(opcode, &mut core.vector_unit),
+            RSPCommand::VLT => execute_VLT(opcode, &mut core.vector_unit),
+            RSPCommand::VEQ => execute_VEQ(opcode, &mut core.vector_unit),
+            RSPCommand::VNE => execute_VNE(opcode, &mut core.vector_unit),
+            RSPCommand::VGE => execute_VGE(opcode, &mut core.vector_unit),
+            RSPCommand::VCL => execute_VCL(opcode, &mut core.vector_unit),
+            RSPCommand::VCH => execute_VCH(opcode, &mut core.vector_unit),
+            RSPCommand::VCR => execute_VCR(opcode, &mut core.vector_unit),
+            RSPCommand::VMRG => execute_VMRG(opcode, &mut core.vector_unit),
+            RSPCommand::VAND => execute_VAND(opcode, &mut core.vector_unit),
+            RSPCommand::VNAND => execute_VNAND(opcode, &mut core.vector_unit),
+            RSPCommand::VOR => execute_VOR(opcode, &mut core.vector_unit),
+            RSPCommand::VNOR => execute_VNOR(opcode, &mut core.vector_unit),
+            RSPCommand::VXOR => execute_VXOR(opcode, &mut core.vector_unit),
+            RSPCommand::VNXOR => execute_VNXOR(opcode, &mut core.vector_unit),
+            RSPCommand::VRCP => execute_VRCP(opcode, &mut core.vector_unit),
+            RSPCommand::VRCPL => execute_VRCPL(opcode, &mut core.vector_unit),
+            RSPCommand::VRCPH => execute_VRCPH(opcode, &mut core.vector_unit),
+            RSPCommand::VMOV => execute_VMOV(opcode, &mut core.vector_unit),
+            RSPCommand::VRSQ => execute_VRSQ(opcode, &mut core.vector_unit),
+            RSPCommand::VRSQL => execute_VRSQL(opcode, &mut core.vector_unit),
+            RSPCommand::VRSQH => execute_VRSQH(opcode, &mut core.vector_unit),
+            RSPCommand::VNOP => (),
+            RSPCommand::VZERO => execute_VZERO(opcode, &mut core.vector_unit),
+            RSPCommand::UNIMPLEMENTED => return Err(Exception::UNIMPLEMENTED_OPCODE),
+        };
+        Ok(())
+    }
+}
+
+impl fmt::Display for RSPCommand
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+fn current_pc(connector: &Connector) -> u32
+{
+    connector.rsp.program_counter.get_value() as u32
+}
+
+//The program counter already points at the delay slot when the branch executes
+fn branch(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    let target = (current_pc(connector) as i32 + ((opcode.imm as i16 as i32) * 4)) as u32;
+    core.pc_save = target & RSP_PC_MASK;
+    core.pc_save_count = 2;
+}
+
+fn link(core: &mut RSPCore, register: u8, connector: &Connector)
+{
+    core.write_register(register, (current_pc(connector) + 4) & RSP_LINK_MASK);
+}
+
+fn address(opcode: &RSPOpcode, core: &RSPCore) -> usize
+{
+    (core.read_register(opcode.base) as i32).wrapping_add(opcode.offset as i16 as i32) as u32 as usize & SP_MEMORY_MASK
+}
+
+fn read_dmem(connector: &Connector, address: usize, length: usize) -> u32
+{
+    (0..length).fold(0_u32, |value, offset| (value << 8) | connector.rsp.read_dmem_u8(address + offset) as u32)
+}
+
+fn store_dmem(connector: &mut Connector, address: usize, length: usize, value: u32)
+{
+    for offset in 0..length
+    {
+        connector.rsp.store_dmem_u8(address + offset, (value >> ((length - 1 - offset) * 8)) as u8);
+    }
+}
+
+fn execute_ADDIU(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = (core.read_register(opcode.rs) as i32).wrapping_add(opcode.imm as i16 as i32) as u32;
+    core.write_register(opcode.rt, new_value);
+}
+
+fn execute_ADDU(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = core.read_register(opcode.rs).wrapping_add(core.read_register(opcode.rt));
+    core.write_register(opcode.rd, new_value);
+}
+
+fn execute_AND(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = core.read_register(opcode.rs) & core.read_register(opcode.rt);
+    core.write_register(opcode.rd, new_value);
+}
+
+fn execute_ANDI(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = core.read_register(opcode.rs) & (opcode.imm as u32);
+    core.write_register(opcode.rt, new_value);
+}
+
+fn execute_BEQ(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    if core.read_register(opcode.rs) == core.read_register(opcode.rt)
+    {
+        branch(opcode, core, connector);
+    }
+}
+
+fn execute_BGEZ(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    if core.read_register(opcode.rs) as i32 >= 0
+    {
+        branch(opcode, core, connector);
+    }
+}
+
+fn execute_BGEZAL(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    let test_value = core.read_register(opcode.rs) as i32;
+    link(core, 31, connector);
+    if test_value >= 0
+    {
+        branch(opcode, core, connector);
+    }
+}
+
+fn execute_BGTZ(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    if core.read_register(opcode.rs) as i32 > 0
+    {
+        branch(opcode, core, connector);
+    }
+}
+
+fn execute_BLEZ(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    if core.read_register(opcode.rs) as i32 <= 0
+    {
+        branch(opcode, core, connector);
+    }
+}
+
+fn execute_BLTZ(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    if (core.read_register(opcode.rs) as i32) < 0
+    {
+        branch(opcode, core, connector);
+    }
+}
+
+fn execute_BLTZAL(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    let test_value = core.read_register(opcode.rs) as i32;
+    link(core, 31, connector);
+    if test_value < 0
+    {
+        branch(opcode, core, connector);
+    }
+}
+
+fn execute_BNE(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    if core.read_register(opcode.rs) != core.read_register(opcode.rt)
+    {
+        branch(opcode, core, connector);
+    }
+}
+
+fn execute_BREAK(connector: &mut Connector)
+{
+    connector.rsp.set_status_bits(SP_STATUS_HALT | SP_STATUS_BROKE);
+    if connector.rsp.status.get_value() as u32 & SP_STATUS_INTR_BREAK != 0
+    {
+        connector.mips_interface.raise_interrupt(MI_INTR_SP);
+    }
+}
+
+fn execute_J(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    core.pc_save = (opcode.target << 2) & RSP_PC_MASK;
+    core.pc_save_count = 2;
+}
+
+fn execute_JAL(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    link(core, 31, connector);
+    execute_J(opcode, core);
+}
+
+fn execute_JALR(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    let target = core.read_register(opcode.rs);
+    link(core, opcode.rd, connector);
+    core.pc_save = target & RSP_PC_MASK;
+    core.pc_save_count = 2;
+}
+
+fn execute_JR(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    core.pc_save = core.read_register(opcode.rs) & RSP_PC_MASK;
+    core.pc_save_count = 2;
+}
+
+fn execute_LB(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    let new_value = read_dmem(connector, address(opcode, core), 1) as u8 as i8 as i32 as u32;
+    core.write_register(opcode.rt, new_value);
+}
+
+fn execute_LBU(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    let new_value = read_dmem(connector, address(opcode, core), 1);
+    core.write_register(opcode.rt, new_value);
+}
+
+fn execute_LH(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    let new_value = read_dmem(connector, address(opcode, core), 2) as u16 as i16 as i32 as u32;
+    core.write_register(opcode.rt, new_value);
+}
+
+fn execute_LHU(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    let new_value = read_dmem(connector, address(opcode, core), 2);
+    core.write_register(opcode.rt, new_value);
+}
+
+fn execute_LW(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    let new_value = read_dmem(connector, address(opcode, core), 4);
+    core.write_register(opcode.rt, new_value);
+}
+
+fn execute_LUI(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    core.write_register(opcode.rt, (opcode.imm as u32) << 16);
+}
+
+fn execute_MFC0(opcode: &RSPOpcode, core: &mut RSPCore, connector: &mut Connector) -> Result<(), Exception>
+{
+    let new_value = connector.read_rsp_cop0((opcode.rd & 0xF) as usize)?;
+    core.write_register(opcode.rt, new_value);
+    Ok(())
+}
+
+fn execute_MTC0(opcode: &RSPOpcode, core: &mut RSPCore, connector: &mut Connector) -> Result<(), Exception>
+{
+    connector.store_rsp_cop0((opcode.rd & 0xF) as usize, core.read_register(opcode.rt))
+}
+
+fn execute_NOR(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = !(core.read_register(opcode.rs) | core.read_register(opcode.rt));
+    core.write_register(opcode.rd, new_value);
+}
+
+fn execute_OR(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = core.read_register(opcode.rs) | core.read_register(opcode.rt);
+    core.write_register(opcode.rd, new_value);
+}
+
+fn execute_ORI(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = core.read_register(opcode.rs) | (opcode.imm as u32);
+    core.write_register(opcode.rt, new_value);
+}
+
+fn execute_SB(opcode: &RSPOpcode, core: &RSPCore, connector: &mut Connector)
+{
+    store_dmem(connector, address(opcode, core), 1, core.read_register(opcode.rt));
+}
+
+fn execute_SH(opcode: &RSPOpcode, core: &RSPCore, connector: &mut Connector)
+{
+    store_dmem(connector, address(opcode, core), 2, core.read_register(opcode.rt));
+}
+
+fn execute_SW(opcode: &RSPOpcode, core: &RSPCore, connector: &mut Connector)
+{
+    store_dmem(connector, address(opcode, core), 4, core.read_register(opcode.rt));
+}
+
+fn execute_SLL(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = core.read_register(opcode.rt) << (opcode.sa as u32);
+    core.write_register(opcode.rd, new_value);
+}
+
+fn execute_SLLV(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = core.read_register(opcode.rt) << (core.read_register(opcode.rs) & 0x1F);
+    core.write_register(opcode.rd, new_value);
+}
+
+fn execute_SLT(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = ((core.read_register(opcode.rs) as i32) < (core.read_register(opcode.rt) as i32)) as u32;
+    core.write_register(opcode.rd, new_value);
+}
+
+fn execute_SLTI(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = ((core.read_register(opcode.rs) as i32) < (opcode.imm as i16 as i32)) as u32;
+    core.write_register(opcode.rt, new_value);
+}
+
+fn execute_SLTIU(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    //The immediate is sign extended before the unsigned compare
+    let new_value = (core.read_register(opcode.rs) < (opcode.imm as i16 as i32 as u32)) as u32;
+    core.write_register(opcode.rt, new_value);
+}
+
+fn execute_SLTU(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = (core.read_register(opcode.rs) < core.read_register(opcode.rt)) as u32;
+    core.write_register(opcode.rd, new_value);
+}
+
+fn execute_SRA(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = ((core.read_register(opcode.rt) as i32) >> (opcode.sa as u32)) as u32;
+    core.write_register(opcode.rd, new_value);
+}
+
+fn execute_SRAV(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = ((core.read_register(opcode.rt) as i32) >> (core.read_register(opcode.rs) & 0x1F)) as u32;
+    core.write_register(opcode.rd, new_value);
+}
+
+fn execute_SRL(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = core.read_register(opcode.rt) >> (opcode.sa as u32);
+    core.write_register(opcode.rd, new_value);
+}
+
+fn execute_SRLV(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = core.read_register(opcode.rt) >> (core.read_register(opcode.rs) & 0x1F);
+    core.write_register(opcode.rd, new_value);
+}
+
+fn execute_SUBU(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = core.read_register(opcode.rs).wrapping_sub(core.read_register(opcode.rt));
+    core.write_register(opcode.rd, new_value);
+}
+
+fn execute_XOR(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = core.read_register(opcode.rs) ^ core.read_register(opcode.rt);
+    core.write_register(opcode.rd, new_value);
+}
+
+fn execute_XORI(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let new_value = core.read_register(opcode.rs) ^ (opcode.imm as u32);
+    core.write_register(opcode.rt, new_value);
+}
@@ -1,7 +1,17 @@
 #[cfg(test)]
 mod rsp_tests
 {
-    use n64::rsp::RealitySignalProcessor;
+    use n64::rsp::*;
+    use n64::connector::Connector;
+    use n64::mips_iface::MI_INTR_SP;
+
+    const SP_MEM_ADDR: u32 = 0x04040000;
+    const SP_DRAM_ADDR: u32 = 0x04040004;
+    const SP_RD_LEN: u32 = 0x04040008;
+    const SP_WR_LEN: u32 = 0x0404000C;
+    const SP_STATUS: u32 = 0x04040010;
+    const SP_DMA_BUSY: u32 = 0x04040018;
+    const SP_SEMAPHORE: u32 = 0x0404001C;
 
     #[test]
     fn load_all_dmem_and_imem_values_by_u8_vector()
@@ -16,4 +26,98 @@ mod rsp_tests
             assert_eq!(0xFF, rsp.read_u8_from_address(0x1000 + address).unwrap());
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn status_writes_set_and_clear_bits()
+    {
+        let mut connector = Connector::test();
+        //Clear halt, set sstep, set intr break and set signal 0 and signal 7
+        connector.store_u32(SP_STATUS, 0x00000001 | 0x00000040 | 0x00000100 | 0x00000400 | 0x01000000).unwrap();
+        assert_eq!(connector.read_u32(SP_STATUS).unwrap(), SP_STATUS_SSTEP | SP_STATUS_INTR_BREAK | SP_STATUS_SIGNAL_0 | (SP_STATUS_SIGNAL_0 << 7));
+
+        //Writing both halves of a pair changes nothing
+        connector.store_u32(SP_STATUS, 0x00000020 | 0x00000040 | 0x00000200).unwrap();
+        assert_eq!(connector.read_u32(SP_STATUS).unwrap(), SP_STATUS_SSTEP | SP_STATUS_INTR_BREAK | (SP_STATUS_SIGNAL_0 << 7));
+
+        connector.rsp.set_status_bits(SP_STATUS_BROKE);
+        connector.store_u32(SP_STATUS, 0x00000002 | 0x00000004).unwrap();
+        assert!(connector.rsp.is_halted());
+        assert_eq!(connector.read_u32(SP_STATUS).unwrap() & SP_STATUS_BROKE, 0);
+
+        connector.store_u32(SP_STATUS, 0x00000010).unwrap();
+        assert_eq!(connector.mips_interface.interrupt.get_value() as u32 & MI_INTR_SP, MI_INTR_SP);
+        connector.store_u32(SP_STATUS, 0x00000008).unwrap();
+        assert_eq!(connector.mips_interface.interrupt.get_value() as u32 & MI_INTR_SP, 0);
+    }
+
+    #[test]
+    fn semaphore_is_acquired_by_reading()
+    {
+        let mut connector = Connector::test();
+        assert_eq!(connector.read_u32(SP_SEMAPHORE).unwrap(), 0);
+        assert_eq!(connector.read_u32(SP_SEMAPHORE).unwrap(), 1);
+        connector.store_u32(SP_SEMAPHORE, 0).unwrap();
+        assert_eq!(connector.read_u32(SP_SEMAPHORE).unwrap(), 0);
+    }
+
+    #[test]
+    fn dma_copies_rows_with_skip()
+    {
+        let mut connector = Connector::test();
+        for address in 0..0x40
+        {
+            connector.rdram.store_u8(0x1000 + address, address as u8).unwrap();
+        }
+        connector.store_u32(SP_MEM_ADDR, 0x1FF8).unwrap();
+        connector.store_u32(SP_DRAM_ADDR, 0x1000).unwrap();
+        //Two rows of 8 bytes, skipping 8 bytes between them, into the end of IMEM
+        connector.store_u32(SP_RD_LEN, (8 << 20) | (1 << 12) | 7).unwrap();
+        //Nothing lands until the 16 bytes have taken their 2 cycles
+        connector.step(1);
+        assert_eq!(connector.rsp.instruction_memory[0xFF8..0x1000].to_vec(), vec![0; 8]);
+        assert_eq!(connector.read_u32(SP_MEM_ADDR).unwrap(), 0x1FF8);
+        connector.step(1);
+        assert_eq!(connector.rsp.instruction_memory[0xFF8..0x1000].to_vec(), (0x00..0x08).collect::<Vec<u8>>());
+        assert_eq!(connector.rsp.instruction_memory[0x000..0x008].to_vec(), (0x10..0x18).collect::<Vec<u8>>());
+        assert_eq!(connector.read_u32(SP_MEM_ADDR).unwrap(), 0x1008);
+        assert_eq!(connector.read_u32(SP_DRAM_ADDR).unwrap(), 0x1020);
+        assert_eq!(connector.read_u32(SP_RD_LEN).unwrap(), (8 << 20) | 0xFF8);
+
+        connector.step(100);
+        connector.store_u32(SP_MEM_ADDR, 0x0004).unwrap();
+        connector.store_u32(SP_DRAM_ADDR, 0x2000).unwrap();
+        //Lengths round up to whole words and the SP address is word aligned
+        connector.rsp.dynamic_memory[0..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        connector.store_u32(SP_WR_LEN, 2).unwrap();
+        connector.step(1);
+        assert_eq!(connector.rdram.read_bytes(0x2000, 8).unwrap(), vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn dma_queues_behind_busy_transfer()
+    {
+        let mut connector = Connector::test();
+        connector.rdram.store_u8(0x100, 0xAA).unwrap();
+        connector.rdram.store_u8(0x200, 0xBB).unwrap();
+        connector.store_u32(SP_DRAM_ADDR, 0x100).unwrap();
+        connector.store_u32(SP_RD_LEN, 0xFF).unwrap();
+        connector.store_u32(SP_MEM_ADDR, 0x800).unwrap();
+        connector.store_u32(SP_DRAM_ADDR, 0x200).unwrap();
+        connector.store_u32(SP_RD_LEN, 0x7).unwrap();
+        assert_eq!(connector.read_u32(SP_DMA_BUSY).unwrap(), 1);
+        assert_eq!(connector.read_u32(SP_STATUS).unwrap() & (SP_STATUS_DMA_BUSY | SP_STATUS_DMA_FULL), SP_STATUS_DMA_BUSY | SP_STATUS_DMA_FULL);
+
+        //256 bytes take 32 cycles, then the queued transfer starts
+        connector.step(31);
+        assert!(connector.rsp.is_dma_full());
+        assert_eq!(connector.rsp.dynamic_memory[0], 0);
+        connector.step(1);
+        assert!(!connector.rsp.is_dma_full());
+        assert!(connector.rsp.is_dma_busy());
+        assert_eq!(connector.rsp.dynamic_memory[0], 0xAA);
+        assert_eq!(connector.rsp.dynamic_memory[0x800], 0);
+        connector.step(1);
+        assert_eq!(connector.rsp.dynamic_memory[0x800], 0xBB);
+        assert_eq!(connector.read_u32(SP_STATUS).unwrap() & (SP_STATUS_DMA_BUSY | SP_STATUS_DMA_FULL), 0);
+    }
+}
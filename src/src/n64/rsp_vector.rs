@@ -0,0 +1,225 @@
+// Referenced: https://n64brew.dev/wiki/Reality_Signal_Processor/CPU_Core and ares' RSP VU
+pub const VECTOR_REGISTERS: usize = 32;
+pub const VECTOR_ELEMENTS: usize = 8;
+const ACCUMULATOR_BITS: u32 = 48;
+const RECIPROCAL_TABLE_SIZE: usize = 512;
+
+//Element selectors for the e field of computational instructions
+const ELEMENT_SELECTION: [[usize; VECTOR_ELEMENTS]; 16] = [
+    [0, 1, 2, 3, 4, 5, 6, 7],
+    [0, 1, 2, 3, 4, 5, 6, 7],
+    [0, 0, 2, 2, 4, 4, 6, 6],
+    [1, 1, 3, 3, 5, 5, 7, 7],
+    [0, 0, 0, 0, 4, 4, 4, 4],
+    [1, 1, 1, 1, 5, 5, 5, 5],
+    [2, 2, 2, 2, 6, 6, 6, 6],
+    [3, 3, 3, 3, 7, 7, 7, 7],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [1, 1, 1, 1, 1, 1, 1, 1],
+    [2, 2, 2, 2, 2, 2, 2, 2],
+    [3, 3, 3, 3, 3, 3, 3, 3],
+    [4, 4, 4, 4, 4, 4, 4, 4],
+    [5, 5, 5, 5, 5, 5, 5, 5],
+    [6, 6, 6, 6, 6, 6, 6, 6],
+    [7, 7, 7, 7, 7, 7, 7, 7],
+];
+
+pub type Vector = [u16; VECTOR_ELEMENTS];
+
+pub struct VectorUnit
+{
+    pub registers: Vec<Vector>,
+    //48 bit accumulator lanes kept sign extended
+    pub accumulator: [i64; VECTOR_ELEMENTS],
+    //Low byte is carry and high byte is not equal
+    pub vco: u16,
+    //Low byte is compare and high byte is clip
+    pub vcc: u16,
+    pub vce: u8,
+    pub divide_in: u16,
+    pub divide_out: u16,
+    pub divide_in_loaded: bool,
+    pub reciprocal_table: Vec<u16>,
+    pub inverse_square_root_table: Vec<u16>,
+}
+
+impl VectorUnit
+{
+    pub fn new() -> VectorUnit
+    {
+        return VectorUnit
+        {
+            registers: vec![[0; VECTOR_ELEMENTS]; VECTOR_REGISTERS],
+            accumulator: [0; VECTOR_ELEMENTS],
+            vco: 0,
+            vcc: 0,
+            vce: 0,
+            divide_in: 0,
+            divide_out: 0,
+            divide_in_loaded: false,
+            reciprocal_table: build_reciprocal_table(),
+            inverse_square_root_table: build_inverse_square_root_table(),
+        }
+    }
+
+    //Registers are big endian, so byte 0 is the high byte of element 0
+    pub fn read_byte(&self, register: usize, byte: usize) -> u8
+    {
+        let element = self.registers[register][(byte & 0xF) >> 1];
+        if byte & 1 == 0 {(element >> 8) as u8} else {element as u8}
+    }
+
+    pub fn write_byte(&mut self, register: usize, byte: usize, value: u8)
+    {
+        let element = &mut self.registers[register][(byte & 0xF) >> 1];
+        *element = if byte & 1 == 0 {(*element & 0x00FF) | ((value as u16) << 8)} else {(*element & 0xFF00) | value as u16};
+    }
+
+    pub fn select(&self, register: usize, element: usize) -> Vector
+    {
+        let source = self.registers[register];
+        let mut result: Vector = [0; VECTOR_ELEMENTS];
+        for (index, selected) in ELEMENT_SELECTION[element & 0xF].iter().enumerate()
+        {
+            result[index] = source[*selected];
+        }
+        result
+    }
+
+    pub fn set_accumulator(&mut self, element: usize, value: i64)
+    {
+        let shift = 64 - ACCUMULATOR_BITS;
+        self.accumulator[element] = (value << shift) >> shift;
+    }
+
+    pub fn accumulator_low(&self, element: usize) -> u16
+    {
+        self.accumulator[element] as u16
+    }
+
+    pub fn accumulator_mid(&self, element: usize) -> u16
+    {
+        (self.accumulator[element] >> 16) as u16
+    }
+
+    pub fn accumulator_high(&self, element: usize) -> u16
+    {
+        (self.accumulator[element] >> 32) as u16
+    }
+
+    pub fn set_accumulator_low(&mut self, element: usize, value: u16)
+    {
+        let accumulator = (self.accumulator[element] & !0xFFFF) | value as i64;
+        self.set_accumulator(element, accumulator);
+    }
+
+    //Signed saturation of the high 32 bits of the accumulator
+    pub fn clamp_signed(&self, element: usize) -> u16
+    {
+        let value = self.accumulator[element] >> 16;
+        if value < -32768 {0x8000} else if value > 32767 {0x7FFF} else {value as u16}
+    }
+
+    pub fn clamp_unsigned(&self, element: usize) -> u16
+    {
+        let value = self.accumulator[element] >> 16;
+        if value < 0 {0x0000} else if value > 32767 {0xFFFF} else {value as u16}
+    }
+
+    //Low lane when the accumulator fits 32 bits, otherwise saturated
+    pub fn clamp_low(&self, element: usize) -> u16
+    {
+        let value = self.accumulator[element] >> 16;
+        if value < -32768 {0x0000} else if value > 32767 {0xFFFF} else {self.accumulator_low(element)}
+    }
+
+    pub fn reciprocal(&self, input: i32) -> u32
+    {
+        let mask = input >> 31;
+        let mut data = input ^ mask;
+        if input > -32768
+        {
+            data = data.wrapping_sub(mask);
+        }
+        if data == 0
+        {
+            return 0x7FFFFFFF;
+        }
+        if input == -32768
+        {
+            return 0xFFFF0000;
+        }
+        let shift = (data as u32).leading_zeros();
+        let index = ((((data as u32 as u64) << shift) & 0x7FC00000) >> 22) as usize;
+        let result = (0x10000 | self.reciprocal_table[index] as u32) << 14;
+        (result >> (31 - shift)) ^ mask as u32
+    }
+
+    pub fn inverse_square_root(&self, input: i32) -> u32
+    {
+        let mask = input >> 31;
+        let mut data = input ^ mask;
+        if input > -32768
+        {
+            data = data.wrapping_sub(mask);
+        }
+        if data == 0
+        {
+            return 0x7FFFFFFF;
+        }
+        if input == -32768
+        {
+            return 0xFFFF0000;
+        }
+        let shift = (data as u32).leading_zeros();
+        let index = ((((data as u32 as u64) << shift) & 0x7FC00000) >> 22) as usize;
+        let result = (0x10000 | self.inverse_square_root_table[(index & 0x1FE) | (shift as usize & 1)] as u32) << 14;
+        (result >> ((31 - shift) >> 1)) ^ mask as u32
+    }
+
+    pub fn flag(flags: u16, element: usize) -> bool
+    {
+        flags & (1 << element) != 0
+    }
+
+    pub fn set_flag(flags: &mut u16, element: usize, value: bool)
+    {
+        if value
+        {
+            *flags |= 1 << element;
+        }
+        else
+        {
+            *flags &= !(1 << element);
+        }
+    }
+}
+
+//Mantissas of 1/x for x in [1, 2), rounded the way the hardware ROM is
+fn build_reciprocal_table() -> Vec<u16>
+{
+    (0..RECIPROCAL_TABLE_SIZE).map(|index|
+    {
+        let divisor = (index + RECIPROCAL_TABLE_SIZE) as u64;
+        let quotient = (1_u64 << 34) / divisor;
+        //1/1.0 does not fit the 16 bit mantissa and saturates
+        (((quotient + 1) >> 8).min(0x1FFFF)) as u16
+    }).collect()
+}
+
+//Odd indices are used for odd exponents and take a halved input
+fn build_inverse_square_root_table() -> Vec<u16>
+{
+    (0..RECIPROCAL_TABLE_SIZE).map(|index|
+    {
+        let value = ((index + RECIPROCAL_TABLE_SIZE) >> (index % 2)) as u64;
+        let limit = 1_u64 << 44;
+        //Smallest b >= 2^17 where value * (b + 1)^2 reaches 2^44, the estimate starts just below it
+        let mut b = ((limit as f64 / value as f64).sqrt() as u64).saturating_sub(2).max(1 << 17);
+        while value * (b + 1) * (b + 1) < limit
+        {
+            b += 1;
+        }
+        (b >> 1) as u16
+    }).collect()
+}
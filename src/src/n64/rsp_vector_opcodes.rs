@@ -0,0 +1,852 @@
+//Handlers are named after the instruction mnemonics
+#![allow(non_snake_case)]
+
+use n64::rsp_opcodes::RSPOpcode;
+use n64::rsp_core::RSPCore;
+use n64::rsp_vector::{VectorUnit, Vector, VECTOR_ELEMENTS};
+use n64::connector::Connector;
+
+// Referenced: https://n64brew.dev/wiki/Reality_Signal_Processor/CPU_Core and ares' RSP VU
+
+fn operands(opcode: &RSPOpcode, unit: &VectorUnit) -> (Vector, Vector)
+{
+    (unit.registers[opcode.vs as usize], unit.select(opcode.vt as usize, opcode.element as usize))
+}
+
+fn clamp_i16(value: i64) -> u16
+{
+    if value < -32768 {0x8000} else if value > 32767 {0x7FFF} else {value as u16}
+}
+
+//Shared shape of the multiply family: update each accumulator lane, then pick the result from it
+fn multiply<F, C>(opcode: &RSPOpcode, unit: &mut VectorUnit, accumulate: F, clamp: C)
+    where F: Fn(i64, u16, u16) -> i64, C: Fn(&VectorUnit, usize) -> u16
+{
+    let (vs, vt) = operands(opcode, unit);
+    let mut result: Vector = [0; VECTOR_ELEMENTS];
+    for element in 0..VECTOR_ELEMENTS
+    {
+        let accumulator = accumulate(unit.accumulator[element], vs[element], vt[element]);
+        unit.set_accumulator(element, accumulator);
+        result[element] = clamp(unit, element);
+    }
+    unit.registers[opcode.vd as usize] = result;
+}
+
+//Results that only touch the low accumulator lane
+fn write_low(opcode: &RSPOpcode, unit: &mut VectorUnit, result: Vector)
+{
+    for element in 0..VECTOR_ELEMENTS
+    {
+        unit.set_accumulator_low(element, result[element]);
+    }
+    unit.registers[opcode.vd as usize] = result;
+}
+
+fn signed_product(vs: u16, vt: u16) -> i64
+{
+    vs as i16 as i64 * vt as i16 as i64
+}
+
+pub fn execute_VMULF(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    multiply(opcode, unit, |_, vs, vt| (signed_product(vs, vt) * 2) + 0x8000, VectorUnit::clamp_signed);
+}
+
+pub fn execute_VMULU(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    multiply(opcode, unit, |_, vs, vt| (signed_product(vs, vt) * 2) + 0x8000, VectorUnit::clamp_unsigned);
+}
+
+pub fn execute_VMACF(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    multiply(opcode, unit, |accumulator, vs, vt| accumulator + (signed_product(vs, vt) * 2), VectorUnit::clamp_signed);
+}
+
+pub fn execute_VMACU(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    multiply(opcode, unit, |accumulator, vs, vt| accumulator + (signed_product(vs, vt) * 2), VectorUnit::clamp_unsigned);
+}
+
+pub fn execute_VMUDL(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    multiply(opcode, unit, |_, vs, vt| (vs as i64 * vt as i64) >> 16, VectorUnit::clamp_low);
+}
+
+pub fn execute_VMADL(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    multiply(opcode, unit, |accumulator, vs, vt| accumulator + ((vs as i64 * vt as i64) >> 16), VectorUnit::clamp_low);
+}
+
+pub fn execute_VMUDM(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    multiply(opcode, unit, |_, vs, vt| vs as i16 as i64 * vt as i64, VectorUnit::clamp_signed);
+}
+
+pub fn execute_VMADM(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    multiply(opcode, unit, |accumulator, vs, vt| accumulator + (vs as i16 as i64 * vt as i64), VectorUnit::clamp_signed);
+}
+
+pub fn execute_VMUDN(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    multiply(opcode, unit, |_, vs, vt| vs as i64 * vt as i16 as i64, VectorUnit::clamp_low);
+}
+
+pub fn execute_VMADN(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    multiply(opcode, unit, |accumulator, vs, vt| accumulator + (vs as i64 * vt as i16 as i64), VectorUnit::clamp_low);
+}
+
+pub fn execute_VMUDH(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    multiply(opcode, unit, |_, vs, vt| signed_product(vs, vt) << 16, VectorUnit::clamp_signed);
+}
+
+pub fn execute_VMADH(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    multiply(opcode, unit, |accumulator, vs, vt| accumulator + (signed_product(vs, vt) << 16), VectorUnit::clamp_signed);
+}
+
+//MPEG helpers, results are rounded down to a multiple of 16
+pub fn execute_VMULQ(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    multiply(opcode, unit, |_, vs, vt|
+    {
+        let product = signed_product(vs, vt);
+        (if product < 0 {product + 31} else {product}) << 16
+    }, |unit, element| clamp_i16(unit.accumulator[element] >> 17) & !0xF);
+}
+
+pub fn execute_VMACQ(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    multiply(opcode, unit, |accumulator, _, _|
+    {
+        let mut product = accumulator >> 16;
+        if product < 0 && product & 0x20 == 0
+        {
+            product += 32;
+        }
+        else if product >= 32 && product & 0x20 == 0
+        {
+            product -= 32;
+        }
+        (product << 16) | (accumulator & 0xFFFF)
+    }, |unit, element| clamp_i16(unit.accumulator[element] >> 17) & !0xF);
+}
+
+fn round(opcode: &RSPOpcode, unit: &mut VectorUnit, positive: bool)
+{
+    let shift = if opcode.vs & 1 != 0 {16} else {0};
+    multiply(opcode, unit, |accumulator, _, vt|
+    {
+        if (accumulator >= 0) == positive
+        {
+            accumulator + ((vt as i16 as i64) << shift)
+        }
+        else
+        {
+            accumulator
+        }
+    }, VectorUnit::clamp_signed);
+}
+
+pub fn execute_VRNDP(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    round(opcode, unit, true);
+}
+
+pub fn execute_VRNDN(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    round(opcode, unit, false);
+}
+
+pub fn execute_VADD(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    let (vs, vt) = operands(opcode, unit);
+    let mut result: Vector = [0; VECTOR_ELEMENTS];
+    for element in 0..VECTOR_ELEMENTS
+    {
+        let sum = vs[element] as i16 as i64 + vt[element] as i16 as i64 + VectorUnit::flag(unit.vco, element) as i64;
+        unit.set_accumulator_low(element, sum as u16);
+        result[element] = clamp_i16(sum);
+    }
+    unit.registers[opcode.vd as usize] = result;
+    unit.vco = 0;
+}
+
+pub fn execute_VSUB(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    let (vs, vt) = operands(opcode, unit);
+    let mut result: Vector = [0; VECTOR_ELEMENTS];
+    for element in 0..VECTOR_ELEMENTS
+    {
+        let difference = vs[element] as i16 as i64 - vt[element] as i16 as i64 - VectorUnit::flag(unit.vco, element) as i64;
+        unit.set_accumulator_low(element, difference as u16);
+        result[element] = clamp_i16(difference);
+    }
+    unit.registers[opcode.vd as usize] = result;
+    unit.vco = 0;
+}
+
+pub fn execute_VABS(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    let (vs, vt) = operands(opcode, unit);
+    let mut result: Vector = [0; VECTOR_ELEMENTS];
+    for element in 0..VECTOR_ELEMENTS
+    {
+        let value = match (vs[element] as i16).signum()
+        {
+            -1 => -(vt[element] as i16 as i64),
+            0 => 0,
+            _ => vt[element] as i16 as i64,
+        };
+        //-0x8000 wraps in the accumulator but saturates in the result
+        unit.set_accumulator_low(element, value as u16);
+        result[element] = clamp_i16(value);
+    }
+    unit.registers[opcode.vd as usize] = result;
+}
+
+pub fn execute_VADDC(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    let (vs, vt) = operands(opcode, unit);
+    let mut result: Vector = [0; VECTOR_ELEMENTS];
+    unit.vco = 0;
+    for element in 0..VECTOR_ELEMENTS
+    {
+        let sum = vs[element] as u32 + vt[element] as u32;
+        result[element] = sum as u16;
+        VectorUnit::set_flag(&mut unit.vco, element, sum > 0xFFFF);
+    }
+    write_low(opcode, unit, result);
+}
+
+pub fn execute_VSUBC(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    let (vs, vt) = operands(opcode, unit);
+    let mut result: Vector = [0; VECTOR_ELEMENTS];
+    unit.vco = 0;
+    for element in 0..VECTOR_ELEMENTS
+    {
+        let difference = vs[element] as i32 - vt[element] as i32;
+        result[element] = difference as u16;
+        VectorUnit::set_flag(&mut unit.vco, element, difference < 0);
+        VectorUnit::set_flag(&mut unit.vco, element + 8, difference != 0);
+    }
+    write_low(opcode, unit, result);
+}
+
+pub fn execute_VSAR(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    let mut result: Vector = [0; VECTOR_ELEMENTS];
+    for element in 0..VECTOR_ELEMENTS
+    {
+        result[element] = match opcode.element
+        {
+            0x8 => unit.accumulator_high(element),
+            0x9 => unit.accumulator_mid(element),
+            0xA => unit.accumulator_low(element),
+            _ => 0,
+        };
+    }
+    unit.registers[opcode.vd as usize] = result;
+}
+
+//Undefined computational opcodes still add into the accumulator and write zero
+pub fn execute_VZERO(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    let (vs, vt) = operands(opcode, unit);
+    for element in 0..VECTOR_ELEMENTS
+    {
+        unit.set_accumulator_low(element, vs[element].wrapping_add(vt[element]));
+    }
+    unit.registers[opcode.vd as usize] = [0; VECTOR_ELEMENTS];
+}
+
+//Selects between vs and vt per element, recording the choice in VCC
+fn select_compare<F>(opcode: &RSPOpcode, unit: &mut VectorUnit, condition: F)
+    where F: Fn(i16, i16, bool, bool) -> bool
+{
+    let (vs, vt) = operands(opcode, unit);
+    let mut result: Vector = [0; VECTOR_ELEMENTS];
+    let mut vcc: u16 = 0;
+    for element in 0..VECTOR_ELEMENTS
+    {
+        let carry = VectorUnit::flag(unit.vco, element);
+        let not_equal = VectorUnit::flag(unit.vco, element + 8);
+        let compare = condition(vs[element] as i16, vt[element] as i16, carry, not_equal);
+        VectorUnit::set_flag(&mut vcc, element, compare);
+        result[element] = if compare {vs[element]} else {vt[element]};
+    }
+    unit.vcc = vcc;
+    unit.vco = 0;
+    write_low(opcode, unit, result);
+}
+
+pub fn execute_VLT(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    select_compare(opcode, unit, |vs, vt, carry, not_equal| vs < vt || (vs == vt && carry && not_equal));
+}
+
+pub fn execute_VEQ(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    select_compare(opcode, unit, |vs, vt, _, not_equal| vs == vt && !not_equal);
+}
+
+pub fn execute_VNE(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    select_compare(opcode, unit, |vs, vt, _, not_equal| vs != vt || not_equal);
+}
+
+pub fn execute_VGE(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    select_compare(opcode, unit, |vs, vt, carry, not_equal| vs > vt || (vs == vt && !(carry && not_equal)));
+}
+
+pub fn execute_VCH(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    let (vs, vt) = operands(opcode, unit);
+    let mut result: Vector = [0; VECTOR_ELEMENTS];
+    unit.vco = 0;
+    unit.vcc = 0;
+    unit.vce = 0;
+    for element in 0..VECTOR_ELEMENTS
+    {
+        let s = vs[element] as i16 as i32;
+        let t = vt[element] as i16 as i32;
+        let not_complement = vs[element] != (vt[element] ^ 0xFFFF);
+        if (s ^ t) < 0
+        {
+            let sum = s + t;
+            let less_equal = sum <= 0;
+            VectorUnit::set_flag(&mut unit.vcc, element, less_equal);
+            VectorUnit::set_flag(&mut unit.vcc, element + 8, t < 0);
+            VectorUnit::set_flag(&mut unit.vco, element, true);
+            VectorUnit::set_flag(&mut unit.vco, element + 8, sum != 0 && not_complement);
+            if sum == -1
+            {
+                unit.vce |= 1 << element;
+            }
+            result[element] = if less_equal {(-t) as u16} else {vs[element]};
+        }
+        else
+        {
+            let difference = s - t;
+            let greater_equal = difference >= 0;
+            VectorUnit::set_flag(&mut unit.vcc, element, t < 0);
+            VectorUnit::set_flag(&mut unit.vcc, element + 8, greater_equal);
+            VectorUnit::set_flag(&mut unit.vco, element + 8, difference != 0 && not_complement);
+            result[element] = if greater_equal {vt[element]} else {vs[element]};
+        }
+    }
+    write_low(opcode, unit, result);
+}
+
+pub fn execute_VCL(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    let (vs, vt) = operands(opcode, unit);
+    let mut result: Vector = [0; VECTOR_ELEMENTS];
+    for element in 0..VECTOR_ELEMENTS
+    {
+        let carry = VectorUnit::flag(unit.vco, element);
+        let not_equal = VectorUnit::flag(unit.vco, element + 8);
+        if carry
+        {
+            let less_equal = if not_equal
+            {
+                VectorUnit::flag(unit.vcc, element)
+            }
+            else
+            {
+                let sum = vs[element] as u32 + vt[element] as u32;
+                let zero = sum as u16 == 0;
+                let overflow = sum > 0xFFFF;
+                if unit.vce & (1 << element) != 0 {zero || !overflow} else {zero && !overflow}
+            };
+            VectorUnit::set_flag(&mut unit.vcc, element, less_equal);
+            result[element] = if less_equal {vt[element].wrapping_neg()} else {vs[element]};
+        }
+        else
+        {
+            let greater_equal = if not_equal
+            {
+                VectorUnit::flag(unit.vcc, element + 8)
+            }
+            else
+            {
+                vs[element] >= vt[element]
+            };
+            VectorUnit::set_flag(&mut unit.vcc, element + 8, greater_equal);
+            result[element] = if greater_equal {vt[element]} else {vs[element]};
+        }
+    }
+    unit.vco = 0;
+    unit.vce = 0;
+    write_low(opcode, unit, result);
+}
+
+pub fn execute_VCR(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    let (vs, vt) = operands(opcode, unit);
+    let mut result: Vector = [0; VECTOR_ELEMENTS];
+    unit.vcc = 0;
+    for element in 0..VECTOR_ELEMENTS
+    {
+        let s = vs[element] as i16 as i32;
+        let t = vt[element] as i16 as i32;
+        let (less_equal, greater_equal) = if (s ^ t) < 0
+        {
+            let less_equal = s + t + 1 <= 0;
+            result[element] = if less_equal {!vt[element]} else {vs[element]};
+            (less_equal, t < 0)
+        }
+        else
+        {
+            let greater_equal = s - t >= 0;
+            result[element] = if greater_equal {vt[element]} else {vs[element]};
+            (t < 0, greater_equal)
+        };
+        VectorUnit::set_flag(&mut unit.vcc, element, less_equal);
+        VectorUnit::set_flag(&mut unit.vcc, element + 8, greater_equal);
+    }
+    unit.vco = 0;
+    unit.vce = 0;
+    write_low(opcode, unit, result);
+}
+
+pub fn execute_VMRG(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    let (vs, vt) = operands(opcode, unit);
+    let mut result: Vector = [0; VECTOR_ELEMENTS];
+    for element in 0..VECTOR_ELEMENTS
+    {
+        result[element] = if VectorUnit::flag(unit.vcc, element) {vs[element]} else {vt[element]};
+    }
+    unit.vco = 0;
+    write_low(opcode, unit, result);
+}
+
+fn logical<F>(opcode: &RSPOpcode, unit: &mut VectorUnit, operation: F)
+    where F: Fn(u16, u16) -> u16
+{
+    let (vs, vt) = operands(opcode, unit);
+    let mut result: Vector = [0; VECTOR_ELEMENTS];
+    for element in 0..VECTOR_ELEMENTS
+    {
+        result[element] = operation(vs[element], vt[element]);
+    }
+    write_low(opcode, unit, result);
+}
+
+pub fn execute_VAND(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    logical(opcode, unit, |vs, vt| vs & vt);
+}
+
+pub fn execute_VNAND(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    logical(opcode, unit, |vs, vt| !(vs & vt));
+}
+
+pub fn execute_VOR(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    logical(opcode, unit, |vs, vt| vs | vt);
+}
+
+pub fn execute_VNOR(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    logical(opcode, unit, |vs, vt| !(vs | vt));
+}
+
+pub fn execute_VXOR(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    logical(opcode, unit, |vs, vt| vs ^ vt);
+}
+
+pub fn execute_VNXOR(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    logical(opcode, unit, |vs, vt| !(vs ^ vt));
+}
+
+//Single lane operations take their destination element from the vs field
+fn load_accumulator_from_vt(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    let vt = unit.select(opcode.vt as usize, opcode.element as usize);
+    for element in 0..VECTOR_ELEMENTS
+    {
+        unit.set_accumulator_low(element, vt[element]);
+    }
+}
+
+fn divide<F>(opcode: &RSPOpcode, unit: &mut VectorUnit, double_precision: bool, function: F)
+    where F: Fn(&VectorUnit, i32) -> u32
+{
+    let source = unit.registers[opcode.vt as usize][(opcode.element & 0x7) as usize];
+    let input = if double_precision && unit.divide_in_loaded
+    {
+        ((unit.divide_in as u32) << 16 | source as u32) as i32
+    }
+    else
+    {
+        source as i16 as i32
+    };
+    let result = function(unit, input);
+    load_accumulator_from_vt(opcode, unit);
+    unit.registers[opcode.vd as usize][(opcode.vs & 0x7) as usize] = result as u16;
+    unit.divide_out = (result >> 16) as u16;
+    unit.divide_in_loaded = false;
+}
+
+fn divide_high(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    load_accumulator_from_vt(opcode, unit);
+    unit.divide_in = unit.registers[opcode.vt as usize][(opcode.element & 0x7) as usize];
+    unit.divide_in_loaded = true;
+    unit.registers[opcode.vd as usize][(opcode.vs & 0x7) as usize] = unit.divide_out;
+}
+
+pub fn execute_VRCP(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    divide(opcode, unit, false, VectorUnit::reciprocal);
+}
+
+pub fn execute_VRCPL(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    divide(opcode, unit, true, VectorUnit::reciprocal);
+}
+
+pub fn execute_VRSQ(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    divide(opcode, unit, false, VectorUnit::inverse_square_root);
+}
+
+pub fn execute_VRSQL(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    divide(opcode, unit, true, VectorUnit::inverse_square_root);
+}
+
+pub fn execute_VRCPH(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    divide_high(opcode, unit);
+}
+
+pub fn execute_VRSQH(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    divide_high(opcode, unit);
+}
+
+pub fn execute_VMOV(opcode: &RSPOpcode, unit: &mut VectorUnit)
+{
+    let destination = (opcode.vs & 0x7) as usize;
+    let vt = unit.select(opcode.vt as usize, opcode.element as usize);
+    load_accumulator_from_vt(opcode, unit);
+    unit.registers[opcode.vd as usize][destination] = vt[destination];
+}
+
+//Moves between scalar and vector registers address bytes, wrapping within the register
+pub fn execute_MFC2(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let element = opcode.memory_element as usize;
+    let unit = &core.vector_unit;
+    let value = (unit.read_byte(opcode.vs as usize, element) as u16) << 8 | unit.read_byte(opcode.vs as usize, element + 1) as u16;
+    core.write_register(opcode.rt, value as i16 as i32 as u32);
+}
+
+pub fn execute_MTC2(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let element = opcode.memory_element as usize;
+    let value = core.read_register(opcode.rt);
+    core.vector_unit.write_byte(opcode.vs as usize, element, (value >> 8) as u8);
+    if element < 15
+    {
+        core.vector_unit.write_byte(opcode.vs as usize, element + 1, value as u8);
+    }
+}
+
+pub fn execute_CFC2(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let value = match opcode.rd & 0x3
+    {
+        0 => core.vector_unit.vco as i16 as i32 as u32,
+        1 => core.vector_unit.vcc as i16 as i32 as u32,
+        _ => core.vector_unit.vce as u32,
+    };
+    core.write_register(opcode.rt, value);
+}
+
+pub fn execute_CTC2(opcode: &RSPOpcode, core: &mut RSPCore)
+{
+    let value = core.read_register(opcode.rt);
+    match opcode.rd & 0x3
+    {
+        0 => core.vector_unit.vco = value as u16,
+        1 => core.vector_unit.vcc = value as u16,
+        _ => core.vector_unit.vce = value as u8,
+    }
+}
+
+fn vector_address(opcode: &RSPOpcode, core: &RSPCore, size: i32) -> usize
+{
+    (core.read_register(opcode.base) as i32).wrapping_add(opcode.vector_offset as i32 * size) as u32 as usize
+}
+
+//LBV, LSV, LLV and LDV
+pub fn execute_load_bytes(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector, size: usize)
+{
+    let address = vector_address(opcode, core, size as i32);
+    let element = opcode.memory_element as usize;
+    for offset in 0..size
+    {
+        if element + offset < 16
+        {
+            core.vector_unit.write_byte(opcode.vt as usize, element + offset, connector.rsp.read_dmem_u8(address + offset));
+        }
+    }
+}
+
+//SBV, SSV, SLV and SDV
+pub fn execute_store_bytes(opcode: &RSPOpcode, core: &RSPCore, connector: &mut Connector, size: usize)
+{
+    let address = vector_address(opcode, core, size as i32);
+    let element = opcode.memory_element as usize;
+    for offset in 0..size
+    {
+        connector.rsp.store_dmem_u8(address + offset, core.vector_unit.read_byte(opcode.vt as usize, element + offset));
+    }
+}
+
+//Loads up to the end of the 16 byte line containing the address
+pub fn execute_LQV(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    let address = vector_address(opcode, core, 16);
+    let element = opcode.memory_element as usize;
+    let end = (element + 16 - (address & 0xF)).min(16);
+    for (offset, byte) in (element..end).enumerate()
+    {
+        core.vector_unit.write_byte(opcode.vt as usize, byte, connector.rsp.read_dmem_u8(address + offset));
+    }
+}
+
+//Loads the start of the 16 byte line into the end of the register
+pub fn execute_LRV(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    let address = vector_address(opcode, core, 16);
+    let start = address & 0xF;
+    let aligned = address & !0xF;
+    let mut byte = opcode.memory_element as usize + (16 - start);
+    for offset in 0..start
+    {
+        if byte < 16
+        {
+            core.vector_unit.write_byte(opcode.vt as usize, byte, connector.rsp.read_dmem_u8(aligned + offset));
+        }
+        byte += 1;
+    }
+}
+
+pub fn execute_SQV(opcode: &RSPOpcode, core: &RSPCore, connector: &mut Connector)
+{
+    let address = vector_address(opcode, core, 16);
+    let element = opcode.memory_element as usize;
+    let end = element + 16 - (address & 0xF);
+    for (offset, byte) in (element..end).enumerate()
+    {
+        connector.rsp.store_dmem_u8(address + offset, core.vector_unit.read_byte(opcode.vt as usize, byte));
+    }
+}
+
+pub fn execute_SRV(opcode: &RSPOpcode, core: &RSPCore, connector: &mut Connector)
+{
+    let address = vector_address(opcode, core, 16);
+    let element = opcode.memory_element as usize;
+    let rotation = 16 - (address & 0xF);
+    let aligned = address & !0xF;
+    for (offset, byte) in (element..(element + (address & 0xF))).enumerate()
+    {
+        connector.rsp.store_dmem_u8(aligned + offset, core.vector_unit.read_byte(opcode.vt as usize, byte + rotation));
+    }
+}
+
+//Packed loads put each byte in the upper bits of an element
+fn load_packed(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector, size: i32, stride: usize, shift: u32)
+{
+    let address = vector_address(opcode, core, size);
+    let aligned = address & !0x7;
+    let index = (address & 0x7).wrapping_sub(opcode.memory_element as usize);
+    for element in 0..VECTOR_ELEMENTS
+    {
+        let value = connector.rsp.read_dmem_u8(aligned + (index.wrapping_add(element * stride) & 0xF));
+        core.vector_unit.registers[opcode.vt as usize][element] = (value as u16) << shift;
+    }
+}
+
+pub fn execute_LPV(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    load_packed(opcode, core, connector, 8, 1, 8);
+}
+
+pub fn execute_LUV(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    load_packed(opcode, core, connector, 8, 1, 7);
+}
+
+pub fn execute_LHV(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    load_packed(opcode, core, connector, 16, 2, 7);
+}
+
+pub fn execute_LFV(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    let address = vector_address(opcode, core, 16);
+    let aligned = address & !0x7;
+    let index = (address & 0x7).wrapping_sub(opcode.memory_element as usize);
+    let mut loaded: Vector = [0; VECTOR_ELEMENTS];
+    for offset in 0..4
+    {
+        loaded[offset] = (connector.rsp.read_dmem_u8(aligned + (index.wrapping_add(offset * 4) & 0xF)) as u16) << 7;
+        loaded[offset + 4] = (connector.rsp.read_dmem_u8(aligned + (index.wrapping_add((offset * 4) + 8) & 0xF)) as u16) << 7;
+    }
+    //Only the half of the register starting at the element is written
+    let start = opcode.memory_element as usize;
+    for byte in start..(start + 8).min(16)
+    {
+        let value = if byte & 1 == 0 {(loaded[byte >> 1] >> 8) as u8} else {loaded[byte >> 1] as u8};
+        core.vector_unit.write_byte(opcode.vt as usize, byte, value);
+    }
+}
+
+pub fn execute_SPV(opcode: &RSPOpcode, core: &RSPCore, connector: &mut Connector)
+{
+    let address = vector_address(opcode, core, 8);
+    let element = opcode.memory_element as usize;
+    for (offset, index) in (element..(element + 8)).enumerate()
+    {
+        let value = if index & 0xF < 8
+        {
+            core.vector_unit.read_byte(opcode.vt as usize, (index & 0x7) << 1)
+        }
+        else
+        {
+            (core.vector_unit.registers[opcode.vt as usize][index & 0x7] >> 7) as u8
+        };
+        connector.rsp.store_dmem_u8(address + offset, value);
+    }
+}
+
+pub fn execute_SUV(opcode: &RSPOpcode, core: &RSPCore, connector: &mut Connector)
+{
+    let address = vector_address(opcode, core, 8);
+    let element = opcode.memory_element as usize;
+    for (offset, index) in (element..(element + 8)).enumerate()
+    {
+        let value = if index & 0xF < 8
+        {
+            (core.vector_unit.registers[opcode.vt as usize][index & 0x7] >> 7) as u8
+        }
+        else
+        {
+            core.vector_unit.read_byte(opcode.vt as usize, (index & 0x7) << 1)
+        };
+        connector.rsp.store_dmem_u8(address + offset, value);
+    }
+}
+
+pub fn execute_SHV(opcode: &RSPOpcode, core: &RSPCore, connector: &mut Connector)
+{
+    let address = vector_address(opcode, core, 16);
+    let aligned = address & !0x7;
+    let index = address & 0x7;
+    let element = opcode.memory_element as usize;
+    for offset in 0..VECTOR_ELEMENTS
+    {
+        let byte = element + (offset * 2);
+        let high = core.vector_unit.read_byte(opcode.vt as usize, byte);
+        let low = core.vector_unit.read_byte(opcode.vt as usize, byte + 1);
+        connector.rsp.store_dmem_u8(aligned + ((index + (offset * 2)) & 0xF), (high << 1) | (low >> 7));
+    }
+}
+
+pub fn execute_SFV(opcode: &RSPOpcode, core: &RSPCore, connector: &mut Connector)
+{
+    let address = vector_address(opcode, core, 16);
+    let aligned = address & !0x7;
+    let index = address & 0x7;
+    //Only some element values pick a set of lanes, the rest store zeros
+    let elements: Option<[usize; 4]> = match opcode.memory_element
+    {
+        0 | 15 => Some([0, 1, 2, 3]),
+        1 => Some([6, 7, 4, 5]),
+        4 => Some([1, 2, 3, 0]),
+        5 => Some([7, 4, 5, 6]),
+        8 => Some([4, 5, 6, 7]),
+        11 => Some([3, 0, 1, 2]),
+        12 => Some([5, 6, 7, 4]),
+        _ => None,
+    };
+    for offset in 0..4
+    {
+        let value = match elements
+        {
+            Some(elements) => (core.vector_unit.registers[opcode.vt as usize][elements[offset]] >> 7) as u8,
+            None => 0,
+        };
+        connector.rsp.store_dmem_u8(aligned + ((index + (offset * 4)) & 0xF), value);
+    }
+}
+
+pub fn execute_SWV(opcode: &RSPOpcode, core: &RSPCore, connector: &mut Connector)
+{
+    let address = vector_address(opcode, core, 16);
+    let aligned = address & !0x7;
+    let mut index = address & 0x7;
+    let element = opcode.memory_element as usize;
+    for byte in element..(element + 16)
+    {
+        connector.rsp.store_dmem_u8(aligned + (index & 0xF), core.vector_unit.read_byte(opcode.vt as usize, byte));
+        index += 1;
+    }
+}
+
+//Transposed accesses walk a group of eight registers, one element from each
+pub fn execute_LTV(opcode: &RSPOpcode, core: &mut RSPCore, connector: &Connector)
+{
+    let address = vector_address(opcode, core, 16);
+    let begin = address & !0x7;
+    let element = opcode.memory_element as usize;
+    let mut location = begin + ((element + (address & 0x8)) & 0xF);
+    let group = (opcode.vt & !0x7) as usize;
+    let mut register = element >> 1;
+    for lane in 0..VECTOR_ELEMENTS
+    {
+        for half in 0..2
+        {
+            core.vector_unit.write_byte(group + register, (lane * 2) + half, connector.rsp.read_dmem_u8(location));
+            location += 1;
+            if location == begin + 16
+            {
+                location = begin;
+            }
+        }
+        register = (register + 1) & 0x7;
+    }
+}
+
+pub fn execute_STV(opcode: &RSPOpcode, core: &RSPCore, connector: &mut Connector)
+{
+    let address = vector_address(opcode, core, 16);
+    let aligned = address & !0x7;
+    let element = opcode.memory_element as usize & !1;
+    let group = (opcode.vt & !0x7) as usize;
+    let mut byte = 16 - element;
+    let mut index = (address & 0x7).wrapping_sub(element);
+    for register in group..(group + 8)
+    {
+        for _ in 0..2
+        {
+            connector.rsp.store_dmem_u8(aligned + (index & 0xF), core.vector_unit.read_byte(register, byte));
+            index = index.wrapping_add(1);
+            byte += 1;
+        }
+    }
+}
@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod rsp_vector_tests
+{
+    use n64::rsp_core::RSPCore;
+    use n64::rsp_opcodes::RSPOpcode;
+    use n64::connector::Connector;
+
+    fn compute(funct: u32, vd: u32, vs: u32, vt: u32, element: u32) -> u32
+    {
+        (0x12 << 26) | (1 << 25) | (element << 21) | (vt << 16) | (vs << 11) | (vd << 6) | funct
+    }
+
+    fn memory(op: u32, sub: u32, base: u32, vt: u32, element: u32, offset: u32) -> u32
+    {
+        (op << 26) | (base << 21) | (vt << 16) | (sub << 11) | (element << 7) | (offset & 0x7F)
+    }
+
+    fn execute(core: &mut RSPCore, connector: &mut Connector, opcode: u32)
+    {
+        RSPOpcode::new(opcode).execute(core, connector).unwrap();
+    }
+
+    #[test]
+    fn element_selection_broadcasts_lanes()
+    {
+        let mut connector = Connector::test();
+        let mut core = RSPCore::new();
+        core.vector_unit.registers[1] = [1, 2, 3, 4, 5, 6, 7, 8];
+        core.vector_unit.registers[2] = [10, 20, 30, 40, 50, 60, 70, 80];
+
+        //VADD v3, v1, v2[5]
+        execute(&mut core, &mut connector, compute(0x10, 3, 1, 2, 13));
+        assert_eq!(core.vector_unit.registers[3], [61, 62, 63, 64, 65, 66, 67, 68]);
+        //VADD v3, v1, v2[1q]
+        execute(&mut core, &mut connector, compute(0x10, 3, 1, 2, 3));
+        assert_eq!(core.vector_unit.registers[3], [21, 22, 43, 44, 65, 66, 87, 88]);
+    }
+
+    #[test]
+    fn vmulf_rounds_and_saturates()
+    {
+        let mut connector = Connector::test();
+        let mut core = RSPCore::new();
+        core.vector_unit.registers[1] = [0x8000, 0x4000, 0x7FFF, 0xC000, 0, 0, 0, 0];
+        core.vector_unit.registers[2] = [0x8000, 0x4000, 0x0001, 0x4000, 0, 0, 0, 0];
+        execute(&mut core, &mut connector, compute(0x00, 3, 1, 2, 0));
+        assert_eq!(core.vector_unit.registers[3][0..4].to_vec(), vec![0x7FFF, 0x2000, 0x0001, 0xE000]);
+        assert_eq!(core.vector_unit.accumulator_high(0), 0x0000);
+        assert_eq!(core.vector_unit.accumulator_mid(0), 0x8000);
+        assert_eq!(core.vector_unit.accumulator_low(0), 0x8000);
+        //The accumulator only keeps 48 bits
+        assert_eq!(core.vector_unit.accumulator[0], 0x0000_8000_8000);
+    }
+
+    #[test]
+    fn vadd_uses_carry_and_clamps()
+    {
+        let mut connector = Connector::test();
+        let mut core = RSPCore::new();
+        core.vector_unit.registers[1] = [0x7FFF, 0x8000, 0xFFFF, 0xFFFF, 0, 0, 0, 0];
+        core.vector_unit.registers[2] = [0x0001, 0xFFFF, 0x0001, 0xFFFF, 0, 0, 0, 0];
+        //VADDC records unsigned carries in VCO
+        execute(&mut core, &mut connector, compute(0x14, 3, 1, 2, 0));
+        assert_eq!(core.vector_unit.registers[3][0..4].to_vec(), vec![0x8000, 0x7FFF, 0x0000, 0xFFFE]);
+        assert_eq!(core.vector_unit.vco, 0x000E);
+
+        execute(&mut core, &mut connector, compute(0x10, 4, 1, 2, 0));
+        assert_eq!(core.vector_unit.registers[4][0..4].to_vec(), vec![0x7FFF, 0x8000, 0x0001, 0xFFFF]);
+        assert_eq!(core.vector_unit.accumulator_low(0), 0x8000);
+        assert_eq!(core.vector_unit.vco, 0);
+    }
+
+    #[test]
+    fn vch_and_vcl_clip()
+    {
+        let mut connector = Connector::test();
+        let mut core = RSPCore::new();
+        core.vector_unit.registers[1] = [100, 0xFF00, 5, 0xFFF0, 0, 0, 0, 0];
+        core.vector_unit.registers[2] = [50, 50, 0xFFFB, 0x0010, 0, 0, 0, 0];
+        execute(&mut core, &mut connector, compute(0x25, 3, 1, 2, 0));
+        assert_eq!(core.vector_unit.registers[3][0..4].to_vec(), vec![50, 0xFFCE, 5, 0xFFF0]);
+        assert_eq!(core.vector_unit.vcc & 0x000F, 0x000E);
+        assert_eq!(core.vector_unit.vcc & 0x0F00, 0x0500);
+        assert_eq!(core.vector_unit.vco & 0x000F, 0x000E);
+
+        core.vector_unit.vco = 0;
+        core.vector_unit.vcc = 0;
+        core.vector_unit.registers[1] = [10, 50, 0, 0, 0, 0, 0, 0];
+        core.vector_unit.registers[2] = [20, 20, 0, 0, 0, 0, 0, 0];
+        execute(&mut core, &mut connector, compute(0x24, 3, 1, 2, 0));
+        assert_eq!(core.vector_unit.registers[3][0..2].to_vec(), vec![10, 20]);
+        assert_eq!(core.vector_unit.vcc, 0xFE00);
+    }
+
+    #[test]
+    fn reciprocal_uses_the_rom_table()
+    {
+        let mut connector = Connector::test();
+        let mut core = RSPCore::new();
+        core.vector_unit.registers[1][0] = 1;
+        core.vector_unit.registers[1][1] = 2;
+        //VRCP v2[0], v1[0] then VRCPH v3[0], v1[0]
+        execute(&mut core, &mut connector, compute(0x30, 2, 0, 1, 8));
+        assert_eq!(core.vector_unit.registers[2][0], 0xC000);
+        execute(&mut core, &mut connector, compute(0x32, 3, 0, 1, 8));
+        assert_eq!(core.vector_unit.registers[3][0], 0x7FFF);
+
+        execute(&mut core, &mut connector, compute(0x30, 2, 1, 1, 9));
+        assert_eq!(core.vector_unit.registers[2][1], 0xE000);
+        assert_eq!(core.vector_unit.divide_out, 0x3FFF);
+
+        core.vector_unit.registers[1][2] = 0;
+        execute(&mut core, &mut connector, compute(0x30, 2, 2, 1, 10));
+        assert_eq!(core.vector_unit.registers[2][2], 0xFFFF);
+        assert_eq!(core.vector_unit.divide_out, 0x7FFF);
+    }
+
+    #[test]
+    fn quad_loads_and_stores_round_trip()
+    {
+        let mut connector = Connector::test();
+        let mut core = RSPCore::new();
+        for byte in 0..0x20
+        {
+            connector.rsp.store_dmem_u8(byte, byte as u8);
+        }
+        core.write_register(1, 0x10);
+        //LQV v4[0], 0(r1)
+        execute(&mut core, &mut connector, memory(0x32, 4, 1, 4, 0, 0));
+        assert_eq!(core.vector_unit.registers[4][0], 0x1011);
+        assert_eq!(core.vector_unit.registers[4][7], 0x1E1F);
+        //SQV v4[0], 1(r1) stores 16 bytes at 0x20
+        execute(&mut core, &mut connector, memory(0x3A, 4, 1, 4, 0, 1));
+        assert_eq!(connector.rsp.dynamic_memory[0x20..0x30].to_vec(), (0x10..0x20).collect::<Vec<u8>>());
+
+        //An unaligned LQV stops at the end of the line and LRV loads the rest
+        core.write_register(2, 0x04);
+        execute(&mut core, &mut connector, memory(0x32, 4, 2, 5, 0, 0));
+        execute(&mut core, &mut connector, memory(0x32, 5, 2, 5, 0, 1));
+        assert_eq!(core.vector_unit.registers[5][0], 0x0405);
+        assert_eq!(core.vector_unit.registers[5][5], 0x0E0F);
+        assert_eq!(core.vector_unit.registers[5][6], 0x1011);
+        assert_eq!(core.vector_unit.registers[5][7], 0x1213);
+
+        //LSV v6[2], -1(r1) with the offset scaled by the access size
+        execute(&mut core, &mut connector, memory(0x32, 1, 1, 6, 2, 0x7F));
+        assert_eq!(core.vector_unit.registers[6][1], 0x0E0F);
+    }
+
+    #[test]
+    fn moves_between_scalar_and_vector_registers()
+    {
+        let mut connector = Connector::test();
+        let mut core = RSPCore::new();
+        core.write_register(1, 0x1234);
+        //MTC2 r1, v7[3] writes bytes 3 and 4
+        execute(&mut core, &mut connector, (0x12 << 26) | (0x04 << 21) | (1 << 16) | (7 << 11) | (3 << 7));
+        assert_eq!(core.vector_unit.registers[7][1], 0x0012);
+        assert_eq!(core.vector_unit.registers[7][2], 0x3400);
+
+        core.vector_unit.registers[7][7] = 0x80FF;
+        //MFC2 r2, v7[14] sign extends
+        execute(&mut core, &mut connector, (0x12 << 26) | (2 << 16) | (7 << 11) | (14 << 7));
+        assert_eq!(core.read_register(2), 0xFFFF80FF);
+
+        core.vector_unit.vcc = 0x8001;
+        //CFC2 r3, vcc
+        execute(&mut core, &mut connector, (0x12 << 26) | (0x02 << 21) | (3 << 16) | (1 << 11));
+        assert_eq!(core.read_register(3), 0xFFFF8001);
+    }
+}
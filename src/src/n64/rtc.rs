@@ -0,0 +1,314 @@
+use n64::joybus::{JoybusDevice, JoybusError, check_command_size};
+use n64::rom::RomHeader;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Referenced: https://n64brew.dev/wiki/Joybus_Protocol and mupen64plus af_rtc.c
+const RTC_COMMAND_STATUS: u8 = 0x06;
+const RTC_COMMAND_READ: u8 = 0x07;
+const RTC_COMMAND_WRITE: u8 = 0x08;
+pub const RTC_BLOCK_SIZE: usize = 8;
+const RTC_IDENTIFIER: u16 = 0x0010;
+const RTC_STATUS_RUNNING: u8 = 0x00;
+const RTC_STATUS_STOPPED: u8 = 0x80;
+
+//Block 0 holds the control registers, block 1 is spare battery backed RAM and block 2 is the time
+pub const RTC_BLOCK_CONTROL: u8 = 0;
+pub const RTC_BLOCK_RAM: u8 = 1;
+pub const RTC_BLOCK_TIME: u8 = 2;
+pub const RTC_WRITE_PROTECT_RAM: u8 = 0x01;
+pub const RTC_WRITE_PROTECT_TIME: u8 = 0x02;
+pub const RTC_STOP: u8 = 0x04;
+//Hour register flag selecting the 24 hour clock
+const RTC_HOUR_24: u8 = 0x80;
+
+//Homebrew flags an RTC in the low bit of the ED64 save byte, retail carts are matched by ID
+const ED64_CARTRIDGE_ID: u16 = 0x4544;
+const ED64_RTC_FLAG: u8 = 0x01;
+const RTC_CARTRIDGE_IDS: [&str; 1] = ["AF"];
+
+pub trait ClockSource
+{
+    //Seconds since the Unix epoch
+    fn now(&mut self) -> i64;
+}
+
+pub struct HostClock;
+
+impl ClockSource for HostClock
+{
+    fn now(&mut self) -> i64
+    {
+        match SystemTime::now().duration_since(UNIX_EPOCH)
+        {
+            Ok(duration) => duration.as_secs() as i64,
+            Err(_) => 0,
+        }
+    }
+}
+
+//Reproducible time for traces and tests, moves forward by increment every time it is read
+pub struct DeterministicClock
+{
+    pub time: i64,
+    pub increment: i64,
+}
+
+impl DeterministicClock
+{
+    pub fn new(time: i64) -> DeterministicClock
+    {
+        DeterministicClock::with_increment(time, 0)
+    }
+
+    pub fn with_increment(time: i64, increment: i64) -> DeterministicClock
+    {
+        return DeterministicClock
+        {
+            time: time,
+            increment: increment,
+        }
+    }
+}
+
+impl ClockSource for DeterministicClock
+{
+    fn now(&mut self) -> i64
+    {
+        let time = self.time;
+        self.time += self.increment;
+        time
+    }
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+pub struct DateTime
+{
+    pub year: i64,
+    pub month: u8,
+    pub day: u8,
+    pub weekday: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl DateTime
+{
+    // Referenced: Howard Hinnant's civil_from_days
+    pub fn from_timestamp(timestamp: i64) -> DateTime
+    {
+        let days = timestamp.div_euclid(86400);
+        let seconds = timestamp.rem_euclid(86400);
+
+        let shifted = days + 719468;
+        let era = shifted.div_euclid(146097);
+        let day_of_era = shifted - (era * 146097);
+        let year_of_era = (day_of_era - (day_of_era / 1460) + (day_of_era / 36524) - (day_of_era / 146096)) / 365;
+        let day_of_year = day_of_era - ((365 * year_of_era) + (year_of_era / 4) - (year_of_era / 100));
+        let month_index = ((5 * day_of_year) + 2) / 153;
+        let day = day_of_year - (((153 * month_index) + 2) / 5) + 1;
+        let month = if month_index < 10 {month_index + 3} else {month_index - 9};
+        let year = year_of_era + (era * 400) + if month <= 2 {1} else {0};
+
+        return DateTime
+        {
+            year: year,
+            month: month as u8,
+            day: day as u8,
+            //1970-01-01 was a Thursday
+            weekday: (days + 4).rem_euclid(7) as u8,
+            hour: (seconds / 3600) as u8,
+            minute: ((seconds / 60) % 60) as u8,
+            second: (seconds % 60) as u8,
+        }
+    }
+
+    // Referenced: Howard Hinnant's days_from_civil
+    pub fn to_timestamp(&self) -> i64
+    {
+        let month = self.month as i64;
+        let year = self.year - if month <= 2 {1} else {0};
+        let era = year.div_euclid(400);
+        let year_of_era = year - (era * 400);
+        let month_index = if month > 2 {month - 3} else {month + 9};
+        let day_of_year = (((153 * month_index) + 2) / 5) + self.day as i64 - 1;
+        let day_of_era = (year_of_era * 365) + (year_of_era / 4) - (year_of_era / 100) + day_of_year;
+        let days = (era * 146097) + day_of_era - 719468;
+        (days * 86400) + (self.hour as i64 * 3600) + (self.minute as i64 * 60) + self.second as i64
+    }
+
+    pub fn to_bcd_block(&self) -> [u8; RTC_BLOCK_SIZE]
+    {
+        [
+            to_bcd(self.second),
+            to_bcd(self.minute),
+            to_bcd(self.hour) | RTC_HOUR_24,
+            to_bcd(self.day),
+            to_bcd(self.weekday),
+            to_bcd(self.month),
+            to_bcd(self.year.rem_euclid(100) as u8),
+            //Century counts from 1900
+            to_bcd((self.year.div_euclid(100) - 19) as u8),
+        ]
+    }
+
+    pub fn from_bcd_block(block: &[u8]) -> DateTime
+    {
+        return DateTime
+        {
+            year: 1900 + (from_bcd(block[7]) as i64 * 100) + from_bcd(block[6]) as i64,
+            month: from_bcd(block[5]),
+            day: from_bcd(block[3]),
+            weekday: from_bcd(block[4]),
+            hour: from_bcd(block[2] & !RTC_HOUR_24),
+            minute: from_bcd(block[1]),
+            second: from_bcd(block[0]),
+        }
+    }
+}
+
+fn to_bcd(value: u8) -> u8
+{
+    ((value / 10) << 4) | (value % 10)
+}
+
+fn from_bcd(value: u8) -> u8
+{
+    ((value >> 4) * 10) + (value & 0x0F)
+}
+
+pub fn detect_rtc(rom_header: &RomHeader) -> bool
+{
+    if rom_header.cartridge_id == ED64_CARTRIDGE_ID
+    {
+        return rom_header.revision & ED64_RTC_FLAG != 0;
+    }
+    let cartridge_id: String = rom_header.game_code.chars().skip(1).take(2).collect();
+    RTC_CARTRIDGE_IDS.contains(&cartridge_id.as_str())
+}
+
+pub struct RTC
+{
+    pub clock: Box<dyn ClockSource>,
+    pub control: [u8; RTC_BLOCK_SIZE],
+    pub ram: [u8; RTC_BLOCK_SIZE],
+    //Seconds between the guest time and the clock source, set when the game writes the time
+    pub offset: i64,
+    //Guest time held while the stop bit is set
+    pub stopped_time: Option<i64>,
+}
+
+impl RTC
+{
+    pub fn new(clock: Box<dyn ClockSource>) -> RTC
+    {
+        return RTC
+        {
+            clock: clock,
+            //Both blocks come up write protected
+            control: [RTC_WRITE_PROTECT_RAM | RTC_WRITE_PROTECT_TIME, 0, 0, 0, 0, 0, 0, 0],
+            ram: [0; RTC_BLOCK_SIZE],
+            offset: 0,
+            stopped_time: None,
+        }
+    }
+
+    pub fn time(&mut self) -> i64
+    {
+        match self.stopped_time
+        {
+            Some(time) => time,
+            None => self.clock.now() + self.offset,
+        }
+    }
+
+    pub fn set_time(&mut self, time: i64)
+    {
+        match self.stopped_time
+        {
+            Some(_) => self.stopped_time = Some(time),
+            None => self.offset = time - self.clock.now(),
+        }
+    }
+
+    fn status(&self) -> u8
+    {
+        if self.stopped_time.is_some() {RTC_STATUS_STOPPED} else {RTC_STATUS_RUNNING}
+    }
+
+    fn write_control(&mut self, data: &[u8])
+    {
+        let stop = data[1] & RTC_STOP != 0;
+        match (self.stopped_time, stop)
+        {
+            (None, true) => self.stopped_time = Some(self.time()),
+            (Some(time), false) =>
+            {
+                self.stopped_time = None;
+                self.offset = time - self.clock.now();
+            },
+            _ => (),
+        }
+        self.control.copy_from_slice(data);
+    }
+
+    fn read_block(&mut self, block: u8, receive: &mut [u8])
+    {
+        match block
+        {
+            RTC_BLOCK_CONTROL => receive.copy_from_slice(&self.control),
+            RTC_BLOCK_RAM => receive.copy_from_slice(&self.ram),
+            RTC_BLOCK_TIME =>
+            {
+                let time = self.time();
+                receive.copy_from_slice(&DateTime::from_timestamp(time).to_bcd_block());
+            },
+            _ => for value in receive.iter_mut() { *value = 0; },
+        }
+    }
+
+    fn write_block(&mut self, block: u8, data: &[u8])
+    {
+        match block
+        {
+            RTC_BLOCK_CONTROL => self.write_control(data),
+            RTC_BLOCK_RAM if self.control[0] & RTC_WRITE_PROTECT_RAM == 0 => self.ram.copy_from_slice(data),
+            RTC_BLOCK_TIME if self.control[0] & RTC_WRITE_PROTECT_TIME == 0 => self.set_time(DateTime::from_bcd_block(data).to_timestamp()),
+            //Protected and missing blocks ignore writes
+            _ => (),
+        }
+    }
+}
+
+impl JoybusDevice for RTC
+{
+    fn process_command(&mut self, send: &[u8], receive: &mut [u8]) -> Result<(), JoybusError>
+    {
+        match send[0]
+        {
+            RTC_COMMAND_STATUS =>
+            {
+                check_command_size(send, receive, 1, 3)?;
+                receive.copy_from_slice(&[(RTC_IDENTIFIER >> 8) as u8, RTC_IDENTIFIER as u8, self.status()]);
+                Ok(())
+            },
+            RTC_COMMAND_READ =>
+            {
+                check_command_size(send, receive, 2, RTC_BLOCK_SIZE + 1)?;
+                self.read_block(send[1], &mut receive[0..RTC_BLOCK_SIZE]);
+                receive[RTC_BLOCK_SIZE] = self.status();
+                Ok(())
+            },
+            RTC_COMMAND_WRITE =>
+            {
+                check_command_size(send, receive, RTC_BLOCK_SIZE + 2, 1)?;
+                self.write_block(send[1], &send[2..]);
+                receive[0] = self.status();
+                Ok(())
+            },
+            _ => Err(JoybusError::NO_RESPONSE),
+        }
+    }
+}
@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod rtc_tests
+{
+    use n64::rtc::*;
+    use n64::joybus::*;
+    use n64::rom::{Rom, RomHeader};
+    use n64::n64::N64;
+    use n64::pif::{PIF_CONTROL_BYTE, PIF_CONTROL_JOYBUS};
+
+    //2001-09-09 01:46:40 UTC, a Sunday
+    const TEST_TIME: i64 = 1000000000;
+
+    fn test_header(game_code: &[u8], revision: u8) -> RomHeader
+    {
+        let mut header_data: Vec<u8> = vec![0; 0x1000];
+        header_data[0x3B..0x3F].copy_from_slice(game_code);
+        header_data[0x3F] = revision;
+        RomHeader::new(header_data).unwrap()
+    }
+
+    fn read_block(rtc: &mut RTC, block: u8) -> [u8; 9]
+    {
+        let mut receive = [0_u8; 9];
+        rtc.process_command(&[0x07, block], &mut receive).unwrap();
+        receive
+    }
+
+    fn write_block(rtc: &mut RTC, block: u8, data: [u8; 8])
+    {
+        let mut send = vec![0x08, block];
+        send.extend_from_slice(&data);
+        rtc.process_command(&send, &mut [0_u8; 1]).unwrap();
+    }
+
+    #[test]
+    fn rtc_is_detected_from_header()
+    {
+        assert!(detect_rtc(&test_header(b"NAFJ", 0x00)));
+        assert!(detect_rtc(&test_header(b"NEDA", 0x11)));
+        assert!(!detect_rtc(&test_header(b"NEDA", 0x10)));
+        assert!(!detect_rtc(&test_header(b"NSME", 0x00)));
+    }
+
+    #[test]
+    fn dates_convert_both_ways()
+    {
+        let date = DateTime::from_timestamp(TEST_TIME);
+        assert_eq!(date, DateTime {year: 2001, month: 9, day: 9, weekday: 0, hour: 1, minute: 46, second: 40});
+        assert_eq!(date.to_bcd_block(), [0x40, 0x46, 0x81, 0x09, 0x00, 0x09, 0x01, 0x01]);
+        assert_eq!(DateTime::from_bcd_block(&date.to_bcd_block()).to_timestamp(), TEST_TIME);
+        assert_eq!(DateTime::from_timestamp(0).to_bcd_block(), [0x00, 0x00, 0x80, 0x01, 0x04, 0x01, 0x70, 0x00]);
+    }
+
+    #[test]
+    fn status_and_time_come_from_the_clock()
+    {
+        let mut rtc = RTC::new(Box::new(DeterministicClock::with_increment(TEST_TIME, 1)));
+        let mut receive = [0_u8; 3];
+        rtc.process_command(&[0x06], &mut receive).unwrap();
+        assert_eq!(receive, [0x00, 0x10, 0x00]);
+
+        assert_eq!(read_block(&mut rtc, 2), [0x40, 0x46, 0x81, 0x09, 0x00, 0x09, 0x01, 0x01, 0x00]);
+        assert_eq!(read_block(&mut rtc, 2)[0], 0x41);
+        assert_eq!(rtc.process_command(&[0x07, 0x02], &mut [0_u8; 8]).err(), Some(JoybusError::SIZE_MISMATCH));
+    }
+
+    #[test]
+    fn write_protect_guards_ram_and_time()
+    {
+        let mut rtc = RTC::new(Box::new(DeterministicClock::new(TEST_TIME)));
+        write_block(&mut rtc, 1, [1, 2, 3, 4, 5, 6, 7, 8]);
+        write_block(&mut rtc, 2, [0x00, 0x00, 0x80, 0x01, 0x01, 0x01, 0x00, 0x01]);
+        assert_eq!(read_block(&mut rtc, 1)[0..8].to_vec(), vec![0; 8]);
+        assert_eq!(read_block(&mut rtc, 2)[0..8].to_vec(), vec![0x40, 0x46, 0x81, 0x09, 0x00, 0x09, 0x01, 0x01]);
+
+        //Unprotect and stop the clock while setting 2000-01-01
+        write_block(&mut rtc, 0, [0x00, RTC_STOP, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(read_block(&mut rtc, 0)[8], 0x80);
+        write_block(&mut rtc, 1, [1, 2, 3, 4, 5, 6, 7, 8]);
+        write_block(&mut rtc, 2, [0x00, 0x00, 0x80, 0x01, 0x06, 0x01, 0x00, 0x01]);
+        write_block(&mut rtc, 0, [RTC_WRITE_PROTECT_RAM | RTC_WRITE_PROTECT_TIME, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(read_block(&mut rtc, 1)[0..8].to_vec(), vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(read_block(&mut rtc, 2), [0x00, 0x00, 0x80, 0x01, 0x06, 0x01, 0x00, 0x01, 0x00]);
+        assert_eq!(rtc.time(), 946684800);
+    }
+
+    #[test]
+    fn cartridge_port_shares_eeprom_and_rtc()
+    {
+        let mut rom_data: Vec<u8> = vec![0; 0x1000];
+        rom_data[0..4].copy_from_slice(&[0x80, 0x37, 0x12, 0x40]);
+        //Homebrew header asking for a 4Kbit EEPROM and the RTC
+        rom_data[0x3B..0x40].copy_from_slice(&[b'N', b'E', b'D', b'A', 0x11]);
+        let mut n64 = N64::from_rom(Rom::from_bytes(rom_data).unwrap());
+        n64.set_rtc_clock(Box::new(DeterministicClock::new(TEST_TIME)));
+
+        //Channels 0-3 are skipped to reach the cartridge, then the EEPROM and RTC status are requested
+        let mut commands: Vec<u8> = vec![0x00, 0x00, 0x00, 0x00, 0x01, 0x03, 0x00, 0x00, 0x00, 0x00, 0xFE];
+        commands.resize(0x40, 0);
+        commands[PIF_CONTROL_BYTE] = PIF_CONTROL_JOYBUS;
+        n64.connector.pif.ram = commands;
+        n64.connector.pif.process_joybus_commands();
+        assert_eq!(n64.connector.pif.ram[7..10].to_vec(), vec![0x00, 0x80, 0x00]);
+
+        let mut commands: Vec<u8> = vec![0x00, 0x00, 0x00, 0x00, 0x02, 0x09, 0x07, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xFE];
+        commands.resize(0x40, 0);
+        commands[PIF_CONTROL_BYTE] = PIF_CONTROL_JOYBUS;
+        n64.connector.pif.ram = commands;
+        n64.connector.pif.process_joybus_commands();
+        assert_eq!(n64.connector.pif.ram[8..17].to_vec(), vec![0x40, 0x46, 0x81, 0x09, 0x00, 0x09, 0x01, 0x01, 0x00]);
+    }
+}
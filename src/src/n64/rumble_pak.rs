@@ -0,0 +1,74 @@
+use n64::controller::ControllerAccessory;
+
+// Referenced: https://n64brew.dev/wiki/Rumble_Pak
+const RUMBLE_PAK_IDENTIFIER: u8 = 0x80;
+const RUMBLE_PAK_PROBE_START: u16 = 0x8000;
+const RUMBLE_PAK_PROBE_END: u16 = 0x8FFF;
+const RUMBLE_PAK_MOTOR_START: u16 = 0xC000;
+const RUMBLE_PAK_MOTOR_END: u16 = 0xCFFF;
+
+pub struct RumblePak
+{
+    pub rumbling: bool,
+    pub on_change: Option<Box<dyn FnMut(bool)>>,
+}
+
+impl RumblePak
+{
+    pub fn new() -> RumblePak
+    {
+        return RumblePak
+        {
+            rumbling: false,
+            on_change: None,
+        }
+    }
+
+    //Callback receives the new motor state whenever the game turns it on or off
+    pub fn with_callback(on_change: Box<dyn FnMut(bool)>) -> RumblePak
+    {
+        return RumblePak
+        {
+            rumbling: false,
+            on_change: Some(on_change),
+        }
+    }
+
+    fn set_rumbling(&mut self, rumbling: bool)
+    {
+        if self.rumbling == rumbling
+        {
+            return;
+        }
+        self.rumbling = rumbling;
+        if let Some(ref mut on_change) = self.on_change
+        {
+            on_change(rumbling);
+        }
+    }
+}
+
+impl ControllerAccessory for RumblePak
+{
+    fn read_block(&mut self, address: u16, data: &mut [u8])
+    {
+        let value = match address
+        {
+            RUMBLE_PAK_PROBE_START...RUMBLE_PAK_PROBE_END => RUMBLE_PAK_IDENTIFIER,
+            _ => 0x00,
+        };
+        for byte in data.iter_mut()
+        {
+            *byte = value;
+        }
+    }
+
+    fn write_block(&mut self, address: u16, data: &[u8])
+    {
+        match address
+        {
+            RUMBLE_PAK_MOTOR_START...RUMBLE_PAK_MOTOR_END => self.set_rumbling(data[data.len() - 1] & 0x01 != 0),
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod rumble_pak_tests
+{
+    use n64::rumble_pak::*;
+    use n64::controller::*;
+    use n64::joybus::JoybusDevice;
+    use std::rc::Rc;
+    use std::cell::RefCell;
+
+    fn write_command(address: u16, value: u8) -> Vec<u8>
+    {
+        let address = address | accessory_address_crc(address) as u16;
+        let mut command: Vec<u8> = vec![0x03, (address >> 8) as u8, address as u8];
+        command.extend_from_slice(&[value; 32]);
+        command
+    }
+
+    #[test]
+    fn probe_identifies_rumble_pak()
+    {
+        let mut rumble_pak = RumblePak::new();
+        let mut data = [0_u8; 32];
+        rumble_pak.read_block(0x8000, &mut data);
+        assert_eq!(data, [0x80; 32]);
+        rumble_pak.read_block(0x0000, &mut data);
+        assert_eq!(data, [0x00; 32]);
+    }
+
+    #[test]
+    fn motor_changes_are_reported()
+    {
+        let events: Rc<RefCell<Vec<bool>>> = Rc::new(RefCell::new(Vec::new()));
+        let recorded = events.clone();
+        let rumble_pak = RumblePak::with_callback(Box::new(move |rumbling| recorded.borrow_mut().push(rumbling)));
+        let mut controller = Controller::with_accessory(Box::new(ControllerState::neutral()), Box::new(rumble_pak));
+
+        let mut response = [0_u8; 1];
+        controller.process_command(&write_command(0xC000, 0x01), &mut response).unwrap();
+        controller.process_command(&write_command(0xC000, 0x01), &mut response).unwrap();
+        controller.process_command(&write_command(0xC000, 0x00), &mut response).unwrap();
+        //Probe writes do not touch the motor
+        controller.process_command(&write_command(0x8000, 0x80), &mut response).unwrap();
+
+        assert_eq!(*events.borrow(), vec![true, false]);
+    }
+}
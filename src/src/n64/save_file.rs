@@ -0,0 +1,147 @@
+use std::fs;
+use std::io;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum SaveError
+{
+    IO(String),
+    INVALID_SIZE(usize),
+    NOTE_NOT_FOUND(usize),
+    NO_FREE_NOTE,
+    NO_FREE_PAGES(usize),
+    INVALID_NOTE(String),
+}
+
+impl fmt::Display for SaveError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self
+        {
+            SaveError::IO(s) => write!(f, "Save file access failed: {}", s),
+            SaveError::INVALID_SIZE(size) => write!(f, "Save file has an unexpected size (0x{:X} bytes)", size),
+            SaveError::NOTE_NOT_FOUND(index) => write!(f, "Controller Pak note {} does not exist", index),
+            SaveError::NO_FREE_NOTE => write!(f, "Controller Pak has no free note slots"),
+            SaveError::NO_FREE_PAGES(pages) => write!(f, "Controller Pak does not have {} free pages", pages),
+            SaveError::INVALID_NOTE(s) => write!(f, "Controller Pak note is invalid: {}", s),
+        }
+    }
+}
+
+impl From<io::Error> for SaveError
+{
+    fn from(error: io::Error) -> SaveError
+    {
+        SaveError::IO(error.to_string())
+    }
+}
+
+//Backing storage for cartridge and accessory saves, written out when dirty
+pub struct SaveFile
+{
+    pub path: Option<PathBuf>,
+    //Bytes that sit in front of the save on disk, kept so the file keeps its format
+    pub header: Vec<u8>,
+    pub data: Vec<u8>,
+    pub dirty: bool,
+}
+
+impl SaveFile
+{
+    //Save that only lives in memory
+    pub fn new(size: usize, fill: u8) -> SaveFile
+    {
+        return SaveFile
+        {
+            path: None,
+            header: Vec::new(),
+            data: vec![fill; size],
+            dirty: false,
+        }
+    }
+
+    //Loads an existing save, or starts a fresh one that is created on the first flush
+    pub fn open<P: AsRef<Path>>(path: P, size: usize, fill: u8) -> Result<SaveFile, SaveError>
+    {
+        SaveFile::open_with_header(path, size, fill, |_| 0)
+    }
+
+    //A file of the wrong size is refused rather than padded or cut, flushing it would destroy the original
+    pub fn open_with_header<P: AsRef<Path>, F: Fn(&[u8]) -> usize>(path: P, size: usize, fill: u8, header_size: F) -> Result<SaveFile, SaveError>
+    {
+        let path = path.as_ref().to_path_buf();
+        let (header, data) = match fs::read(&path)
+        {
+            Ok(mut data) =>
+            {
+                let header_size = header_size(&data).min(data.len());
+                let save = data.split_off(header_size);
+                (data, save)
+            },
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => (Vec::new(), vec![fill; size]),
+            Err(e) => return Err(SaveError::from(e)),
+        };
+        if data.len() != size
+        {
+            return Err(SaveError::INVALID_SIZE(data.len()));
+        }
+
+        return Ok(SaveFile
+        {
+            path: Some(path),
+            header: header,
+            data: data,
+            dirty: false,
+        })
+    }
+
+    pub fn exists(&self) -> bool
+    {
+        match self.path
+        {
+            Some(ref path) => path.exists(),
+            None => false,
+        }
+    }
+
+    pub fn read(&self, offset: usize, buffer: &mut [u8])
+    {
+        buffer.copy_from_slice(&self.data[offset..(offset + buffer.len())]);
+    }
+
+    pub fn write(&mut self, offset: usize, data: &[u8])
+    {
+        self.data[offset..(offset + data.len())].copy_from_slice(data);
+        self.dirty = true;
+    }
+
+    pub fn flush(&mut self) -> Result<(), SaveError>
+    {
+        if !self.dirty
+        {
+            return Ok(());
+        }
+        if let Some(ref path) = self.path
+        {
+            let mut contents = self.header.to_vec();
+            contents.extend_from_slice(&self.data);
+            fs::write(path, &contents)?;
+        }
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+impl Drop for SaveFile
+{
+    fn drop(&mut self)
+    {
+        if let Err(e) = self.flush()
+        {
+            eprintln!("{}", e);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use n64::rom::RomHeader;
+use n64::flash_ram::FLASH_RAM_SIZE;
+
+//Homebrew declares its save type in the header when the cartridge ID is "ED"
+// Referenced: https://n64brew.dev/wiki/ROM_Header#Advanced_Homebrew_ROM_Header
+const ED64_CARTRIDGE_ID: u16 = 0x4544;
+
+//Titles that need the larger EEPROM, anything else missing from the game database has no save
+const EEPROM_16K_CARTRIDGE_IDS: [&str; 6] = ["YS", "PD", "DO", "B7", "FU", "MX"];
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum SaveType
+{
+    NONE,
+    EEPROM_4K,
+    EEPROM_16K,
+    SRAM_32K,
+    SRAM_96K,
+    SRAM_128K,
+    FLASH_RAM,
+}
+
+impl SaveType
+{
+    pub fn from_ed64_header(value: u8) -> Option<SaveType>
+    {
+        match value >> 4
+        {
+            0x0 => Some(SaveType::NONE),
+            0x1 => Some(SaveType::EEPROM_4K),
+            0x2 => Some(SaveType::EEPROM_16K),
+            0x3 => Some(SaveType::SRAM_32K),
+            0x4 => Some(SaveType::SRAM_96K),
+            0x5 => Some(SaveType::FLASH_RAM),
+            0x6 => Some(SaveType::SRAM_128K),
+            _ => None,
+        }
+    }
+
+    pub fn size(self) -> usize
+    {
+        match self
+        {
+            SaveType::NONE => 0,
+            SaveType::EEPROM_4K => 0x200,
+            SaveType::EEPROM_16K => 0x800,
+            SaveType::SRAM_32K => 0x8000,
+            SaveType::SRAM_96K => 0x18000,
+            SaveType::SRAM_128K => 0x20000,
+            SaveType::FLASH_RAM => FLASH_RAM_SIZE,
+        }
+    }
+
+    //Value of a blank save
+    pub fn fill_value(self) -> u8
+    {
+        match self
+        {
+            SaveType::SRAM_32K | SaveType::SRAM_96K | SaveType::SRAM_128K => 0x00,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn file_extension(self) -> &'static str
+    {
+        match self
+        {
+            SaveType::NONE => "",
+            SaveType::EEPROM_4K | SaveType::EEPROM_16K => "eep",
+            SaveType::SRAM_32K | SaveType::SRAM_96K | SaveType::SRAM_128K => "sra",
+            SaveType::FLASH_RAM => "fla",
+        }
+    }
+}
+
+pub fn detect_save_type(rom_header: &RomHeader) -> SaveType
+{
+    if rom_header.cartridge_id == ED64_CARTRIDGE_ID
+    {
+        if let Some(save_type) = SaveType::from_ed64_header(rom_header.revision)
+        {
+            return save_type;
+        }
+    }
+
+    let cartridge_id: String = rom_header.game_code.chars().skip(1).take(2).collect();
+    if EEPROM_16K_CARTRIDGE_IDS.contains(&cartridge_id.as_str())
+    {
+        SaveType::EEPROM_16K
+    }
+    else
+    {
+        SaveType::NONE
+    }
+}
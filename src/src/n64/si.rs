@@ -0,0 +1,158 @@
+const SI_DRAM_ADDR_REG_START: usize = 0x00000000;
+const SI_DRAM_ADDR_REG_END: usize = 0x00000003;
+const SI_PIF_ADDR_RD64B_REG_START: usize = 0x00000004;
+const SI_PIF_ADDR_RD64B_REG_END: usize = 0x00000007;
+const SI_PIF_ADDR_WR64B_REG_START: usize = 0x00000010;
+const SI_PIF_ADDR_WR64B_REG_END: usize = 0x00000013;
+const SI_STATUS_REG_START: usize = 0x00000018;
+const SI_STATUS_REG_END: usize = 0x0000001B;
+
+pub const SI_STATUS_DMA_BUSY: u32 = 0x00000001;
+pub const SI_STATUS_IO_READ_BUSY: u32 = 0x00000002;
+pub const SI_STATUS_DMA_ERROR: u32 = 0x00000008;
+pub const SI_STATUS_INTERRUPT: u32 = 0x00001000;
+
+const SI_DRAM_ADDR_MASK: u32 = 0x00FFFFFF;
+// Referenced: mupen64plus schedules SI completion 0x900 count cycles after the transfer
+pub const SI_DMA_DURATION: u32 = 0x900;
+
+use n64::arch::Reg;
+use n64::exceptions::Exception;
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum SIDMADirection
+{
+    PIF_TO_RDRAM,
+    RDRAM_TO_PIF,
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+pub struct SIDMARequest
+{
+    pub direction: SIDMADirection,
+    pub dram_address: u32,
+}
+
+pub struct SerialInterface
+{
+    pub dram_address: Reg,
+    pub pif_address_read: Reg,
+    pub pif_address_write: Reg,
+    pub status: Reg,
+    pub active_dma: Option<SIDMARequest>,
+    pub dma_cycles_remaining: u32,
+    pub dma_duration: u32,
+}
+
+impl SerialInterface
+{
+    pub fn new() -> SerialInterface
+    {
+        return SerialInterface
+        {
+            dram_address: Reg::default(),
+            pif_address_read: Reg::default(),
+            pif_address_write: Reg::default(),
+            status: Reg::default(),
+            active_dma: None,
+            dma_cycles_remaining: 0,
+            dma_duration: SI_DMA_DURATION,
+        }
+    }
+
+    pub fn is_busy(&self) -> bool
+    {
+        self.status.get_value() as u32 & SI_STATUS_DMA_BUSY != 0
+    }
+
+    //A transfer started while another is in flight is dropped
+    fn start_dma(&mut self, direction: SIDMADirection)
+    {
+        if self.is_busy()
+        {
+            return;
+        }
+        self.active_dma = Some(SIDMARequest
+        {
+            direction: direction,
+            dram_address: self.dram_address.get_value() as u32,
+        });
+        let value = self.status.get_value() as u32 | SI_STATUS_DMA_BUSY;
+        self.status.set_value(value);
+        self.dma_cycles_remaining = self.dma_duration;
+    }
+
+    //Returns the transfer once it completes, the connector moves the data and raises the SI interrupt
+    pub fn step(&mut self, cycles: u32) -> Option<SIDMARequest>
+    {
+        if !self.is_busy()
+        {
+            return None;
+        }
+        self.dma_cycles_remaining = self.dma_cycles_remaining.saturating_sub(cycles);
+        if self.dma_cycles_remaining > 0
+        {
+            return None;
+        }
+        let value = (self.status.get_value() as u32 & !SI_STATUS_DMA_BUSY) | SI_STATUS_INTERRUPT;
+        self.status.set_value(value);
+        self.active_dma.take()
+    }
+
+    pub fn read_u32_from_address(&self, address: usize) -> Result<u32, Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match address
+        {
+            SI_DRAM_ADDR_REG_START...SI_DRAM_ADDR_REG_END => Ok(self.dram_address.get_value() as u32),
+            SI_PIF_ADDR_RD64B_REG_START...SI_PIF_ADDR_RD64B_REG_END => Ok(self.pif_address_read.get_value() as u32),
+            SI_PIF_ADDR_WR64B_REG_START...SI_PIF_ADDR_WR64B_REG_END => Ok(self.pif_address_write.get_value() as u32),
+            SI_STATUS_REG_START...SI_STATUS_REG_END => Ok(self.status.get_value() as u32),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    pub fn load_u32_to_address(&mut self, address: usize, value: u32) -> Result<(), Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match address
+        {
+            SI_DRAM_ADDR_REG_START...SI_DRAM_ADDR_REG_END => Ok(self.dram_address.set_value(value & SI_DRAM_ADDR_MASK)),
+            SI_PIF_ADDR_RD64B_REG_START...SI_PIF_ADDR_RD64B_REG_END =>
+            {
+                self.pif_address_read.set_value(value);
+                self.start_dma(SIDMADirection::PIF_TO_RDRAM);
+                Ok(())
+            },
+            SI_PIF_ADDR_WR64B_REG_START...SI_PIF_ADDR_WR64B_REG_END =>
+            {
+                self.pif_address_write.set_value(value);
+                self.start_dma(SIDMADirection::RDRAM_TO_PIF);
+                Ok(())
+            },
+            //Any write acknowledges the interrupt, the connector clears the MI side
+            SI_STATUS_REG_START...SI_STATUS_REG_END =>
+            {
+                let status = self.status.get_value() as u32 & !SI_STATUS_INTERRUPT;
+                self.status.set_value(status);
+                Ok(())
+            },
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+}
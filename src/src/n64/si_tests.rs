@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod si_tests
+{
+    use n64::si::*;
+    use n64::connector::Connector;
+    use n64::n64::N64;
+    use n64::rom::Rom;
+    use n64::mips_iface::MI_INTR_SI;
+    use n64::pif::{PIF_CONTROL_BYTE, PIF_CONTROL_TERMINATE_BOOT};
+    use n64::cpu::COP0RegisterName;
+
+    #[test]
+    fn registers_read_back()
+    {
+        let mut si = SerialInterface::new();
+        si.load_u32_to_address(0x00, 0xFF123456).unwrap();
+        assert_eq!(si.read_u32_from_address(0x00).unwrap(), 0x00123456);
+        assert_eq!(si.read_u32_from_address(0x18).unwrap(), 0);
+        assert!(si.read_u32_from_address(0x1C).is_err());
+    }
+
+    #[test]
+    fn dma_completes_after_duration()
+    {
+        let mut si = SerialInterface::new();
+        si.load_u32_to_address(0x00, 0x1000).unwrap();
+        si.load_u32_to_address(0x04, 0x1FC007C0).unwrap();
+        assert!(si.is_busy());
+        //A second transfer while busy is dropped
+        si.load_u32_to_address(0x00, 0x2000).unwrap();
+        si.load_u32_to_address(0x10, 0x1FC007C0).unwrap();
+        assert_eq!(si.step(SI_DMA_DURATION - 1), None);
+        assert_eq!(si.step(1), Some(SIDMARequest {direction: SIDMADirection::PIF_TO_RDRAM, dram_address: 0x1000}));
+        assert_eq!(si.read_u32_from_address(0x18).unwrap(), SI_STATUS_INTERRUPT);
+        assert_eq!(si.step(SI_DMA_DURATION), None);
+    }
+
+    #[test]
+    fn dma_moves_pif_ram_both_ways()
+    {
+        let mut connector = Connector::test();
+        for index in 0..0x40
+        {
+            connector.rdram.store_u8(0x1000 + index, index as u8).unwrap();
+        }
+        connector.rdram.store_u8(0x1000 + PIF_CONTROL_BYTE, PIF_CONTROL_TERMINATE_BOOT).unwrap();
+
+        connector.store_u32(0xA4800000, 0x00001000).unwrap();
+        connector.store_u32(0xA4800010, 0x1FC007C0).unwrap();
+        assert_eq!(connector.read_u32(0xA4800018).unwrap(), SI_STATUS_DMA_BUSY);
+        //PIF RAM only changes once the transfer completes
+        assert_eq!(connector.pif.ram[0x10], 0);
+
+        connector.step(SI_DMA_DURATION);
+        assert_eq!(connector.pif.ram[0x10], 0x10);
+        assert!(connector.pif.boot_terminated);
+        assert_eq!(connector.read_u32(0xA4800018).unwrap(), SI_STATUS_INTERRUPT);
+        assert_eq!(connector.mips_interface.interrupt.get_value() as u32, MI_INTR_SI);
+
+        //Writing status acknowledges both sides
+        connector.store_u32(0xA4800018, 0).unwrap();
+        assert_eq!(connector.read_u32(0xA4800018).unwrap(), 0);
+        assert_eq!(connector.mips_interface.interrupt.get_value(), 0);
+
+        connector.pif.ram[0x20] = 0xAB;
+        connector.store_u32(0xA4800000, 0x00002000).unwrap();
+        connector.store_u32(0xA4800004, 0x1FC007C0).unwrap();
+        assert_eq!(connector.rdram.read_u8(0x2020).unwrap(), 0);
+        connector.step(SI_DMA_DURATION);
+        assert_eq!(connector.rdram.read_u8(0x2020).unwrap(), 0xAB);
+        assert_eq!(connector.rdram.read_bytes(0x2000, 4).unwrap(), vec![0x00, 0x01, 0x02, 0x03]);
+    }
+
+    #[test]
+    fn masked_interrupt_raises_ip2()
+    {
+        let mut n64 = N64::from_rom(Rom::test());
+        n64.connector.mips_interface.interrupt_mask.set_value(MI_INTR_SI);
+        n64.connector.store_u32(0xA4800010, 0x1FC007C0).unwrap();
+        n64.step_devices(SI_DMA_DURATION);
+        assert_eq!(n64.cpu.cop0_registers.register[COP0RegisterName::Cause as usize].get_value() & 0x400, 0x400);
+
+        n64.connector.store_u32(0xA4800018, 0).unwrap();
+        n64.step_devices(1);
+        assert_eq!(n64.cpu.cop0_registers.register[COP0RegisterName::Cause as usize].get_value() & 0x400, 0);
+    }
+}
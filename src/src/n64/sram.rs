@@ -0,0 +1,91 @@
+use n64::exceptions::Exception;
+use n64::save_file::{SaveFile, SaveError};
+use n64::save_type::SaveType;
+use binary_helpers::*;
+
+// Referenced: https://n64brew.dev/wiki/SRAM
+//Larger parts are 32KB banks spaced 256KB apart in the domain
+const SRAM_BANK_SIZE: usize = 0x8000;
+const SRAM_BANK_SHIFT: usize = 18;
+const SRAM_BANK_MASK: usize = 0x3;
+
+pub struct SRAM
+{
+    pub save_type: SaveType,
+    pub save: SaveFile,
+}
+
+impl SRAM
+{
+    pub fn new(save_type: SaveType, save: SaveFile) -> SRAM
+    {
+        return SRAM
+        {
+            save_type: save_type,
+            save: save,
+        }
+    }
+
+    fn location(&self, address: usize) -> Option<usize>
+    {
+        let bank = (address >> SRAM_BANK_SHIFT) & SRAM_BANK_MASK;
+        let location = (bank * SRAM_BANK_SIZE) + (address & (SRAM_BANK_SIZE - 1));
+        if location < self.save.data.len() {Some(location)} else {None}
+    }
+
+    pub fn read_u32_from_address(&self, address: usize) -> Result<u32, Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match self.location(address)
+        {
+            Some(location) => Ok(u8_slice_to_u32(self.save.data[location..(location + 4)].to_vec())),
+            None => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    pub fn load_u32_to_address(&mut self, address: usize, value: u32) -> Result<(), Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match self.location(address)
+        {
+            Some(location) => Ok(self.save.write(location, &[(value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8])),
+            None => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    //Used by PI DMA, which moves arbitrary lengths
+    pub fn dma_read(&self, address: usize, length: usize) -> Vec<u8>
+    {
+        (address..(address + length)).map(|address| match self.location(address)
+        {
+            Some(location) => self.save.data[location],
+            None => 0,
+        }).collect()
+    }
+
+    pub fn dma_write(&mut self, address: usize, data: &[u8])
+    {
+        for (offset, value) in data.iter().enumerate()
+        {
+            if let Some(location) = self.location(address + offset)
+            {
+                self.save.write(location, &[*value]);
+            }
+        }
+    }
+
+    pub fn flush(&mut self) -> Result<(), SaveError>
+    {
+        self.save.flush()
+    }
+}
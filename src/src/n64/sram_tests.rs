@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod sram_tests
+{
+    use n64::sram::*;
+    use n64::save_type::SaveType;
+    use n64::save_file::SaveFile;
+    use n64::connector::Connector;
+    use n64::n64::N64;
+    use n64::rom::Rom;
+    use n64::pi::PI_DMA_CYCLES_PER_BYTE;
+    use std::env;
+    use std::fs;
+
+    fn test_sram(save_type: SaveType) -> SRAM
+    {
+        SRAM::new(save_type, SaveFile::new(save_type.size(), save_type.fill_value()))
+    }
+
+    #[test]
+    fn words_are_stored_big_endian()
+    {
+        let mut sram = test_sram(SaveType::SRAM_32K);
+        sram.load_u32_to_address(0x10, 0x11223344).unwrap();
+        assert_eq!(sram.read_u32_from_address(0x10).unwrap(), 0x11223344);
+        assert_eq!(sram.save.data[0x10..0x14].to_vec(), vec![0x11, 0x22, 0x33, 0x44]);
+        assert!(sram.save.dirty);
+        assert_eq!(sram.read_u32_from_address(0x11).err(), Some(::n64::exceptions::Exception::ADDRESS_ERROR));
+    }
+
+    #[test]
+    fn banked_sram_maps_each_bank()
+    {
+        let mut sram = test_sram(SaveType::SRAM_96K);
+        sram.dma_write(0x80000, &[0xAB, 0xCD]);
+        assert_eq!(sram.save.data[0x10000..0x10002].to_vec(), vec![0xAB, 0xCD]);
+        assert_eq!(sram.dma_read(0x80000, 2), vec![0xAB, 0xCD]);
+        //Fourth bank does not exist on 96KB parts
+        assert!(sram.read_u32_from_address(0xC0000).is_err());
+
+        let mut sram = test_sram(SaveType::SRAM_32K);
+        assert!(sram.load_u32_to_address(0x40000, 0x1).is_err());
+    }
+
+    #[test]
+    fn sram_is_mapped_in_domain_2()
+    {
+        let mut connector = Connector::test();
+        assert!(connector.read_u32(0xA8000000).is_err());
+        connector.sram = Some(test_sram(SaveType::SRAM_32K));
+        connector.store_u32(0xA8000100, 0xCAFEBABE).unwrap();
+        assert_eq!(connector.read_u32(0xA8000100).unwrap(), 0xCAFEBABE);
+    }
+
+    fn pi_dma(n64: &mut N64, length_register: u32, dram_address: u32, cart_address: u32, length: u32)
+    {
+        n64.connector.store_u32(0xA4600000, dram_address).unwrap();
+        n64.connector.store_u32(0xA4600004, cart_address).unwrap();
+        n64.connector.store_u32(length_register, length - 1).unwrap();
+        n64.connector.step(length * PI_DMA_CYCLES_PER_BYTE);
+    }
+
+    #[test]
+    fn saves_survive_a_reload_through_pi_dma()
+    {
+        let rom_path = env::temp_dir().join(format!("n8x8_sram_dma_test_{}.z64", ::std::process::id()));
+        let sra_path = rom_path.with_extension("sra");
+        let mut rom_data: Vec<u8> = vec![0; 0x1000];
+        rom_data[0..4].copy_from_slice(&[0x80, 0x37, 0x12, 0x40]);
+        rom_data[0x3B..0x40].copy_from_slice(&[b'N', b'E', b'D', b'A', 0x40]);
+        fs::write(&rom_path, &rom_data).unwrap();
+        let _ = fs::remove_file(&sra_path);
+
+        let data: Vec<u8> = (0..0x100).map(|value| value as u8).collect();
+        let mut n64 = N64::from_rom(Rom::new(rom_path.to_str().unwrap()).unwrap());
+        n64.open_save_files().unwrap();
+        n64.connector.rdram.store_bytes(0x1000, &data).unwrap();
+        //Second bank of a 96KB part
+        pi_dma(&mut n64, 0xA4600008, 0x1000, 0x08040080, 0x100);
+        n64.flush_saves().unwrap();
+        assert_eq!(fs::read(&sra_path).unwrap()[0x8080..0x8180].to_vec(), data);
+
+        let mut n64 = N64::from_rom(Rom::new(rom_path.to_str().unwrap()).unwrap());
+        n64.open_save_files().unwrap();
+        pi_dma(&mut n64, 0xA460000C, 0x2000, 0x08040080, 0x100);
+        assert_eq!(n64.connector.rdram.read_bytes(0x2000, 0x100).unwrap(), data);
+        fs::remove_file(&rom_path).unwrap();
+        fs::remove_file(&sra_path).unwrap();
+    }
+}
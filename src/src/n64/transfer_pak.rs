@@ -0,0 +1,381 @@
+use n64::controller::ControllerAccessory;
+use n64::save_file::{SaveFile, SaveError};
+use std::fs;
+use std::path::Path;
+
+// Referenced: https://n64brew.dev/wiki/Transfer_Pak
+const TRANSFER_PAK_IDENTIFIER: u8 = 0x84;
+const TRANSFER_PAK_POWER_OFF: u8 = 0xFE;
+const TRANSFER_PAK_ID_START: u16 = 0x8000;
+const TRANSFER_PAK_ID_END: u16 = 0x8FFF;
+const TRANSFER_PAK_BANK_START: u16 = 0xA000;
+const TRANSFER_PAK_BANK_END: u16 = 0xAFFF;
+const TRANSFER_PAK_STATUS_START: u16 = 0xB000;
+const TRANSFER_PAK_STATUS_END: u16 = 0xBFFF;
+const TRANSFER_PAK_CARTRIDGE_START: u16 = 0xC000;
+const TRANSFER_PAK_CARTRIDGE_END: u16 = 0xFFFF;
+const TRANSFER_PAK_BANK_SIZE: usize = 0x4000;
+
+const TRANSFER_PAK_STATUS_ACCESS: u8 = 0x01;
+const TRANSFER_PAK_STATUS_RESET: u8 = 0x04;
+const TRANSFER_PAK_STATUS_POWERED: u8 = 0x80;
+const TRANSFER_PAK_STATUS_ACCESS_ON: u8 = 0x08;
+const TRANSFER_PAK_STATUS_NO_CARTRIDGE: u8 = 0x40;
+
+//Game Boy cartridge header
+const GB_HEADER_SIZE: usize = 0x150;
+const GB_CARTRIDGE_TYPE: usize = 0x147;
+const GB_RAM_SIZE: usize = 0x149;
+const GB_ROM_BANK_SIZE: usize = 0x4000;
+const GB_RAM_BANK_SIZE: usize = 0x2000;
+
+//Game Boy address space as seen through the pak
+const GB_RAM_ENABLE_START: u16 = 0x0000;
+const GB_RAM_ENABLE_END: u16 = 0x1FFF;
+const GB_ROM_BANK_START: u16 = 0x2000;
+const GB_ROM_BANK_END: u16 = 0x3FFF;
+const GB_RAM_BANK_START: u16 = 0x4000;
+const GB_RAM_BANK_END: u16 = 0x5FFF;
+const GB_MODE_START: u16 = 0x6000;
+const GB_MODE_END: u16 = 0x7FFF;
+const GB_ROM_BANK_0_END: u16 = 0x3FFF;
+const GB_ROM_BANK_N_START: u16 = 0x4000;
+const GB_ROM_BANK_N_END: u16 = 0x7FFF;
+const GB_EXTERNAL_RAM_START: u16 = 0xA000;
+const GB_EXTERNAL_RAM_END: u16 = 0xBFFF;
+
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum MemoryBankController
+{
+    ROM_ONLY,
+    MBC1,
+    MBC2,
+    MBC3,
+    MBC5,
+    UNSUPPORTED(u8),
+}
+
+impl MemoryBankController
+{
+    pub fn from_u8(value: u8) -> MemoryBankController
+    {
+        match value
+        {
+            0x00 | 0x08 | 0x09 => MemoryBankController::ROM_ONLY,
+            0x01...0x03 => MemoryBankController::MBC1,
+            0x05 | 0x06 => MemoryBankController::MBC2,
+            0x0F...0x13 => MemoryBankController::MBC3,
+            0x19...0x1E => MemoryBankController::MBC5,
+            _ => MemoryBankController::UNSUPPORTED(value),
+        }
+    }
+}
+
+pub struct GameBoyCartridge
+{
+    pub rom: Vec<u8>,
+    pub ram: SaveFile,
+    pub controller: MemoryBankController,
+    pub ram_enabled: bool,
+    pub rom_bank: usize,
+    pub ram_bank: usize,
+    pub banking_mode: u8,
+}
+
+impl GameBoyCartridge
+{
+    pub fn new(rom: Vec<u8>, ram: SaveFile) -> Result<GameBoyCartridge, SaveError>
+    {
+        if rom.len() < GB_HEADER_SIZE
+        {
+            return Err(SaveError::INVALID_SIZE(rom.len()));
+        }
+        return Ok(GameBoyCartridge
+        {
+            controller: MemoryBankController::from_u8(rom[GB_CARTRIDGE_TYPE]),
+            rom: rom,
+            ram: ram,
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            banking_mode: 0,
+        })
+    }
+
+    //Cartridge RAM is kept in a .sav file in the layout other Game Boy emulators use
+    pub fn open<P: AsRef<Path>, Q: AsRef<Path>>(rom_path: P, save_path: Q) -> Result<GameBoyCartridge, SaveError>
+    {
+        let mut cartridge = GameBoyCartridge::from_rom(fs::read(rom_path)?)?;
+        cartridge.ram = SaveFile::open(save_path, cartridge.ram.data.len(), 0xFF)?;
+        Ok(cartridge)
+    }
+
+    //Cartridge RAM starts blank and is never written to disk
+    pub fn from_rom(rom: Vec<u8>) -> Result<GameBoyCartridge, SaveError>
+    {
+        if rom.len() < GB_HEADER_SIZE
+        {
+            return Err(SaveError::INVALID_SIZE(rom.len()));
+        }
+        let ram = SaveFile::new(ram_size(rom[GB_CARTRIDGE_TYPE], rom[GB_RAM_SIZE]), 0xFF);
+        GameBoyCartridge::new(rom, ram)
+    }
+
+    pub fn read_u8(&self, address: u16) -> u8
+    {
+        match address
+        {
+            0x0000...GB_ROM_BANK_0_END =>
+            {
+                //MBC1 mode 1 can also bank the lower half
+                let bank = if self.controller == MemoryBankController::MBC1 && self.banking_mode == 1 {self.ram_bank << 5} else {0};
+                self.read_rom(bank, address as usize)
+            },
+            GB_ROM_BANK_N_START...GB_ROM_BANK_N_END => self.read_rom(self.current_rom_bank(), (address - GB_ROM_BANK_N_START) as usize),
+            GB_EXTERNAL_RAM_START...GB_EXTERNAL_RAM_END =>
+            {
+                match self.ram_location(address)
+                {
+                    Some(location) if self.controller == MemoryBankController::MBC2 => self.ram.data[location] | 0xF0,
+                    Some(location) => self.ram.data[location],
+                    None => 0xFF,
+                }
+            },
+            _ => 0x00,
+        }
+    }
+
+    pub fn write_u8(&mut self, address: u16, value: u8)
+    {
+        match address
+        {
+            GB_RAM_ENABLE_START...GB_MODE_END => self.write_register(address, value),
+            GB_EXTERNAL_RAM_START...GB_EXTERNAL_RAM_END =>
+            {
+                if let Some(location) = self.ram_location(address)
+                {
+                    self.ram.write(location, &[value]);
+                }
+            },
+            _ => (),
+        }
+    }
+
+    fn write_register(&mut self, address: u16, value: u8)
+    {
+        match (self.controller, address)
+        {
+            (MemoryBankController::ROM_ONLY, _) => (),
+            (MemoryBankController::MBC2, GB_RAM_ENABLE_START...GB_ROM_BANK_END) =>
+            {
+                //Address bit 8 picks between RAM enable and ROM bank
+                if address & 0x0100 == 0
+                {
+                    self.ram_enabled = value & 0x0F == 0x0A;
+                }
+                else
+                {
+                    self.rom_bank = if value & 0x0F == 0 {1} else {(value & 0x0F) as usize};
+                }
+            },
+            (_, GB_RAM_ENABLE_START...GB_RAM_ENABLE_END) => self.ram_enabled = value & 0x0F == 0x0A,
+            (MemoryBankController::MBC1, GB_ROM_BANK_START...GB_ROM_BANK_END) => self.rom_bank = if value & 0x1F == 0 {1} else {(value & 0x1F) as usize},
+            (MemoryBankController::MBC3, GB_ROM_BANK_START...GB_ROM_BANK_END) => self.rom_bank = if value & 0x7F == 0 {1} else {(value & 0x7F) as usize},
+            (MemoryBankController::MBC5, GB_ROM_BANK_START...0x2FFF) => self.rom_bank = (self.rom_bank & 0x100) | value as usize,
+            (MemoryBankController::MBC5, 0x3000...GB_ROM_BANK_END) => self.rom_bank = (self.rom_bank & 0xFF) | (((value & 0x01) as usize) << 8),
+            (MemoryBankController::MBC1, GB_RAM_BANK_START...GB_RAM_BANK_END) => self.ram_bank = (value & 0x03) as usize,
+            //RTC registers (0x08-0x0C) are selected here but not emulated
+            (MemoryBankController::MBC3, GB_RAM_BANK_START...GB_RAM_BANK_END) => self.ram_bank = value as usize,
+            (MemoryBankController::MBC5, GB_RAM_BANK_START...GB_RAM_BANK_END) => self.ram_bank = (value & 0x0F) as usize,
+            (MemoryBankController::MBC1, GB_MODE_START...GB_MODE_END) => self.banking_mode = value & 0x01,
+            _ => (),
+        }
+    }
+
+    fn current_rom_bank(&self) -> usize
+    {
+        match self.controller
+        {
+            MemoryBankController::ROM_ONLY => 1,
+            MemoryBankController::MBC1 => self.rom_bank | (self.ram_bank << 5),
+            _ => self.rom_bank,
+        }
+    }
+
+    fn read_rom(&self, bank: usize, offset: usize) -> u8
+    {
+        let bank_count = (self.rom.len() + GB_ROM_BANK_SIZE - 1) / GB_ROM_BANK_SIZE;
+        let location = ((bank % bank_count) * GB_ROM_BANK_SIZE) + offset;
+        match self.rom.get(location)
+        {
+            Some(value) => *value,
+            None => 0xFF,
+        }
+    }
+
+    fn ram_location(&self, address: u16) -> Option<usize>
+    {
+        if !self.ram_enabled || self.ram.data.is_empty()
+        {
+            return None;
+        }
+        let offset = (address - GB_EXTERNAL_RAM_START) as usize;
+        let location = match self.controller
+        {
+            MemoryBankController::MBC2 => offset & 0x1FF,
+            MemoryBankController::MBC1 if self.banking_mode == 0 => offset,
+            MemoryBankController::MBC3 if self.ram_bank > 0x03 => return None,
+            _ => (self.ram_bank * GB_RAM_BANK_SIZE) + offset,
+        };
+        Some(location % self.ram.data.len())
+    }
+}
+
+pub fn ram_size(cartridge_type: u8, ram_size: u8) -> usize
+{
+    //MBC2 has 512 half bytes built in
+    if MemoryBankController::from_u8(cartridge_type) == MemoryBankController::MBC2
+    {
+        return 0x200;
+    }
+    match ram_size
+    {
+        0x01 => 0x800,
+        0x02 => 0x2000,
+        0x03 => 0x8000,
+        0x04 => 0x20000,
+        0x05 => 0x10000,
+        _ => 0,
+    }
+}
+
+pub struct TransferPak
+{
+    pub cartridge: Option<GameBoyCartridge>,
+    pub powered: bool,
+    pub bank: usize,
+    pub access_mode: bool,
+    pub access_mode_changed: bool,
+}
+
+impl TransferPak
+{
+    pub fn new(cartridge: Option<GameBoyCartridge>) -> TransferPak
+    {
+        return TransferPak
+        {
+            cartridge: cartridge,
+            powered: false,
+            bank: 0,
+            access_mode: false,
+            access_mode_changed: false,
+        }
+    }
+
+    fn status(&mut self) -> u8
+    {
+        let mut status = TRANSFER_PAK_STATUS_POWERED;
+        match self.cartridge
+        {
+            Some(_) if self.access_mode => status |= TRANSFER_PAK_STATUS_ACCESS | TRANSFER_PAK_STATUS_ACCESS_ON,
+            Some(_) => (),
+            None => status |= TRANSFER_PAK_STATUS_NO_CARTRIDGE,
+        }
+        if self.access_mode_changed
+        {
+            status |= TRANSFER_PAK_STATUS_RESET;
+            self.access_mode_changed = false;
+        }
+        status
+    }
+
+    fn cartridge_address(&self, address: u16, offset: usize) -> u16
+    {
+        ((self.bank * TRANSFER_PAK_BANK_SIZE) + (address - TRANSFER_PAK_CARTRIDGE_START) as usize + offset) as u16
+    }
+}
+
+impl ControllerAccessory for TransferPak
+{
+    fn read_block(&mut self, address: u16, data: &mut [u8])
+    {
+        match address
+        {
+            TRANSFER_PAK_ID_START...TRANSFER_PAK_ID_END =>
+            {
+                let value = if self.powered {TRANSFER_PAK_IDENTIFIER} else {0x00};
+                for byte in data.iter_mut()
+                {
+                    *byte = value;
+                }
+            },
+            TRANSFER_PAK_STATUS_START...TRANSFER_PAK_STATUS_END if self.powered =>
+            {
+                let value = self.status();
+                for byte in data.iter_mut()
+                {
+                    *byte = value;
+                }
+            },
+            TRANSFER_PAK_CARTRIDGE_START...TRANSFER_PAK_CARTRIDGE_END if self.powered && self.access_mode =>
+            {
+                let addresses: Vec<u16> = (0..data.len()).map(|offset| self.cartridge_address(address, offset)).collect();
+                if let Some(ref cartridge) = self.cartridge
+                {
+                    for (byte, gb_address) in data.iter_mut().zip(addresses.iter())
+                    {
+                        *byte = cartridge.read_u8(*gb_address);
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+
+    fn write_block(&mut self, address: u16, data: &[u8])
+    {
+        match address
+        {
+            TRANSFER_PAK_ID_START...TRANSFER_PAK_ID_END =>
+            {
+                match data[0]
+                {
+                    TRANSFER_PAK_IDENTIFIER => self.powered = true,
+                    TRANSFER_PAK_POWER_OFF => self.powered = false,
+                    _ => (),
+                }
+            },
+            TRANSFER_PAK_BANK_START...TRANSFER_PAK_BANK_END if self.powered => self.bank = (data[0] & 0x03) as usize,
+            TRANSFER_PAK_STATUS_START...TRANSFER_PAK_STATUS_END if self.powered =>
+            {
+                let access_mode = data[0] & TRANSFER_PAK_STATUS_ACCESS != 0;
+                self.access_mode_changed |= access_mode != self.access_mode;
+                self.access_mode = access_mode;
+            },
+            TRANSFER_PAK_CARTRIDGE_START...TRANSFER_PAK_CARTRIDGE_END if self.powered && self.access_mode =>
+            {
+                let addresses: Vec<u16> = (0..data.len()).map(|offset| self.cartridge_address(address, offset)).collect();
+                if let Some(ref mut cartridge) = self.cartridge
+                {
+                    for (byte, gb_address) in data.iter().zip(addresses.iter())
+                    {
+                        cartridge.write_u8(*gb_address, *byte);
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), SaveError>
+    {
+        match self.cartridge
+        {
+            Some(ref mut cartridge) => cartridge.ram.flush(),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod transfer_pak_tests
+{
+    use n64::transfer_pak::*;
+    use n64::controller::ControllerAccessory;
+
+    //Each ROM bank starts with its own bank number
+    fn test_gb_rom(cartridge_type: u8, ram_size: u8, banks: usize) -> Vec<u8>
+    {
+        let mut rom: Vec<u8> = vec![0; banks * 0x4000];
+        for bank in 0..banks
+        {
+            rom[bank * 0x4000] = bank as u8;
+        }
+        rom[0x147] = cartridge_type;
+        rom[0x149] = ram_size;
+        rom
+    }
+
+    fn write(transfer_pak: &mut TransferPak, address: u16, value: u8)
+    {
+        transfer_pak.write_block(address, &[value; 32]);
+    }
+
+    fn read(transfer_pak: &mut TransferPak, address: u16) -> [u8; 32]
+    {
+        let mut data = [0_u8; 32];
+        transfer_pak.read_block(address, &mut data);
+        data
+    }
+
+    #[test]
+    fn cartridge_types_and_ram_sizes()
+    {
+        assert_eq!(MemoryBankController::from_u8(0x13), MemoryBankController::MBC3);
+        assert_eq!(MemoryBankController::from_u8(0x1B), MemoryBankController::MBC5);
+        assert_eq!(MemoryBankController::from_u8(0xFC), MemoryBankController::UNSUPPORTED(0xFC));
+        assert_eq!(ram_size(0x13, 0x03), 0x8000);
+        assert_eq!(ram_size(0x06, 0x00), 0x200);
+        assert!(GameBoyCartridge::from_rom(vec![0; 0x100]).is_err());
+    }
+
+    #[test]
+    fn mbc_bank_switching()
+    {
+        let mut cartridge = GameBoyCartridge::from_rom(test_gb_rom(0x13, 0x03, 8)).unwrap();
+        assert_eq!(cartridge.read_u8(0x4000), 1);
+        cartridge.write_u8(0x2000, 0x05);
+        assert_eq!(cartridge.read_u8(0x4000), 5);
+        cartridge.write_u8(0x2000, 0x00);
+        assert_eq!(cartridge.read_u8(0x4000), 1);
+
+        //RAM is ignored until enabled
+        cartridge.write_u8(0xA000, 0x12);
+        assert_eq!(cartridge.read_u8(0xA000), 0xFF);
+        cartridge.write_u8(0x0000, 0x0A);
+        cartridge.write_u8(0x4000, 0x02);
+        cartridge.write_u8(0xA000, 0x12);
+        assert_eq!(cartridge.ram.data[0x4000], 0x12);
+        assert!(cartridge.ram.dirty);
+
+        let mut cartridge = GameBoyCartridge::from_rom(test_gb_rom(0x1B, 0x03, 0x120)).unwrap();
+        cartridge.write_u8(0x2000, 0x10);
+        cartridge.write_u8(0x3000, 0x01);
+        assert_eq!(cartridge.read_u8(0x4000), 0x10);
+        assert_eq!(cartridge.rom_bank, 0x110);
+    }
+
+    #[test]
+    fn transfer_pak_protocol()
+    {
+        let cartridge = GameBoyCartridge::from_rom(test_gb_rom(0x13, 0x03, 8)).unwrap();
+        let mut transfer_pak = TransferPak::new(Some(cartridge));
+        assert_eq!(read(&mut transfer_pak, 0x8000)[0], 0x00);
+
+        write(&mut transfer_pak, 0x8000, 0x84);
+        assert_eq!(read(&mut transfer_pak, 0x8000)[0], 0x84);
+        assert_eq!(read(&mut transfer_pak, 0xB000)[0], 0x80);
+
+        write(&mut transfer_pak, 0xB000, 0x01);
+        assert_eq!(read(&mut transfer_pak, 0xB000)[0], 0x8D);
+        assert_eq!(read(&mut transfer_pak, 0xB000)[0], 0x89);
+
+        //Bank 1 maps Game Boy 0x4000-0x7FFF at 0xC000
+        write(&mut transfer_pak, 0xA000, 0x00);
+        write(&mut transfer_pak, 0xE000, 0x03);
+        write(&mut transfer_pak, 0xA000, 0x01);
+        assert_eq!(read(&mut transfer_pak, 0xC000)[0], 0x03);
+
+        //Bank 2 maps cartridge RAM at 0xE000
+        write(&mut transfer_pak, 0xA000, 0x00);
+        write(&mut transfer_pak, 0xC000, 0x0A);
+        write(&mut transfer_pak, 0xA000, 0x02);
+        write(&mut transfer_pak, 0xE000, 0x5A);
+        assert_eq!(read(&mut transfer_pak, 0xE000), [0x5A; 32]);
+
+        write(&mut transfer_pak, 0x8000, 0xFE);
+        assert_eq!(read(&mut transfer_pak, 0x8000)[0], 0x00);
+    }
+
+    #[test]
+    fn empty_transfer_pak_reports_no_cartridge()
+    {
+        let mut transfer_pak = TransferPak::new(None);
+        write(&mut transfer_pak, 0x8000, 0x84);
+        assert_eq!(read(&mut transfer_pak, 0xB000)[0], 0xC0);
+    }
+}
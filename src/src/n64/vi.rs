@@ -0,0 +1,319 @@
+const VI_STATUS_REG_START: usize = 0x00000000;
+const VI_STATUS_REG_END: usize = 0x00000003;
+const VI_ORIGIN_REG_START: usize = 0x00000004;
+const VI_ORIGIN_REG_END: usize = 0x00000007;
+const VI_WIDTH_REG_START: usize = 0x00000008;
+const VI_WIDTH_REG_END: usize = 0x0000000B;
+const VI_V_INTR_REG_START: usize = 0x0000000C;
+const VI_V_INTR_REG_END: usize = 0x0000000F;
+const VI_V_CURRENT_REG_START: usize = 0x00000010;
+const VI_V_CURRENT_REG_END: usize = 0x00000013;
+const VI_BURST_REG_START: usize = 0x00000014;
+const VI_BURST_REG_END: usize = 0x00000017;
+const VI_V_SYNC_REG_START: usize = 0x00000018;
+const VI_V_SYNC_REG_END: usize = 0x0000001B;
+const VI_H_SYNC_REG_START: usize = 0x0000001C;
+const VI_H_SYNC_REG_END: usize = 0x0000001F;
+const VI_LEAP_REG_START: usize = 0x00000020;
+const VI_LEAP_REG_END: usize = 0x00000023;
+const VI_H_START_REG_START: usize = 0x00000024;
+const VI_H_START_REG_END: usize = 0x00000027;
+const VI_V_START_REG_START: usize = 0x00000028;
+const VI_V_START_REG_END: usize = 0x0000002B;
+const VI_V_BURST_REG_START: usize = 0x0000002C;
+const VI_V_BURST_REG_END: usize = 0x0000002F;
+const VI_X_SCALE_REG_START: usize = 0x00000030;
+const VI_X_SCALE_REG_END: usize = 0x00000033;
+const VI_Y_SCALE_REG_START: usize = 0x00000034;
+const VI_Y_SCALE_REG_END: usize = 0x00000037;
+
+//VI_STATUS bits
+pub const VI_STATUS_TYPE_MASK: u32 = 0x00003;
+pub const VI_STATUS_GAMMA_DITHER: u32 = 0x00004;
+pub const VI_STATUS_GAMMA: u32 = 0x00008;
+pub const VI_STATUS_DIVOT: u32 = 0x00010;
+pub const VI_STATUS_SERRATE: u32 = 0x00040;
+pub const VI_STATUS_AA_MODE_MASK: u32 = 0x00300;
+pub const VI_STATUS_DITHER_FILTER: u32 = 0x10000;
+
+//Pixel types in the low bits of VI_STATUS
+pub const VI_TYPE_BLANK: u32 = 0;
+pub const VI_TYPE_16: u32 = 2;
+pub const VI_TYPE_32: u32 = 3;
+
+const VI_STATUS_MASK: u32 = 0x0001FFFF;
+const VI_ORIGIN_MASK: u32 = 0x00FFFFFF;
+const VI_WIDTH_MASK: u32 = 0x00000FFF;
+const VI_LINE_MASK: u32 = 0x000003FF;
+const VI_BURST_MASK: u32 = 0x3FFFFFFF;
+const VI_H_SYNC_MASK: u32 = 0x001F0FFF;
+const VI_PAIR_MASK: u32 = 0x0FFF0FFF;
+const VI_RANGE_MASK: u32 = 0x03FF03FF;
+
+// Referenced: https://n64brew.dev/wiki/Video_Interface
+pub const NTSC_VIDEO_CLOCK: u32 = 48681812;
+pub const PAL_VIDEO_CLOCK: u32 = 49656530;
+pub const MPAL_VIDEO_CLOCK: u32 = 48628316;
+//Devices are stepped at the COUNT rate, half of the 93.75MHz CPU clock
+pub const COUNT_CLOCK: u64 = 46875000;
+//Timing used until the game programs its own
+const DEFAULT_H_SYNC: u32 = 3093;
+const DEFAULT_V_SYNC: u32 = 525;
+//Scale factors are 2.10 fixed point
+const SCALE_SHIFT: u32 = 10;
+
+use n64::arch::Reg;
+use n64::exceptions::Exception;
+use n64::rdram::RDRAM;
+use n64::rom::TvType;
+
+pub fn video_clock(tv_type: TvType) -> u32
+{
+    match tv_type
+    {
+        TvType::NTSC => NTSC_VIDEO_CLOCK,
+        TvType::PAL => PAL_VIDEO_CLOCK,
+        TvType::MPAL => MPAL_VIDEO_CLOCK,
+    }
+}
+
+//RGBA8888 rows as the VI would send them to the screen
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct Frame
+{
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Frame
+{
+    pub fn blank() -> Frame
+    {
+        return Frame
+        {
+            width: 0,
+            height: 0,
+            pixels: Vec::new(),
+        }
+    }
+}
+
+pub struct VideoInterface
+{
+    pub status: Reg,
+    pub origin: Reg,
+    pub width: Reg,
+    pub v_intr: Reg,
+    pub v_current: Reg,
+    pub burst: Reg,
+    pub v_sync: Reg,
+    pub h_sync: Reg,
+    pub leap: Reg,
+    pub h_start: Reg,
+    pub v_start: Reg,
+    pub v_burst: Reg,
+    pub x_scale: Reg,
+    pub y_scale: Reg,
+    pub video_clock: u32,
+    pub field: u32,
+    //Number of fields scanned so far
+    pub field_count: u64,
+    pub pending_interrupt: Option<bool>,
+    line_cycles: u64,
+}
+
+impl VideoInterface
+{
+    pub fn new(tv_type: TvType) -> VideoInterface
+    {
+        return VideoInterface
+        {
+            status: Reg::default(),
+            origin: Reg::default(),
+            width: Reg::default(),
+            //No interrupt until the game picks a line
+            v_intr: Reg::new(VI_LINE_MASK as u64, false),
+            v_current: Reg::default(),
+            burst: Reg::default(),
+            v_sync: Reg::default(),
+            h_sync: Reg::default(),
+            leap: Reg::default(),
+            h_start: Reg::default(),
+            v_start: Reg::default(),
+            v_burst: Reg::default(),
+            x_scale: Reg::default(),
+            y_scale: Reg::default(),
+            video_clock: video_clock(tv_type),
+            field: 0,
+            field_count: 0,
+            pending_interrupt: None,
+            line_cycles: 0,
+        }
+    }
+
+    pub fn read_u32_from_address(&self, address: usize) -> Result<u32, Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match address
+        {
+            VI_STATUS_REG_START...VI_STATUS_REG_END => Ok(self.status.get_value() as u32),
+            VI_ORIGIN_REG_START...VI_ORIGIN_REG_END => Ok(self.origin.get_value() as u32),
+            VI_WIDTH_REG_START...VI_WIDTH_REG_END => Ok(self.width.get_value() as u32),
+            VI_V_INTR_REG_START...VI_V_INTR_REG_END => Ok(self.v_intr.get_value() as u32),
+            VI_V_CURRENT_REG_START...VI_V_CURRENT_REG_END => Ok(self.v_current.get_value() as u32),
+            VI_BURST_REG_START...VI_BURST_REG_END => Ok(self.burst.get_value() as u32),
+            VI_V_SYNC_REG_START...VI_V_SYNC_REG_END => Ok(self.v_sync.get_value() as u32),
+            VI_H_SYNC_REG_START...VI_H_SYNC_REG_END => Ok(self.h_sync.get_value() as u32),
+            VI_LEAP_REG_START...VI_LEAP_REG_END => Ok(self.leap.get_value() as u32),
+            VI_H_START_REG_START...VI_H_START_REG_END => Ok(self.h_start.get_value() as u32),
+            VI_V_START_REG_START...VI_V_START_REG_END => Ok(self.v_start.get_value() as u32),
+            VI_V_BURST_REG_START...VI_V_BURST_REG_END => Ok(self.v_burst.get_value() as u32),
+            VI_X_SCALE_REG_START...VI_X_SCALE_REG_END => Ok(self.x_scale.get_value() as u32),
+            VI_Y_SCALE_REG_START...VI_Y_SCALE_REG_END => Ok(self.y_scale.get_value() as u32),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    pub fn load_u32_to_address(&mut self, address: usize, value: u32) -> Result<(), Exception>
+    {
+        //Only allow alligned addresses (unaligned handled exterior to function)
+        if address % 4 != 0
+        {
+            return Err(Exception::ADDRESS_ERROR);
+        }
+
+        match address
+        {
+            VI_STATUS_REG_START...VI_STATUS_REG_END => Ok(self.status.set_value(value & VI_STATUS_MASK)),
+            VI_ORIGIN_REG_START...VI_ORIGIN_REG_END => Ok(self.origin.set_value(value & VI_ORIGIN_MASK)),
+            VI_WIDTH_REG_START...VI_WIDTH_REG_END => Ok(self.width.set_value(value & VI_WIDTH_MASK)),
+            VI_V_INTR_REG_START...VI_V_INTR_REG_END => Ok(self.v_intr.set_value(value & VI_LINE_MASK)),
+            //Writing the current line acknowledges the interrupt
+            VI_V_CURRENT_REG_START...VI_V_CURRENT_REG_END => Ok(self.pending_interrupt = Some(false)),
+            VI_BURST_REG_START...VI_BURST_REG_END => Ok(self.burst.set_value(value & VI_BURST_MASK)),
+            VI_V_SYNC_REG_START...VI_V_SYNC_REG_END => Ok(self.v_sync.set_value(value & VI_LINE_MASK)),
+            VI_H_SYNC_REG_START...VI_H_SYNC_REG_END => Ok(self.h_sync.set_value(value & VI_H_SYNC_MASK)),
+            VI_LEAP_REG_START...VI_LEAP_REG_END => Ok(self.leap.set_value(value & VI_PAIR_MASK)),
+            VI_H_START_REG_START...VI_H_START_REG_END => Ok(self.h_start.set_value(value & VI_RANGE_MASK)),
+            VI_V_START_REG_START...VI_V_START_REG_END => Ok(self.v_start.set_value(value & VI_RANGE_MASK)),
+            VI_V_BURST_REG_START...VI_V_BURST_REG_END => Ok(self.v_burst.set_value(value & VI_RANGE_MASK)),
+            VI_X_SCALE_REG_START...VI_X_SCALE_REG_END => Ok(self.x_scale.set_value(value & VI_PAIR_MASK)),
+            VI_Y_SCALE_REG_START...VI_Y_SCALE_REG_END => Ok(self.y_scale.set_value(value & VI_PAIR_MASK)),
+            _ => Err(Exception::UNIMPLEMENTED_ADDRESS),
+        }
+    }
+
+    pub fn pixel_type(&self) -> u32
+    {
+        self.status.get_value() as u32 & VI_STATUS_TYPE_MASK
+    }
+
+    fn is_interlaced(&self) -> bool
+    {
+        self.status.get_value() as u32 & VI_STATUS_SERRATE != 0
+    }
+
+    //H_SYNC holds the length of a line in video clocks
+    pub fn cycles_per_line(&self) -> u64
+    {
+        let h_sync = match self.h_sync.get_value() as u32 & 0xFFF
+        {
+            0 => DEFAULT_H_SYNC,
+            h_sync => h_sync,
+        };
+        ((h_sync as u64 + 1) * COUNT_CLOCK / self.video_clock as u64).max(1)
+    }
+
+    //Lines are counted in half lines with the field in bit 0
+    fn advance_line(&mut self) -> bool
+    {
+        let v_sync = match self.v_sync.get_value() as u32
+        {
+            0 => DEFAULT_V_SYNC,
+            v_sync => v_sync,
+        };
+        let mut line = (self.v_current.get_value() as u32 & !1) + 2;
+        if line >= v_sync
+        {
+            line = 0;
+            self.field = if self.is_interlaced() {self.field ^ 1} else {0};
+            self.field_count += 1;
+        }
+        self.v_current.set_value((line | self.field) & VI_LINE_MASK);
+        line == self.v_intr.get_value() as u32 & VI_LINE_MASK & !1
+    }
+
+    //Returns true when the line counter reaches V_INTR and the VI interrupt should be raised
+    pub fn step(&mut self, cycles: u32) -> bool
+    {
+        self.line_cycles += cycles as u64;
+        let cycles_per_line = self.cycles_per_line();
+        let mut interrupt = false;
+        while self.line_cycles >= cycles_per_line
+        {
+            self.line_cycles -= cycles_per_line;
+            interrupt |= self.advance_line();
+        }
+        interrupt
+    }
+
+    // Referenced: https://n64brew.dev/wiki/Video_Interface#Scaling
+    //Each output pixel steps through the framebuffer by the 2.10 scale from its offset
+    pub fn scanout(&self, rdram: &RDRAM) -> Frame
+    {
+        let pixel_type = self.pixel_type();
+        let range = |register: &Reg| ((register.get_value() as u32 >> 16) & 0x3FF, register.get_value() as u32 & 0x3FF);
+        let (h_start, h_end) = range(&self.h_start);
+        let (v_start, v_end) = range(&self.v_start);
+        if pixel_type < VI_TYPE_16 || h_end <= h_start || v_end <= v_start
+        {
+            return Frame::blank();
+        }
+        let width = (h_end - h_start) as usize;
+        //V_START is in half lines
+        let height = ((v_end - v_start) >> 1) as usize;
+        let scale = |register: &Reg| (register.get_value() as u32 & 0xFFF, (register.get_value() as u32 >> 16) & 0xFFF);
+        let (x_scale, x_offset) = scale(&self.x_scale);
+        let (y_scale, y_offset) = scale(&self.y_scale);
+        let origin = self.origin.get_value() as usize;
+        let framebuffer_width = self.width.get_value() as usize;
+        let read = |address: usize| rdram.read_u8(address).unwrap_or(0);
+
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for y in 0..height
+        {
+            let source_y = ((y_offset + (y as u32 * y_scale)) >> SCALE_SHIFT) as usize;
+            for x in 0..width
+            {
+                let source_x = ((x_offset + (x as u32 * x_scale)) >> SCALE_SHIFT) as usize;
+                let index = (source_y * framebuffer_width) + source_x;
+                if pixel_type == VI_TYPE_32
+                {
+                    let address = origin + (index * 4);
+                    pixels.extend_from_slice(&[read(address), read(address + 1), read(address + 2), 0xFF]);
+                }
+                else
+                {
+                    let address = origin + (index * 2);
+                    let value = ((read(address) as u16) << 8) | read(address + 1) as u16;
+                    let expand = |value: u16| (((value & 0x1F) << 3) | ((value & 0x1F) >> 2)) as u8;
+                    pixels.extend_from_slice(&[expand(value >> 11), expand(value >> 6), expand(value >> 1), 0xFF]);
+                }
+            }
+        }
+        return Frame
+        {
+            width: width,
+            height: height,
+            pixels: pixels,
+        }
+    }
+}
@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod vi_tests
+{
+    use n64::vi::*;
+    use n64::connector::Connector;
+    use n64::hle::{store_rdram_u16, store_rdram_u32};
+    use n64::mips_iface::MI_INTR_VI;
+
+    const VI_STATUS: u32 = 0x04400000;
+    const VI_ORIGIN: u32 = 0x04400004;
+    const VI_WIDTH: u32 = 0x04400008;
+    const VI_V_INTR: u32 = 0x0440000C;
+    const VI_V_CURRENT: u32 = 0x04400010;
+    const VI_V_SYNC: u32 = 0x04400018;
+    const VI_H_SYNC: u32 = 0x0440001C;
+    const VI_H_START: u32 = 0x04400024;
+    const VI_V_START: u32 = 0x04400028;
+    const VI_X_SCALE: u32 = 0x04400030;
+    const VI_Y_SCALE: u32 = 0x04400034;
+
+    fn vi_interrupt(connector: &Connector) -> bool
+    {
+        connector.mips_interface.interrupt.get_value() as u32 & MI_INTR_VI != 0
+    }
+
+    fn pixel(frame: &Frame, x: usize, y: usize) -> [u8; 4]
+    {
+        let offset = ((y * frame.width) + x) * 4;
+        [frame.pixels[offset], frame.pixels[offset + 1], frame.pixels[offset + 2], frame.pixels[offset + 3]]
+    }
+
+    #[test]
+    fn registers_are_masked()
+    {
+        let mut connector = Connector::test();
+        let writes = [(VI_ORIGIN, 0x00FFFFFF), (VI_WIDTH, 0x00000FFF), (VI_V_INTR, 0x000003FF), (VI_H_SYNC, 0x001F0FFF), (VI_H_START, 0x03FF03FF), (VI_X_SCALE, 0x0FFF0FFF)];
+        for &(address, mask) in writes.iter()
+        {
+            connector.store_u32(address, 0xFFFFFFFF).unwrap();
+            assert_eq!(connector.read_u32(address).unwrap(), mask);
+        }
+        //The current line only moves with the beam
+        connector.store_u32(VI_V_CURRENT, 0x100).unwrap();
+        assert_eq!(connector.read_u32(VI_V_CURRENT).unwrap(), 0);
+        assert!(connector.read_u32(0x04400038).is_err());
+    }
+
+    #[test]
+    fn line_counter_raises_the_vertical_interrupt()
+    {
+        let mut connector = Connector::test();
+        connector.store_u32(VI_H_SYNC, 99).unwrap();
+        connector.store_u32(VI_V_SYNC, 20).unwrap();
+        connector.store_u32(VI_V_INTR, 6).unwrap();
+        let line = connector.vi.cycles_per_line() as u32;
+        //A hundred video clocks at the NTSC rate
+        assert_eq!(line, 96);
+
+        connector.step(line * 2);
+        assert_eq!(connector.read_u32(VI_V_CURRENT).unwrap(), 4);
+        assert!(!vi_interrupt(&connector));
+        connector.step(line);
+        assert!(vi_interrupt(&connector));
+        connector.store_u32(VI_V_CURRENT, 0).unwrap();
+        assert!(!vi_interrupt(&connector));
+
+        //The count wraps at V_SYNC to start the next field
+        connector.step(line * 7);
+        assert_eq!(connector.read_u32(VI_V_CURRENT).unwrap(), 0);
+        assert_eq!(connector.vi.field_count, 1);
+
+        //Interlaced output alternates the field in bit 0
+        connector.store_u32(VI_STATUS, VI_STATUS_SERRATE).unwrap();
+        connector.step(line * 10);
+        assert_eq!(connector.read_u32(VI_V_CURRENT).unwrap(), 1);
+        assert_eq!(connector.vi.field_count, 2);
+        assert!(vi_interrupt(&connector));
+    }
+
+    #[test]
+    fn scanout_scales_16_bit_framebuffers()
+    {
+        let mut connector = Connector::test();
+        let rows = [[0xF801, 0x07C1, 0x003F, 0xFFFF], [0x0001, 0x8421, 0x0000, 0x0000]];
+        for (y, row) in rows.iter().enumerate()
+        {
+            for (x, value) in row.iter().enumerate()
+            {
+                store_rdram_u16(&mut connector, 0x10000 + (y as u32 * 8) + (x as u32 * 2), *value);
+            }
+        }
+        assert_eq!(connector.scanout(), Frame::blank());
+
+        let registers = [(VI_STATUS, VI_TYPE_16), (VI_ORIGIN, 0x10000), (VI_WIDTH, 4), (VI_H_START, 8), (VI_V_START, 4), (VI_X_SCALE, 0x200), (VI_Y_SCALE, 0x400)];
+        for &(address, value) in registers.iter()
+        {
+            connector.store_u32(address, value).unwrap();
+        }
+        let frame = connector.scanout();
+        assert_eq!((frame.width, frame.height), (8, 2));
+        //Half scale shows every framebuffer pixel twice
+        assert_eq!(pixel(&frame, 0, 0), [0xFF, 0x00, 0x00, 0xFF]);
+        assert_eq!(pixel(&frame, 1, 0), [0xFF, 0x00, 0x00, 0xFF]);
+        assert_eq!(pixel(&frame, 2, 0), [0x00, 0xFF, 0x00, 0xFF]);
+        assert_eq!(pixel(&frame, 7, 0), [0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(pixel(&frame, 0, 1), [0x00, 0x00, 0x00, 0xFF]);
+        assert_eq!(pixel(&frame, 2, 1), [0x84, 0x84, 0x84, 0xFF]);
+    }
+
+    #[test]
+    fn scanout_reads_32_bit_framebuffers_from_the_start_offset()
+    {
+        let mut connector = Connector::test();
+        store_rdram_u32(&mut connector, 0x20000, 0x11223344);
+        store_rdram_u32(&mut connector, 0x20004, 0x55667788);
+        let registers = [(VI_STATUS, VI_TYPE_32), (VI_ORIGIN, 0x20000), (VI_WIDTH, 2), (VI_H_START, 0x00100011), (VI_V_START, 0x00200022), (VI_X_SCALE, 0x04000400), (VI_Y_SCALE, 0x400)];
+        for &(address, value) in registers.iter()
+        {
+            connector.store_u32(address, value).unwrap();
+        }
+        let frame = connector.scanout();
+        assert_eq!((frame.width, frame.height), (1, 1));
+        assert_eq!(frame.pixels, vec![0x55, 0x66, 0x77, 0xFF]);
+    }
+
+    #[test]
+    fn interlaced_fields_follow_the_origin_set_for_each_field()
+    {
+        let mut connector = Connector::test();
+        store_rdram_u32(&mut connector, 0x20000, 0x11223344);
+        store_rdram_u32(&mut connector, 0x20004, 0x55667788);
+        let registers = [(VI_STATUS, VI_TYPE_32 | VI_STATUS_SERRATE), (VI_ORIGIN, 0x20000), (VI_WIDTH, 1), (VI_V_SYNC, 4), (VI_H_START, 0x00100011), (VI_V_START, 0x00200022), (VI_X_SCALE, 0x400), (VI_Y_SCALE, 0x800)];
+        for &(address, value) in registers.iter()
+        {
+            connector.store_u32(address, value).unwrap();
+        }
+        //Two lines make up a field at a V_SYNC of 4
+        let line = connector.vi.cycles_per_line() as u32;
+        connector.step(line * 2);
+        assert_eq!(connector.vi.field_count, 1);
+        assert_eq!(connector.scanout().pixels, vec![0x11, 0x22, 0x33, 0xFF]);
+
+        //Games move the origin down a line for the odd field, the VI itself adds no offset
+        assert_eq!(connector.read_u32(VI_V_CURRENT).unwrap() & 1, 1);
+        connector.store_u32(VI_ORIGIN, 0x20004).unwrap();
+        connector.step(line * 2);
+        assert_eq!(connector.vi.field_count, 2);
+        assert_eq!(connector.scanout().pixels, vec![0x55, 0x66, 0x77, 0xFF]);
+        assert_eq!(connector.read_u32(VI_V_CURRENT).unwrap() & 1, 0);
+    }
+}